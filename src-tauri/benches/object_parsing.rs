@@ -0,0 +1,99 @@
+use std::hint::black_box;
+
+use branchwise_lib::git::{
+    git_blob::GitBlob,
+    git_commit::GitCommit,
+    git_commit_author::GitCommitAuthor,
+    git_tree::{GitTree, GitTreeMode},
+    git_user::GitUser,
+    object::GitObject,
+};
+use criterion::{criterion_group, criterion_main, Criterion};
+
+// The fixtures are built from constant data so that every run parses the exact same bytes
+fn commit_fixture() -> Vec<u8> {
+    let author = GitCommitAuthor::new(
+        GitUser::new(
+            "Bench User".to_string(),
+            "bench.user@brewingbytes.com".to_string(),
+        ),
+        1700000000,
+        "+0200".to_string(),
+    );
+    let parents = vec![
+        "50c8353444afbef3172c999ef6cff8d31309ac3e".to_string(),
+        "50c8353444afbef3172c999ef6cff8d31309ac33".to_string(),
+    ];
+    let message = format!(
+        "Merge branch 'feature' into main\n\n{}",
+        "Describe the change in a few words per line.\n".repeat(20)
+    );
+
+    GitCommit::new(
+        "df6773ea47ed3fce3b3bb14e3d1101963e77ef08",
+        &parents,
+        author.clone(),
+        author,
+        &message,
+    )
+    .get_encoded_data()
+    .unwrap()
+}
+
+fn tree_fixture() -> Vec<u8> {
+    let mut tree = GitTree::new();
+    for index in 0..1000 {
+        let mode = if index % 10 == 0 {
+            GitTreeMode::Tree
+        } else {
+            GitTreeMode::File
+        };
+
+        tree.add_entry(
+            mode,
+            format!("{:040x}", index),
+            format!("file_{:04}.rs", index),
+        )
+        .unwrap();
+    }
+
+    tree.get_encoded_data().unwrap()
+}
+
+fn blob_fixture() -> Vec<u8> {
+    let data = "fn main() { println!(\"Hello, world!\"); }\n"
+        .repeat(1600)
+        .into_bytes();
+
+    GitBlob::new(data.len(), data).get_encoded_data().unwrap()
+}
+
+fn bench_commit(c: &mut Criterion) {
+    let encoded_data = commit_fixture();
+
+    c.bench_function("commit_from_encoded_data", |b| {
+        b.iter(|| GitCommit::from_encoded_data(black_box(&encoded_data)).unwrap())
+    });
+    c.bench_function("commit_from_encoded_data_headers_only", |b| {
+        b.iter(|| GitCommit::from_encoded_data_headers_only(black_box(&encoded_data)).unwrap())
+    });
+}
+
+fn bench_tree(c: &mut Criterion) {
+    let encoded_data = tree_fixture();
+
+    c.bench_function("tree_from_encoded_data", |b| {
+        b.iter(|| GitTree::from_encoded_data(black_box(&encoded_data)).unwrap())
+    });
+}
+
+fn bench_blob(c: &mut Criterion) {
+    let encoded_data = blob_fixture();
+
+    c.bench_function("blob_from_encoded_data", |b| {
+        b.iter(|| GitBlob::from_encoded_data(black_box(&encoded_data)).unwrap())
+    });
+}
+
+criterion_group!(benches, bench_commit, bench_tree, bench_blob);
+criterion_main!(benches);
@@ -12,6 +12,7 @@ pub enum GitError {
     NoGitFolder,
     NoLocalBranches,
     PackedRefsError,
+    CorruptRepository(Vec<String>),
 }
 
 #[derive(Clone, Serialize)]
@@ -2,14 +2,36 @@
 pub enum GitObjectError {
     CompressionError,
     DecompressionError,
+    EmptyObject,
+    SizeMismatch,
     InvalidObjectFile(ObjectError),
+    UnknownObjectType,
     InvalidCommitFile(CommitError),
     InvalidBlobFile,
     InvalidTreeFile,
+    InvalidTreeMode,
+    DuplicateTreeEntry(String),
+    InvalidIndexFile,
+    InvalidTagFile,
+    InvalidPackFile,
+    InvalidBundleFile,
+    InvalidRefFile,
     FileReadError,
     ParsingError,
     ShaError,
     InvalidHash,
+    HashMismatch,
+    AmbiguousHash,
+    ObjectNotFound,
+    RefNotFound,
+    RefAlreadyExists,
+    RefLocked,
+    InvalidRefName,
+    BranchCheckedOut,
+    PathNotFound,
+    NotAGitRepository,
+    UnbornBranch,
+    ArchiveError,
 }
 
 #[derive(Debug, PartialEq)]
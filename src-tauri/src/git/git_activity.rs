@@ -0,0 +1,55 @@
+use chrono::{DateTime, Datelike, Days, NaiveDate};
+use std::collections::{BTreeMap, HashSet};
+
+use super::{
+    git_commit::GitCommit, git_commit_author::parse_timezone_offset, git_project::GitProject,
+};
+use crate::errors::git_object_error::{CommitError, GitObjectError};
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Bucket {
+    Day,
+    // Weeks start on Monday, like ISO weeks
+    Week,
+}
+
+// Counts every commit reachable from `ref_name` by the day, or week, of its author
+// date in the author's own timezone. Only the headers of each commit are parsed
+pub fn commit_activity(
+    project: &GitProject,
+    ref_name: &str,
+    bucket: Bucket,
+) -> Result<Vec<(NaiveDate, usize)>, GitObjectError> {
+    let start = project.resolve_revision(ref_name)?;
+
+    let mut counts = BTreeMap::<NaiveDate, usize>::new();
+    let mut visited = HashSet::new();
+    let mut to_visit = vec![start];
+    while let Some(hash) = to_visit.pop() {
+        if !visited.insert(hash.clone()) {
+            continue;
+        }
+
+        let commit = GitCommit::from_hash_headers_only(project, &hash)?;
+        let author = commit.get_author();
+
+        let local_seconds =
+            author.date_seconds + parse_timezone_offset(&author.timezone).unwrap_or_default();
+        let date = DateTime::from_timestamp(local_seconds, 0)
+            .ok_or(GitObjectError::InvalidCommitFile(
+                CommitError::InvalidAuthor,
+            ))?
+            .date_naive();
+        let bucket_start = match bucket {
+            Bucket::Day => date,
+            Bucket::Week => date
+                .checked_sub_days(Days::new(date.weekday().num_days_from_monday().into()))
+                .unwrap_or(date),
+        };
+        *counts.entry(bucket_start).or_default() += 1;
+
+        to_visit.extend(commit.get_parent_hashes().iter().cloned());
+    }
+
+    Ok(counts.into_iter().collect())
+}
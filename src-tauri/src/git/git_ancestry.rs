@@ -0,0 +1,79 @@
+use std::collections::{HashMap, HashSet, VecDeque};
+
+use super::{
+    git_commit::GitCommit, git_folders::GitRefs, git_project::GitProject, git_worktree::ref_names,
+    object::GitObject,
+};
+use crate::errors::git_object_error::GitObjectError;
+
+// Commits reached while walking, shared between the walks of one query so every
+// commit is read at most once
+#[derive(Default)]
+struct AncestryWalk {
+    parents: HashMap<String, Vec<String>>,
+    // Commits whose ancestors were all visited without finding the target
+    unreachable: HashSet<String>,
+}
+
+impl AncestryWalk {
+    // Breadth first walk from `start` looking for `target`, a commit counts as its own
+    // ancestor like it does for `git merge-base --is-ancestor`
+    fn reaches(
+        &mut self,
+        project: &GitProject,
+        start: &str,
+        target: &str,
+    ) -> Result<bool, GitObjectError> {
+        let mut visited = HashSet::new();
+        let mut queue = VecDeque::from([start.to_string()]);
+
+        while let Some(hash) = queue.pop_front() {
+            if hash == target {
+                return Ok(true);
+            }
+            if self.unreachable.contains(&hash) || !visited.insert(hash.clone()) {
+                continue;
+            }
+
+            if !self.parents.contains_key(&hash) {
+                let commit = GitCommit::from_hash(project, &hash)?;
+                self.parents
+                    .insert(hash.clone(), commit.get_parent_hashes().clone());
+            }
+
+            queue.extend(self.parents[&hash].iter().cloned());
+        }
+
+        self.unreachable.extend(visited);
+        Ok(false)
+    }
+}
+
+pub fn is_ancestor_of(
+    project: &GitProject,
+    ancestor: &str,
+    descendant: &str,
+) -> Result<bool, GitObjectError> {
+    AncestryWalk::default().reaches(project, descendant, ancestor)
+}
+
+// Like `git branch --contains`, the local branches whose tip has the commit in its history
+pub fn branches_containing(
+    project: &GitProject,
+    commit_hash: &str,
+) -> Result<Vec<String>, GitObjectError> {
+    let mut walk = AncestryWalk::default();
+    let mut branches = Vec::new();
+
+    for name in ref_names(&project.common_dir(), &GitRefs::HEADS)? {
+        let Some(tip) = project.resolve_ref(&format!("refs/heads/{}", name))? else {
+            continue;
+        };
+
+        if walk.reaches(project, &tip, commit_hash)? {
+            branches.push(name);
+        }
+    }
+
+    Ok(branches)
+}
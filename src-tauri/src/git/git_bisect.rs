@@ -0,0 +1,178 @@
+use serde::{Deserialize, Serialize};
+use std::{
+    collections::{HashMap, HashSet, VecDeque},
+    fs,
+    path::Path,
+};
+
+use super::{
+    git_commit::GitCommit,
+    git_folders::GitFolders,
+    git_project::GitProject,
+    git_worktree::{resolve_git_dirs, symbolic_ref_target},
+    object::GitObject,
+};
+use crate::errors::git_object_error::GitObjectError;
+
+const BISECT_START_FILE: &str = "BISECT_START";
+const BISECT_TERMS_FILE: &str = "BISECT_TERMS";
+const BISECT_EXPECTED_REV_FILE: &str = "BISECT_EXPECTED_REV";
+const BISECT_REFS_FOLDER: &str = "bisect";
+const DEFAULT_BAD_TERM: &str = "bad";
+const DEFAULT_GOOD_TERM: &str = "good";
+
+#[derive(Debug, PartialEq, Serialize, Deserialize, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct BisectStatus {
+    start: String,
+    bad: Option<String>,
+    good: Vec<String>,
+    skipped: Vec<String>,
+    current: Option<String>,
+}
+
+impl BisectStatus {
+    pub fn get_start(&self) -> &String {
+        &self.start
+    }
+
+    pub fn get_bad(&self) -> Option<&String> {
+        self.bad.as_ref()
+    }
+
+    pub fn get_good(&self) -> &Vec<String> {
+        &self.good
+    }
+
+    pub fn get_skipped(&self) -> &Vec<String> {
+        &self.skipped
+    }
+
+    pub fn get_current(&self) -> Option<&String> {
+        self.current.as_ref()
+    }
+
+    pub fn read(project: &GitProject) -> Result<Option<BisectStatus>, GitObjectError> {
+        let (git_dir, _) = resolve_git_dirs(Path::new(project.get_directory()))?;
+
+        let start = match fs::read_to_string(git_dir.join(BISECT_START_FILE)) {
+            Ok(start) => start.trim().to_string(),
+            Err(_) => return Ok(None),
+        };
+
+        // `git bisect terms` allows replacing bad/good with custom words
+        let terms = fs::read_to_string(git_dir.join(BISECT_TERMS_FILE)).unwrap_or_default();
+        let mut terms = terms.lines().map(str::trim);
+        let bad_term = terms.next().unwrap_or(DEFAULT_BAD_TERM);
+        let good_term = terms.next().unwrap_or(DEFAULT_GOOD_TERM);
+
+        let mut status = BisectStatus {
+            start,
+            bad: None,
+            good: Vec::new(),
+            skipped: Vec::new(),
+            current: None,
+        };
+
+        let bisect_refs = git_dir
+            .join(GitFolders::REFS.to_string())
+            .join(BISECT_REFS_FOLDER);
+        if let Ok(entries) = fs::read_dir(bisect_refs) {
+            for entry in entries.flatten() {
+                let name = entry.file_name().to_string_lossy().to_string();
+                let hash = fs::read_to_string(entry.path())
+                    .map_err(|_| GitObjectError::FileReadError)?
+                    .trim()
+                    .to_string();
+
+                if name == bad_term {
+                    status.bad = Some(hash);
+                } else if name.starts_with(&format!("{}-", good_term)) {
+                    status.good.push(hash);
+                } else if name.starts_with("skip-") {
+                    status.skipped.push(hash);
+                }
+            }
+        }
+        status.good.sort();
+        status.skipped.sort();
+
+        status.current = match fs::read_to_string(git_dir.join(BISECT_EXPECTED_REV_FILE)) {
+            Ok(expected_rev) => Some(expected_rev.trim().to_string()),
+            Err(_) => fs::read_to_string(git_dir.join("HEAD"))
+                .ok()
+                .map(|head| head.trim().to_string())
+                .filter(|head| symbolic_ref_target(head).is_none()),
+        };
+
+        Ok(Some(status))
+    }
+}
+
+// Picks the commit that splits the suspect range (reachable from bad, but not from any
+// good commit) as evenly as possible, like `git bisect` does. Returns None when bad is
+// the only suspect left, which means it is the first bad commit.
+pub fn bisect_next(
+    project: &GitProject,
+    good: &[String],
+    bad: &str,
+) -> Result<Option<String>, GitObjectError> {
+    let mut parents = HashMap::<String, Vec<String>>::new();
+
+    let good_ancestors = ancestors(project, good, &HashSet::new(), &mut parents)?
+        .into_iter()
+        .collect::<HashSet<String>>();
+    let candidates = ancestors(project, &[bad.to_string()], &good_ancestors, &mut parents)?;
+    if candidates.len() <= 1 {
+        return Ok(None);
+    }
+
+    let mut best = None;
+    let mut best_distance = 0;
+    for candidate in &candidates {
+        // Everything a suspect reaches without crossing a good commit is a suspect too
+        let weight = ancestors(
+            project,
+            std::slice::from_ref(candidate),
+            &good_ancestors,
+            &mut parents,
+        )?
+        .len();
+        let distance = weight.min(candidates.len() - weight);
+
+        if distance > best_distance {
+            best_distance = distance;
+            best = Some(candidate.clone());
+        }
+    }
+
+    Ok(best)
+}
+
+// Breadth first walk over the commits reachable from `start`, stopping at `excluded`
+fn ancestors(
+    project: &GitProject,
+    start: &[String],
+    excluded: &HashSet<String>,
+    parents: &mut HashMap<String, Vec<String>>,
+) -> Result<Vec<String>, GitObjectError> {
+    let mut visited = HashSet::new();
+    let mut ancestors = Vec::new();
+    let mut queue = start.iter().cloned().collect::<VecDeque<String>>();
+
+    while let Some(hash) = queue.pop_front() {
+        if excluded.contains(&hash) || !visited.insert(hash.clone()) {
+            continue;
+        }
+
+        if !parents.contains_key(&hash) {
+            let commit = GitCommit::from_hash(project, &hash)?;
+            parents.insert(hash.clone(), commit.get_parent_hashes().clone());
+        }
+
+        queue.extend(parents[&hash].iter().cloned());
+        ancestors.push(hash);
+    }
+
+    Ok(ancestors)
+}
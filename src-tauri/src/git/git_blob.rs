@@ -1,8 +1,47 @@
 use serde::{Deserialize, Serialize};
+use std::io::BufRead;
 
 use crate::errors::git_object_error::GitObjectError;
 
-use super::object::{GitObject, Header};
+use super::{
+    git_project::GitProject,
+    object::{open_object_reader, GitObject, Header, ObjectFormat},
+};
+
+// Git treats a file as binary when it finds a NUL byte in its first 8000 bytes
+const BINARY_CHECK_SIZE: usize = 8000;
+const IMAGE_MAGIC_BYTES: [(&[u8], ImageType); 4] = [
+    (b"\x89PNG\r\n\x1a\n", ImageType::Png),
+    (b"\xff\xd8\xff", ImageType::Jpeg),
+    (b"GIF87a", ImageType::Gif),
+    (b"GIF89a", ImageType::Gif),
+];
+const TEXT_EXTENSIONS: [&str; 20] = [
+    "rs", "md", "txt", "toml", "json", "yml", "yaml", "js", "ts", "tsx", "vue", "html", "css", "c",
+    "h", "cpp", "py", "sh", "xml", "svg",
+];
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub enum ImageType {
+    Png,
+    Jpeg,
+    Gif,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub enum ContentType {
+    Text,
+    Image(ImageType),
+    Binary,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub enum Eol {
+    Lf,
+    Crlf,
+    Mixed,
+    None,
+}
 
 #[derive(Serialize, Deserialize, Debug, PartialEq)]
 pub struct GitBlob {
@@ -22,15 +61,100 @@ impl GitBlob {
     pub fn data(&self) -> &[u8] {
         &self.data
     }
+
+    // The file contents a tree entry points to
+    pub fn content(&self) -> &[u8] {
+        self.data()
+    }
+
+    pub fn is_binary(&self) -> bool {
+        self.data[..self.data.len().min(BINARY_CHECK_SIZE)].contains(&0)
+    }
+
+    // Binary content and text with fewer than two line breaks, like a single line that
+    // ends in one, have no line ending to follow
+    pub fn dominant_eol(&self) -> Eol {
+        if self.is_binary() {
+            return Eol::None;
+        }
+
+        let crlf = self.data.windows(2).filter(|pair| pair == b"\r\n").count();
+        let lf = self.data.iter().filter(|byte| **byte == b'\n').count() - crlf;
+
+        match (lf, crlf) {
+            _ if lf + crlf < 2 => Eol::None,
+            (_, 0) => Eol::Lf,
+            (0, _) => Eol::Crlf,
+            _ => Eol::Mixed,
+        }
+    }
+
+    // The content decides first, the extension only rescues text in legacy encodings
+    pub fn content_type(&self, name: &str) -> ContentType {
+        if let Some((_, image_type)) = IMAGE_MAGIC_BYTES
+            .iter()
+            .find(|(magic, _)| self.data.starts_with(magic))
+        {
+            return ContentType::Image(image_type.clone());
+        }
+
+        if self.is_binary() {
+            return ContentType::Binary;
+        }
+
+        let is_text_extension = name
+            .rsplit_once('.')
+            .map(|(_, extension)| TEXT_EXTENSIONS.contains(&extension.to_lowercase().as_str()))
+            .unwrap_or(false);
+        if is_text_extension || std::str::from_utf8(&self.data).is_ok() {
+            return ContentType::Text;
+        }
+
+        ContentType::Binary
+    }
+
+    // A window of lines starting at the zero based `start`, without line endings. Only one
+    // line is held at a time while skipping, and a range past the end returns fewer lines
+    pub fn read_lines(
+        project: &GitProject,
+        hash: &str,
+        start: usize,
+        count: usize,
+    ) -> Result<Vec<String>, GitObjectError> {
+        let (object_type, _, mut reader) = open_object_reader(project, hash)?;
+        if object_type != Header::Blob {
+            return Err(GitObjectError::InvalidBlobFile);
+        }
+
+        let mut lines = Vec::new();
+        let mut line = Vec::new();
+        for index in 0..start.saturating_add(count) {
+            line.clear();
+            let read = reader
+                .read_until(b'\n', &mut line)
+                .map_err(|_| GitObjectError::DecompressionError)?;
+            if read == 0 {
+                break;
+            }
+
+            if index >= start {
+                let content = line.strip_suffix(b"\n").unwrap_or(&line);
+                let content = content.strip_suffix(b"\r").unwrap_or(content);
+                lines.push(String::from_utf8_lossy(content).into_owned());
+            }
+        }
+
+        Ok(lines)
+    }
 }
 
 impl GitObject for GitBlob {
-    fn from_encoded_data(encoded_data: &[u8]) -> Result<Self, GitObjectError> {
-        let decoded_data = Self::decode_data(encoded_data)?;
-        let (data, size) = Self::check_header_valid_and_get_data(&decoded_data)?;
-        let (data, _) = data.split_once("\n").ok_or(GitObjectError::ParsingError)?;
-
-        Ok(Self::new(size, data.as_bytes().to_vec()))
+    fn from_object_data(
+        data: &[u8],
+        size: usize,
+        _object_format: ObjectFormat,
+    ) -> Result<Self, GitObjectError> {
+        Ok(Self::new(size, data[..size].to_vec()))
     }
 
     fn get_type(&self) -> Header {
@@ -43,6 +167,10 @@ impl GitObject for GitBlob {
             .map(|byte| *byte as char)
             .collect::<String>()
     }
+
+    fn get_data(&self) -> Vec<u8> {
+        self.data.clone()
+    }
 }
 
 impl std::fmt::Display for GitBlob {
@@ -65,7 +193,7 @@ mod tests {
 
     fn create_encoded_blob_file(data: Option<String>) -> Result<Vec<u8>, GitObjectError> {
         let file_content = data.unwrap_or_else(|| "test".to_string());
-        let file_content_to_encode = format!("blob {}\x00{}\n", file_content.len(), file_content);
+        let file_content_to_encode = format!("blob {}\x00{}", file_content.len(), file_content);
 
         let mut zlib = flate2::bufread::ZlibEncoder::new(
             file_content_to_encode.as_bytes(),
@@ -93,7 +221,8 @@ mod tests {
 
         let blob = GitBlob::from_encoded_data(encoded_data.as_slice()).unwrap();
 
-        assert_eq!(blob.get_hash(), "9daeafb9864cf43055ae93beb0afd6c7d144bfa4");
+        // The same as `printf test | git hash-object --stdin`
+        assert_eq!(blob.get_hash(), "30d74d258442c7c65512eafab474568dd706c430");
     }
 
     #[test]
@@ -107,6 +236,28 @@ mod tests {
         assert_eq!(blob.data(), data.as_bytes());
     }
 
+    #[test]
+    fn test_git_blob_from_encoded_data_multiple_lines() {
+        let data = String::from("first line\nsecond line\n");
+        let encoded_data = create_encoded_blob_file(Some(data.clone())).unwrap();
+
+        let blob = GitBlob::from_encoded_data(encoded_data.as_slice()).unwrap();
+
+        assert_eq!(blob.data(), data.as_bytes());
+    }
+
+    #[test]
+    fn test_git_blob_from_encoded_data_empty() {
+        assert_eq!(
+            GitBlob::from_encoded_data(&[]).err(),
+            Some(GitObjectError::EmptyObject)
+        );
+        assert_eq!(
+            GitBlob::from_encoded_data(&[0x00, 0x01]).err(),
+            Some(GitObjectError::DecompressionError)
+        );
+    }
+
     #[test]
     fn test_git_blob_from_encoded_data_invalid_blob_file() {
         let result = GitBlob::from_encoded_data(vec![0, 1, 2, 3, 4, 5].as_slice());
@@ -140,6 +291,7 @@ mod tests {
         let blob = GitBlob::new(data.len(), data.clone());
         assert_eq!(blob.size(), data.len());
         assert_eq!(blob.data(), data.as_slice());
+        assert_eq!(blob.content(), data.as_slice());
     }
 
     #[test]
@@ -160,4 +312,49 @@ mod tests {
         assert_eq!(blob.size(), data.len());
         assert_eq!(blob.data(), data.as_slice());
     }
+
+    #[test]
+    fn test_git_blob_content_type() {
+        let source = GitBlob::new(12, b"fn main() {}".to_vec());
+        assert_eq!(source.content_type("src/main.rs"), ContentType::Text);
+
+        let png = GitBlob::new(12, b"\x89PNG\r\n\x1a\n\0\0\0\x0d".to_vec());
+        assert_eq!(
+            png.content_type("logo.bin"),
+            ContentType::Image(ImageType::Png)
+        );
+
+        let script = GitBlob::new(19, b"#!/bin/sh\necho hi\n".to_vec());
+        assert_eq!(script.content_type("configure"), ContentType::Text);
+
+        let latin1 = GitBlob::new(6, b"caf\xe9\n".to_vec());
+        assert_eq!(latin1.content_type("notes.txt"), ContentType::Text);
+        assert_eq!(latin1.content_type("notes"), ContentType::Binary);
+
+        let binary = GitBlob::new(4, vec![0x7f, b'E', b'L', 0]);
+        assert_eq!(binary.content_type("main.rs"), ContentType::Binary);
+    }
+
+    #[test]
+    fn test_git_blob_dominant_eol() {
+        let lf = GitBlob::new(13, b"first\nsecond\n".to_vec());
+        assert_eq!(lf.dominant_eol(), Eol::Lf);
+
+        let crlf = GitBlob::new(15, b"first\r\nsecond\r\n".to_vec());
+        assert_eq!(crlf.dominant_eol(), Eol::Crlf);
+
+        let mixed = GitBlob::new(14, b"first\r\nsecond\n".to_vec());
+        assert_eq!(mixed.dominant_eol(), Eol::Mixed);
+
+        let single_line = GitBlob::new(11, b"single line".to_vec());
+        assert_eq!(single_line.dominant_eol(), Eol::None);
+
+        for terminated in [&b"single line\n"[..], b"single line\r\n"] {
+            let single_line = GitBlob::new(terminated.len(), terminated.to_vec());
+            assert_eq!(single_line.dominant_eol(), Eol::None);
+        }
+
+        let binary = GitBlob::new(4, vec![b'\n', 0, b'\r', b'\n']);
+        assert_eq!(binary.dominant_eol(), Eol::None);
+    }
 }
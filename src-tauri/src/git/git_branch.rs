@@ -1,4 +1,20 @@
 use serde::{Deserialize, Serialize};
+use std::fs;
+
+use super::{
+    git_commit_author::{local_timezone, GitCommitAuthor},
+    git_config::get_user,
+    git_files::GitFilesOptional,
+    git_folders::{GitFolders, GitRefs},
+    git_project::GitProject,
+    git_reflog::{append_reflog, delete_reflog, ReflogEntry},
+    git_refs::{check_new_ref_name, create_ref, remove_empty_parents, remove_packed_ref},
+    git_revspec::now,
+    git_tag::peel_tag,
+    git_worktree::{ref_names, WorktreeInfo},
+    object::{peek_object_header, Header},
+};
+use crate::errors::git_object_error::{CommitError, GitObjectError};
 
 #[derive(Debug, PartialEq, Serialize, Deserialize, Clone)]
 #[serde(rename_all = "camelCase")]
@@ -13,6 +29,82 @@ impl GitBranch {
     }
 }
 
+// Creates `refs/heads/<name>` at the commit `start_point` resolves to, like `git branch`
+pub fn create_branch(
+    project: &GitProject,
+    name: &str,
+    start_point: &str,
+) -> Result<(), GitObjectError> {
+    check_new_ref_name(project, &GitRefs::HEADS, name)?;
+
+    let (hash, _) = peel_tag(project, project.resolve_revspec(start_point)?)?;
+    if peek_object_header(project, &hash)?.0 != Header::Commit {
+        return Err(GitObjectError::InvalidCommitFile(
+            CommitError::InvalidHeader,
+        ));
+    }
+
+    let ref_name = branch_ref_name(name);
+    create_ref(project, &ref_name, &hash)?;
+
+    // git needs an identity for the entry, without a configured user nothing is logged
+    if let Some(user) = get_user(project) {
+        let date = now();
+        let entry = ReflogEntry::new(
+            "0".repeat(hash.len()),
+            hash,
+            GitCommitAuthor::new(user, date, local_timezone(date)),
+            format!("branch: Created from {}", start_point),
+        );
+        append_reflog(project, &ref_name, &entry)?;
+    }
+
+    Ok(())
+}
+
+// Removes the branch wherever it is stored, loose or packed, together with its reflog.
+// Without `force` a branch that the HEAD of any worktree is on is kept
+pub fn delete_branch(project: &GitProject, name: &str, force: bool) -> Result<(), GitObjectError> {
+    let common_dir = project.common_dir();
+    if !ref_names(&common_dir, &GitRefs::HEADS)?
+        .iter()
+        .any(|branch| branch == name)
+    {
+        return Err(GitObjectError::RefNotFound);
+    }
+
+    if !force
+        && WorktreeInfo::list(project)?
+            .iter()
+            .any(|worktree| worktree.get_branch().is_some_and(|branch| branch == name))
+    {
+        return Err(GitObjectError::BranchCheckedOut);
+    }
+
+    // The packed copy goes first, so a locked `packed-refs` leaves the branch as it was
+    let ref_name = branch_ref_name(name);
+    let path = common_dir.join(&ref_name);
+    remove_packed_ref(project, &ref_name)?;
+    match fs::remove_file(&path) {
+        Err(error) if error.kind() != std::io::ErrorKind::NotFound => {
+            return Err(GitObjectError::FileReadError);
+        }
+        _ => remove_empty_parents(&path, &common_dir.join(branch_ref_name(""))),
+    }
+    delete_reflog(project, &ref_name)?;
+
+    project.invalidate(&[
+        path,
+        common_dir.join(GitFilesOptional::PackedRefs.to_string()),
+    ]);
+
+    Ok(())
+}
+
+fn branch_ref_name(name: &str) -> String {
+    format!("{}/{}/{}", GitFolders::REFS, GitRefs::HEADS, name)
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -0,0 +1,253 @@
+use serde::{Deserialize, Serialize};
+use std::{collections::HashMap, fs, path::Path};
+
+use super::{
+    git_pack::read_pack_objects,
+    object::{GitObject, Header, ObjectFormat},
+};
+use crate::errors::git_object_error::GitObjectError;
+
+const BUNDLE_V2_SIGNATURE: &str = "# v2 git bundle";
+const BUNDLE_V3_SIGNATURE: &str = "# v3 git bundle";
+const OBJECT_FORMAT_CAPABILITY: &str = "@object-format=";
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct BundleRef {
+    name: String,
+    hash: String,
+}
+
+impl BundleRef {
+    pub fn get_name(&self) -> &String {
+        &self.name
+    }
+
+    pub fn get_hash(&self) -> &String {
+        &self.hash
+    }
+}
+
+// A bundle is a header listing the refs it carries and the commits it was built on
+// top of (the prerequisites), followed by a pack with the objects
+#[derive(Debug)]
+pub struct GitBundle {
+    object_format: ObjectFormat,
+    prerequisites: Vec<String>,
+    refs: Vec<BundleRef>,
+    objects: HashMap<String, (Header, Vec<u8>)>,
+}
+
+impl GitBundle {
+    pub fn open(path: &Path) -> Result<Self, GitObjectError> {
+        let data = fs::read(path).map_err(|_| GitObjectError::FileReadError)?;
+
+        Self::from_data(&data)
+    }
+
+    pub fn from_data(data: &[u8]) -> Result<Self, GitObjectError> {
+        let mut object_format = ObjectFormat::Sha1;
+        let mut prerequisites = Vec::new();
+        let mut refs = Vec::new();
+
+        let mut position = 0;
+        let mut next_line = || -> Result<&str, GitObjectError> {
+            let length = data[position..]
+                .iter()
+                .position(|byte| *byte == b'\n')
+                .ok_or(GitObjectError::InvalidBundleFile)?;
+            let line = std::str::from_utf8(&data[position..position + length])
+                .map_err(|_| GitObjectError::InvalidBundleFile)?;
+            position += length + 1;
+
+            Ok(line)
+        };
+
+        let signature = next_line()?;
+        if signature != BUNDLE_V2_SIGNATURE && signature != BUNDLE_V3_SIGNATURE {
+            return Err(GitObjectError::InvalidBundleFile);
+        }
+
+        // The header ends at the first empty line, the pack starts right after it
+        loop {
+            let line = next_line()?;
+            if line.is_empty() {
+                break;
+            }
+
+            // Only v3 bundles have capabilities, the only one that matters for reading
+            // is the hash function of the objects
+            if line.starts_with('@') {
+                if let Some(format) = line.strip_prefix(OBJECT_FORMAT_CAPABILITY) {
+                    object_format = match format {
+                        "sha1" => ObjectFormat::Sha1,
+                        "sha256" => ObjectFormat::Sha256,
+                        _ => return Err(GitObjectError::InvalidBundleFile),
+                    };
+                }
+                continue;
+            }
+
+            // Prerequisites may be followed by the subject of the commit
+            if let Some(prerequisite) = line.strip_prefix('-') {
+                let hash = prerequisite.split(' ').next().unwrap_or_default();
+                if !is_hash(hash, object_format) {
+                    return Err(GitObjectError::InvalidBundleFile);
+                }
+
+                prerequisites.push(hash.to_string());
+                continue;
+            }
+
+            let (hash, name) = line
+                .split_once(' ')
+                .filter(|(hash, _)| is_hash(hash, object_format))
+                .ok_or(GitObjectError::InvalidBundleFile)?;
+            refs.push(BundleRef {
+                name: name.to_string(),
+                hash: hash.to_string(),
+            });
+        }
+
+        let objects = read_pack_objects(&data[position..], object_format)?;
+
+        Ok(Self {
+            object_format,
+            prerequisites,
+            refs,
+            objects,
+        })
+    }
+
+    pub fn get_object_format(&self) -> ObjectFormat {
+        self.object_format
+    }
+
+    pub fn get_prerequisites(&self) -> &Vec<String> {
+        &self.prerequisites
+    }
+
+    pub fn get_refs(&self) -> &Vec<BundleRef> {
+        &self.refs
+    }
+
+    pub fn len(&self) -> usize {
+        self.objects.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.objects.is_empty()
+    }
+
+    pub fn contains(&self, hash: &str) -> bool {
+        self.objects.contains_key(hash)
+    }
+
+    pub fn read_raw(&self, hash: &str) -> Result<(&Header, &[u8]), GitObjectError> {
+        self.objects
+            .get(hash)
+            .map(|(header, data)| (header, data.as_slice()))
+            .ok_or(GitObjectError::FileReadError)
+    }
+
+    // Parsed from the inflated payload, like an object read from the packs of the
+    // repository
+    pub fn read_object<T: GitObject>(&self, hash: &str) -> Result<T, GitObjectError> {
+        let (_, data) = self.read_raw(hash)?;
+
+        T::from_object_data(data, data.len(), self.object_format)
+    }
+}
+
+fn is_hash(hash: &str, object_format: ObjectFormat) -> bool {
+    hash.len() == object_format.hash_hex_len() && hash.chars().all(|c| c.is_ascii_hexdigit())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::git::{git_blob::GitBlob, git_commit::GitCommit};
+
+    // `git bundle create sample.bundle main v1` of a repository with two commits, each
+    // changing README.md
+    const SAMPLE_BUNDLE: &[u8] = include_bytes!("../../tests/fixtures/sample.bundle");
+    const MAIN_HASH: &str = "c4fd459c34892e3827974a5c66cbdae2cffdf6fc";
+    const V1_HASH: &str = "4bf4d4ac2a0ad18f960fda8b8d4561b9e08a1c0b";
+    const FIRST_README_HASH: &str = "eac7cc26af30bac2dc97b319b7b5ccb0226a6711";
+
+    #[test]
+    fn test_git_bundle_from_data() {
+        let bundle = GitBundle::from_data(SAMPLE_BUNDLE).unwrap();
+
+        assert_eq!(bundle.get_object_format(), ObjectFormat::Sha1);
+        assert!(bundle.get_prerequisites().is_empty());
+        assert_eq!(
+            bundle
+                .get_refs()
+                .iter()
+                .map(|bundle_ref| (
+                    bundle_ref.get_name().as_str(),
+                    bundle_ref.get_hash().as_str()
+                ))
+                .collect::<Vec<_>>(),
+            vec![("refs/heads/main", MAIN_HASH), ("refs/tags/v1", V1_HASH)]
+        );
+
+        // Two commits, two trees and two versions of README.md
+        assert_eq!(bundle.len(), 6);
+        assert!(bundle.contains(MAIN_HASH));
+        assert!(bundle.contains(V1_HASH));
+    }
+
+    #[test]
+    fn test_git_bundle_read_object() {
+        let bundle = GitBundle::from_data(SAMPLE_BUNDLE).unwrap();
+
+        let main = bundle.read_object::<GitCommit>(MAIN_HASH).unwrap();
+        assert_eq!(main.get_message(), "Second commit");
+        assert_eq!(main.get_parent_hashes(), &vec![V1_HASH.to_string()]);
+        assert_eq!(main.get_committer().date_seconds, 1704153600);
+        assert_eq!(
+            bundle.read_raw(main.get_tree_hash()).unwrap().0,
+            &Header::Tree
+        );
+
+        let readme = bundle.read_object::<GitBlob>(FIRST_README_HASH).unwrap();
+        assert_eq!(readme.data(), b"hello bundle\n");
+
+        assert_eq!(
+            bundle.read_raw("0000000000000000000000000000000000000000"),
+            Err(GitObjectError::FileReadError)
+        );
+    }
+
+    #[test]
+    fn test_git_bundle_prerequisites() {
+        let pack_start = SAMPLE_BUNDLE
+            .windows(4)
+            .position(|window| window == b"PACK")
+            .unwrap();
+        let mut data = format!(
+            "{}\n-{} First commit\n{} refs/heads/main\n\n",
+            BUNDLE_V2_SIGNATURE, V1_HASH, MAIN_HASH
+        )
+        .into_bytes();
+        data.extend_from_slice(&SAMPLE_BUNDLE[pack_start..]);
+
+        let bundle = GitBundle::from_data(&data).unwrap();
+        assert_eq!(bundle.get_prerequisites(), &vec![V1_HASH.to_string()]);
+        assert_eq!(bundle.get_refs().len(), 1);
+    }
+
+    #[test]
+    fn test_git_bundle_invalid() {
+        assert_eq!(
+            GitBundle::from_data(b"# v9 git bundle\n\n").unwrap_err(),
+            GitObjectError::InvalidBundleFile
+        );
+        assert_eq!(
+            GitBundle::from_data(b"# v2 git bundle\nnot a ref\n\n").unwrap_err(),
+            GitObjectError::InvalidBundleFile
+        );
+    }
+}
@@ -0,0 +1,143 @@
+use std::{
+    collections::HashMap,
+    path::{Component, Path},
+    sync::{Arc, Mutex},
+    time::SystemTime,
+};
+
+use super::{
+    git_commit::GitCommit,
+    git_files::GitFilesOptional,
+    git_folders::GitFolders,
+    git_pack::{GitPack, PACK_FOLDER},
+};
+
+const HEAD_REF: &str = "HEAD";
+
+// Kept with the modification time of the pack folder they were listed from
+type CachedPacks = (SystemTime, Vec<Arc<GitPack>>);
+
+// Caches are derived from the files on disk, so they are neither compared nor serialized
+// with the project they belong to
+#[derive(Debug, Default)]
+pub struct GitCache {
+    refs: Mutex<HashMap<String, String>>,
+    head_commit: Mutex<Option<GitCommit>>,
+    packs: Mutex<Option<CachedPacks>>,
+}
+
+impl GitCache {
+    pub fn get_ref(&self, ref_name: &str) -> Option<String> {
+        self.refs.lock().unwrap().get(ref_name).cloned()
+    }
+
+    pub fn set_ref(&self, ref_name: &str, hash: String) {
+        self.refs.lock().unwrap().insert(ref_name.to_string(), hash);
+    }
+
+    pub fn get_head_commit(&self) -> Option<GitCommit> {
+        self.head_commit.lock().unwrap().clone()
+    }
+
+    pub fn set_head_commit(&self, commit: GitCommit) {
+        *self.head_commit.lock().unwrap() = Some(commit);
+    }
+
+    // Packs listed while the folder had a different modification time are stale
+    pub fn get_packs(&self, modified: Option<SystemTime>) -> Option<Vec<Arc<GitPack>>> {
+        self.packs
+            .lock()
+            .unwrap()
+            .as_ref()
+            .filter(|(cached_modified, _)| Some(*cached_modified) == modified)
+            .map(|(_, packs)| packs.clone())
+    }
+
+    pub fn set_packs(&self, modified: SystemTime, packs: Vec<Arc<GitPack>>) {
+        *self.packs.lock().unwrap() = Some((modified, packs));
+    }
+
+    pub fn clear(&self) {
+        self.refs.lock().unwrap().clear();
+        *self.head_commit.lock().unwrap() = None;
+        *self.packs.lock().unwrap() = None;
+    }
+
+    // `path` is relative to the git directory
+    pub fn invalidate(&self, path: &Path) {
+        let components = path
+            .components()
+            .filter_map(|component| match component {
+                Component::Normal(name) => Some(name.to_string_lossy().to_string()),
+                _ => None,
+            })
+            .collect::<Vec<String>>();
+
+        let Some(first) = components.first() else {
+            return;
+        };
+
+        // Objects are immutable, a new one can't change anything that was already read,
+        // but packs come and go with fetches and repacks
+        if *first == GitFolders::OBJECTS.to_string() {
+            if components
+                .get(1)
+                .is_some_and(|folder| folder == PACK_FOLDER)
+            {
+                *self.packs.lock().unwrap() = None;
+            }
+            return;
+        }
+
+        if *first == GitFilesOptional::PackedRefs.to_string() {
+            self.clear();
+        } else if *first == HEAD_REF || *first == GitFolders::REFS.to_string() {
+            let mut refs = self.refs.lock().unwrap();
+            refs.remove(&components.join("/"));
+            // HEAD usually points at a branch, so it may resolve differently now
+            refs.remove(HEAD_REF);
+            *self.head_commit.lock().unwrap() = None;
+        }
+    }
+}
+
+impl Clone for GitCache {
+    fn clone(&self) -> Self {
+        Self {
+            refs: Mutex::new(self.refs.lock().unwrap().clone()),
+            head_commit: Mutex::new(self.get_head_commit()),
+            packs: Mutex::new(self.packs.lock().unwrap().clone()),
+        }
+    }
+}
+
+impl PartialEq for GitCache {
+    fn eq(&self, _: &Self) -> bool {
+        true
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_invalidate() {
+        let cache = GitCache::default();
+        cache.set_ref("HEAD", "head".to_string());
+        cache.set_ref("refs/heads/main", "main".to_string());
+        cache.set_ref("refs/tags/v1", "v1".to_string());
+
+        cache.invalidate(Path::new("objects/ab/cdef"));
+        cache.invalidate(Path::new("index"));
+        assert_eq!(cache.get_ref("HEAD"), Some("head".to_string()));
+
+        cache.invalidate(Path::new("refs/heads/main"));
+        assert_eq!(cache.get_ref("HEAD"), None);
+        assert_eq!(cache.get_ref("refs/heads/main"), None);
+        assert_eq!(cache.get_ref("refs/tags/v1"), Some("v1".to_string()));
+
+        cache.invalidate(Path::new("packed-refs"));
+        assert_eq!(cache.get_ref("refs/tags/v1"), None);
+    }
+}
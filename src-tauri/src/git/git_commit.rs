@@ -1,17 +1,32 @@
 use super::{
     git_commit_author::GitCommitAuthor,
+    git_diff::{canonical_patch, TreeChange},
+    git_hash::hash_object,
     git_project::GitProject,
-    object::{GitObject, Header},
+    git_tree::{GitTree, GitTreeMode},
+    object::{
+        is_loose_object_valid, parse_object_header, read_object_data, GitObject, Header,
+        ObjectFormat,
+    },
 };
 use crate::errors::git_object_error::{CommitError, GitObjectError};
 use core::fmt;
 use serde::{Deserialize, Serialize};
+use std::{borrow::Cow, collections::HashSet};
+
+const REVERT_PREFIX: &str = "This reverts commit ";
+const ENCODING_HEADER: &[u8] = b"encoding ";
+// The names git and iconv accept for the encoding where every byte is its own code point
+const LATIN1_ENCODINGS: [&str; 5] = ["iso-8859-1", "iso8859-1", "latin1", "latin-1", "l1"];
 
 pub enum CommitPrefix {
     Tree,
     Parent,
     Author,
     Committer,
+    Encoding,
+    GpgSig,
+    GpgSigSha256,
     Message,
     Invalid,
 }
@@ -23,19 +38,55 @@ impl From<&str> for CommitPrefix {
             "parent" => CommitPrefix::Parent,
             "author" => CommitPrefix::Author,
             "committer" => CommitPrefix::Committer,
+            "encoding" => CommitPrefix::Encoding,
+            "gpgsig" => CommitPrefix::GpgSig,
+            "gpgsig-sha256" => CommitPrefix::GpgSigSha256,
             "message" => CommitPrefix::Message,
             _ => CommitPrefix::Invalid,
         }
     }
 }
 
-#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+// SHA-256 and transition repositories sign with `gpgsig-sha256` instead of `gpgsig`
+#[derive(Clone, Debug, Default, PartialEq, Serialize, Deserialize)]
+pub enum SignatureHeader {
+    #[default]
+    GpgSig,
+    GpgSigSha256,
+}
+
+impl fmt::Display for SignatureHeader {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            SignatureHeader::GpgSig => write!(f, "gpgsig"),
+            SignatureHeader::GpgSigSha256 => write!(f, "gpgsig-sha256"),
+        }
+    }
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
 pub struct GitCommit {
     tree_hash: String,
     parent_hashes: Vec<String>,
     author: GitCommitAuthor,
     committer: GitCommitAuthor,
     message: String,
+    #[serde(default)]
+    message_lossy: bool,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    encoding: Option<String>,
+    #[serde(default)]
+    gpg_signature: Option<String>,
+    #[serde(default)]
+    signature_header: SignatureHeader,
+    // Headers this crate doesn't interpret, like `mergetag`, in the order they were read.
+    // Continuation lines are joined with a newline
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    extra_headers: Vec<(String, String)>,
+    // The stored bytes when decoding didn't give them back or they lack the final newline,
+    // so the commit keeps its hash
+    #[serde(skip)]
+    raw_data: Option<Vec<u8>>,
 }
 
 impl GitCommit {
@@ -52,7 +103,80 @@ impl GitCommit {
             author,
             committer,
             message: message.to_string(),
+            message_lossy: false,
+            encoding: None,
+            gpg_signature: None,
+            signature_header: SignatureHeader::GpgSig,
+            extra_headers: Vec::new(),
+            raw_data: None,
+        }
+    }
+
+    pub fn with_signature(mut self, header: SignatureHeader, signature: &str) -> GitCommit {
+        self.signature_header = header;
+        self.gpg_signature = Some(signature.to_string());
+        self.raw_data = None;
+
+        self
+    }
+
+    // Checks the contents against the hash the commit is stored under, to catch loose
+    // objects that were corrupted on disk
+    pub fn from_hash_verified(project: &GitProject, hash: &str) -> Result<Self, GitObjectError> {
+        let (object_type, _, data) = read_object_data(project, hash)?;
+        Self::from_object_data_verified(&object_type, &data, project.get_object_format(), hash)
+    }
+
+    pub fn from_encoded_data_verified(
+        encoded_data: &[u8],
+        object_format: ObjectFormat,
+        expected_hash: &str,
+    ) -> Result<Self, GitObjectError> {
+        let decoded_data = Self::decode_data_bytes(encoded_data)?;
+        let (object_type, _, data) = parse_object_header(&decoded_data)?;
+
+        Self::from_object_data_verified(&object_type, data, object_format, expected_hash)
+    }
+
+    fn from_object_data_verified(
+        object_type: &Header,
+        data: &[u8],
+        object_format: ObjectFormat,
+        expected_hash: &str,
+    ) -> Result<Self, GitObjectError> {
+        if !hash_object(object_format, object_type, data).eq_ignore_ascii_case(expected_hash) {
+            return Err(GitObjectError::HashMismatch);
         }
+
+        Self::from_data_bytes(data, false)
+    }
+
+    pub fn from_hash_lenient(project: &GitProject, hash: &str) -> Result<Self, GitObjectError> {
+        let (_, _, data) = read_object_data(project, hash)?;
+        Self::from_data_bytes(&data, true)
+    }
+
+    pub fn from_encoded_data_lenient(encoded_data: &[u8]) -> Result<Self, GitObjectError> {
+        let decoded_data = Self::decode_data_bytes(encoded_data)?;
+        let (_, _, data) = parse_object_header(&decoded_data)?;
+
+        Self::from_data_bytes(data, true)
+    }
+
+    // Objects never change once written, so a commit the repository already has, loose
+    // or packed, is left as it is. A loose file that doesn't hold the commit, like one
+    // cut short by a crash, is written again. Returns the hash it is stored under
+    pub fn write(&self, project: &GitProject) -> Result<String, GitObjectError> {
+        let object_format = project.get_object_format();
+        let hash = self.get_hash_with_format(object_format);
+
+        let is_stored = is_loose_object_valid(project, &hash)
+            || project.packs()?.iter().any(|pack| pack.contains(&hash));
+        if !is_stored {
+            self.write_object(project)?;
+        }
+
+        Ok(hash)
     }
 
     pub fn get_tree_hash(&self) -> &String {
@@ -63,6 +187,14 @@ impl GitCommit {
         &self.parent_hashes
     }
 
+    pub fn is_merge(&self) -> bool {
+        self.parent_hashes.len() > 1
+    }
+
+    pub fn is_root(&self) -> bool {
+        self.parent_hashes.is_empty()
+    }
+
     pub fn get_author(&self) -> &GitCommitAuthor {
         &self.author
     }
@@ -75,6 +207,108 @@ impl GitCommit {
         &self.message
     }
 
+    pub fn is_message_lossy(&self) -> bool {
+        self.message_lossy
+    }
+
+    pub fn get_encoding(&self) -> Option<&String> {
+        self.encoding.as_ref()
+    }
+
+    // The first line of the message, without the blank lines some tools put before it
+    pub fn subject(&self) -> &str {
+        self.message
+            .trim_start()
+            .lines()
+            .next()
+            .unwrap_or_default()
+            .trim_end()
+    }
+
+    // Everything after the subject, with the blank line that separates them and any
+    // trailing whitespace dropped. A message of a single line has no body
+    pub fn body(&self) -> Option<&str> {
+        let body = self.message.trim_start().split_once('\n')?.1.trim();
+
+        (!body.is_empty()).then_some(body)
+    }
+
+    // The commit named by the `This reverts commit <hash>.` line `git revert` writes,
+    // reverted merges continue the line with `, reversing`
+    pub fn revert_target(&self) -> Option<String> {
+        self.message.lines().find_map(|line| {
+            let hash = line
+                .trim()
+                .strip_prefix(REVERT_PREFIX)?
+                .split(['.', ','])
+                .next()?;
+
+            (matches!(hash.len(), 40 | 64) && hash.chars().all(|c| c.is_ascii_hexdigit()))
+                .then(|| hash.to_lowercase())
+        })
+    }
+
+    pub fn get_gpg_signature(&self) -> Option<&String> {
+        self.gpg_signature.as_ref()
+    }
+
+    pub fn get_signature_header(&self) -> &SignatureHeader {
+        &self.signature_header
+    }
+
+    pub fn get_extra_headers(&self) -> &Vec<(String, String)> {
+        &self.extra_headers
+    }
+
+    // The bytes git signs, which is the commit without its signature header
+    pub fn signing_payload(&self) -> Vec<u8> {
+        self.format_data(false).into_bytes()
+    }
+
+    fn format_data(&self, with_signature: bool) -> String {
+        let parent_hashes = self
+            .parent_hashes
+            .iter()
+            .map(|parent_hash| format!("parent {}\n", parent_hash))
+            .collect::<Vec<String>>()
+            .join("");
+
+        // Continuation lines of a multi-line header are prefixed with a space
+        let signature = match &self.gpg_signature {
+            Some(signature) if with_signature => format!(
+                "{} {}\n",
+                self.signature_header,
+                signature.replace('\n', "\n ")
+            ),
+            _ => String::new(),
+        };
+
+        let encoding = match &self.encoding {
+            Some(encoding) => format!("encoding {}\n", encoding),
+            None => String::new(),
+        };
+
+        // git writes them after the encoding and before the signature
+        let extra_headers = self
+            .extra_headers
+            .iter()
+            .map(|(name, value)| format!("{} {}\n", name, value.replace('\n', "\n ")))
+            .collect::<Vec<String>>()
+            .join("");
+
+        format!(
+            "tree {}\n{}{}\n{}\n{}{}{}\n{}",
+            self.tree_hash,
+            parent_hashes,
+            self.author.to_string(true),
+            self.committer.to_string(false),
+            encoding,
+            extra_headers,
+            signature,
+            self.message
+        )
+    }
+
     pub fn get_parent_commits(
         &self,
         project: &GitProject,
@@ -84,42 +318,188 @@ impl GitCommit {
             .map(|parent_hash| GitCommit::from_hash(project, parent_hash))
             .collect()
     }
-}
 
-impl GitObject for GitCommit {
-    fn from_encoded_data(encoded_data: &[u8]) -> Result<Self, GitObjectError> {
-        let decoded_data = Self::decode_data(encoded_data)?;
-        let (data, _) = Self::check_header_valid_and_get_data(&decoded_data)?;
+    // A parent that isn't available locally (e.g. in a shallow clone) gets a placeholder
+    pub fn parent_subjects(&self, project: &GitProject) -> Result<Vec<String>, GitObjectError> {
+        self.parent_hashes
+            .iter()
+            .map(
+                |parent_hash| match GitCommit::from_hash(project, parent_hash) {
+                    Ok(parent) => Ok(parent.subject().to_string()),
+                    Err(GitObjectError::FileReadError) => {
+                        Ok(format!("<missing commit {}>", parent_hash))
+                    }
+                    Err(error) => Err(error),
+                },
+            )
+            .collect()
+    }
+
+    // Merge commits are compared against their first parent, like `git show --first-parent`
+    pub fn changes(&self, project: &GitProject) -> Result<Vec<TreeChange>, GitObjectError> {
+        let tree = GitTree::from_hash(project, &self.tree_hash)?;
+
+        match self.parent_hashes.first() {
+            Some(parent_hash) => {
+                let parent = GitCommit::from_hash(project, parent_hash)?;
+                GitTree::from_hash(project, parent.get_tree_hash())?.diff(project, &tree)
+            }
+            None => tree.diff_against_empty(project),
+        }
+    }
 
+    // Equal for commits making the same change, like a commit and its cherry-pick, no
+    // matter their parents, message or authors
+    pub fn patch_id(&self, project: &GitProject) -> Result<String, GitObjectError> {
+        let patch = canonical_patch(project, &self.changes(project)?)?;
+
+        Ok(project.get_object_format().hash(&patch))
+    }
+
+    // The trees and blobs of this commit that none of its parents have, a tree that a
+    // parent already has is skipped together with everything below it
+    pub fn new_objects(&self, project: &GitProject) -> Result<Vec<String>, GitObjectError> {
+        let mut known = HashSet::new();
+        for parent_hash in &self.parent_hashes {
+            let parent = GitCommit::from_hash(project, parent_hash)?;
+            collect_tree_objects(project, parent.get_tree_hash(), &mut known, &mut Vec::new())?;
+        }
+
+        let mut new_objects = Vec::new();
+        collect_tree_objects(project, &self.tree_hash, &mut known, &mut new_objects)?;
+
+        Ok(new_objects)
+    }
+
+    // The distinct ancestors at most `depth` generations away, breadth first so the
+    // parents come before the grandparents. A commit reached by a shorter path through a
+    // merge is listed at the first generation it was seen in
+    pub fn ancestors_within(
+        &self,
+        project: &GitProject,
+        depth: usize,
+    ) -> Result<Vec<GitCommit>, GitObjectError> {
+        let mut visited = HashSet::from([self.get_hash_with_format(project.get_object_format())]);
+        let mut ancestors = Vec::new();
+
+        let mut generation = self.parent_hashes.clone();
+        for _ in 0..depth {
+            let mut next_generation = Vec::new();
+            for hash in generation {
+                if !visited.insert(hash.clone()) {
+                    continue;
+                }
+
+                let commit = GitCommit::from_hash(project, &hash)?;
+                next_generation.extend(commit.parent_hashes.iter().cloned());
+                ancestors.push(commit);
+            }
+
+            if next_generation.is_empty() {
+                break;
+            }
+            generation = next_generation;
+        }
+
+        Ok(ancestors)
+    }
+
+    // The headers are ASCII apart from the names, and the names and the message are in the
+    // encoding the commit declares. Anything that can't be decoded is replaced
+    fn from_data_bytes(data: &[u8], lenient: bool) -> Result<Self, GitObjectError> {
+        let (headers, message) = data.split_at(Self::headers_end(data));
+        let encoding = headers
+            .split(|byte| *byte == b'\n')
+            .find_map(|line| line.strip_prefix(ENCODING_HEADER))
+            .and_then(|encoding| std::str::from_utf8(encoding).ok())
+            .map(str::trim);
+
+        let (headers, headers_lossy) = decode_text(headers, encoding);
+        let (message, message_lossy) = decode_text(message, encoding);
+        let text = headers + message;
+
+        let mut commit = Self::from_data(&text, lenient)?;
+        commit.message_lossy = headers_lossy || message_lossy;
+        // Extra headers may have been stored in another order than the one they are
+        // written back in
+        if text.as_bytes() != data || !data.ends_with(b"\n") || !commit.extra_headers.is_empty() {
+            commit.raw_data = Some(data.to_vec());
+        }
+
+        Ok(commit)
+    }
+
+    // Lenient parsing also accepts idents that lack the email, and keeps the headers it
+    // doesn't know instead of rejecting the commit
+    fn from_data(data: &str, lenient: bool) -> Result<Self, GitObjectError> {
+        let parse_ident = if lenient {
+            GitCommitAuthor::from_string_lenient
+        } else {
+            GitCommitAuthor::from_string
+        };
         let mut tree = String::new();
         let mut parents = Vec::<String>::new();
         let mut author = Option::<GitCommitAuthor>::None;
         let mut committer = Option::<GitCommitAuthor>::None;
         let mut message = String::new();
+        let mut encoding = Option::<String>::None;
+        let mut signature = Option::<(SignatureHeader, String)>::None;
+        let mut extra_headers = Vec::<(String, String)>::new();
 
-        let mut data = &data[..data.len() - 1];
+        let mut data = data.strip_suffix('\n').unwrap_or(data);
         while !data.is_empty() {
-            let (line, remaining_data) =
-                data.split_once('\n')
-                    .ok_or(GitObjectError::InvalidCommitFile(
-                        CommitError::InvalidContent,
-                    ))?;
+            let (line, remaining_data) = split_header_line(data, lenient).ok_or(
+                GitObjectError::InvalidCommitFile(CommitError::InvalidContent),
+            )?;
 
-            let prefix = line.split_whitespace().next().unwrap_or("message");
+            // Only the blank line starts the message, so message lines that look like
+            // headers stay in it and a header can never be named "message"
+            let (name, prefix, value) = match line.split_once(' ') {
+                _ if line.is_empty() => ("", CommitPrefix::Message, remaining_data),
+                Some((name, value)) => match CommitPrefix::from(name) {
+                    CommitPrefix::Message => (name, CommitPrefix::Invalid, value),
+                    prefix => (name, prefix, value),
+                },
+                None => ("", CommitPrefix::Invalid, line),
+            };
 
-            let value = line
-                .strip_prefix((String::from(prefix) + " ").as_str())
-                .unwrap_or(remaining_data);
-
-            match CommitPrefix::from(prefix) {
+            match prefix {
                 CommitPrefix::Tree => tree = value.to_string(),
                 CommitPrefix::Parent => parents.push(value.to_string()),
-                CommitPrefix::Author => author = Some(GitCommitAuthor::from_string(value)?),
-                CommitPrefix::Committer => committer = Some(GitCommitAuthor::from_string(value)?),
+                // Exactly one author line followed by one committer line, like git fsck
+                CommitPrefix::Author if author.is_none() && committer.is_none() => {
+                    author = Some(parse_ident(value)?)
+                }
+                CommitPrefix::Committer if author.is_some() && committer.is_none() => {
+                    committer = Some(parse_ident(value)?)
+                }
+                CommitPrefix::Author | CommitPrefix::Committer => {
+                    return Err(GitObjectError::InvalidCommitFile(
+                        CommitError::InvalidHeader,
+                    ));
+                }
+                CommitPrefix::Encoding => encoding = Some(value.to_string()),
+                CommitPrefix::GpgSig | CommitPrefix::GpgSigSha256 => {
+                    let header = match prefix {
+                        CommitPrefix::GpgSigSha256 => SignatureHeader::GpgSigSha256,
+                        _ => SignatureHeader::GpgSig,
+                    };
+
+                    let (value, remaining_data) = read_continuation(value, remaining_data);
+                    signature = Some((header, value));
+                    data = remaining_data;
+                    continue;
+                }
                 CommitPrefix::Message => {
                     message = value.to_string();
                     break;
                 }
+                CommitPrefix::Invalid if lenient && !name.is_empty() => {
+                    let (value, remaining_data) = read_continuation(value, remaining_data);
+                    extra_headers.push((name.to_string(), value));
+                    data = remaining_data;
+                    continue;
+                }
                 CommitPrefix::Invalid => {
                     return Err(GitObjectError::InvalidCommitFile(
                         CommitError::InvalidContent,
@@ -137,13 +517,80 @@ impl GitObject for GitCommit {
             CommitError::InvalidHeader,
         ))?;
 
-        Ok(GitCommit::new(
-            &tree,
-            &parents,
-            author,
-            committer,
-            message.as_str(),
-        ))
+        let mut commit = GitCommit::new(&tree, &parents, author, committer, message.as_str());
+        commit.encoding = encoding;
+        commit.extra_headers = extra_headers;
+
+        Ok(match signature {
+            Some((header, signature)) => commit.with_signature(header, &signature),
+            None => commit,
+        })
+    }
+
+    // For walks that only need the tree and the parents, the message is neither
+    // validated nor copied and comes back empty
+    pub fn from_encoded_data_headers_only(encoded_data: &[u8]) -> Result<Self, GitObjectError> {
+        let decoded_data = Self::decode_data_bytes(encoded_data)?;
+        let (_, _, data) = parse_object_header(&decoded_data)?;
+
+        Self::from_headers(data)
+    }
+
+    pub fn from_hash_headers_only(
+        project: &GitProject,
+        hash: &str,
+    ) -> Result<Self, GitObjectError> {
+        let (_, _, data) = read_object_data(project, hash)?;
+        Self::from_headers(&data)
+    }
+
+    fn from_headers(data: &[u8]) -> Result<Self, GitObjectError> {
+        let headers = String::from_utf8_lossy(&data[..Self::headers_end(data)]);
+
+        // from_data expects the trailing newline every encoded object ends with
+        Self::from_data(&format!("{}\n", headers), false)
+    }
+
+    // The committer date without touching the message, for sorting many commits
+    pub fn commit_time(project: &GitProject, hash: &str) -> Result<i64, GitObjectError> {
+        Ok(Self::from_hash_headers_only(project, hash)?
+            .committer
+            .date_seconds)
+    }
+
+    // The headers end at the first empty line
+    fn headers_end(data: &[u8]) -> usize {
+        data.windows(2)
+            .position(|window| window == b"\n\n")
+            .map(|position| position + 1)
+            .unwrap_or(data.len())
+    }
+}
+
+// The stored bytes aren't serialized, so they are left out here too and a commit that
+// went through serde is still equal to the one it was read from
+impl PartialEq for GitCommit {
+    fn eq(&self, other: &Self) -> bool {
+        self.tree_hash == other.tree_hash
+            && self.parent_hashes == other.parent_hashes
+            && self.author == other.author
+            && self.committer == other.committer
+            && self.message == other.message
+            && self.message_lossy == other.message_lossy
+            && self.encoding == other.encoding
+            && self.gpg_signature == other.gpg_signature
+            && self.signature_header == other.signature_header
+            && self.extra_headers == other.extra_headers
+    }
+}
+
+impl GitObject for GitCommit {
+    fn from_object_data(
+        data: &[u8],
+        _size: usize,
+        _object_format: ObjectFormat,
+    ) -> Result<Self, GitObjectError> {
+        Self::from_data_bytes(data, false)
     }
 
     fn get_type(&self) -> Header {
@@ -153,28 +600,100 @@ impl GitObject for GitCommit {
     fn get_data_string(&self) -> String {
         self.to_string()
     }
+
+    // Reading drops the newline that ends a commit, it is written back here. Commits
+    // that didn't end with one keep their stored bytes and are written as they were
+    fn get_data(&self) -> Vec<u8> {
+        match &self.raw_data {
+            Some(raw_data) => raw_data.clone(),
+            None => {
+                let mut data = self.get_data_string().into_bytes();
+                data.push(b'\n');
+
+                data
+            }
+        }
+    }
 }
 
 impl fmt::Display for GitCommit {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
-        let parent_hashes = self
-            .parent_hashes
-            .iter()
-            .map(|parent_hash| format!("parent {}\n", parent_hash))
-            .collect::<Vec<String>>()
-            .join("");
+        write!(f, "{}", self.format_data(true))
+    }
+}
 
-        let content = format!(
-            "tree {}\n{}{}\n{}\n\n{}",
-            self.tree_hash,
-            parent_hashes,
-            self.author.to_string(true),
-            self.committer.to_string(false),
-            self.message
-        );
+// Adds a tree and everything below it to `known`, pushing what wasn't known yet to
+// `found` with every tree before its entries. Submodules point at commits of another
+// repository and are left out
+fn collect_tree_objects(
+    project: &GitProject,
+    tree_hash: &str,
+    known: &mut HashSet<String>,
+    found: &mut Vec<String>,
+) -> Result<(), GitObjectError> {
+    if !known.insert(tree_hash.to_string()) {
+        return Ok(());
+    }
+    found.push(tree_hash.to_string());
 
-        write!(f, "{}", content)
+    for entry in GitTree::from_hash(project, tree_hash)?.entries() {
+        match entry.mode {
+            GitTreeMode::Tree => collect_tree_objects(project, &entry.hash, known, found)?,
+            GitTreeMode::Submodule => {}
+            _ => {
+                if known.insert(entry.hash.clone()) {
+                    found.push(entry.hash.clone());
+                }
+            }
+        }
     }
+
+    Ok(())
+}
+
+// Latin-1 text is decoded byte for byte, anything else is read as UTF-8. The flag tells
+// whether something had to be replaced
+fn decode_text<'a>(bytes: &'a [u8], encoding: Option<&str>) -> (Cow<'a, str>, bool) {
+    if encoding.is_some_and(|encoding| LATIN1_ENCODINGS.contains(&encoding.to_lowercase().as_str()))
+    {
+        return (bytes.iter().map(|byte| *byte as char).collect(), false);
+    }
+
+    match String::from_utf8_lossy(bytes) {
+        Cow::Borrowed(text) => (Cow::Borrowed(text), false),
+        lossy => (lossy, true),
+    }
+}
+
+// Old Mac objects separate the header lines with a bare `\r`, which is only accepted
+// when parsing leniently. The message after the blank line is kept as it is
+fn split_header_line(data: &str, lenient: bool) -> Option<(&str, &str)> {
+    if !lenient {
+        return data.split_once('\n');
+    }
+
+    let end = data.find(['\n', '\r'])?;
+    let separator_len = if data[end..].starts_with("\r\n") {
+        2
+    } else {
+        1
+    };
+
+    Some((&data[..end], &data[end + separator_len..]))
+}
+
+// The lines after a multi-line header start with a space, which is dropped. Returns the
+// value with its lines joined and the data after it
+fn read_continuation<'a>(value: &str, mut remaining_data: &'a str) -> (String, &'a str) {
+    let mut value = value.to_string();
+    while let Some(continuation) = remaining_data.strip_prefix(' ') {
+        let (line, rest) = continuation.split_once('\n').unwrap_or((continuation, ""));
+        value.push('\n');
+        value.push_str(line);
+        remaining_data = rest;
+    }
+
+    (value, remaining_data)
 }
 
 #[cfg(test)]
@@ -239,10 +758,10 @@ mod tests {
         );
 
         let file_content = format!(
-            "{}{}{}{}\n{}",
+            "{}{}{}{}\n{}\n",
             tree_line, parent_lines, author_line, committer_line, message
         );
-        let file_content_to_encode = format!("commit {}\x00{}\n", file_content.len(), file_content);
+        let file_content_to_encode = format!("commit {}\x00{}", file_content.len(), file_content);
 
         let mut zlib = flate2::bufread::ZlibEncoder::new(
             file_content_to_encode.as_bytes(),
@@ -289,6 +808,279 @@ mod tests {
         assert!(git_commit.is_err());
     }
 
+    #[test]
+    fn test_from_encoded_data_lenient_invalid_message() {
+        let committer = mock_git_commit_author();
+
+        let mut file_content = format!(
+            "tree 50c8353444afbef3172c999ef6cff8d31309ac3e\n{}\n{}\n\nbroken ",
+            committer.to_string(true),
+            committer.to_string(false)
+        )
+        .into_bytes();
+        file_content.extend_from_slice(&[0xff, 0xfe]);
+        file_content.extend_from_slice(b" message");
+
+        let mut file_content_to_encode =
+            format!("commit {}\x00", file_content.len() + 1).into_bytes();
+        file_content_to_encode.extend_from_slice(&file_content);
+        file_content_to_encode.push(b'\n');
+
+        let mut zlib = flate2::bufread::ZlibEncoder::new(
+            file_content_to_encode.as_slice(),
+            flate2::Compression::default(),
+        );
+        let mut encoded_file_content = Vec::new();
+        zlib.read_to_end(&mut encoded_file_content).unwrap();
+
+        let git_commit = GitCommit::from_encoded_data_lenient(&encoded_file_content).unwrap();
+        assert!(git_commit.is_message_lossy());
+        assert_eq!(git_commit.get_message(), "broken \u{fffd}\u{fffd} message");
+        assert_eq!(
+            GitCommit::from_encoded_data(&encoded_file_content),
+            Ok(git_commit.clone())
+        );
+        assert_eq!(
+            git_commit.get_hash(),
+            hash_object(
+                ObjectFormat::Sha1,
+                &Header::Commit,
+                &[&file_content[..], b"\n"].concat()
+            )
+        );
+        assert_eq!(
+            git_commit.get_tree_hash(),
+            "50c8353444afbef3172c999ef6cff8d31309ac3e"
+        );
+        assert_eq!(*git_commit.get_author(), committer);
+        assert_eq!(*git_commit.get_committer(), committer);
+
+        // A replaced byte at the very end, with no newline after it
+        let mut file_content = file_content[..file_content.len() - b" message".len()].to_vec();
+        file_content.push(0xff);
+        let mut file_content_to_encode = format!("commit {}\x00", file_content.len()).into_bytes();
+        file_content_to_encode.extend_from_slice(&file_content);
+
+        let mut zlib = flate2::bufread::ZlibEncoder::new(
+            file_content_to_encode.as_slice(),
+            flate2::Compression::default(),
+        );
+        let mut encoded_file_content = Vec::new();
+        zlib.read_to_end(&mut encoded_file_content).unwrap();
+
+        let git_commit = GitCommit::from_encoded_data_lenient(&encoded_file_content).unwrap();
+        assert!(git_commit.is_message_lossy());
+        assert_eq!(git_commit.get_message(), "broken \u{fffd}\u{fffd}\u{fffd}");
+        assert_eq!(
+            git_commit.get_hash(),
+            hash_object(ObjectFormat::Sha1, &Header::Commit, &file_content)
+        );
+    }
+
+    #[test]
+    fn test_from_encoded_data_latin1() {
+        let encode = |file_content: &[u8]| {
+            let mut file_content_to_encode =
+                format!("commit {}\x00", file_content.len() + 1).into_bytes();
+            file_content_to_encode.extend_from_slice(file_content);
+            file_content_to_encode.push(b'\n');
+
+            let mut zlib = flate2::bufread::ZlibEncoder::new(
+                file_content_to_encode.as_slice(),
+                flate2::Compression::default(),
+            );
+            let mut encoded_file_content = Vec::new();
+            zlib.read_to_end(&mut encoded_file_content).unwrap();
+
+            encoded_file_content
+        };
+        let headers = b"tree 50c8353444afbef3172c999ef6cff8d31309ac3e\n\
+            author Andr\xe9 M\xfcller <andre@example.com> 1234567890 +0100\n\
+            committer Test User <test@example.com> 1234567890 +0100\n";
+        let message = b"\nMise \xe0 jour du r\xe9sum\xe9";
+        let file_content = [&headers[..], b"encoding ISO-8859-1\n", message].concat();
+
+        let git_commit = GitCommit::from_encoded_data(&encode(&file_content)).unwrap();
+        assert_eq!(
+            git_commit.get_message(),
+            "Mise \u{e0} jour du r\u{e9}sum\u{e9}"
+        );
+        assert_eq!(
+            git_commit.get_author().get_user().name,
+            "Andr\u{e9} M\u{fc}ller"
+        );
+        assert_eq!(git_commit.get_encoding(), Some(&"ISO-8859-1".to_string()));
+        assert!(!git_commit.is_message_lossy());
+        assert!(git_commit
+            .to_string()
+            .contains("+0100\nencoding ISO-8859-1\n\nMise"));
+
+        // The stored bytes are hashed and written back, not the decoded text
+        assert_eq!(
+            git_commit.get_hash(),
+            hash_object(
+                ObjectFormat::Sha1,
+                &Header::Commit,
+                &[&file_content[..], b"\n"].concat()
+            )
+        );
+        assert_eq!(
+            GitCommit::from_encoded_data(&git_commit.get_encoded_data().unwrap()),
+            Ok(git_commit.clone())
+        );
+        // The stored bytes don't make it a different commit
+        let mut decoded = git_commit.clone();
+        decoded.raw_data = None;
+        assert_eq!(decoded, git_commit);
+
+        // Without the header the same bytes can only be replaced
+        let git_commit =
+            GitCommit::from_encoded_data(&encode(&[&headers[..], message].concat())).unwrap();
+        assert_eq!(git_commit.get_encoding(), None);
+        assert!(git_commit.is_message_lossy());
+        assert_eq!(
+            git_commit.get_message(),
+            "Mise \u{fffd} jour du r\u{fffd}sum\u{fffd}"
+        );
+    }
+
+    #[test]
+    fn test_from_encoded_data_without_final_newline() {
+        let encode = |file_content: &[u8]| {
+            let mut file_content_to_encode =
+                format!("commit {}\x00", file_content.len()).into_bytes();
+            file_content_to_encode.extend_from_slice(file_content);
+
+            let mut zlib = flate2::bufread::ZlibEncoder::new(
+                file_content_to_encode.as_slice(),
+                flate2::Compression::default(),
+            );
+            let mut encoded_file_content = Vec::new();
+            zlib.read_to_end(&mut encoded_file_content).unwrap();
+
+            encoded_file_content
+        };
+        let headers = b"tree 50c8353444afbef3172c999ef6cff8d31309ac3e\n\
+            author Test User <test@example.com> 1234567890 +0100\n\
+            committer Test User <test@example.com> 1234567890 +0100\n";
+
+        let file_content = [&headers[..], b"\nUpdate the resume"].concat();
+        let git_commit = GitCommit::from_encoded_data(&encode(&file_content)).unwrap();
+        assert_eq!(git_commit.get_message(), "Update the resume");
+        assert_eq!(git_commit.get_data(), file_content);
+        assert_eq!(
+            git_commit.get_hash(),
+            hash_object(ObjectFormat::Sha1, &Header::Commit, &file_content)
+        );
+
+        let file_content = [
+            &headers[..],
+            b"encoding ISO-8859-1\n\nMise \xe0 jour du r\xe9sum\xe9",
+        ]
+        .concat();
+        let git_commit = GitCommit::from_encoded_data(&encode(&file_content)).unwrap();
+        assert_eq!(
+            git_commit.get_message(),
+            "Mise \u{e0} jour du r\u{e9}sum\u{e9}"
+        );
+        assert_eq!(git_commit.get_data(), file_content);
+        assert_eq!(
+            git_commit.get_hash(),
+            hash_object(ObjectFormat::Sha1, &Header::Commit, &file_content)
+        );
+
+        assert_eq!(
+            GitCommit::from_encoded_data(&encode(b"")),
+            Err(GitObjectError::InvalidCommitFile(
+                CommitError::InvalidHeader
+            ))
+        );
+    }
+
+    #[test]
+    fn test_from_encoded_data_lenient_author_without_email() {
+        let file_content = "tree 50c8353444afbef3172c999ef6cff8d31309ac3e\n\
+            author Test User 1234567890 +0000\n\
+            committer Test User <test@example.com> 1234567890 +0000\n\
+            \n\
+            old message";
+        let file_content_to_encode =
+            format!("commit {}\x00{}\n", file_content.len() + 1, file_content);
+        let mut zlib = flate2::bufread::ZlibEncoder::new(
+            file_content_to_encode.as_bytes(),
+            flate2::Compression::default(),
+        );
+        let mut encoded_file_content = Vec::new();
+        zlib.read_to_end(&mut encoded_file_content).unwrap();
+
+        assert_eq!(
+            GitCommit::from_encoded_data(&encoded_file_content).err(),
+            Some(GitObjectError::InvalidCommitFile(
+                CommitError::InvalidAuthor
+            ))
+        );
+
+        let git_commit = GitCommit::from_encoded_data_lenient(&encoded_file_content).unwrap();
+        assert!(git_commit.get_author().is_email_missing());
+        assert_eq!(git_commit.get_author().get_user().name, "Test User");
+        assert!(!git_commit.get_committer().is_email_missing());
+        assert_eq!(git_commit.to_string(), file_content);
+    }
+
+    #[test]
+    fn test_from_encoded_data_lenient_carriage_return_headers() {
+        let file_content = "tree 50c8353444afbef3172c999ef6cff8d31309ac3e\r\
+            author Test User <test@example.com> 1234567890 +0000\r\
+            committer Test User <test@example.com> 1234567890 +0000\r\
+            \r\
+            old message\rsecond line";
+        let file_content_to_encode =
+            format!("commit {}\x00{}\n", file_content.len() + 1, file_content);
+        let mut zlib = flate2::bufread::ZlibEncoder::new(
+            file_content_to_encode.as_bytes(),
+            flate2::Compression::default(),
+        );
+        let mut encoded_file_content = Vec::new();
+        zlib.read_to_end(&mut encoded_file_content).unwrap();
+
+        assert_eq!(
+            GitCommit::from_encoded_data(&encoded_file_content).err(),
+            Some(GitObjectError::InvalidCommitFile(
+                CommitError::InvalidContent
+            ))
+        );
+
+        let git_commit = GitCommit::from_encoded_data_lenient(&encoded_file_content).unwrap();
+        assert_eq!(
+            git_commit.get_tree_hash(),
+            "50c8353444afbef3172c999ef6cff8d31309ac3e"
+        );
+        assert_eq!(git_commit.get_author().get_user().email, "test@example.com");
+        assert_eq!(git_commit.get_committer().date_seconds, 1234567890);
+        assert_eq!(git_commit.get_message(), "old message\rsecond line");
+    }
+
+    #[test]
+    fn test_from_encoded_data_lenient_valid_message() {
+        let committer = mock_git_commit_author();
+
+        let encoded_file_content = create_encoded_commit_file(
+            committer.clone(),
+            committer.clone(),
+            Some("50c8353444afbef3172c999ef6cff8d31309ac3e"),
+            Vec::new(),
+            "test commit",
+        )
+        .unwrap();
+
+        let git_commit = GitCommit::from_encoded_data_lenient(&encoded_file_content).unwrap();
+        assert!(!git_commit.is_message_lossy());
+        assert_eq!(
+            git_commit,
+            GitCommit::from_encoded_data(&encoded_file_content).unwrap()
+        );
+    }
+
     #[test]
     fn test_to_string_no_parent() {
         let committer = mock_git_commit_author();
@@ -362,14 +1154,372 @@ mod tests {
         assert_eq!(git_commit.author, committer);
     }
 
+    #[test]
+    fn test_from_encoded_data_header_like_message() {
+        let committer = mock_git_commit_author();
+        let message = "tree of knowledge\nparent company decisions\n\ntree 1234\nmessage body";
+        let encoded_file_content = create_encoded_commit_file(
+            committer.clone(),
+            committer.clone(),
+            Some("50c8353444afbef3172c999ef6cff8d31309ac3e"),
+            vec![],
+            message,
+        )
+        .unwrap();
+
+        let git_commit = GitCommit::from_encoded_data(&encoded_file_content).unwrap();
+        assert_eq!(git_commit.get_message(), message);
+        assert_eq!(
+            git_commit.tree_hash,
+            "50c8353444afbef3172c999ef6cff8d31309ac3e"
+        );
+        assert!(git_commit.parent_hashes.is_empty());
+    }
+
+    #[test]
+    fn test_from_encoded_data_message_header() {
+        let committer = mock_git_commit_author();
+        for header in ["message old", "tree"] {
+            let file_content = format!(
+                "tree 50c8353444afbef3172c999ef6cff8d31309ac3e\n{}\n{}\n{}\n\nmessage",
+                header,
+                committer.to_string(true),
+                committer.to_string(false)
+            );
+            let file_content_to_encode =
+                format!("commit {}\x00{}\n", file_content.len() + 1, file_content);
+            let mut zlib = flate2::bufread::ZlibEncoder::new(
+                file_content_to_encode.as_bytes(),
+                flate2::Compression::default(),
+            );
+            let mut encoded_file_content = Vec::new();
+            zlib.read_to_end(&mut encoded_file_content).unwrap();
+
+            assert_eq!(
+                GitCommit::from_encoded_data(&encoded_file_content).err(),
+                Some(GitObjectError::InvalidCommitFile(
+                    CommitError::InvalidContent
+                ))
+            );
+        }
+    }
+
+    #[test]
+    fn test_from_encoded_data_ident_headers() {
+        let author = mock_git_commit_author();
+        let tree = "tree 50c8353444afbef3172c999ef6cff8d31309ac3e\n";
+        let author_line = format!("{}\n", author.to_string(true));
+        let committer_line = format!("{}\n", author.to_string(false));
+        let misspelled = committer_line.replacen("committer", "commiter", 1);
+
+        for (headers, error) in [
+            (
+                format!("{}{}", author_line, misspelled),
+                CommitError::InvalidContent,
+            ),
+            (author_line.clone(), CommitError::InvalidHeader),
+            (committer_line.clone(), CommitError::InvalidHeader),
+            (
+                format!("{}{}", committer_line, author_line),
+                CommitError::InvalidHeader,
+            ),
+            (
+                format!("{}{}{}", author_line, author_line, committer_line),
+                CommitError::InvalidHeader,
+            ),
+            (
+                format!("{}{}{}", author_line, committer_line, committer_line),
+                CommitError::InvalidHeader,
+            ),
+        ] {
+            let file_content = format!("{}{}\nmessage", tree, headers);
+            let file_content_to_encode =
+                format!("commit {}\x00{}\n", file_content.len() + 1, file_content);
+            let mut zlib = flate2::bufread::ZlibEncoder::new(
+                file_content_to_encode.as_bytes(),
+                flate2::Compression::default(),
+            );
+            let mut encoded_file_content = Vec::new();
+            zlib.read_to_end(&mut encoded_file_content).unwrap();
+
+            assert_eq!(
+                GitCommit::from_encoded_data(&encoded_file_content).err(),
+                Some(GitObjectError::InvalidCommitFile(error))
+            );
+        }
+    }
+
+    #[test]
+    fn test_from_encoded_data_size_mismatch() {
+        let committer = mock_git_commit_author();
+        let file_content = format!(
+            "tree 50c8353444afbef3172c999ef6cff8d31309ac3e\n{}\n{}\n\nsized message",
+            committer.to_string(true),
+            committer.to_string(false)
+        );
+
+        for size in [10, file_content.len(), file_content.len() + 2] {
+            let file_content_to_encode = format!("commit {}\x00{}\n", size, file_content);
+            let mut zlib = flate2::bufread::ZlibEncoder::new(
+                file_content_to_encode.as_bytes(),
+                flate2::Compression::default(),
+            );
+            let mut encoded_file_content = Vec::new();
+            zlib.read_to_end(&mut encoded_file_content).unwrap();
+
+            assert_eq!(
+                GitCommit::from_encoded_data(&encoded_file_content).err(),
+                Some(GitObjectError::SizeMismatch)
+            );
+            assert_eq!(
+                GitCommit::from_encoded_data_lenient(&encoded_file_content).err(),
+                Some(GitObjectError::SizeMismatch)
+            );
+        }
+    }
+
+    #[test]
+    fn test_from_encoded_data_large_message() {
+        let committer = mock_git_commit_author();
+        // About 3 MB, like a generated changelog
+        let changelog = (0..65_000)
+            .map(|line| format!("- change {:05}: regenerated the changelog entry\n", line))
+            .collect::<String>();
+        assert!(changelog.len() > 3_000_000);
+
+        let commit = GitCommit::new(
+            "50c8353444afbef3172c999ef6cff8d31309ac3e",
+            &[],
+            committer.clone(),
+            committer,
+            &format!("Release notes\n\n{}", changelog),
+        );
+        let encoded_data = commit.get_encoded_data().unwrap();
+        let decoded = GitCommit::from_encoded_data(&encoded_data).unwrap();
+        assert_eq!(decoded, commit);
+        assert!(decoded.get_message().ends_with(&changelog));
+
+        // The declared size still has to match however much was inflated
+        let data = commit.get_data_string();
+        let file_content_to_encode = format!("commit {}\x00{}\n", data.len() - 1, data);
+        let mut zlib = flate2::bufread::ZlibEncoder::new(
+            file_content_to_encode.as_bytes(),
+            flate2::Compression::default(),
+        );
+        let mut encoded_data = Vec::new();
+        zlib.read_to_end(&mut encoded_data).unwrap();
+        assert_eq!(
+            GitCommit::from_encoded_data(&encoded_data),
+            Err(GitObjectError::SizeMismatch)
+        );
+
+        let encoded_data = commit.get_encoded_data().unwrap();
+        assert_eq!(
+            GitCommit::from_encoded_data(&encoded_data[..encoded_data.len() / 2]),
+            Err(GitObjectError::DecompressionError)
+        );
+    }
+
+    #[test]
+    fn test_from_encoded_data_verified() {
+        let committer = mock_git_commit_author();
+        let commit = GitCommit::new(
+            "50c8353444afbef3172c999ef6cff8d31309ac3e",
+            &[],
+            committer.clone(),
+            committer,
+            "verified commit",
+        );
+        let encoded_data = commit.get_encoded_data().unwrap();
+
+        assert_eq!(
+            GitCommit::from_encoded_data_verified(
+                &encoded_data,
+                ObjectFormat::Sha1,
+                &commit.get_hash()
+            ),
+            Ok(commit.clone())
+        );
+        assert_eq!(
+            GitCommit::from_encoded_data_verified(
+                &encoded_data,
+                ObjectFormat::Sha1,
+                &commit.get_hash().to_uppercase()
+            ),
+            Ok(commit.clone())
+        );
+        assert_eq!(
+            GitCommit::from_encoded_data_verified(
+                &encoded_data,
+                ObjectFormat::Sha1,
+                "df6773ea47ed3fce3b3bb14e3d1101963e77ef08"
+            ),
+            Err(GitObjectError::HashMismatch)
+        );
+        assert_eq!(
+            GitCommit::from_encoded_data_verified(
+                &encoded_data,
+                ObjectFormat::Sha256,
+                &commit.get_hash()
+            ),
+            Err(GitObjectError::HashMismatch)
+        );
+    }
+
+    #[test]
+    fn test_from_encoded_data_gpgsig() {
+        let file_content = "tree 50c8353444afbef3172c999ef6cff8d31309ac3e\n\
+            parent df6773ea47ed3fce3b3bb14e3d1101963e77ef08\n\
+            author Test User <test@example.com> 1234567890 +0000\n\
+            committer Test User <test@example.com> 1234567890 +0000\n\
+            gpgsig -----BEGIN PGP SIGNATURE-----\n \n iQEzBAABCAAdFiEE\n =abcd\n -----END PGP SIGNATURE-----\n\
+            \n\
+            signed message\n\nwith a body";
+        let file_content_to_encode =
+            format!("commit {}\x00{}\n", file_content.len() + 1, file_content);
+        let mut zlib = flate2::bufread::ZlibEncoder::new(
+            file_content_to_encode.as_bytes(),
+            flate2::Compression::default(),
+        );
+        let mut encoded_file_content = Vec::new();
+        zlib.read_to_end(&mut encoded_file_content).unwrap();
+
+        let git_commit = GitCommit::from_encoded_data(encoded_file_content.as_slice()).unwrap();
+
+        assert_eq!(git_commit.get_signature_header(), &SignatureHeader::GpgSig);
+        assert_eq!(
+            git_commit.get_gpg_signature().unwrap(),
+            "-----BEGIN PGP SIGNATURE-----\n\niQEzBAABCAAdFiEE\n=abcd\n-----END PGP SIGNATURE-----"
+        );
+        assert_eq!(git_commit.get_message(), "signed message\n\nwith a body");
+        assert_eq!(git_commit.to_string(), file_content);
+    }
+
+    #[test]
+    fn test_from_encoded_data_lenient_mergetag() {
+        let file_content = "tree 50c8353444afbef3172c999ef6cff8d31309ac3e\n\
+            parent df6773ea47ed3fce3b3bb14e3d1101963e77ef08\n\
+            parent df6773ea47ed3fce3b3bb14e3d1101963e77ef09\n\
+            author Test User <test@example.com> 1234567890 +0000\n\
+            committer Test User <test@example.com> 1234567890 +0000\n\
+            mergetag object df6773ea47ed3fce3b3bb14e3d1101963e77ef09\n \
+            type commit\n \
+            tag v1.0\n \
+            tagger Test User <test@example.com> 1234567890 +0000\n \n \
+            Version 1.0\n\
+            x-tool branchwise\n\
+            \n\
+            Merge tag 'v1.0'";
+        let file_content_to_encode =
+            format!("commit {}\x00{}\n", file_content.len() + 1, file_content);
+        let mut zlib = flate2::bufread::ZlibEncoder::new(
+            file_content_to_encode.as_bytes(),
+            flate2::Compression::default(),
+        );
+        let mut encoded_file_content = Vec::new();
+        zlib.read_to_end(&mut encoded_file_content).unwrap();
+
+        assert_eq!(
+            GitCommit::from_encoded_data(&encoded_file_content).err(),
+            Some(GitObjectError::InvalidCommitFile(
+                CommitError::InvalidContent
+            ))
+        );
+
+        let git_commit = GitCommit::from_encoded_data_lenient(&encoded_file_content).unwrap();
+        assert_eq!(
+            git_commit.get_extra_headers(),
+            &vec![
+                (
+                    "mergetag".to_string(),
+                    "object df6773ea47ed3fce3b3bb14e3d1101963e77ef09\ntype commit\ntag v1.0\n\
+                     tagger Test User <test@example.com> 1234567890 +0000\n\nVersion 1.0"
+                        .to_string()
+                ),
+                ("x-tool".to_string(), "branchwise".to_string()),
+            ]
+        );
+        assert_eq!(git_commit.get_parent_hashes().len(), 2);
+        assert_eq!(git_commit.get_message(), "Merge tag 'v1.0'");
+        assert_eq!(git_commit.to_string(), file_content);
+        assert_eq!(
+            git_commit.get_data(),
+            format!("{}\n", file_content).into_bytes()
+        );
+    }
+
+    #[test]
+    fn test_from_encoded_data_gpgsig_sha256() {
+        let unsigned_content = "tree tree_hash\n\
+            author Test User <test@example.com> 1234567890 +0000\n\
+            committer Test User <test@example.com> 1234567890 +0000\n\
+            \n\
+            signed message";
+        let file_content = "tree tree_hash\n\
+            author Test User <test@example.com> 1234567890 +0000\n\
+            committer Test User <test@example.com> 1234567890 +0000\n\
+            gpgsig-sha256 -----BEGIN PGP SIGNATURE-----\n \n iQEzBAABCAAdFiEE\n -----END PGP SIGNATURE-----\n\
+            \n\
+            signed message";
+        let file_content_to_encode =
+            format!("commit {}\x00{}\n", file_content.len() + 1, file_content);
+        let mut zlib = flate2::bufread::ZlibEncoder::new(
+            file_content_to_encode.as_bytes(),
+            flate2::Compression::default(),
+        );
+        let mut encoded_file_content = Vec::new();
+        zlib.read_to_end(&mut encoded_file_content).unwrap();
+
+        let git_commit = GitCommit::from_encoded_data(encoded_file_content.as_slice()).unwrap();
+
+        assert_eq!(
+            git_commit.get_signature_header(),
+            &SignatureHeader::GpgSigSha256
+        );
+        assert_eq!(
+            git_commit.get_gpg_signature().unwrap(),
+            "-----BEGIN PGP SIGNATURE-----\n\niQEzBAABCAAdFiEE\n-----END PGP SIGNATURE-----"
+        );
+        assert_eq!(git_commit.get_message(), "signed message");
+        assert_eq!(git_commit.to_string(), file_content);
+        assert_eq!(git_commit.signing_payload(), unsigned_content.as_bytes());
+    }
+
+    #[test]
+    fn test_git_commit_from_encoded_data_empty() {
+        assert_eq!(
+            GitCommit::from_encoded_data(&[]).err(),
+            Some(GitObjectError::EmptyObject)
+        );
+        assert_eq!(
+            GitCommit::from_encoded_data(&[0x00, 0x01]).err(),
+            Some(GitObjectError::DecompressionError)
+        );
+    }
+
     #[test]
     fn test_serialize_git_commit() {
         let git_commit = mock_git_commit();
         let serialized = serde_json::to_string(&git_commit).unwrap();
-        let expected = r#"{"tree_hash":"tree_hash","parent_hashes":["parent_hash1","parent_hash2"],"author":{"user":{"name":"Test User","email":"test@example.com"},"date_seconds":1234567890,"timezone":"+0000"},"committer":{"user":{"name":"Test User","email":"test@example.com"},"date_seconds":1234567890,"timezone":"+0000"},"message":"commit message"}"#;
+        let expected = r#"{"tree_hash":"tree_hash","parent_hashes":["parent_hash1","parent_hash2"],"author":{"user":{"name":"Test User","email":"test@example.com"},"date_seconds":1234567890,"timezone":"+0000"},"committer":{"user":{"name":"Test User","email":"test@example.com"},"date_seconds":1234567890,"timezone":"+0000"},"message":"commit message","message_lossy":false,"gpg_signature":null,"signature_header":"GpgSig"}"#;
         assert_eq!(serialized, expected);
     }
 
+    #[test]
+    fn test_serialize_git_commit_with_encoding() {
+        let mut git_commit = mock_git_commit();
+        git_commit.encoding = Some("ISO-8859-1".to_string());
+        git_commit.raw_data = Some(b"stored bytes".to_vec());
+
+        let serialized = serde_json::to_string(&git_commit).unwrap();
+        assert!(serialized.contains(r#""encoding":"ISO-8859-1""#));
+        assert!(!serialized.contains("raw_data"));
+        assert_eq!(
+            serde_json::from_str::<GitCommit>(&serialized).unwrap(),
+            git_commit
+        );
+    }
+
     #[test]
     fn test_deserialize_git_commit() {
         let json_str = r#"{"tree_hash":"tree_hash","parent_hashes":["parent_hash1","parent_hash2"],"author":{"user":{"name":"Test User","email":"test@example.com"},"date_seconds":1234567890,"timezone":"+0000"},"committer":{"user":{"name":"Test User","email":"test@example.com"},"date_seconds":1234567890,"timezone":"+0000"},"message":"commit message"}"#;
@@ -412,4 +1562,105 @@ mod tests {
         assert_eq!(git_commit.committer.get_user().email, email);
         assert_eq!(git_commit.message, message);
     }
+
+    #[test]
+    fn test_is_merge_and_is_root() {
+        let git_user = GitUser::new("name".to_string(), "email".to_string());
+        let author = GitCommitAuthor::new(git_user, 1, "+0000".to_string());
+        let commit_with_parents = |parents: &[String]| {
+            GitCommit::new(
+                "tree_hash",
+                parents,
+                author.clone(),
+                author.clone(),
+                "message",
+            )
+        };
+
+        let root = commit_with_parents(&[]);
+        assert!(root.is_root());
+        assert!(!root.is_merge());
+
+        let normal = commit_with_parents(&["parent".to_string()]);
+        assert!(!normal.is_root());
+        assert!(!normal.is_merge());
+
+        let merge = commit_with_parents(&["parent1".to_string(), "parent2".to_string()]);
+        assert!(!merge.is_root());
+        assert!(merge.is_merge());
+    }
+
+    #[test]
+    fn test_revert_target() {
+        let author = mock_git_commit_author();
+        let commit_with_message = |message: &str| {
+            GitCommit::new("tree_hash", &[], author.clone(), author.clone(), message)
+        };
+
+        let revert = commit_with_message(
+            "Revert \"Add the thing\"\n\nThis reverts commit df6773ea47ed3fce3b3bb14e3d1101963e77ef08.\n",
+        );
+        assert_eq!(
+            revert.revert_target(),
+            Some("df6773ea47ed3fce3b3bb14e3d1101963e77ef08".to_string())
+        );
+
+        let merge_revert = commit_with_message(
+            "Revert \"Merge branch 'feature'\"\n\nThis reverts commit df6773ea47ed3fce3b3bb14e3d1101963e77ef08, reversing\nchanges made to 50c8353444afbef3172c999ef6cff8d31309ac3e.\n",
+        );
+        assert_eq!(
+            merge_revert.revert_target(),
+            Some("df6773ea47ed3fce3b3bb14e3d1101963e77ef08".to_string())
+        );
+
+        assert_eq!(commit_with_message("Add the thing\n").revert_target(), None);
+        assert_eq!(
+            commit_with_message("This reverts commit abc.").revert_target(),
+            None
+        );
+    }
+
+    #[test]
+    fn test_from_encoded_data_headers_only() {
+        let commit = mock_git_commit();
+        let encoded_data = commit.get_encoded_data().unwrap();
+
+        let headers_only = GitCommit::from_encoded_data_headers_only(&encoded_data).unwrap();
+        assert_eq!(headers_only.get_tree_hash(), commit.get_tree_hash());
+        assert_eq!(headers_only.get_parent_hashes(), commit.get_parent_hashes());
+        assert_eq!(headers_only.get_author(), commit.get_author());
+        assert_eq!(headers_only.get_committer(), commit.get_committer());
+        assert_eq!(headers_only.get_message(), "");
+    }
+
+    #[test]
+    fn test_subject_and_body() {
+        let with_message = |message: &str| {
+            let author = mock_git_commit_author();
+            GitCommit::new("tree_hash", &[], author.clone(), author, message)
+        };
+
+        let commit = with_message("Single line\n");
+        assert_eq!(commit.subject(), "Single line");
+        assert_eq!(commit.body(), None);
+
+        let commit = with_message("Subject\n\nFirst paragraph\nwrapped\n\nSecond paragraph\n");
+        assert_eq!(commit.subject(), "Subject");
+        assert_eq!(
+            commit.body(),
+            Some("First paragraph\nwrapped\n\nSecond paragraph")
+        );
+
+        let commit = with_message("\nSubject  \t\n\n  Body  \n\n\n");
+        assert_eq!(commit.subject(), "Subject");
+        assert_eq!(commit.body(), Some("Body"));
+
+        let commit = with_message("Subject\r\n\r\nBody\r\nmore\r\n");
+        assert_eq!(commit.subject(), "Subject");
+        assert_eq!(commit.body(), Some("Body\r\nmore"));
+
+        let commit = with_message("Subject\n \n\t\n");
+        assert_eq!(commit.body(), None);
+        assert_eq!(with_message("").subject(), "");
+    }
 }
@@ -1,3 +1,4 @@
+use chrono::{DateTime, FixedOffset, Local, Offset, TimeZone};
 use serde::{Deserialize, Serialize};
 
 use crate::errors::git_object_error::{CommitError, GitObjectError};
@@ -9,6 +10,8 @@ pub struct GitCommitAuthor {
     user: GitUser,
     pub date_seconds: i64,
     pub timezone: String,
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    email_missing: bool,
 }
 
 impl GitCommitAuthor {
@@ -17,6 +20,7 @@ impl GitCommitAuthor {
             user,
             date_seconds,
             timezone,
+            email_missing: false,
         }
     }
 
@@ -24,6 +28,26 @@ impl GitCommitAuthor {
         &self.user
     }
 
+    // Set for idents written without the `<email>` part, which only lenient parsing accepts
+    pub fn is_email_missing(&self) -> bool {
+        self.email_missing
+    }
+
+    // The date in the timezone it was recorded in, "+0200" is 7200 seconds east of UTC
+    pub fn get_datetime(&self) -> Result<DateTime<FixedOffset>, GitObjectError> {
+        let offset = parse_timezone_offset(&self.timezone)
+            .and_then(|offset_seconds| FixedOffset::east_opt(i32::try_from(offset_seconds).ok()?))
+            .ok_or(GitObjectError::InvalidCommitFile(
+                CommitError::InvalidAuthor,
+            ))?;
+
+        DateTime::from_timestamp(self.date_seconds, 0)
+            .map(|datetime| datetime.with_timezone(&offset))
+            .ok_or(GitObjectError::InvalidCommitFile(
+                CommitError::InvalidAuthor,
+            ))
+    }
+
     pub fn from_string(author_line: &str) -> Result<GitCommitAuthor, GitObjectError> {
         let (name, rest_line) =
             author_line
@@ -53,15 +77,90 @@ impl GitCommitAuthor {
         ))
     }
 
+    // Some old tools wrote `Name 123 +0000`, everything before the date becomes the name
+    pub fn from_string_lenient(author_line: &str) -> Result<GitCommitAuthor, GitObjectError> {
+        if author_line.contains('<') {
+            return Self::from_string(author_line);
+        }
+
+        let mut parts = author_line.rsplitn(3, ' ');
+        let (Some(timezone), Some(date_seconds), Some(name)) =
+            (parts.next(), parts.next(), parts.next())
+        else {
+            return Err(GitObjectError::InvalidCommitFile(
+                CommitError::InvalidAuthor,
+            ));
+        };
+
+        let mut author = GitCommitAuthor::new(
+            GitUser::new(name.to_string(), String::new()),
+            date_seconds
+                .parse()
+                .map_err(|_| GitObjectError::InvalidCommitFile(CommitError::InvalidAuthor))?,
+            timezone.to_string(),
+        );
+        author.email_missing = true;
+
+        Ok(author)
+    }
+
     pub fn to_string(&self, author: bool) -> String {
-        let author_or_commiter = if author { "author" } else { "committer" };
+        let author_or_committer = if author { "author" } else { "committer" };
+        // Written back the way it was read, so the commit keeps its hash
+        if self.email_missing {
+            return format!(
+                "{} {} {} {}",
+                author_or_committer, self.user.name, self.date_seconds, self.timezone
+            );
+        }
+
         format!(
             "{} {} <{}> {} {}",
-            author_or_commiter, self.user.name, self.user.email, self.date_seconds, self.timezone
+            author_or_committer, self.user.name, self.user.email, self.date_seconds, self.timezone
         )
     }
 }
 
+// The offset in seconds of a git timezone like "+0130" or "-0500", which is always a sign
+// followed by two digits of hours and two of minutes
+pub fn parse_timezone_offset(timezone: &str) -> Option<i64> {
+    let (sign, digits) = match timezone.split_at_checked(1)? {
+        ("+", digits) => (1, digits),
+        ("-", digits) => (-1, digits),
+        _ => return None,
+    };
+    if digits.len() != 4 || !digits.chars().all(|c| c.is_ascii_digit()) {
+        return None;
+    }
+
+    let hours = digits[..2].parse::<i64>().ok()?;
+    let minutes = digits[2..].parse::<i64>().ok()?;
+    if minutes > 59 {
+        return None;
+    }
+    Some(sign * (hours * 60 + minutes) * 60)
+}
+
+// The git timezone for an offset in seconds, the inverse of `parse_timezone_offset`
+pub fn format_timezone_offset(offset: i64) -> String {
+    let sign = if offset < 0 { '-' } else { '+' };
+    let minutes = offset.unsigned_abs() / 60;
+
+    format!("{}{:02}{:02}", sign, minutes / 60, minutes % 60)
+}
+
+// The timezone of this machine at `timestamp`, which can differ through the year with
+// daylight saving time
+pub fn local_timezone(timestamp: i64) -> String {
+    let offset = Local
+        .timestamp_opt(timestamp, 0)
+        .single()
+        .map(|date| date.offset().fix().local_minus_utc())
+        .unwrap_or_default();
+
+    format_timezone_offset(offset as i64)
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -87,7 +186,7 @@ mod tests {
     }
 
     #[test]
-    fn test_from_string_commiter() {
+    fn test_from_string_committer() {
         let git_commit_author =
             GitCommitAuthor::from_string("name name <email> 1 timezone").unwrap();
         assert_eq!(git_commit_author.get_user().name, "name name".to_string());
@@ -107,7 +206,7 @@ mod tests {
     }
 
     #[test]
-    fn test_from_to_string_commiter() {
+    fn test_from_to_string_committer() {
         let git_commit_author =
             GitCommitAuthor::from_string("name name <email> 1 timezone").unwrap();
         assert_eq!(
@@ -125,6 +224,86 @@ mod tests {
         );
     }
 
+    #[test]
+    fn test_from_string_without_email() {
+        assert_eq!(
+            GitCommitAuthor::from_string("name name 1 timezone"),
+            Err(GitObjectError::InvalidCommitFile(
+                CommitError::InvalidAuthor
+            ))
+        );
+
+        let git_commit_author =
+            GitCommitAuthor::from_string_lenient("name name 1 timezone").unwrap();
+        assert!(git_commit_author.is_email_missing());
+        assert_eq!(git_commit_author.get_user().name, "name name".to_string());
+        assert_eq!(git_commit_author.get_user().email, String::new());
+        assert_eq!(git_commit_author.date_seconds, 1);
+        assert_eq!(git_commit_author.timezone, "timezone".to_string());
+        assert_eq!(
+            git_commit_author.to_string(true),
+            "author name name 1 timezone".to_string()
+        );
+
+        let git_commit_author =
+            GitCommitAuthor::from_string_lenient("name name <email> 1 timezone").unwrap();
+        assert!(!git_commit_author.is_email_missing());
+        assert_eq!(git_commit_author.get_user().email, "email".to_string());
+    }
+
+    #[test]
+    fn test_parse_timezone_offset() {
+        assert_eq!(parse_timezone_offset("+0000"), Some(0));
+        assert_eq!(parse_timezone_offset("+0130"), Some(5400));
+        assert_eq!(parse_timezone_offset("-0500"), Some(-18000));
+        assert_eq!(parse_timezone_offset("timezone"), None);
+        assert_eq!(parse_timezone_offset("+0199"), None);
+        assert_eq!(parse_timezone_offset("-0060"), None);
+    }
+
+    #[test]
+    fn test_format_timezone_offset() {
+        assert_eq!(format_timezone_offset(0), "+0000");
+        assert_eq!(format_timezone_offset(5400), "+0130");
+        assert_eq!(format_timezone_offset(-18000), "-0500");
+        assert_eq!(format_timezone_offset(-1800), "-0030");
+
+        let timezone = local_timezone(1_700_000_000);
+        assert_eq!(
+            parse_timezone_offset(&timezone).map(format_timezone_offset),
+            Some(timezone)
+        );
+    }
+
+    #[test]
+    fn test_get_datetime() {
+        let git_user = GitUser::new("name".to_string(), "email".to_string());
+
+        let datetime = GitCommitAuthor::new(git_user.clone(), 1700000000, "+0200".to_string())
+            .get_datetime()
+            .unwrap();
+        assert_eq!(datetime.timestamp(), 1700000000);
+        assert_eq!(datetime.offset().local_minus_utc(), 7200);
+        assert_eq!(datetime.to_rfc3339(), "2023-11-15T00:13:20+02:00");
+
+        let datetime = GitCommitAuthor::new(git_user.clone(), 1700000000, "-0530".to_string())
+            .get_datetime()
+            .unwrap();
+        assert_eq!(datetime.offset().local_minus_utc(), -19800);
+        assert_eq!(datetime.to_rfc3339(), "2023-11-14T16:43:20-05:30");
+
+        for timezone in [
+            "timezone", "0200", "+200", "+02:00", "+0x00", "+0199", "+9900", "",
+        ] {
+            assert_eq!(
+                GitCommitAuthor::new(git_user.clone(), 1, timezone.to_string()).get_datetime(),
+                Err(GitObjectError::InvalidCommitFile(
+                    CommitError::InvalidAuthor
+                ))
+            );
+        }
+    }
+
     #[test]
     fn test_serialize() {
         let git_user = GitUser::new("name".to_string(), "email".to_string());
@@ -0,0 +1,62 @@
+use serde::{Deserialize, Serialize};
+
+use super::{
+    git_commit::GitCommit,
+    git_diff::{DiffStat, FileStat},
+    git_project::GitProject,
+    object::GitObject,
+};
+use crate::errors::git_object_error::GitObjectError;
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct CommitDetail {
+    hash: String,
+    commit: GitCommit,
+    parent_subjects: Vec<String>,
+    stat: DiffStat,
+    files: Vec<FileStat>,
+}
+
+impl CommitDetail {
+    // Everything the commit detail view shows, so the frontend needs a single request
+    pub fn read(project: &GitProject, hash: &str) -> Result<Self, GitObjectError> {
+        let commit = GitCommit::from_hash(project, hash)?;
+        let parent_subjects = commit.parent_subjects(project)?;
+
+        let files = commit
+            .changes(project)?
+            .iter()
+            .map(|change| FileStat::from_change(project, change))
+            .collect::<Result<Vec<FileStat>, GitObjectError>>()?;
+        let stat = DiffStat::from_files(&files);
+
+        Ok(Self {
+            hash: hash.to_string(),
+            commit,
+            parent_subjects,
+            stat,
+            files,
+        })
+    }
+
+    pub fn get_hash(&self) -> &String {
+        &self.hash
+    }
+
+    pub fn get_commit(&self) -> &GitCommit {
+        &self.commit
+    }
+
+    pub fn get_parent_subjects(&self) -> &Vec<String> {
+        &self.parent_subjects
+    }
+
+    pub fn get_stat(&self) -> &DiffStat {
+        &self.stat
+    }
+
+    pub fn get_files(&self) -> &Vec<FileStat> {
+        &self.files
+    }
+}
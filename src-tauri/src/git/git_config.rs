@@ -0,0 +1,346 @@
+use std::{env, fs, path::PathBuf};
+
+use super::{git_files::GitFilesRequired, git_project::GitProject, git_user::GitUser};
+
+const COMMENT_PREFIXES: [char; 2] = ['#', ';'];
+const WORKTREE_CONFIG_FILE: &str = "config.worktree";
+const GLOBAL_CONFIG_FILE: &str = ".gitconfig";
+const XDG_CONFIG_FILE: &str = "git/config";
+
+#[derive(Debug, Clone, PartialEq)]
+struct ConfigEntry {
+    section: String,
+    subsection: Option<String>,
+    key: String,
+    value: String,
+}
+
+// The entries of a config file in the order they were written, section and key names
+// are kept lowercase since git compares them without case
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct GitConfig {
+    entries: Vec<ConfigEntry>,
+}
+
+impl GitConfig {
+    // Only the repository config is read, a missing one is the same as an empty one. With
+    // `extensions.worktreeConfig` the `config.worktree` of the current worktree comes
+    // after it, so its values win
+    pub fn read(project: &GitProject) -> Self {
+        let read_file = |path: std::path::PathBuf| {
+            fs::read_to_string(path)
+                .map(|config| Self::parse(&config))
+                .unwrap_or_default()
+        };
+
+        let mut config = read_file(
+            project
+                .common_dir()
+                .join(GitFilesRequired::CONFIG.to_string()),
+        );
+        if config.get_bool("extensions", None, "worktreeconfig") == Some(true) {
+            let worktree_config = read_file(project.git_dir().join(WORKTREE_CONFIG_FILE));
+            config.entries.extend(worktree_config.entries);
+        }
+
+        config
+    }
+
+    // The config of the user: `$XDG_CONFIG_HOME/git/config` (`~/.config/git/config` by
+    // default) and then `~/.gitconfig`, whose values win. `GIT_CONFIG_GLOBAL` names a
+    // single file to read instead
+    pub fn read_global() -> Self {
+        let paths = match env::var_os("GIT_CONFIG_GLOBAL") {
+            Some(path) => vec![PathBuf::from(path)],
+            None => {
+                let home = env::var_os("HOME")
+                    .or_else(|| env::var_os("USERPROFILE"))
+                    .map(PathBuf::from);
+                let xdg_config = env::var_os("XDG_CONFIG_HOME")
+                    .filter(|path| !path.is_empty())
+                    .map(PathBuf::from)
+                    .or_else(|| home.as_ref().map(|home| home.join(".config")));
+
+                xdg_config
+                    .map(|config| config.join(XDG_CONFIG_FILE))
+                    .into_iter()
+                    .chain(home.map(|home| home.join(GLOBAL_CONFIG_FILE)))
+                    .collect()
+            }
+        };
+
+        let mut config = Self::default();
+        for path in paths {
+            if let Ok(file) = fs::read_to_string(path) {
+                config = config.merged(Self::parse(&file));
+            }
+        }
+
+        config
+    }
+
+    // The entries of `other` come last, so its values win
+    pub fn merged(mut self, other: GitConfig) -> Self {
+        self.entries.extend(other.entries);
+        self
+    }
+
+    pub fn parse(config: &str) -> Self {
+        let mut entries = Vec::new();
+        let mut section = String::new();
+        let mut subsection = None;
+
+        let mut lines = config.lines();
+        while let Some(line) = lines.next() {
+            let mut line = line.trim_start();
+
+            // A header can be followed by an entry on the same line
+            if let Some(header) = line.strip_prefix('[') {
+                match parse_section_header(header) {
+                    Some((name, sub, rest)) => {
+                        section = name;
+                        subsection = sub;
+                        line = rest.trim_start();
+                    }
+                    None => {
+                        section = String::new();
+                        subsection = None;
+                        continue;
+                    }
+                }
+            }
+
+            if line.is_empty() || line.starts_with(COMMENT_PREFIXES) {
+                continue;
+            }
+
+            let (key, value) = match line.split_once('=') {
+                Some((key, value)) => (key, parse_value(value, &mut lines)),
+                // A key without a value is a boolean set to true
+                None => (
+                    line.split(COMMENT_PREFIXES).next().unwrap_or_default(),
+                    "true".to_string(),
+                ),
+            };
+
+            entries.push(ConfigEntry {
+                section: section.clone(),
+                subsection: subsection.clone(),
+                key: key.trim().to_lowercase(),
+                value,
+            });
+        }
+
+        Self { entries }
+    }
+
+    // The last value wins, like in git. Subsections are compared with their case
+    pub fn get(&self, section: &str, subsection: Option<&str>, key: &str) -> Option<&String> {
+        self.entries
+            .iter()
+            .rev()
+            .find(|entry| {
+                entry.section.eq_ignore_ascii_case(section)
+                    && entry.subsection.as_deref() == subsection
+                    && entry.key.eq_ignore_ascii_case(key)
+            })
+            .map(|entry| &entry.value)
+    }
+
+    // git booleans can be spelled in a few ways, anything else isn't a boolean
+    pub fn get_bool(&self, section: &str, subsection: Option<&str>, key: &str) -> Option<bool> {
+        match self.get(section, subsection, key)?.to_lowercase().as_str() {
+            "true" | "yes" | "on" | "1" => Some(true),
+            "false" | "no" | "off" | "0" | "" => Some(false),
+            _ => None,
+        }
+    }
+
+    pub fn user(&self) -> Option<GitUser> {
+        Some(GitUser::new(
+            self.get("user", None, "name")?.clone(),
+            self.get("user", None, "email")?.clone(),
+        ))
+    }
+}
+
+// The identity new commits would be made with, when both `user.name` and `user.email`
+// are set. They can come from the global config, the repository config overrides it
+pub fn get_user(project: &GitProject) -> Option<GitUser> {
+    GitConfig::read_global()
+        .merged(GitConfig::read(project))
+        .user()
+}
+
+// Parses what follows the `[` of `[section]`, `[section "subsection"]` or the old
+// `[section.subsection]`, returning the rest of the line after the `]`
+fn parse_section_header(header: &str) -> Option<(String, Option<String>, &str)> {
+    let name_end = header.find([' ', '\t', ']'])?;
+    let name = header[..name_end].to_lowercase();
+    let rest = header[name_end..].trim_start();
+
+    if let Some(rest) = rest.strip_prefix(']') {
+        return Some(match name.split_once('.') {
+            Some((name, subsection)) => (name.to_string(), Some(subsection.to_string()), rest),
+            None => (name, None, rest),
+        });
+    }
+
+    let quoted = rest.strip_prefix('"')?;
+    let mut subsection = String::new();
+    let mut chars = quoted.char_indices();
+    while let Some((position, c)) = chars.next() {
+        match c {
+            '\\' => subsection.extend(chars.next().map(|(_, c)| c)),
+            '"' => {
+                return quoted[position + 1..]
+                    .trim_start()
+                    .strip_prefix(']')
+                    .map(|rest| (name, Some(subsection), rest));
+            }
+            c => subsection.push(c),
+        }
+    }
+
+    None
+}
+
+// Whitespace around the value is dropped while the one inside is kept, unless it is
+// quoted. A comment outside of quotes ends the value and a trailing `\` continues it on
+// the next line
+fn parse_value<'a>(value: &'a str, lines: &mut impl Iterator<Item = &'a str>) -> String {
+    let mut parsed = String::new();
+    let mut spaces = 0;
+    let mut in_quotes = false;
+
+    let mut chars = value.chars();
+    while let Some(c) = chars.next() {
+        if !in_quotes && c.is_whitespace() {
+            if !parsed.is_empty() {
+                spaces += 1;
+            }
+            continue;
+        }
+        if !in_quotes && COMMENT_PREFIXES.contains(&c) {
+            break;
+        }
+
+        parsed.extend(std::iter::repeat_n(' ', spaces));
+        spaces = 0;
+
+        match c {
+            '"' => in_quotes = !in_quotes,
+            '\\' => match chars.next() {
+                None => {
+                    if let Some(next_line) = lines.next() {
+                        chars = next_line.chars();
+                    }
+                }
+                Some('n') => parsed.push('\n'),
+                Some('t') => parsed.push('\t'),
+                Some('b') => parsed.push('\u{8}'),
+                Some(c) => parsed.push(c),
+            },
+            c => parsed.push(c),
+        }
+    }
+
+    parsed
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const CONFIG: &str = "# written by hand\n\
+        [core]\n\
+        \trepositoryformatversion = 0\n\
+        \tbare = false ; not a bare repository\n\
+        \tlogallrefupdates\n\
+        [User]\n\
+        \tName = \"Test  User\" # with two spaces\n\
+        \temail = test.user@email.com\n\
+        [remote \"origin\"]\n\
+        \turl = https://example.com/repo.git\n\
+        \tfetch = +refs/heads/*:refs/remotes/origin/*\n\
+        [branch \"Main\"] remote = origin\n\
+        [branch.topic]\n\
+        \tdescription = first line\\n\\\n\
+        second \\\"line\\\"\n\
+        [user]\n\
+        \temail = other@email.com\n";
+
+    #[test]
+    fn test_parse() {
+        let config = GitConfig::parse(CONFIG);
+
+        assert_eq!(config.get("core", None, "bare"), Some(&"false".to_string()));
+        assert_eq!(
+            config.get("core", None, "logAllRefUpdates"),
+            Some(&"true".to_string())
+        );
+        assert_eq!(
+            config.get("user", None, "name"),
+            Some(&"Test  User".to_string())
+        );
+        assert_eq!(
+            config.get("USER", None, "email"),
+            Some(&"other@email.com".to_string())
+        );
+        assert_eq!(
+            config.get("remote", Some("origin"), "url"),
+            Some(&"https://example.com/repo.git".to_string())
+        );
+        assert_eq!(
+            config.get("branch", Some("Main"), "remote"),
+            Some(&"origin".to_string())
+        );
+        assert_eq!(config.get("branch", Some("main"), "remote"), None);
+        assert_eq!(
+            config.get("branch", Some("topic"), "description"),
+            Some(&"first line\nsecond \"line\"".to_string())
+        );
+        assert_eq!(config.get("remote", None, "url"), None);
+        assert_eq!(config.get("core", None, "missing"), None);
+    }
+
+    #[test]
+    fn test_get_bool() {
+        let config = GitConfig::parse(
+            "[core]\n\tbare = No\n\tfilemode\n\tignorecase = 1\n\tsymlinks = maybe\n",
+        );
+
+        assert_eq!(config.get_bool("core", None, "bare"), Some(false));
+        assert_eq!(config.get_bool("core", None, "filemode"), Some(true));
+        assert_eq!(config.get_bool("core", None, "ignorecase"), Some(true));
+        assert_eq!(config.get_bool("core", None, "symlinks"), None);
+        assert_eq!(config.get_bool("core", None, "missing"), None);
+    }
+
+    #[test]
+    fn test_parse_invalid_header() {
+        let config = GitConfig::parse("[user\n\tname = Lost\n[user \"open]\n\temail = lost\n");
+
+        assert_eq!(config.get("user", None, "name"), None);
+        assert_eq!(config.get("user", None, "email"), None);
+    }
+
+    #[test]
+    fn test_merged_user() {
+        let global = GitConfig::parse("[user]\n\tname = Global\n\temail = global@email.com\n");
+        let local = GitConfig::parse("[user]\n\temail = local@email.com\n");
+
+        assert_eq!(local.user(), None);
+        assert_eq!(
+            global.clone().merged(local).user(),
+            Some(GitUser::new(
+                "Global".to_string(),
+                "local@email.com".to_string()
+            ))
+        );
+        assert_eq!(
+            global.clone().merged(GitConfig::default()).user(),
+            global.user()
+        );
+    }
+}
@@ -0,0 +1,1089 @@
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, BTreeSet, HashMap, HashSet};
+
+use super::{
+    git_blob::GitBlob,
+    git_index::GitIndex,
+    git_project::GitProject,
+    git_tree::{GitTree, GitTreeEntry, GitTreeMode},
+    object::GitObject,
+};
+use crate::errors::git_object_error::GitObjectError;
+
+// The fewest steps a line diff searches for the shortest edit script before settling
+// for a longer one
+const MIN_DIFF_COST: isize = 256;
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub enum ChangeKind {
+    Added,
+    Modified,
+    Deleted,
+    SubmoduleUpdated,
+    Renamed,
+    Unmerged,
+}
+
+// A change to one path. A renamed entry kept its content and mode, any other move is a
+// deletion and an addition
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all_fields = "camelCase")]
+pub enum TreeChange {
+    Added {
+        path: String,
+        mode: GitTreeMode,
+        hash: String,
+    },
+    Deleted {
+        path: String,
+        mode: GitTreeMode,
+        hash: String,
+    },
+    Modified {
+        path: String,
+        old_mode: GitTreeMode,
+        new_mode: GitTreeMode,
+        old_hash: String,
+        new_hash: String,
+    },
+    Renamed {
+        old_path: String,
+        path: String,
+        mode: GitTreeMode,
+        hash: String,
+    },
+    // A gitlink that now pins another commit of the submodule, `old` and `new` are the
+    // two commits
+    SubmoduleChange {
+        path: String,
+        old: String,
+        new: String,
+    },
+    // A path with a merge conflict, whose stages are not compared with anything
+    Unmerged {
+        path: String,
+    },
+}
+
+impl TreeChange {
+    fn added(path: String, (mode, hash): (&GitTreeMode, &String)) -> Self {
+        Self::Added {
+            path,
+            mode: mode.clone(),
+            hash: hash.clone(),
+        }
+    }
+
+    fn deleted(path: String, (mode, hash): (&GitTreeMode, &String)) -> Self {
+        Self::Deleted {
+            path,
+            mode: mode.clone(),
+            hash: hash.clone(),
+        }
+    }
+
+    fn modified(
+        path: String,
+        (old_mode, old_hash): (&GitTreeMode, &String),
+        (new_mode, new_hash): (&GitTreeMode, &String),
+    ) -> Self {
+        if *old_mode == GitTreeMode::Submodule && *new_mode == GitTreeMode::Submodule {
+            return Self::SubmoduleChange {
+                path,
+                old: old_hash.clone(),
+                new: new_hash.clone(),
+            };
+        }
+
+        Self::Modified {
+            path,
+            old_mode: old_mode.clone(),
+            new_mode: new_mode.clone(),
+            old_hash: old_hash.clone(),
+            new_hash: new_hash.clone(),
+        }
+    }
+
+    // The path after the change, a rename is reported under its new name
+    pub fn get_path(&self) -> &String {
+        match self {
+            Self::Added { path, .. }
+            | Self::Deleted { path, .. }
+            | Self::Modified { path, .. }
+            | Self::Renamed { path, .. }
+            | Self::SubmoduleChange { path, .. }
+            | Self::Unmerged { path } => path,
+        }
+    }
+
+    pub fn get_kind(&self) -> &ChangeKind {
+        match self {
+            Self::Added { .. } => &ChangeKind::Added,
+            Self::Deleted { .. } => &ChangeKind::Deleted,
+            Self::Modified { .. } => &ChangeKind::Modified,
+            Self::SubmoduleChange { .. } => &ChangeKind::SubmoduleUpdated,
+            Self::Renamed { .. } => &ChangeKind::Renamed,
+            Self::Unmerged { .. } => &ChangeKind::Unmerged,
+        }
+    }
+
+    pub fn get_old_hash(&self) -> Option<&String> {
+        self.old_side().map(|(_, hash)| hash)
+    }
+
+    pub fn get_new_hash(&self) -> Option<&String> {
+        self.new_side().map(|(_, hash)| hash)
+    }
+
+    pub fn get_old_mode(&self) -> Option<&GitTreeMode> {
+        self.old_side().map(|(mode, _)| mode)
+    }
+
+    pub fn get_new_mode(&self) -> Option<&GitTreeMode> {
+        self.new_side().map(|(mode, _)| mode)
+    }
+
+    fn old_side(&self) -> Option<(&GitTreeMode, &String)> {
+        match self {
+            Self::Deleted { mode, hash, .. } | Self::Renamed { mode, hash, .. } => {
+                Some((mode, hash))
+            }
+            Self::Modified {
+                old_mode, old_hash, ..
+            } => Some((old_mode, old_hash)),
+            Self::SubmoduleChange { old, .. } => Some((&GitTreeMode::Submodule, old)),
+            Self::Added { .. } | Self::Unmerged { .. } => None,
+        }
+    }
+
+    fn new_side(&self) -> Option<(&GitTreeMode, &String)> {
+        match self {
+            Self::Added { mode, hash, .. } | Self::Renamed { mode, hash, .. } => Some((mode, hash)),
+            Self::Modified {
+                new_mode, new_hash, ..
+            } => Some((new_mode, new_hash)),
+            Self::SubmoduleChange { new, .. } => Some((&GitTreeMode::Submodule, new)),
+            Self::Deleted { .. } | Self::Unmerged { .. } => None,
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct FileStat {
+    path: String,
+    kind: ChangeKind,
+    insertions: usize,
+    deletions: usize,
+    binary: bool,
+}
+
+impl FileStat {
+    // Binary files are reported without line counts, like `git diff --stat`
+    pub fn from_change(project: &GitProject, change: &TreeChange) -> Result<Self, GitObjectError> {
+        let old = read_side(project, change.old_side())?;
+        let new = read_side(project, change.new_side())?;
+        let binary = old.is_binary() || new.is_binary();
+        let (insertions, deletions) = blob_line_changes(&old, &new);
+
+        Ok(Self {
+            path: change.get_path().clone(),
+            kind: change.get_kind().clone(),
+            insertions,
+            deletions,
+            binary,
+        })
+    }
+
+    pub fn get_path(&self) -> &String {
+        &self.path
+    }
+
+    pub fn get_kind(&self) -> &ChangeKind {
+        &self.kind
+    }
+
+    pub fn get_insertions(&self) -> usize {
+        self.insertions
+    }
+
+    pub fn get_deletions(&self) -> usize {
+        self.deletions
+    }
+
+    pub fn is_binary(&self) -> bool {
+        self.binary
+    }
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct DiffStat {
+    files_changed: usize,
+    insertions: usize,
+    deletions: usize,
+}
+
+impl DiffStat {
+    pub fn from_files(files: &[FileStat]) -> Self {
+        Self {
+            files_changed: files.len(),
+            insertions: files.iter().map(|file| file.insertions).sum(),
+            deletions: files.iter().map(|file| file.deletions).sum(),
+        }
+    }
+
+    pub fn get_files_changed(&self) -> usize {
+        self.files_changed
+    }
+
+    pub fn get_insertions(&self) -> usize {
+        self.insertions
+    }
+
+    pub fn get_deletions(&self) -> usize {
+        self.deletions
+    }
+}
+
+pub fn diff_trees(
+    project: &GitProject,
+    old: &GitTree,
+    new: &GitTree,
+) -> Result<Vec<TreeChange>, GitObjectError> {
+    let mut changes = Vec::new();
+    diff_entries(project, old, new, "", &mut changes)?;
+
+    // The walk follows tree order, where `dir/file` comes before `dir.txt`, so the
+    // result is sorted by the full path instead
+    changes.sort_by(|a, b| {
+        a.get_path()
+            .cmp(b.get_path())
+            .then_with(|| kind_order(a.get_kind()).cmp(&kind_order(b.get_kind())))
+    });
+
+    Ok(detect_renames(changes))
+}
+
+// The inserted and deleted line counts of a change between two blobs, for when the hunks
+// themselves aren't shown. Binary blobs have no lines and count as (0, 0)
+pub fn diff_blob_summary(
+    project: &GitProject,
+    old_hash: &str,
+    new_hash: &str,
+) -> Result<(usize, usize), GitObjectError> {
+    let old = read_blob(project, Some(old_hash))?;
+    let new = read_blob(project, Some(new_hash))?;
+
+    Ok(blob_line_changes(&old, &new))
+}
+
+// The changes in a form that only depends on what they do, for patch ids. Line numbers
+// and context are left out and whitespace is dropped from the changed lines, binary
+// files are represented by their blob hashes. The changes come sorted by path
+pub fn canonical_patch(
+    project: &GitProject,
+    changes: &[TreeChange],
+) -> Result<Vec<u8>, GitObjectError> {
+    let mut patch = Vec::new();
+    for change in changes {
+        if let TreeChange::Renamed { old_path, path, .. } = change {
+            patch.extend_from_slice(format!("rename {} {}\n", old_path, path).as_bytes());
+            continue;
+        }
+
+        let old = read_side(project, change.old_side())?;
+        let new = read_side(project, change.new_side())?;
+        patch.extend_from_slice(format!("diff {}\n", change.get_path()).as_bytes());
+
+        if old.is_binary() || new.is_binary() {
+            patch.extend_from_slice(
+                format!(
+                    "binary {} {}\n",
+                    change
+                        .get_old_hash()
+                        .map(String::as_str)
+                        .unwrap_or_default(),
+                    change
+                        .get_new_hash()
+                        .map(String::as_str)
+                        .unwrap_or_default()
+                )
+                .as_bytes(),
+            );
+            continue;
+        }
+
+        for edit in line_edits(&split_lines(old.data()), &split_lines(new.data())) {
+            let (prefix, line) = match edit {
+                LineEdit::Delete(line) => (b'-', line),
+                LineEdit::Insert(line) => (b'+', line),
+            };
+            patch.push(prefix);
+            patch.extend(line.iter().filter(|byte| !byte.is_ascii_whitespace()));
+            patch.push(b'\n');
+        }
+    }
+
+    Ok(patch)
+}
+
+// Compares the entries of two trees by name without reading anything, entries whose
+// type changed show up as a deletion followed by an addition
+pub fn diff_tree_entries(old: &GitTree, new: &GitTree) -> Vec<TreeChange> {
+    let mut entries = BTreeMap::<&str, (Option<&GitTreeEntry>, Option<&GitTreeEntry>)>::new();
+    for entry in old.entries() {
+        entries.entry(&entry.name).or_default().0 = Some(entry);
+    }
+    for entry in new.entries() {
+        entries.entry(&entry.name).or_default().1 = Some(entry);
+    }
+
+    let mut changes = Vec::new();
+    for (name, (old_entry, new_entry)) in entries {
+        let old_side = old_entry.map(|entry| (&entry.mode, &entry.hash));
+        let new_side = new_entry.map(|entry| (&entry.mode, &entry.hash));
+
+        match (old_side, new_side) {
+            (Some(old_side), Some(new_side)) if old_side == new_side => {}
+            (Some(old_side), Some(new_side)) if same_kind(old_side.0, new_side.0) => {
+                changes.push(TreeChange::modified(name.to_string(), old_side, new_side));
+            }
+            _ => {
+                if let Some(old_side) = old_side {
+                    changes.push(TreeChange::deleted(name.to_string(), old_side));
+                }
+                if let Some(new_side) = new_side {
+                    changes.push(TreeChange::added(name.to_string(), new_side));
+                }
+            }
+        }
+    }
+
+    changes
+}
+
+// What `git diff --cached` shows, the index is compared path by path with the HEAD tree
+// instead of being written out as trees first
+pub fn diff_staged(project: &GitProject) -> Result<Vec<TreeChange>, GitObjectError> {
+    let mut entries = BTreeMap::<String, (Option<FlatEntry>, Option<FlatEntry>)>::new();
+
+    // An unborn branch has no commit yet, so everything staged is an addition
+    match project.head_commit() {
+        Ok(commit) => {
+            let mut head = Vec::new();
+            flatten_tree(project, commit.get_tree_hash(), "", &mut head)?;
+            for entry in head {
+                let path = entry.path.clone();
+                entries.entry(path).or_default().0 = Some(entry);
+            }
+        }
+        Err(GitObjectError::UnbornBranch) => {}
+        Err(error) => return Err(error),
+    }
+
+    // Paths added with --intent-to-add are not staged yet. A conflicted path is reported
+    // once as unmerged, like status does, and none of its stages is diffed with HEAD
+    let index = GitIndex::read(project)?;
+    let mut staged = Vec::new();
+    let mut unmerged = BTreeSet::new();
+    for entry in index.entries().iter().filter(|entry| !entry.intent_to_add) {
+        if entry.stage != 0 {
+            unmerged.insert(entry.path.clone());
+        } else if entry.is_sparse_directory() {
+            let prefix = format!("{}/", entry.path.trim_end_matches('/'));
+            flatten_tree(project, &entry.hash, &prefix, &mut staged)?;
+        } else {
+            staged.push(FlatEntry {
+                path: entry.path.clone(),
+                mode: entry.mode.clone(),
+                hash: entry.hash.clone(),
+            });
+        }
+    }
+    for entry in staged {
+        let path = entry.path.clone();
+        entries.entry(path).or_default().1 = Some(entry);
+    }
+    for path in &unmerged {
+        entries.entry(path.clone()).or_default();
+    }
+
+    let mut changes = Vec::new();
+    for (path, (old, new)) in entries {
+        if unmerged.contains(&path) {
+            changes.push(TreeChange::Unmerged { path });
+            continue;
+        }
+
+        match (old, new) {
+            (Some(old), Some(new)) if old.hash == new.hash && old.mode == new.mode => {}
+            (Some(old), Some(new)) if same_kind(&old.mode, &new.mode) => {
+                changes.push(TreeChange::modified(
+                    path,
+                    (&old.mode, &old.hash),
+                    (&new.mode, &new.hash),
+                ));
+            }
+            (old, new) => {
+                if let Some(old) = old {
+                    changes.push(TreeChange::deleted(path.clone(), (&old.mode, &old.hash)));
+                }
+                if let Some(new) = new {
+                    changes.push(TreeChange::added(path, (&new.mode, &new.hash)));
+                }
+            }
+        }
+    }
+
+    Ok(detect_renames(changes))
+}
+
+// Pairs each deleted entry with an added one of the same content and mode, the first
+// in path order, the way git finds exact renames. Gitlinks are left as they are
+fn detect_renames(changes: Vec<TreeChange>) -> Vec<TreeChange> {
+    let mut sources = HashMap::<String, Vec<(GitTreeMode, String)>>::new();
+    for change in &changes {
+        if let TreeChange::Deleted { path, mode, hash } = change {
+            if *mode != GitTreeMode::Submodule {
+                sources
+                    .entry(hash.clone())
+                    .or_default()
+                    .push((mode.clone(), path.clone()));
+            }
+        }
+    }
+
+    let mut renamed = HashSet::new();
+    let changes = changes
+        .into_iter()
+        .map(|change| match change {
+            TreeChange::Added { path, mode, hash } => {
+                let source = sources.get_mut(&hash).and_then(|sources| {
+                    let position = sources.iter().position(|(old_mode, _)| *old_mode == mode)?;
+                    Some(sources.remove(position).1)
+                });
+
+                match source {
+                    Some(old_path) => {
+                        renamed.insert(old_path.clone());
+                        TreeChange::Renamed {
+                            old_path,
+                            path,
+                            mode,
+                            hash,
+                        }
+                    }
+                    None => TreeChange::Added { path, mode, hash },
+                }
+            }
+            change => change,
+        })
+        .collect::<Vec<_>>();
+
+    changes
+        .into_iter()
+        .filter(
+            |change| !matches!(change, TreeChange::Deleted { path, .. } if renamed.contains(path)),
+        )
+        .collect()
+}
+
+struct FlatEntry {
+    path: String,
+    mode: GitTreeMode,
+    hash: String,
+}
+
+fn flatten_tree(
+    project: &GitProject,
+    hash: &str,
+    prefix: &str,
+    entries: &mut Vec<FlatEntry>,
+) -> Result<(), GitObjectError> {
+    let tree = GitTree::from_hash(project, hash)?;
+
+    for entry in tree.entries() {
+        let path = format!("{}{}", prefix, entry.name);
+        match entry.mode {
+            GitTreeMode::Tree => {
+                flatten_tree(project, &entry.hash, &format!("{}/", path), entries)?
+            }
+            _ => entries.push(FlatEntry {
+                path,
+                mode: entry.mode.clone(),
+                hash: entry.hash.clone(),
+            }),
+        }
+    }
+
+    Ok(())
+}
+
+// A path whose type changed shows up as a deletion followed by an addition
+fn kind_order(kind: &ChangeKind) -> u8 {
+    match kind {
+        ChangeKind::Deleted => 0,
+        ChangeKind::Modified | ChangeKind::SubmoduleUpdated | ChangeKind::Unmerged => 1,
+        ChangeKind::Added | ChangeKind::Renamed => 2,
+    }
+}
+
+fn diff_entries(
+    project: &GitProject,
+    old: &GitTree,
+    new: &GitTree,
+    prefix: &str,
+    changes: &mut Vec<TreeChange>,
+) -> Result<(), GitObjectError> {
+    let mut entries = BTreeMap::<&str, (Option<&GitTreeEntry>, Option<&GitTreeEntry>)>::new();
+    for entry in old.entries() {
+        entries.entry(&entry.name).or_default().0 = Some(entry);
+    }
+    for entry in new.entries() {
+        entries.entry(&entry.name).or_default().1 = Some(entry);
+    }
+
+    for (name, (old_entry, new_entry)) in entries {
+        let path = format!("{}{}", prefix, name);
+
+        match (old_entry, new_entry) {
+            (Some(old_entry), Some(new_entry))
+                if old_entry.hash == new_entry.hash && old_entry.mode == new_entry.mode => {}
+            (Some(old_entry), Some(new_entry))
+                if old_entry.mode == GitTreeMode::Tree && new_entry.mode == GitTreeMode::Tree =>
+            {
+                let old_tree = GitTree::from_hash(project, &old_entry.hash)?;
+                let new_tree = GitTree::from_hash(project, &new_entry.hash)?;
+                diff_entries(
+                    project,
+                    &old_tree,
+                    &new_tree,
+                    &format!("{}/", path),
+                    changes,
+                )?;
+            }
+            (Some(old_entry), Some(new_entry)) if same_kind(&old_entry.mode, &new_entry.mode) => {
+                changes.push(TreeChange::modified(
+                    path,
+                    (&old_entry.mode, &old_entry.hash),
+                    (&new_entry.mode, &new_entry.hash),
+                ));
+            }
+            // A path that changes between a file, a symlink, a submodule or a directory
+            // is reported as removed and then added again
+            (old_entry, new_entry) => {
+                if let Some(old_entry) = old_entry {
+                    record_entry(project, old_entry, &path, ChangeKind::Deleted, changes)?;
+                }
+                if let Some(new_entry) = new_entry {
+                    record_entry(project, new_entry, &path, ChangeKind::Added, changes)?;
+                }
+            }
+        }
+    }
+
+    Ok(())
+}
+
+// Adds or removes a whole entry, walking into it when it is a tree
+fn record_entry(
+    project: &GitProject,
+    entry: &GitTreeEntry,
+    path: &str,
+    kind: ChangeKind,
+    changes: &mut Vec<TreeChange>,
+) -> Result<(), GitObjectError> {
+    if entry.mode == GitTreeMode::Tree {
+        let tree = GitTree::from_hash(project, &entry.hash)?;
+        let empty = GitTree::new();
+        let (old, new) = match kind {
+            ChangeKind::Deleted => (&tree, &empty),
+            _ => (&empty, &tree),
+        };
+
+        return diff_entries(project, old, new, &format!("{}/", path), changes);
+    }
+
+    let side = (&entry.mode, &entry.hash);
+    changes.push(match kind {
+        ChangeKind::Deleted => TreeChange::deleted(path.to_string(), side),
+        _ => TreeChange::added(path.to_string(), side),
+    });
+
+    Ok(())
+}
+
+// The missing side of an addition or a deletion is an empty file
+fn read_blob(project: &GitProject, hash: Option<&str>) -> Result<GitBlob, GitObjectError> {
+    match hash {
+        Some(hash) => GitBlob::from_hash(project, hash),
+        None => Ok(GitBlob::new(0, Vec::new())),
+    }
+}
+
+// The commit a gitlink pins lives in the submodule, so it is shown as the line git
+// prints for it instead of being read
+fn read_side(
+    project: &GitProject,
+    side: Option<(&GitTreeMode, &String)>,
+) -> Result<GitBlob, GitObjectError> {
+    match side {
+        Some((GitTreeMode::Submodule, hash)) => {
+            let line = format!("Subproject commit {}\n", hash).into_bytes();
+            Ok(GitBlob::new(line.len(), line))
+        }
+        _ => read_blob(project, side.map(|(_, hash)| hash.as_str())),
+    }
+}
+
+fn blob_line_changes(old: &GitBlob, new: &GitBlob) -> (usize, usize) {
+    if old.is_binary() || new.is_binary() {
+        return (0, 0);
+    }
+
+    count_line_changes(&split_lines(old.data()), &split_lines(new.data()))
+}
+
+fn split_lines(data: &[u8]) -> Vec<&[u8]> {
+    if data.is_empty() {
+        return Vec::new();
+    }
+
+    data.strip_suffix(b"\n")
+        .unwrap_or(data)
+        .split(|byte| *byte == b'\n')
+        .collect()
+}
+
+// Counts the inserted and deleted lines of the edit script
+fn count_line_changes(old: &[&[u8]], new: &[&[u8]]) -> (usize, usize) {
+    line_edits(old, new)
+        .iter()
+        .fold((0, 0), |(insertions, deletions), edit| match edit {
+            LineEdit::Insert(_) => (insertions + 1, deletions),
+            LineEdit::Delete(_) => (insertions, deletions + 1),
+        })
+}
+
+#[derive(Debug, PartialEq)]
+enum LineEdit<'a> {
+    Delete(&'a [u8]),
+    Insert(&'a [u8]),
+}
+
+// The shortest edit script (Myers' algorithm) in file order, or one close to it when
+// there are many changes. Instead of keeping every step of the search, each range is
+// split where the searches from both of its ends meet and the halves are diffed on
+// their own, so memory only grows with the lines
+fn line_edits<'a>(old: &[&'a [u8]], new: &[&'a [u8]]) -> Vec<LineEdit<'a>> {
+    let mut line_edits = Vec::new();
+    // The first half of a split is pushed last, so the edits come out in order
+    let mut ranges = vec![(0..old.len(), 0..new.len())];
+    while let Some((mut old_range, mut new_range)) = ranges.pop() {
+        while !old_range.is_empty()
+            && !new_range.is_empty()
+            && old[old_range.start] == new[new_range.start]
+        {
+            old_range.start += 1;
+            new_range.start += 1;
+        }
+        while !old_range.is_empty()
+            && !new_range.is_empty()
+            && old[old_range.end - 1] == new[new_range.end - 1]
+        {
+            old_range.end -= 1;
+            new_range.end -= 1;
+        }
+
+        let split = if old_range.is_empty() || new_range.is_empty() {
+            None
+        } else {
+            split_point(&old[old_range.clone()], &new[new_range.clone()])
+        };
+        match split {
+            Some((old_split, new_split)) => {
+                let (old_split, new_split) =
+                    (old_range.start + old_split, new_range.start + new_split);
+                ranges.push((old_split..old_range.end, new_split..new_range.end));
+                ranges.push((old_range.start..old_split, new_range.start..new_split));
+            }
+            None => {
+                line_edits.extend(old[old_range].iter().copied().map(LineEdit::Delete));
+                line_edits.extend(new[new_range].iter().copied().map(LineEdit::Insert));
+            }
+        }
+    }
+
+    line_edits
+}
+
+// A point the edit script of two ranges goes through, found by searching from both ends
+// until the paths meet. The ranges have to differ in their first and last lines. None
+// when they have nothing in common. Like xdiff, a search that takes more steps than
+// MIN_DIFF_COST or the square root of the lines gives up on the shortest script and
+// splits at the furthest point reached from the start
+fn split_point(old: &[&[u8]], new: &[&[u8]]) -> Option<(usize, usize)> {
+    let (old_len, new_len) = (old.len() as isize, new.len() as isize);
+    let max_cost = (((old.len() + new.len()) as f64).sqrt() as isize).max(MIN_DIFF_COST);
+    let max_edits = ((old_len + new_len + 1) / 2).min(max_cost);
+    let offset = max_edits + 1;
+    let mut forward = vec![-1isize; 2 * offset as usize + 1];
+    let mut backward = forward.clone();
+    forward[offset as usize + 1] = 0;
+    backward[offset as usize + 1] = 0;
+    let at = |furthest: &[isize], diagonal: isize| {
+        usize::try_from(offset + diagonal)
+            .ok()
+            .and_then(|index| furthest.get(index))
+            .copied()
+            .filter(|x| *x >= 0)
+    };
+
+    // The backward search walks the reversed ranges, its diagonal `k` is `delta - k`
+    // of the forward one. Which search can see the paths meet depends on the parity
+    let delta = old_len - new_len;
+    let forward_meets = delta % 2 != 0;
+    // Diagonals that ran off the ranges are skipped from then on
+    let (mut forward_start, mut forward_end) = (0, 0);
+    let (mut backward_start, mut backward_end) = (0, 0);
+    for edits in 0..max_edits {
+        for diagonal in (-edits + forward_start..=edits - forward_end).step_by(2) {
+            let index = (offset + diagonal) as usize;
+            let mut x = if diagonal == -edits
+                || (diagonal != edits && forward[index - 1] < forward[index + 1])
+            {
+                forward[index + 1]
+            } else {
+                forward[index - 1] + 1
+            };
+            let mut y = x - diagonal;
+
+            while x < old_len && y < new_len && old[x as usize] == new[y as usize] {
+                x += 1;
+                y += 1;
+            }
+            forward[index] = x;
+
+            if x > old_len {
+                forward_end += 2;
+            } else if y > new_len {
+                forward_start += 2;
+            } else if forward_meets
+                && at(&backward, delta - diagonal).is_some_and(|back_x| x >= old_len - back_x)
+            {
+                return Some((x as usize, y as usize));
+            }
+        }
+
+        for diagonal in (-edits + backward_start..=edits - backward_end).step_by(2) {
+            let index = (offset + diagonal) as usize;
+            let mut x = if diagonal == -edits
+                || (diagonal != edits && backward[index - 1] < backward[index + 1])
+            {
+                backward[index + 1]
+            } else {
+                backward[index - 1] + 1
+            };
+            let mut y = x - diagonal;
+
+            while x < old_len
+                && y < new_len
+                && old[(old_len - x - 1) as usize] == new[(new_len - y - 1) as usize]
+            {
+                x += 1;
+                y += 1;
+            }
+            backward[index] = x;
+
+            if x > old_len {
+                backward_end += 2;
+            } else if y > new_len {
+                backward_start += 2;
+            } else if !forward_meets {
+                let forward_diagonal = delta - diagonal;
+                if let Some(forward_x) =
+                    at(&forward, forward_diagonal).filter(|forward_x| *forward_x >= old_len - x)
+                {
+                    return Some((forward_x as usize, (forward_x - forward_diagonal) as usize));
+                }
+            }
+        }
+    }
+
+    if max_edits == (old_len + new_len + 1) / 2 {
+        return None;
+    }
+
+    let last = max_edits - 1;
+    (-last + forward_start..=last - forward_end)
+        .step_by(2)
+        .filter_map(|diagonal| {
+            let x = at(&forward, diagonal)?;
+            let y = x - diagonal;
+            (x <= old_len && (0..=new_len).contains(&y)).then_some((x, y))
+        })
+        .filter(|(x, y)| 0 < x + y && x + y < old_len + new_len)
+        .max_by_key(|(x, y)| x + y)
+        .map(|(x, y)| (x as usize, y as usize))
+}
+
+// Only the executable bit can change while the path stays the same kind of file
+fn same_kind(old: &GitTreeMode, new: &GitTreeMode) -> bool {
+    let is_file = |mode: &GitTreeMode| matches!(mode, GitTreeMode::File | GitTreeMode::Executable);
+
+    old == new || (is_file(old) && is_file(new))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_same_kind() {
+        assert!(same_kind(&GitTreeMode::File, &GitTreeMode::File));
+        assert!(same_kind(&GitTreeMode::File, &GitTreeMode::Executable));
+        assert!(!same_kind(&GitTreeMode::File, &GitTreeMode::Symlink));
+        assert!(!same_kind(&GitTreeMode::Submodule, &GitTreeMode::Tree));
+    }
+
+    #[test]
+    fn test_count_line_changes() {
+        let lines = |data: &'static str| split_lines(data.as_bytes());
+
+        assert_eq!(count_line_changes(&lines(""), &lines("")), (0, 0));
+        assert_eq!(count_line_changes(&lines(""), &lines("a\nb\n")), (2, 0));
+        assert_eq!(count_line_changes(&lines("a\nb\n"), &lines("")), (0, 2));
+        assert_eq!(
+            count_line_changes(&lines("a\nb\nc\n"), &lines("a\nb\nc\n")),
+            (0, 0)
+        );
+        assert_eq!(
+            count_line_changes(&lines("a\nb\nc\nd\n"), &lines("a\nx\nc\nd\ne\n")),
+            (2, 1)
+        );
+    }
+
+    #[test]
+    fn test_line_edits() {
+        let lines = |data: &'static str| split_lines(data.as_bytes());
+
+        assert_eq!(line_edits(&lines(""), &lines("")), vec![]);
+        assert_eq!(
+            line_edits(&lines("a\nb\n"), &lines("")),
+            vec![LineEdit::Delete(b"a"), LineEdit::Delete(b"b")]
+        );
+        assert_eq!(
+            line_edits(&lines("a\nb\nc\nd\n"), &lines("a\nx\nc\nd\ne\n")),
+            vec![
+                LineEdit::Delete(b"b"),
+                LineEdit::Insert(b"x"),
+                LineEdit::Insert(b"e")
+            ]
+        );
+
+        let old = lines("one\ntwo\nthree\nfour\nfive\n");
+        let new = lines("zero\none\nthree\nfour\n4.5\nfive\nsix\n");
+        let edits = line_edits(&old, &new);
+        let count = |delete: bool| {
+            edits
+                .iter()
+                .filter(|edit| matches!(edit, LineEdit::Delete(_)) == delete)
+                .count()
+        };
+        assert_eq!((count(false), count(true)), count_line_changes(&old, &new));
+
+        // Long files with a few changes are split in a few steps
+        let old_data = (0..20_000).map(|i| format!("{}\n", i)).collect::<String>();
+        let new_data = old_data.replacen("100\n", "one hundred\n", 1) + "end\n";
+        let edits = line_edits(
+            &split_lines(old_data.as_bytes()),
+            &split_lines(new_data.as_bytes()),
+        );
+        assert_eq!(
+            edits,
+            vec![
+                LineEdit::Delete(b"100"),
+                LineEdit::Insert(b"one hundred"),
+                LineEdit::Insert(b"end")
+            ]
+        );
+    }
+
+    #[test]
+    fn test_line_edits_rewritten_file() {
+        let old_data = (0..100_000)
+            .map(|i| format!("old {}\n", i))
+            .collect::<String>();
+        let new_data = (0..100_000)
+            .map(|i| format!("new {}\n", i))
+            .collect::<String>();
+        let (old, new) = (
+            split_lines(old_data.as_bytes()),
+            split_lines(new_data.as_bytes()),
+        );
+
+        let edits = line_edits(&old, &new);
+        let deleted = edits
+            .iter()
+            .filter_map(|edit| match edit {
+                LineEdit::Delete(line) => Some(*line),
+                LineEdit::Insert(_) => None,
+            })
+            .collect::<Vec<&[u8]>>();
+        let inserted = edits
+            .iter()
+            .filter_map(|edit| match edit {
+                LineEdit::Insert(line) => Some(*line),
+                LineEdit::Delete(_) => None,
+            })
+            .collect::<Vec<&[u8]>>();
+        assert_eq!(deleted, old);
+        assert_eq!(inserted, new);
+        assert_eq!(count_line_changes(&old, &new), (100_000, 100_000));
+    }
+
+    #[test]
+    fn test_diff_tree_entries() {
+        let hash = |byte: u8| format!("{:02x}", byte).repeat(20);
+        let tree = |entries: Vec<(GitTreeMode, String, &str)>| {
+            let mut tree = GitTree::new();
+            for (mode, hash, name) in entries {
+                tree.add_entry(mode, hash, name.to_string()).unwrap();
+            }
+            tree
+        };
+
+        let old = tree(vec![
+            (GitTreeMode::File, hash(1), "kept.txt"),
+            (GitTreeMode::File, hash(2), "edited.txt"),
+            (GitTreeMode::File, hash(3), "removed.txt"),
+            (GitTreeMode::File, hash(4), "run.sh"),
+            (GitTreeMode::Tree, hash(5), "src"),
+            (GitTreeMode::File, hash(6), "was_file"),
+        ]);
+        let new = tree(vec![
+            (GitTreeMode::File, hash(1), "kept.txt"),
+            (GitTreeMode::File, hash(7), "edited.txt"),
+            (GitTreeMode::File, hash(8), "added.txt"),
+            (GitTreeMode::Executable, hash(4), "run.sh"),
+            (GitTreeMode::Tree, hash(9), "src"),
+            (GitTreeMode::Symlink, hash(6), "was_file"),
+        ]);
+
+        let changes = diff_tree_entries(&old, &new);
+        assert_eq!(
+            changes
+                .iter()
+                .map(|change| (
+                    change.get_path().as_str(),
+                    change.get_kind().clone(),
+                    change.get_old_mode().cloned(),
+                    change.get_new_mode().cloned()
+                ))
+                .collect::<Vec<_>>(),
+            vec![
+                (
+                    "added.txt",
+                    ChangeKind::Added,
+                    None,
+                    Some(GitTreeMode::File)
+                ),
+                (
+                    "edited.txt",
+                    ChangeKind::Modified,
+                    Some(GitTreeMode::File),
+                    Some(GitTreeMode::File)
+                ),
+                (
+                    "removed.txt",
+                    ChangeKind::Deleted,
+                    Some(GitTreeMode::File),
+                    None
+                ),
+                (
+                    "run.sh",
+                    ChangeKind::Modified,
+                    Some(GitTreeMode::File),
+                    Some(GitTreeMode::Executable)
+                ),
+                (
+                    "src",
+                    ChangeKind::Modified,
+                    Some(GitTreeMode::Tree),
+                    Some(GitTreeMode::Tree)
+                ),
+                (
+                    "was_file",
+                    ChangeKind::Deleted,
+                    Some(GitTreeMode::File),
+                    None
+                ),
+                (
+                    "was_file",
+                    ChangeKind::Added,
+                    None,
+                    Some(GitTreeMode::Symlink)
+                ),
+            ]
+        );
+        assert_eq!(changes[1].get_old_hash(), Some(&hash(2)));
+        assert_eq!(changes[1].get_new_hash(), Some(&hash(7)));
+        assert!(diff_tree_entries(&new, &new).is_empty());
+    }
+
+    #[test]
+    fn test_detect_renames() {
+        let hash = |index: u8| format!("{:040x}", index);
+        let file = |path: &str, mode: GitTreeMode, index: u8| (path.to_string(), mode, hash(index));
+        let mut changes = Vec::new();
+        for (path, mode, hash) in [
+            file("a.txt", GitTreeMode::File, 1),
+            file("b.txt", GitTreeMode::File, 1),
+            file("lib", GitTreeMode::Submodule, 2),
+            file("run.sh", GitTreeMode::File, 3),
+        ] {
+            changes.push(TreeChange::deleted(path, (&mode, &hash)));
+        }
+        for (path, mode, hash) in [
+            file("bin/run.sh", GitTreeMode::Executable, 3),
+            file("docs/a.txt", GitTreeMode::File, 1),
+            file("vendor/lib", GitTreeMode::Submodule, 2),
+        ] {
+            changes.push(TreeChange::added(path, (&mode, &hash)));
+        }
+
+        // The executable bit changed for run.sh, and b.txt has no destination left
+        let changes = detect_renames(changes);
+        assert_eq!(
+            changes
+                .iter()
+                .map(|change| (change.get_path().as_str(), change.get_kind().clone()))
+                .collect::<Vec<_>>(),
+            vec![
+                ("b.txt", ChangeKind::Deleted),
+                ("lib", ChangeKind::Deleted),
+                ("run.sh", ChangeKind::Deleted),
+                ("bin/run.sh", ChangeKind::Added),
+                ("docs/a.txt", ChangeKind::Renamed),
+                ("vendor/lib", ChangeKind::Added),
+            ]
+        );
+        assert_eq!(
+            changes[4],
+            TreeChange::Renamed {
+                old_path: "a.txt".to_string(),
+                path: "docs/a.txt".to_string(),
+                mode: GitTreeMode::File,
+                hash: hash(1),
+            }
+        );
+        assert_eq!(changes[4].get_old_hash(), changes[4].get_new_hash());
+    }
+
+    #[test]
+    fn test_kind_order() {
+        assert!(kind_order(&ChangeKind::Deleted) < kind_order(&ChangeKind::Modified));
+        assert!(kind_order(&ChangeKind::Modified) < kind_order(&ChangeKind::Added));
+    }
+}
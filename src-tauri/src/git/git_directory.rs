@@ -0,0 +1,124 @@
+use serde::{Deserialize, Serialize};
+
+use super::{
+    git_commit::GitCommit,
+    git_project::GitProject,
+    git_tree::{GitTree, GitTreeMode},
+    object::GitObject,
+};
+use crate::errors::git_object_error::GitObjectError;
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub enum DirEntryKind {
+    Directory,
+    File,
+    Symlink,
+    Submodule,
+}
+
+impl From<&GitTreeMode> for DirEntryKind {
+    fn from(mode: &GitTreeMode) -> Self {
+        match mode {
+            GitTreeMode::Tree => DirEntryKind::Directory,
+            GitTreeMode::File | GitTreeMode::Executable => DirEntryKind::File,
+            GitTreeMode::Symlink => DirEntryKind::Symlink,
+            GitTreeMode::Submodule => DirEntryKind::Submodule,
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct DirEntry {
+    name: String,
+    kind: DirEntryKind,
+    hash: String,
+}
+
+impl DirEntry {
+    pub fn get_name(&self) -> &String {
+        &self.name
+    }
+
+    pub fn get_kind(&self) -> &DirEntryKind {
+        &self.kind
+    }
+
+    pub fn get_hash(&self) -> &String {
+        &self.hash
+    }
+}
+
+pub fn list_dir(
+    project: &GitProject,
+    ref_name: &str,
+    path: &str,
+) -> Result<Vec<DirEntry>, GitObjectError> {
+    let commit = GitCommit::from_hash(project, &project.resolve_revision(ref_name)?)?;
+
+    let mut tree = GitTree::from_hash(project, commit.get_tree_hash())?;
+    for name in path.split('/').filter(|name| !name.is_empty()) {
+        let hash = tree
+            .get_entry_by_name(name)
+            .filter(|entry| entry.mode == GitTreeMode::Tree)
+            .ok_or(GitObjectError::PathNotFound)?
+            .hash
+            .clone();
+
+        tree = GitTree::from_hash(project, &hash)?;
+    }
+
+    let mut entries = tree
+        .entries()
+        .iter()
+        .map(|entry| DirEntry {
+            name: entry.name.clone(),
+            kind: DirEntryKind::from(&entry.mode),
+            hash: entry.hash.clone(),
+        })
+        .collect::<Vec<DirEntry>>();
+
+    // Directories are listed first, like most file explorers do
+    entries.sort_by(|first, second| {
+        (first.kind != DirEntryKind::Directory)
+            .cmp(&(second.kind != DirEntryKind::Directory))
+            .then_with(|| first.name.cmp(&second.name))
+    });
+
+    Ok(entries)
+}
+
+// The blob a path pointed at in the given commit, None when the path did not exist
+// there. Directories and submodules have no blob and are reported as errors
+pub fn blob_hash_at(
+    project: &GitProject,
+    commit_hash: &str,
+    path: &str,
+) -> Result<Option<String>, GitObjectError> {
+    let commit = GitCommit::from_hash(project, commit_hash)?;
+    let mut tree = GitTree::from_hash(project, commit.get_tree_hash())?;
+
+    let mut names = path.split('/').filter(|name| !name.is_empty()).peekable();
+    while let Some(name) = names.next() {
+        let Some(entry) = tree.get_entry_by_name(name) else {
+            return Ok(None);
+        };
+
+        if names.peek().is_none() {
+            return match entry.mode {
+                GitTreeMode::Tree | GitTreeMode::Submodule => Err(GitObjectError::InvalidBlobFile),
+                _ => Ok(Some(entry.hash.clone())),
+            };
+        }
+
+        if entry.mode != GitTreeMode::Tree {
+            return Ok(None);
+        }
+
+        let hash = entry.hash.clone();
+        tree = GitTree::from_hash(project, &hash)?;
+    }
+
+    // An empty path names the root tree
+    Err(GitObjectError::InvalidBlobFile)
+}
@@ -0,0 +1,38 @@
+use super::object::{Header, ObjectFormat};
+
+// The id git gives an object, the hash of its `<type> <size>\0` header followed by the
+// payload
+pub fn hash_object(object_format: ObjectFormat, object_type: &Header, payload: &[u8]) -> String {
+    let mut hasher = object_format.hasher();
+    hasher.update(format!("{} {}\0", object_type, payload.len()).as_bytes());
+    hasher.update(payload);
+
+    hasher.finalize_hex()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_hash_object() {
+        // `echo hello | git hash-object --stdin`
+        assert_eq!(
+            hash_object(ObjectFormat::Sha1, &Header::Blob, b"hello\n"),
+            "ce013625030ba8dba906f756967f9e9ca394464a"
+        );
+        // The empty tree every git repository knows about
+        assert_eq!(
+            hash_object(ObjectFormat::Sha1, &Header::Tree, b""),
+            "4b825dc642cb6eb9a060e54bf8d69288fbee4904"
+        );
+        assert_eq!(
+            hash_object(ObjectFormat::Sha1, &Header::Blob, b""),
+            "e69de29bb2d1d6434b8b29ae775ad8c2e48c5391"
+        );
+        assert_eq!(
+            hash_object(ObjectFormat::Sha256, &Header::Blob, b"hello\n"),
+            "2cf8d83d9ee29543b34a87727421fdecb7e3f3a183d337639025de576db9ebb4"
+        );
+    }
+}
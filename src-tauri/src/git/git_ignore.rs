@@ -0,0 +1,286 @@
+use std::{borrow::Cow, fs, path::Path};
+
+use super::git_project::GitProject;
+
+pub const GITIGNORE_FILE: &str = ".gitignore";
+const INFO_FOLDER: &str = "info";
+const EXCLUDE_FILE: &str = "exclude";
+
+#[derive(Debug, Clone, PartialEq)]
+struct IgnorePattern {
+    pattern: String,
+    // The directory of the file the pattern comes from, e.g. "src/" for src/.gitignore
+    base: String,
+    negated: bool,
+    directory_only: bool,
+    // Patterns with a slash before their end match the whole path below `base`, the
+    // others match the last component at any depth
+    anchored: bool,
+}
+
+impl IgnorePattern {
+    fn parse(line: &str, base: &str) -> Option<Self> {
+        let line = line.strip_suffix('\r').unwrap_or(line);
+        if line.is_empty() || line.starts_with('#') {
+            return None;
+        }
+
+        let (negated, line) = match line.strip_prefix('!') {
+            Some(line) => (true, line),
+            None => (false, line),
+        };
+        let trimmed = trim_trailing_spaces(line);
+        let (directory_only, line) = match trimmed.strip_suffix('/') {
+            Some(line) => (true, line),
+            None => (false, trimmed.as_ref()),
+        };
+        if line.is_empty() {
+            return None;
+        }
+
+        Some(Self {
+            pattern: line.strip_prefix('/').unwrap_or(line).to_string(),
+            base: base.to_string(),
+            negated,
+            directory_only,
+            anchored: line.contains('/'),
+        })
+    }
+
+    fn matches(&self, path: &str, is_dir: bool) -> bool {
+        if self.directory_only && !is_dir {
+            return false;
+        }
+        let Some(relative) = path.strip_prefix(&self.base) else {
+            return false;
+        };
+
+        let target = match self.anchored {
+            true => relative,
+            false => relative.rsplit('/').next().unwrap_or(relative),
+        };
+        wildmatch(self.pattern.as_bytes(), target.as_bytes())
+    }
+}
+
+// The patterns of `info/exclude` and of the `.gitignore` files, in the order git reads
+// them so that the last one matching a path decides
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct IgnoreRules {
+    patterns: Vec<IgnorePattern>,
+}
+
+impl IgnoreRules {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    // Starts with `info/exclude`, the `.gitignore` files are added while the working
+    // tree is walked
+    pub fn for_project(project: &GitProject) -> Self {
+        let mut rules = Self::new();
+        rules.add_file(
+            &project.common_dir().join(INFO_FOLDER).join(EXCLUDE_FILE),
+            "",
+        );
+
+        rules
+    }
+
+    // A file that is missing or can't be read adds nothing, like in git
+    pub fn add_file(&mut self, path: &Path, base: &str) {
+        if let Ok(content) = fs::read(path) {
+            self.add_patterns(&String::from_utf8_lossy(&content), base);
+        }
+    }
+
+    pub fn add_patterns(&mut self, content: &str, base: &str) {
+        self.patterns.extend(
+            content
+                .split('\n')
+                .filter_map(|line| IgnorePattern::parse(line, base)),
+        );
+    }
+
+    pub fn pattern_count(&self) -> usize {
+        self.patterns.len()
+    }
+
+    // Drops the patterns of the directories the walk has left
+    pub fn truncate(&mut self, pattern_count: usize) {
+        self.patterns.truncate(pattern_count);
+    }
+
+    // `path` is relative to the root of the working tree, without a trailing slash
+    pub fn is_ignored(&self, path: &str, is_dir: bool) -> bool {
+        self.patterns
+            .iter()
+            .rev()
+            .find(|pattern| pattern.matches(path, is_dir))
+            .is_some_and(|pattern| !pattern.negated)
+    }
+}
+
+// Trailing spaces are dropped unless the last one is escaped with a backslash
+fn trim_trailing_spaces(line: &str) -> Cow<'_, str> {
+    let mut end = line.len();
+    while line[..end].ends_with(' ') {
+        if line[..end - 1].ends_with('\\') {
+            return Cow::Owned(format!("{} ", &line[..end - 2]));
+        }
+        end -= 1;
+    }
+
+    Cow::Borrowed(&line[..end])
+}
+
+// Git's wildmatch: `*` and `?` stop at slashes, a leading `**/`, a trailing `/**` and
+// a `/**/` also match across them, `[...]` is a set of characters and a backslash escapes the
+// character that follows it
+fn wildmatch(pattern: &[u8], text: &[u8]) -> bool {
+    match pattern {
+        [] => text.is_empty(),
+        [b'*', b'*', rest @ ..] => match rest {
+            // `**/` also matches no directory at all
+            [b'/', rest @ ..] => {
+                wildmatch(rest, text)
+                    || (0..text.len())
+                        .filter(|position| text[*position] == b'/')
+                        .any(|position| wildmatch(rest, &text[position + 1..]))
+            }
+            [] => true,
+            // Anywhere else it is the same as a single `*`
+            _ => wildmatch(&pattern[1..], text),
+        },
+        [b'*', rest @ ..] => (0..=text.len())
+            .take_while(|position| *position == 0 || text[position - 1] != b'/')
+            .any(|position| wildmatch(rest, &text[position..])),
+        [b'?', rest @ ..] => match text {
+            [character, text @ ..] if *character != b'/' => wildmatch(rest, text),
+            _ => false,
+        },
+        [b'[', class @ ..] => match (parse_class(class), text) {
+            (Some((negated, members, rest)), [character, text @ ..]) => {
+                *character != b'/'
+                    && class_contains(members, *character) != negated
+                    && wildmatch(rest, text)
+            }
+            (Some(_), []) => false,
+            // Without a closing bracket the `[` is an ordinary character
+            (None, [b'[', text @ ..]) => wildmatch(class, text),
+            (None, _) => false,
+        },
+        [b'\\', escaped, rest @ ..] => match text {
+            [character, text @ ..] if character == escaped => wildmatch(rest, text),
+            _ => false,
+        },
+        [literal, rest @ ..] => match text {
+            [character, text @ ..] if character == literal => wildmatch(rest, text),
+            _ => false,
+        },
+    }
+}
+
+// The set after a `[` and the rest of the pattern after its closing `]`. A `]` right
+// after the opening bracket, or after its `!` or `^`, is part of the set
+fn parse_class(class: &[u8]) -> Option<(bool, &[u8], &[u8])> {
+    let (negated, members) = match class {
+        [b'!' | b'^', members @ ..] => (true, members),
+        _ => (false, class),
+    };
+    let end = 1 + members.get(1..)?.iter().position(|byte| *byte == b']')?;
+
+    Some((negated, &members[..end], &members[end + 1..]))
+}
+
+fn class_contains(members: &[u8], character: u8) -> bool {
+    let mut members = members;
+    loop {
+        match members {
+            [] => return false,
+            [start, b'-', end, rest @ ..] => {
+                if (*start..=*end).contains(&character) {
+                    return true;
+                }
+                members = rest;
+            }
+            [member, rest @ ..] => {
+                if *member == character {
+                    return true;
+                }
+                members = rest;
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn rules(content: &str) -> IgnoreRules {
+        let mut rules = IgnoreRules::new();
+        rules.add_patterns(content, "");
+        rules
+    }
+
+    #[test]
+    fn test_wildmatch() {
+        assert!(wildmatch(b"*.rs", b"main.rs"));
+        assert!(!wildmatch(b"*.rs", b"src/main.rs"));
+        assert!(wildmatch(b"src/*.rs", b"src/main.rs"));
+        assert!(wildmatch(b"**/main.rs", b"main.rs"));
+        assert!(wildmatch(b"**/main.rs", b"a/b/main.rs"));
+        assert!(wildmatch(b"src/**", b"src/a/b"));
+        assert!(!wildmatch(b"a**b", b"a/b"));
+        assert!(wildmatch(b"a/**/b", b"a/b"));
+        assert!(wildmatch(b"a/**/b", b"a/x/y/b"));
+        assert!(!wildmatch(b"a/**/b", b"a/xb"));
+        assert!(wildmatch(b"file?.txt", b"file1.txt"));
+        assert!(!wildmatch(b"a?b", b"a/b"));
+        assert!(wildmatch(b"[abc].txt", b"b.txt"));
+        assert!(wildmatch(b"[a-c].txt", b"c.txt"));
+        assert!(!wildmatch(b"[!a-c].txt", b"c.txt"));
+        assert!(wildmatch(b"[]]", b"]"));
+        assert!(wildmatch(b"[", b"["));
+        assert!(wildmatch(b"\\*", b"*"));
+        assert!(!wildmatch(b"\\*", b"a"));
+    }
+
+    #[test]
+    fn test_ignore_rules() {
+        let rules = rules(
+            "# build output\ntarget/\n*.log\n!keep.log\n/root.txt\ndocs/*.pdf\n\\#hash\ntrailing   \n",
+        );
+
+        assert!(rules.is_ignored("target", true));
+        assert!(rules.is_ignored("crates/core/target", true));
+        assert!(!rules.is_ignored("target", false));
+        assert!(rules.is_ignored("debug.log", false));
+        assert!(rules.is_ignored("logs/debug.log", false));
+        assert!(!rules.is_ignored("keep.log", false));
+        assert!(rules.is_ignored("root.txt", false));
+        assert!(!rules.is_ignored("src/root.txt", false));
+        assert!(rules.is_ignored("docs/manual.pdf", false));
+        assert!(!rules.is_ignored("docs/api/manual.pdf", false));
+        assert!(rules.is_ignored("#hash", false));
+        assert!(rules.is_ignored("trailing", false));
+        assert!(!rules.is_ignored("# build output", false));
+    }
+
+    #[test]
+    fn test_ignore_rules_nested_base() {
+        let mut rules = rules("*.tmp\n");
+        let count = rules.pattern_count();
+        rules.add_patterns("!keep.tmp\n/local\n", "src/");
+
+        assert!(rules.is_ignored("a.tmp", false));
+        assert!(!rules.is_ignored("src/keep.tmp", false));
+        assert!(rules.is_ignored("keep.tmp", false));
+        assert!(rules.is_ignored("src/local", false));
+        assert!(!rules.is_ignored("local", false));
+
+        rules.truncate(count);
+        assert!(rules.is_ignored("src/keep.tmp", false));
+    }
+}
@@ -0,0 +1,781 @@
+use std::{fs, path::Path};
+
+use super::{
+    git_pack::to_hex,
+    git_project::GitProject,
+    git_tree::{GitTree, GitTreeMode},
+    git_worktree::resolve_git_dirs,
+    object::{GitObject, ObjectFormat},
+};
+use crate::errors::git_object_error::GitObjectError;
+
+pub const INDEX_FILE: &str = "index";
+const INDEX_SIGNATURE: &[u8] = b"DIRC";
+const SPARSE_DIRECTORY_EXTENSION: &[u8] = b"sdir";
+const CACHE_TREE_EXTENSION: &[u8] = b"TREE";
+// Deeper than any real directory structure, it only stops a corrupt extension from
+// recursing until the stack runs out
+const CACHE_TREE_MAX_DEPTH: usize = 1024;
+const INDEX_HEADER_SIZE: usize = 12;
+const ENTRY_STAT_SIZE: usize = 40;
+const ENTRY_FLAGS_SIZE: usize = 2;
+
+const FLAG_EXTENDED: u16 = 0x4000;
+const FLAG_STAGE_MASK: u16 = 0x3000;
+const FLAG_STAGE_SHIFT: u16 = 12;
+const EXTENDED_FLAG_SKIP_WORKTREE: u16 = 0x4000;
+const EXTENDED_FLAG_INTENT_TO_ADD: u16 = 0x2000;
+
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub struct GitIndexTime {
+    pub seconds: u32,
+    pub nanoseconds: u32,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct GitIndexEntry {
+    pub ctime: GitIndexTime,
+    pub mtime: GitIndexTime,
+    pub dev: u32,
+    pub ino: u32,
+    pub mode: GitTreeMode,
+    pub uid: u32,
+    pub gid: u32,
+    pub size: u32,
+    pub hash: String,
+    pub stage: u8,
+    pub skip_worktree: bool,
+    pub intent_to_add: bool,
+    pub path: String,
+}
+
+impl GitIndexEntry {
+    // In a sparse index a whole directory outside the sparse cone is collapsed into
+    // a single entry pointing at its tree
+    pub fn is_sparse_directory(&self) -> bool {
+        self.mode == GitTreeMode::Tree
+    }
+}
+
+// A directory of the `TREE` extension, the tree that was last written for it and how many
+// index entries it covers. Staging a path under it clears the hash, so only the
+// directories that changed since need a new tree
+#[derive(Debug, Clone, PartialEq)]
+pub struct GitCacheTree {
+    name: String,
+    entry_count: usize,
+    hash: Option<String>,
+    subtrees: Vec<GitCacheTree>,
+}
+
+impl GitCacheTree {
+    pub fn get_name(&self) -> &str {
+        &self.name
+    }
+
+    pub fn get_entry_count(&self) -> usize {
+        self.entry_count
+    }
+
+    pub fn get_hash(&self) -> Option<&String> {
+        self.hash.as_ref()
+    }
+
+    pub fn subtrees(&self) -> &Vec<GitCacheTree> {
+        &self.subtrees
+    }
+
+    pub fn get_subtree(&self, name: &str) -> Option<&GitCacheTree> {
+        self.subtrees.iter().find(|subtree| subtree.name == name)
+    }
+
+    // Each directory is `<name>\0<entry count> <subtree count>\n`, followed by its hash
+    // when the entry count isn't -1, then by its subtrees
+    fn from_data(
+        data: &[u8],
+        position: &mut usize,
+        object_format: ObjectFormat,
+        depth: usize,
+    ) -> Result<Self, GitObjectError> {
+        if depth > CACHE_TREE_MAX_DEPTH {
+            return Err(GitObjectError::InvalidIndexFile);
+        }
+
+        let rest = data
+            .get(*position..)
+            .ok_or(GitObjectError::InvalidIndexFile)?;
+        let name_length = rest
+            .iter()
+            .position(|byte| *byte == 0)
+            .ok_or(GitObjectError::InvalidIndexFile)?;
+        let counts_length = rest[name_length..]
+            .iter()
+            .position(|byte| *byte == b'\n')
+            .ok_or(GitObjectError::InvalidIndexFile)?;
+
+        let name = std::str::from_utf8(&rest[..name_length])
+            .map_err(|_| GitObjectError::InvalidIndexFile)?
+            .to_string();
+        let counts = std::str::from_utf8(&rest[name_length + 1..name_length + counts_length])
+            .map_err(|_| GitObjectError::InvalidIndexFile)?;
+        let (entry_count, subtree_count) = counts
+            .split_once(' ')
+            .and_then(|(entries, subtrees)| {
+                Some((
+                    entries.parse::<i64>().ok()?,
+                    subtrees.parse::<usize>().ok()?,
+                ))
+            })
+            .ok_or(GitObjectError::InvalidIndexFile)?;
+        *position += name_length + counts_length + 1;
+
+        let hash = if entry_count >= 0 {
+            let hash_end = *position + object_format.hash_byte_len();
+            let hash = data
+                .get(*position..hash_end)
+                .ok_or(GitObjectError::InvalidIndexFile)?;
+            *position = hash_end;
+
+            Some(to_hex(hash))
+        } else {
+            None
+        };
+
+        // The count comes from the file, the subtrees are only trusted once they parse
+        let mut subtrees = Vec::new();
+        for _ in 0..subtree_count {
+            subtrees.push(Self::from_data(data, position, object_format, depth + 1)?);
+        }
+
+        Ok(Self {
+            name,
+            entry_count: entry_count.max(0) as usize,
+            hash,
+            subtrees,
+        })
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct GitIndex {
+    version: u32,
+    entries: Vec<GitIndexEntry>,
+    sparse: bool,
+    cache_tree: Option<GitCacheTree>,
+}
+
+impl Default for GitIndex {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl GitIndex {
+    pub fn new() -> Self {
+        Self {
+            version: 2,
+            entries: Vec::new(),
+            sparse: false,
+            cache_tree: None,
+        }
+    }
+
+    // A repository without an index (e.g. right after `git init`) has nothing staged
+    pub fn read(project: &GitProject) -> Result<Self, GitObjectError> {
+        let (git_dir, _) = resolve_git_dirs(Path::new(project.get_directory()))?;
+
+        match fs::read(git_dir.join(INDEX_FILE)) {
+            Ok(data) => Self::from_data_with_format(&data, project.get_object_format()),
+            Err(error) if error.kind() == std::io::ErrorKind::NotFound => Ok(Self::new()),
+            Err(_) => Err(GitObjectError::FileReadError),
+        }
+    }
+
+    pub fn from_data(data: &[u8]) -> Result<Self, GitObjectError> {
+        Self::from_data_with_format(data, ObjectFormat::Sha1)
+    }
+
+    pub fn from_data_with_format(
+        data: &[u8],
+        object_format: ObjectFormat,
+    ) -> Result<Self, GitObjectError> {
+        let checksum_size = object_format.hash_byte_len();
+        if data.len() < INDEX_HEADER_SIZE + checksum_size
+            || &data[..INDEX_SIGNATURE.len()] != INDEX_SIGNATURE
+        {
+            return Err(GitObjectError::InvalidIndexFile);
+        }
+
+        let (content, checksum) = data.split_at(data.len() - checksum_size);
+        // index.skipHash writes a null checksum instead of the real one
+        if checksum.iter().any(|byte| *byte != 0) && object_format.digest(content) != checksum {
+            return Err(GitObjectError::InvalidIndexFile);
+        }
+
+        let version = read_u32(content, 4)?;
+        if !(2..=4).contains(&version) {
+            return Err(GitObjectError::InvalidIndexFile);
+        }
+
+        let entries_count = read_u32(content, 8)?;
+        let mut index = Self {
+            version,
+            entries: Vec::new(),
+            sparse: false,
+            cache_tree: None,
+        };
+
+        let mut position = INDEX_HEADER_SIZE;
+        let mut previous_path = String::new();
+        for _ in 0..entries_count {
+            let (entry, next_position) =
+                read_entry(content, position, version, &previous_path, object_format)?;
+            previous_path = entry.path.clone();
+            index.entries.push(entry);
+            position = next_position;
+        }
+
+        while position < content.len() {
+            let signature = content
+                .get(position..position + 4)
+                .ok_or(GitObjectError::InvalidIndexFile)?;
+            let size = read_u32(content, position + 4)? as usize;
+            position += 8;
+            if position + size > content.len() {
+                return Err(GitObjectError::InvalidIndexFile);
+            }
+
+            if signature == SPARSE_DIRECTORY_EXTENSION {
+                index.sparse = true;
+            } else if signature == CACHE_TREE_EXTENSION {
+                let extension = &content[position..position + size];
+                let mut tree_position = 0;
+                index.cache_tree = Some(GitCacheTree::from_data(
+                    extension,
+                    &mut tree_position,
+                    object_format,
+                    0,
+                )?);
+            }
+
+            position += size;
+        }
+
+        Ok(index)
+    }
+
+    pub fn get_version(&self) -> u32 {
+        self.version
+    }
+
+    pub fn entries(&self) -> &Vec<GitIndexEntry> {
+        &self.entries
+    }
+
+    pub fn is_sparse(&self) -> bool {
+        self.sparse
+    }
+
+    pub fn sparse_directories(&self) -> Vec<&GitIndexEntry> {
+        self.entries
+            .iter()
+            .filter(|entry| entry.is_sparse_directory())
+            .collect()
+    }
+
+    pub fn get_entry_by_path(&self, path: &str) -> Option<&GitIndexEntry> {
+        self.entries.iter().find(|entry| entry.path == path)
+    }
+
+    pub fn cache_tree(&self) -> Option<&GitCacheTree> {
+        self.cache_tree.as_ref()
+    }
+
+    // Writes the staged entries as trees like `git write-tree` and returns the root hash.
+    // Directories the cache tree still has a hash for are reused without being rebuilt.
+    // An index with unresolved conflicts has no tree to write
+    pub fn write_tree(&self, project: &GitProject) -> Result<String, GitObjectError> {
+        if self.entries.iter().any(|entry| entry.stage != 0) {
+            return Err(GitObjectError::InvalidIndexFile);
+        }
+
+        let entries = self
+            .entries
+            .iter()
+            .filter(|entry| !entry.intent_to_add)
+            .collect::<Vec<_>>();
+
+        write_tree_entries(project, &entries, "", self.cache_tree.as_ref())
+    }
+}
+
+// Writes the tree of the directory `prefix`, which holds all of `entries`
+fn write_tree_entries(
+    project: &GitProject,
+    entries: &[&GitIndexEntry],
+    prefix: &str,
+    cache_tree: Option<&GitCacheTree>,
+) -> Result<String, GitObjectError> {
+    if let Some(hash) = cache_tree
+        .filter(|cache_tree| cache_tree.entry_count == entries.len())
+        .and_then(|cache_tree| cache_tree.hash.as_ref())
+    {
+        return Ok(hash.clone());
+    }
+
+    let mut tree = GitTree::with_object_format(project.get_object_format());
+    let mut position = 0;
+    while position < entries.len() {
+        let entry = entries[position];
+        let name = &entry.path[prefix.len()..];
+
+        let Some((directory, rest)) = name.split_once('/') else {
+            tree.add_entry(entry.mode.clone(), entry.hash.clone(), name.to_string())?;
+            position += 1;
+            continue;
+        };
+
+        // A sparse directory is already a tree
+        if rest.is_empty() && entry.is_sparse_directory() {
+            tree.add_entry(GitTreeMode::Tree, entry.hash.clone(), directory.to_string())?;
+            position += 1;
+            continue;
+        }
+
+        let subtree_prefix = format!("{}{}/", prefix, directory);
+        let subtree_length = entries[position..]
+            .iter()
+            .take_while(|entry| entry.path.starts_with(&subtree_prefix))
+            .count();
+        let hash = write_tree_entries(
+            project,
+            &entries[position..position + subtree_length],
+            &subtree_prefix,
+            cache_tree.and_then(|cache_tree| cache_tree.get_subtree(directory)),
+        )?;
+
+        tree.add_entry(GitTreeMode::Tree, hash, directory.to_string())?;
+        position += subtree_length;
+    }
+
+    tree.write_object(project)?;
+    Ok(tree.get_hash_with_format(project.get_object_format()))
+}
+
+fn read_u16(data: &[u8], position: usize) -> Result<u16, GitObjectError> {
+    data.get(position..position + 2)
+        .map(|bytes| u16::from_be_bytes([bytes[0], bytes[1]]))
+        .ok_or(GitObjectError::InvalidIndexFile)
+}
+
+fn read_u32(data: &[u8], position: usize) -> Result<u32, GitObjectError> {
+    data.get(position..position + 4)
+        .map(|bytes| u32::from_be_bytes([bytes[0], bytes[1], bytes[2], bytes[3]]))
+        .ok_or(GitObjectError::InvalidIndexFile)
+}
+
+fn mode_from_bits(mode: u32) -> Result<GitTreeMode, GitObjectError> {
+    match mode {
+        0o100644 => Ok(GitTreeMode::File),
+        0o100755 => Ok(GitTreeMode::Executable),
+        0o120000 => Ok(GitTreeMode::Symlink),
+        0o040000 => Ok(GitTreeMode::Tree),
+        0o160000 => Ok(GitTreeMode::Submodule),
+        _ => Err(GitObjectError::InvalidIndexFile),
+    }
+}
+
+// Index v4 stores how many bytes of the previous path to drop using the same
+// variable length encoding as pack offsets
+fn read_prefix_length(data: &[u8], position: &mut usize) -> Result<usize, GitObjectError> {
+    let mut byte = *data
+        .get(*position)
+        .ok_or(GitObjectError::InvalidIndexFile)?;
+    *position += 1;
+
+    let mut value = (byte & 0x7f) as usize;
+    while byte & 0x80 != 0 {
+        byte = *data
+            .get(*position)
+            .ok_or(GitObjectError::InvalidIndexFile)?;
+        *position += 1;
+        value = ((value + 1) << 7) | (byte & 0x7f) as usize;
+    }
+
+    Ok(value)
+}
+
+fn read_entry(
+    data: &[u8],
+    start: usize,
+    version: u32,
+    previous_path: &str,
+    object_format: ObjectFormat,
+) -> Result<(GitIndexEntry, usize), GitObjectError> {
+    let hash_end = start + ENTRY_STAT_SIZE + object_format.hash_byte_len();
+    let hash = to_hex(
+        data.get(start + ENTRY_STAT_SIZE..hash_end)
+            .ok_or(GitObjectError::InvalidIndexFile)?,
+    );
+
+    let flags = read_u16(data, hash_end)?;
+    let mut position = hash_end + ENTRY_FLAGS_SIZE;
+    let extended_flags = if flags & FLAG_EXTENDED != 0 {
+        position += 2;
+        read_u16(data, hash_end + ENTRY_FLAGS_SIZE)?
+    } else {
+        0
+    };
+
+    let path = if version == 4 {
+        let prefix_length = read_prefix_length(data, &mut position)?;
+        let prefix = previous_path
+            .len()
+            .checked_sub(prefix_length)
+            .and_then(|length| previous_path.get(..length))
+            .ok_or(GitObjectError::InvalidIndexFile)?;
+        let suffix_length = data[position..]
+            .iter()
+            .position(|byte| *byte == 0)
+            .ok_or(GitObjectError::InvalidIndexFile)?;
+        let suffix = std::str::from_utf8(&data[position..position + suffix_length])
+            .map_err(|_| GitObjectError::InvalidIndexFile)?;
+        position += suffix_length + 1;
+
+        format!("{}{}", prefix, suffix)
+    } else {
+        let path_length = data
+            .get(position..)
+            .and_then(|rest| rest.iter().position(|byte| *byte == 0))
+            .ok_or(GitObjectError::InvalidIndexFile)?;
+        let path = std::str::from_utf8(&data[position..position + path_length])
+            .map_err(|_| GitObjectError::InvalidIndexFile)?
+            .to_string();
+
+        // Entries are padded with 1 to 8 NUL bytes to a multiple of 8
+        let entry_length = position + path_length - start;
+        position = start + (entry_length + 8) / 8 * 8;
+
+        path
+    };
+
+    let entry = GitIndexEntry {
+        ctime: GitIndexTime {
+            seconds: read_u32(data, start)?,
+            nanoseconds: read_u32(data, start + 4)?,
+        },
+        mtime: GitIndexTime {
+            seconds: read_u32(data, start + 8)?,
+            nanoseconds: read_u32(data, start + 12)?,
+        },
+        dev: read_u32(data, start + 16)?,
+        ino: read_u32(data, start + 20)?,
+        mode: mode_from_bits(read_u32(data, start + 24)?)?,
+        uid: read_u32(data, start + 28)?,
+        gid: read_u32(data, start + 32)?,
+        size: read_u32(data, start + 36)?,
+        hash,
+        stage: ((flags & FLAG_STAGE_MASK) >> FLAG_STAGE_SHIFT) as u8,
+        skip_worktree: extended_flags & EXTENDED_FLAG_SKIP_WORKTREE != 0,
+        intent_to_add: extended_flags & EXTENDED_FLAG_INTENT_TO_ADD != 0,
+        path,
+    };
+
+    Ok((entry, position))
+}
+
+// Builds index files from hand written entries, shared with the project tests
+#[cfg(test)]
+pub mod test_utils {
+    use super::*;
+
+    pub fn create_index_file(
+        entries: Vec<(u32, &str, &str, bool)>,
+        extensions: Vec<(&[u8], &[u8])>,
+        object_format: ObjectFormat,
+    ) -> Vec<u8> {
+        let entries = entries
+            .into_iter()
+            .map(|(mode, hash, path, skip_worktree)| (mode, hash, path, 0, skip_worktree))
+            .collect();
+
+        encode_index(entries, extensions, object_format)
+    }
+
+    // Entries of a merge in progress, each with its stage: 1 for the base, 2 for ours
+    // and 3 for theirs
+    pub fn create_unmerged_index_file(
+        entries: Vec<(u32, &str, &str, u8)>,
+        object_format: ObjectFormat,
+    ) -> Vec<u8> {
+        let entries = entries
+            .into_iter()
+            .map(|(mode, hash, path, stage)| (mode, hash, path, stage, false))
+            .collect();
+
+        encode_index(entries, vec![], object_format)
+    }
+
+    // Gives the first entry the size, modification time and inode of a file, as if git
+    // had just staged it
+    #[cfg(unix)]
+    pub fn stat_first_entry(
+        data: &mut Vec<u8>,
+        metadata: &fs::Metadata,
+        object_format: ObjectFormat,
+    ) {
+        use std::os::unix::fs::MetadataExt;
+
+        let entry = INDEX_HEADER_SIZE;
+        data[entry + 8..entry + 12].copy_from_slice(&(metadata.mtime() as u32).to_be_bytes());
+        data[entry + 12..entry + 16].copy_from_slice(&(metadata.mtime_nsec() as u32).to_be_bytes());
+        data[entry + 20..entry + 24].copy_from_slice(&(metadata.ino() as u32).to_be_bytes());
+        data[entry + 36..entry + 40].copy_from_slice(&(metadata.len() as u32).to_be_bytes());
+
+        data.truncate(data.len() - object_format.hash_byte_len());
+        let checksum = object_format.digest(data);
+        data.extend_from_slice(&checksum);
+    }
+
+    fn encode_index(
+        entries: Vec<(u32, &str, &str, u8, bool)>,
+        extensions: Vec<(&[u8], &[u8])>,
+        object_format: ObjectFormat,
+    ) -> Vec<u8> {
+        let mut data = Vec::new();
+        data.extend_from_slice(INDEX_SIGNATURE);
+        data.extend_from_slice(&3u32.to_be_bytes());
+        data.extend_from_slice(&(entries.len() as u32).to_be_bytes());
+
+        for (mode, hash, path, stage, skip_worktree) in entries {
+            let start = data.len();
+            data.extend_from_slice(&[0; 24]);
+            data.extend_from_slice(&mode.to_be_bytes());
+            data.extend_from_slice(&[0; 12]);
+            for index in (0..hash.len()).step_by(2) {
+                data.push(u8::from_str_radix(&hash[index..index + 2], 16).unwrap());
+            }
+
+            let flags = ((stage as u16) << FLAG_STAGE_SHIFT) | path.len() as u16;
+            if skip_worktree {
+                data.extend_from_slice(&(FLAG_EXTENDED | flags).to_be_bytes());
+                data.extend_from_slice(&EXTENDED_FLAG_SKIP_WORKTREE.to_be_bytes());
+            } else {
+                data.extend_from_slice(&flags.to_be_bytes());
+            }
+            data.extend_from_slice(path.as_bytes());
+
+            let entry_length = data.len() - start;
+            data.resize(start + (entry_length + 8) / 8 * 8, 0);
+        }
+
+        for (signature, content) in extensions {
+            data.extend_from_slice(signature);
+            data.extend_from_slice(&(content.len() as u32).to_be_bytes());
+            data.extend_from_slice(content);
+        }
+
+        let checksum = object_format.digest(&data);
+        data.extend_from_slice(&checksum);
+
+        data
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{test_utils::create_index_file, *};
+    use crate::git::git_pack::from_hex;
+
+    #[test]
+    fn test_read_sparse_index() {
+        let data = create_index_file(
+            vec![
+                (
+                    0o100644,
+                    "d00491fd7e5bb6fa28c517a0bb32b8b506539d4d",
+                    "a/b/f",
+                    false,
+                ),
+                (
+                    0o040000,
+                    "7a685cdf3fb9e1b8bdd3c3206720831554b2ee4f",
+                    "c/",
+                    true,
+                ),
+                (
+                    0o100755,
+                    "00750edc07d6415dcc07ae0351e9397b0222b7ba",
+                    "top",
+                    false,
+                ),
+            ],
+            vec![(SPARSE_DIRECTORY_EXTENSION, b"")],
+            ObjectFormat::Sha1,
+        );
+
+        let index = GitIndex::from_data(&data).unwrap();
+
+        assert_eq!(index.get_version(), 3);
+        assert!(index.is_sparse());
+        assert_eq!(index.entries().len(), 3);
+
+        let sparse_directories = index.sparse_directories();
+        assert_eq!(sparse_directories.len(), 1);
+        assert_eq!(sparse_directories[0].path, "c/");
+        assert_eq!(
+            sparse_directories[0].hash,
+            "7a685cdf3fb9e1b8bdd3c3206720831554b2ee4f"
+        );
+        assert!(sparse_directories[0].skip_worktree);
+
+        let file = index.get_entry_by_path("top").unwrap();
+        assert_eq!(file.mode, GitTreeMode::Executable);
+        assert!(!file.is_sparse_directory());
+    }
+
+    #[test]
+    fn test_read_full_index() {
+        let data = create_index_file(
+            vec![(
+                0o100644,
+                "d00491fd7e5bb6fa28c517a0bb32b8b506539d4d",
+                "README.md",
+                false,
+            )],
+            vec![(b"TREE", b"\0-1 0\n")],
+            ObjectFormat::Sha1,
+        );
+
+        let index = GitIndex::from_data(&data).unwrap();
+
+        assert!(!index.is_sparse());
+        assert!(index.sparse_directories().is_empty());
+        assert_eq!(
+            index.get_entry_by_path("README.md").unwrap().hash,
+            "d00491fd7e5bb6fa28c517a0bb32b8b506539d4d"
+        );
+    }
+
+    #[test]
+    fn test_read_cache_tree() {
+        let root_hash = "4b825dc642cb6eb9a060e54bf8d69288fbee4904";
+        let subtree_hash = "d00491fd7e5bb6fa28c517a0bb32b8b506539d4d";
+        let mut extension = b"\x003 2\n".to_vec();
+        extension.extend_from_slice(&from_hex(root_hash).unwrap());
+        extension.extend_from_slice(b"a\x002 0\n");
+        extension.extend_from_slice(&from_hex(subtree_hash).unwrap());
+        extension.extend_from_slice(b"b\x00-1 0\n");
+
+        let data = create_index_file(
+            vec![(
+                0o100644,
+                "d00491fd7e5bb6fa28c517a0bb32b8b506539d4d",
+                "README.md",
+                false,
+            )],
+            vec![(CACHE_TREE_EXTENSION, &extension)],
+            ObjectFormat::Sha1,
+        );
+
+        let index = GitIndex::from_data(&data).unwrap();
+        let cache_tree = index.cache_tree().unwrap();
+
+        assert_eq!(cache_tree.get_name(), "");
+        assert_eq!(cache_tree.get_entry_count(), 3);
+        assert_eq!(cache_tree.get_hash(), Some(&root_hash.to_string()));
+        assert_eq!(cache_tree.subtrees().len(), 2);
+
+        let subtree = cache_tree.get_subtree("a").unwrap();
+        assert_eq!(subtree.get_entry_count(), 2);
+        assert_eq!(subtree.get_hash(), Some(&subtree_hash.to_string()));
+        assert_eq!(cache_tree.get_subtree("b").unwrap().get_hash(), None);
+
+        let truncated = create_index_file(
+            Vec::new(),
+            vec![(CACHE_TREE_EXTENSION, &extension[..extension.len() - 3])],
+            ObjectFormat::Sha1,
+        );
+        assert_eq!(
+            GitIndex::from_data(&truncated),
+            Err(GitObjectError::InvalidIndexFile)
+        );
+
+        // A subtree count far larger than the data is an error, not an allocation
+        let huge_count = format!("\0-1 {}\n", usize::MAX).into_bytes();
+        let nested = b"a\0-1 1\n".repeat(CACHE_TREE_MAX_DEPTH + 2);
+        for extension in [huge_count, nested] {
+            let data = create_index_file(
+                Vec::new(),
+                vec![(CACHE_TREE_EXTENSION, &extension)],
+                ObjectFormat::Sha1,
+            );
+            assert_eq!(
+                GitIndex::from_data(&data),
+                Err(GitObjectError::InvalidIndexFile)
+            );
+        }
+    }
+
+    #[test]
+    fn test_read_index_invalid_checksum() {
+        let mut data = create_index_file(
+            vec![(
+                0o100644,
+                "d00491fd7e5bb6fa28c517a0bb32b8b506539d4d",
+                "README.md",
+                false,
+            )],
+            Vec::new(),
+            ObjectFormat::Sha1,
+        );
+        let last = data.len() - 1;
+        data[last] ^= 0xff;
+
+        assert_eq!(
+            GitIndex::from_data(&data),
+            Err(GitObjectError::InvalidIndexFile)
+        );
+    }
+
+    #[test]
+    fn test_read_index_invalid_signature() {
+        assert_eq!(
+            GitIndex::from_data(&[0; 40]),
+            Err(GitObjectError::InvalidIndexFile)
+        );
+    }
+
+    #[test]
+    fn test_read_index_entry_count_past_end() {
+        // The count comes from the file, nothing is allocated for entries that aren't there
+        let mut data = INDEX_SIGNATURE.to_vec();
+        data.extend_from_slice(&2u32.to_be_bytes());
+        data.extend_from_slice(&u32::MAX.to_be_bytes());
+        let checksum = ObjectFormat::Sha1.digest(&data);
+        data.extend_from_slice(&checksum);
+
+        assert_eq!(
+            GitIndex::from_data(&data),
+            Err(GitObjectError::InvalidIndexFile)
+        );
+    }
+
+    #[test]
+    fn test_read_index_sha256() {
+        let hash = "8b7f2d6de5b7b3ec4b9a8ec4e8c1b4f1c2a2c9f2c6a1b3f6e3e8f1d0a9b8c7d6";
+        let data = create_index_file(
+            vec![(0o100644, hash, "README.md", false)],
+            Vec::new(),
+            ObjectFormat::Sha256,
+        );
+
+        let index = GitIndex::from_data_with_format(&data, ObjectFormat::Sha256).unwrap();
+
+        assert_eq!(index.get_entry_by_path("README.md").unwrap().hash, hash);
+        assert_eq!(
+            GitIndex::from_data(&data),
+            Err(GitObjectError::InvalidIndexFile)
+        );
+    }
+}
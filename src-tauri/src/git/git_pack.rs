@@ -0,0 +1,978 @@
+use std::{
+    collections::HashMap,
+    fmt::Write,
+    fs,
+    io::{Read, Seek, SeekFrom},
+    path::{Path, PathBuf},
+};
+
+use flate2::read::ZlibDecoder;
+
+use super::{
+    git_hash::hash_object,
+    object::{Header, ObjectFormat},
+};
+use crate::errors::git_object_error::GitObjectError;
+
+pub const PACK_FOLDER: &str = "pack";
+
+const INDEX_SIGNATURE: &[u8] = b"\xfftOc";
+const INDEX_VERSION: u32 = 2;
+const INDEX_HEADER_SIZE: usize = 8;
+const FANOUT_SIZE: usize = 256 * 4;
+const LARGE_OFFSET_FLAG: u32 = 0x8000_0000;
+const PACK_SIGNATURE: &[u8] = b"PACK";
+const PACK_VERSIONS: [u32; 2] = [2, 3];
+const PACK_HEADER_SIZE: usize = 12;
+
+const OBJ_COMMIT: u8 = 1;
+const OBJ_TREE: u8 = 2;
+const OBJ_BLOB: u8 = 3;
+const OBJ_TAG: u8 = 4;
+const OBJ_OFS_DELTA: u8 = 6;
+const OBJ_REF_DELTA: u8 = 7;
+
+// The type and size take at most 10 bytes, followed by the delta base which is
+// at most a sha256 hash
+const ENTRY_HEADER_MAX_SIZE: usize = 10 + 32;
+// Both sizes at the start of a delta are varints of at most 10 bytes
+const DELTA_SIZES_MAX_SIZE: u64 = 20;
+// A copy instruction without any size bytes copies 64KiB
+const DELTA_COPY_DEFAULT_SIZE: usize = 0x10000;
+// The result size comes from the delta itself, only this much is reserved up front
+const DELTA_RESULT_PREALLOCATE_MAX_SIZE: u64 = 16 * 1024 * 1024;
+// Sizes are u64, more 7 bit groups than fit in it mean the varint is corrupt
+const VARINT_MAX_SHIFT: u32 = 64;
+
+enum DeltaBase {
+    Offset(u64),
+    Hash(String),
+}
+
+struct EntryHeader {
+    object_type: u8,
+    size: u64,
+    base: Option<DeltaBase>,
+    data_offset: u64,
+}
+
+// The index tables are kept as they are in the file, the hashes in a single buffer
+// and the fanout to narrow every lookup down to the hashes sharing its first byte
+#[derive(Debug)]
+pub struct GitPack {
+    pack_path: PathBuf,
+    object_format: ObjectFormat,
+    fanout: Vec<u32>,
+    hashes: Vec<u8>,
+    offsets: Vec<u64>,
+}
+
+impl GitPack {
+    // Opens `pack-<hash>.pack` through its `.idx` file, which maps hashes to offsets
+    pub fn open(index_path: &Path, object_format: ObjectFormat) -> Result<Self, GitObjectError> {
+        let data = fs::read(index_path).map_err(|_| GitObjectError::FileReadError)?;
+
+        Self::from_index_data(index_path.with_extension("pack"), &data, object_format)
+    }
+
+    pub fn from_index_data(
+        pack_path: PathBuf,
+        data: &[u8],
+        object_format: ObjectFormat,
+    ) -> Result<Self, GitObjectError> {
+        if data.len() < INDEX_HEADER_SIZE + FANOUT_SIZE
+            || &data[..INDEX_SIGNATURE.len()] != INDEX_SIGNATURE
+            || read_u32(data, 4)? != INDEX_VERSION
+        {
+            return Err(GitObjectError::InvalidPackFile);
+        }
+
+        let fanout = (0..FANOUT_SIZE / 4)
+            .map(|i| read_u32(data, INDEX_HEADER_SIZE + i * 4))
+            .collect::<Result<Vec<u32>, GitObjectError>>()?;
+        if fanout.windows(2).any(|counts| counts[0] > counts[1]) {
+            return Err(GitObjectError::InvalidPackFile);
+        }
+
+        let count = fanout[fanout.len() - 1] as usize;
+        let hash_size = object_format.hash_byte_len();
+        let hashes_start = INDEX_HEADER_SIZE + FANOUT_SIZE;
+        // Every object has a crc32 between its hash and its offset
+        let offsets_start = hashes_start + count * (hash_size + 4);
+        let large_offsets_start = offsets_start + count * 4;
+
+        let hashes = data
+            .get(hashes_start..hashes_start + count * hash_size)
+            .ok_or(GitObjectError::InvalidPackFile)?
+            .to_vec();
+
+        let mut offsets = Vec::with_capacity(count);
+        for i in 0..count {
+            let offset = read_u32(data, offsets_start + i * 4)?;
+            offsets.push(if offset & LARGE_OFFSET_FLAG != 0 {
+                let position = large_offsets_start + (offset & !LARGE_OFFSET_FLAG) as usize * 8;
+                ((read_u32(data, position)? as u64) << 32) | read_u32(data, position + 4)? as u64
+            } else {
+                offset as u64
+            });
+        }
+
+        Ok(Self {
+            pack_path,
+            object_format,
+            fanout,
+            hashes,
+            offsets,
+        })
+    }
+
+    pub fn len(&self) -> usize {
+        self.offsets.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.offsets.is_empty()
+    }
+
+    pub fn contains(&self, hash: &str) -> bool {
+        self.find_offset(hash).is_some()
+    }
+
+    pub fn find_offset(&self, hash: &str) -> Option<u64> {
+        let hash = from_hex(hash)?;
+        let hash_size = self.object_format.hash_byte_len();
+        if hash.len() != hash_size {
+            return None;
+        }
+
+        let first_byte = hash[0] as usize;
+        let mut low = match first_byte {
+            0 => 0,
+            _ => self.fanout[first_byte - 1] as usize,
+        };
+        let mut high = self.fanout[first_byte] as usize;
+        while low < high {
+            let middle = low + (high - low) / 2;
+            let candidate = &self.hashes[middle * hash_size..(middle + 1) * hash_size];
+            match candidate.cmp(hash.as_slice()) {
+                std::cmp::Ordering::Less => low = middle + 1,
+                std::cmp::Ordering::Greater => high = middle,
+                std::cmp::Ordering::Equal => return Some(self.offsets[middle]),
+            }
+        }
+
+        None
+    }
+
+    // The hashes starting with an abbreviated hash of at least two hex digits, only the
+    // ones sharing its first byte are compared
+    pub fn hashes_with_prefix(&self, prefix: &str) -> Vec<String> {
+        let Some(first_byte) = prefix.get(..2).and_then(from_hex) else {
+            return Vec::new();
+        };
+        let first_byte = first_byte[0] as usize;
+        let hash_size = self.object_format.hash_byte_len();
+
+        let low = match first_byte {
+            0 => 0,
+            _ => self.fanout[first_byte - 1] as usize,
+        };
+        let high = self.fanout[first_byte] as usize;
+        self.hashes[low * hash_size..high * hash_size]
+            .chunks(hash_size)
+            .map(to_hex)
+            .filter(|hash| hash.starts_with(prefix))
+            .collect()
+    }
+
+    // How many deltas have to be applied on top of the base object to rebuild
+    // `hash`, 0 when it is stored whole
+    pub fn delta_chain_length(&self, hash: &str) -> Result<usize, GitObjectError> {
+        let offset = self
+            .find_offset(hash)
+            .ok_or(GitObjectError::FileReadError)?;
+        let mut pack = self.open_pack()?;
+
+        let header = self.read_entry_header(&mut pack, offset)?;
+        let (_, length) = self.find_base(&mut pack, header)?;
+
+        Ok(length)
+    }
+
+    // The type is only known from the base at the end of the delta chain, while a delta
+    // records the size of the object it produces at the start of its data
+    pub fn object_header(&self, hash: &str) -> Result<(Header, u64), GitObjectError> {
+        let offset = self
+            .find_offset(hash)
+            .ok_or(GitObjectError::FileReadError)?;
+        let mut pack = self.open_pack()?;
+
+        let header = self.read_entry_header(&mut pack, offset)?;
+        let size = match header.base {
+            Some(_) => self.read_delta_result_size(&mut pack, header.data_offset)?,
+            None => header.size,
+        };
+        let (base, _) = self.find_base(&mut pack, header)?;
+
+        Ok((header_from_type(base.object_type)?, size))
+    }
+
+    // Inflates the base at the end of the delta chain and applies the deltas back up
+    pub fn read_object(&self, hash: &str) -> Result<(Header, Vec<u8>), GitObjectError> {
+        let offset = self
+            .find_offset(hash)
+            .ok_or(GitObjectError::FileReadError)?;
+        let mut pack = self.open_pack()?;
+
+        let mut deltas = Vec::new();
+        let mut header = self.read_entry_header(&mut pack, offset)?;
+        while let Some(base) = header.base.take() {
+            if deltas.len() >= self.len() {
+                return Err(GitObjectError::InvalidPackFile);
+            }
+
+            deltas.push(self.inflate_entry(&mut pack, &header)?);
+            let offset = match base {
+                DeltaBase::Offset(base_offset) => base_offset,
+                DeltaBase::Hash(base_hash) => self
+                    .find_offset(&base_hash)
+                    .ok_or(GitObjectError::InvalidPackFile)?,
+            };
+            header = self.read_entry_header(&mut pack, offset)?;
+        }
+
+        let mut object = self.inflate_entry(&mut pack, &header)?;
+        for delta in deltas.iter().rev() {
+            object = apply_delta(&object, delta)?;
+        }
+
+        Ok((header_from_type(header.object_type)?, object))
+    }
+
+    fn inflate_entry(
+        &self,
+        pack: &mut fs::File,
+        header: &EntryHeader,
+    ) -> Result<Vec<u8>, GitObjectError> {
+        pack.seek(SeekFrom::Start(header.data_offset))
+            .map_err(|_| GitObjectError::FileReadError)?;
+
+        let mut data = Vec::new();
+        ZlibDecoder::new(pack)
+            .take(header.size)
+            .read_to_end(&mut data)
+            .map_err(|_| GitObjectError::DecompressionError)?;
+        if data.len() as u64 != header.size {
+            return Err(GitObjectError::InvalidPackFile);
+        }
+
+        Ok(data)
+    }
+
+    fn open_pack(&self) -> Result<fs::File, GitObjectError> {
+        let mut pack =
+            fs::File::open(&self.pack_path).map_err(|_| GitObjectError::FileReadError)?;
+
+        let mut signature = [0; 4];
+        pack.read_exact(&mut signature)
+            .map_err(|_| GitObjectError::InvalidPackFile)?;
+        if signature != PACK_SIGNATURE {
+            return Err(GitObjectError::InvalidPackFile);
+        }
+
+        Ok(pack)
+    }
+
+    // Follows the deltas down to the object stored whole, counting them on the way
+    fn find_base(
+        &self,
+        pack: &mut fs::File,
+        mut header: EntryHeader,
+    ) -> Result<(EntryHeader, usize), GitObjectError> {
+        let mut length = 0;
+        while let Some(base) = header.base {
+            // A chain can't be longer than the pack, anything else is a loop
+            if length >= self.len() {
+                return Err(GitObjectError::InvalidPackFile);
+            }
+
+            let offset = match base {
+                DeltaBase::Offset(base_offset) => base_offset,
+                DeltaBase::Hash(base_hash) => self
+                    .find_offset(&base_hash)
+                    .ok_or(GitObjectError::InvalidPackFile)?,
+            };
+            header = self.read_entry_header(pack, offset)?;
+            length += 1;
+        }
+
+        Ok((header, length))
+    }
+
+    fn read_entry_header(
+        &self,
+        pack: &mut fs::File,
+        offset: u64,
+    ) -> Result<EntryHeader, GitObjectError> {
+        let mut header = [0; ENTRY_HEADER_MAX_SIZE];
+        pack.seek(SeekFrom::Start(offset))
+            .map_err(|_| GitObjectError::FileReadError)?;
+        let read = pack
+            .read(&mut header)
+            .map_err(|_| GitObjectError::FileReadError)?;
+
+        parse_entry_header(&header[..read], offset, self.object_format)
+    }
+
+    // Delta data starts with the size of its base and then the size of its result
+    fn read_delta_result_size(
+        &self,
+        pack: &mut fs::File,
+        data_offset: u64,
+    ) -> Result<u64, GitObjectError> {
+        pack.seek(SeekFrom::Start(data_offset))
+            .map_err(|_| GitObjectError::FileReadError)?;
+
+        let mut sizes = Vec::new();
+        ZlibDecoder::new(pack)
+            .take(DELTA_SIZES_MAX_SIZE)
+            .read_to_end(&mut sizes)
+            .map_err(|_| GitObjectError::DecompressionError)?;
+
+        let mut sizes = sizes.into_iter();
+        read_delta_size(&mut sizes)?;
+        read_delta_size(&mut sizes)
+    }
+}
+
+// Inflates every object of a pack held in memory, which has no index to look objects
+// up in (e.g. the one inside a bundle). Deltas are applied, so each object comes back
+// whole together with its type
+pub fn read_pack_objects(
+    data: &[u8],
+    object_format: ObjectFormat,
+) -> Result<HashMap<String, (Header, Vec<u8>)>, GitObjectError> {
+    if data.len() < PACK_HEADER_SIZE + object_format.hash_byte_len()
+        || &data[..PACK_SIGNATURE.len()] != PACK_SIGNATURE
+        || !PACK_VERSIONS.contains(&read_u32(data, 4)?)
+    {
+        return Err(GitObjectError::InvalidPackFile);
+    }
+
+    // The count comes from the pack itself, so nothing is reserved up front for it
+    let count = read_u32(data, 8)? as usize;
+    let mut entries = Vec::new();
+    let mut offset = PACK_HEADER_SIZE;
+    for _ in 0..count {
+        let entry = data.get(offset..).ok_or(GitObjectError::InvalidPackFile)?;
+        let header = parse_entry_header(entry, offset as u64, object_format)?;
+        let data_offset = header.data_offset as usize;
+
+        let compressed = data
+            .get(data_offset..)
+            .ok_or(GitObjectError::InvalidPackFile)?;
+        let mut zlib = ZlibDecoder::new(compressed);
+        let mut content = Vec::new();
+        zlib.read_to_end(&mut content)
+            .map_err(|_| GitObjectError::DecompressionError)?;
+        if content.len() as u64 != header.size {
+            return Err(GitObjectError::InvalidPackFile);
+        }
+
+        entries.push((offset as u64, header, content));
+        offset = data_offset + zlib.total_in() as usize;
+    }
+
+    // The pack ends with the hash of everything before it
+    let checksum = data
+        .get(offset..offset + object_format.hash_byte_len())
+        .ok_or(GitObjectError::InvalidPackFile)?;
+    if object_format.digest(&data[..offset]) != checksum {
+        return Err(GitObjectError::InvalidPackFile);
+    }
+
+    // Bases can come after their deltas, so deltas are applied over as many rounds as
+    // it takes for every base to be known. The packs of bundles with prerequisites are
+    // thin, deltas against objects outside of them can't be rebuilt and are left out
+    let mut by_offset = HashMap::<u64, (u8, Vec<u8>)>::new();
+    let mut offsets_by_hash = HashMap::<String, u64>::new();
+    let mut pending = entries;
+    while !pending.is_empty() {
+        let pending_count = pending.len();
+        let mut waiting = Vec::new();
+        for (offset, header, content) in pending {
+            let (object_type, object) = match &header.base {
+                None => (header.object_type, content),
+                Some(base) => {
+                    let base_offset = match base {
+                        DeltaBase::Offset(base_offset) => Some(*base_offset),
+                        DeltaBase::Hash(base_hash) => offsets_by_hash.get(base_hash).copied(),
+                    };
+
+                    match base_offset.and_then(|base_offset| by_offset.get(&base_offset)) {
+                        Some((object_type, base)) => (*object_type, apply_delta(base, &content)?),
+                        None => {
+                            waiting.push((offset, header, content));
+                            continue;
+                        }
+                    }
+                }
+            };
+
+            let hash = hash_object(object_format, &header_from_type(object_type)?, &object);
+
+            offsets_by_hash.insert(hash, offset);
+            by_offset.insert(offset, (object_type, object));
+        }
+
+        if waiting.len() == pending_count {
+            break;
+        }
+        pending = waiting;
+    }
+
+    offsets_by_hash
+        .into_iter()
+        .filter_map(|(hash, offset)| by_offset.remove(&offset).map(|object| (hash, object)))
+        .map(|(hash, (object_type, object))| Ok((hash, (header_from_type(object_type)?, object))))
+        .collect()
+}
+
+// A delta starts with the sizes of its base and its result, followed by instructions
+// that either copy a range of the base or insert the bytes that follow them
+fn apply_delta(base: &[u8], delta: &[u8]) -> Result<Vec<u8>, GitObjectError> {
+    let mut bytes = delta.iter().copied();
+    if read_delta_size(&mut bytes)? != base.len() as u64 {
+        return Err(GitObjectError::InvalidPackFile);
+    }
+    let result_size = read_delta_size(&mut bytes)?;
+
+    let mut result =
+        Vec::with_capacity(result_size.min(DELTA_RESULT_PREALLOCATE_MAX_SIZE) as usize);
+    while let Some(instruction) = bytes.next() {
+        if instruction & 0x80 != 0 {
+            // The low 4 bits say which offset bytes follow, the next 3 which size bytes
+            let mut read_bits = |first_bit: u8, count: u8| -> Result<usize, GitObjectError> {
+                let mut value = 0;
+                for i in 0..count {
+                    if instruction & (1 << (first_bit + i)) != 0 {
+                        let byte = bytes.next().ok_or(GitObjectError::InvalidPackFile)?;
+                        value |= (byte as usize) << (8 * i);
+                    }
+                }
+                Ok(value)
+            };
+            let offset = read_bits(0, 4)?;
+            let size = match read_bits(4, 3)? {
+                0 => DELTA_COPY_DEFAULT_SIZE,
+                size => size,
+            };
+
+            result.extend_from_slice(
+                base.get(offset..offset + size)
+                    .ok_or(GitObjectError::InvalidPackFile)?,
+            );
+        } else if instruction != 0 {
+            for _ in 0..instruction {
+                result.push(bytes.next().ok_or(GitObjectError::InvalidPackFile)?);
+            }
+        } else {
+            return Err(GitObjectError::InvalidPackFile);
+        }
+
+        if result.len() as u64 > result_size {
+            return Err(GitObjectError::InvalidPackFile);
+        }
+    }
+
+    if result.len() as u64 != result_size {
+        return Err(GitObjectError::InvalidPackFile);
+    }
+
+    Ok(result)
+}
+
+fn header_from_type(object_type: u8) -> Result<Header, GitObjectError> {
+    match object_type {
+        OBJ_COMMIT => Ok(Header::Commit),
+        OBJ_TREE => Ok(Header::Tree),
+        OBJ_BLOB => Ok(Header::Blob),
+        OBJ_TAG => Ok(Header::Tag),
+        _ => Err(GitObjectError::InvalidPackFile),
+    }
+}
+
+// `header` holds the bytes of the entry starting at `offset` in the pack
+fn parse_entry_header(
+    header: &[u8],
+    offset: u64,
+    object_format: ObjectFormat,
+) -> Result<EntryHeader, GitObjectError> {
+    let mut byte = *header.first().ok_or(GitObjectError::InvalidPackFile)?;
+    let object_type = (byte >> 4) & 0x7;
+    // The low 4 bits start the size, the following bytes add 7 bits each while
+    // the top bit is set
+    let mut size = (byte & 0x0f) as u64;
+    let mut shift = 4;
+    let mut position = 0;
+    while byte & 0x80 != 0 {
+        position += 1;
+        byte = *header
+            .get(position)
+            .ok_or(GitObjectError::InvalidPackFile)?;
+        if shift >= VARINT_MAX_SHIFT {
+            return Err(GitObjectError::InvalidPackFile);
+        }
+        size |= ((byte & 0x7f) as u64) << shift;
+        shift += 7;
+    }
+    position += 1;
+
+    let base = match object_type {
+        OBJ_OFS_DELTA => {
+            let mut byte = *header
+                .get(position)
+                .ok_or(GitObjectError::InvalidPackFile)?;
+            let mut distance = (byte & 0x7f) as u64;
+            while byte & 0x80 != 0 {
+                position += 1;
+                byte = *header
+                    .get(position)
+                    .ok_or(GitObjectError::InvalidPackFile)?;
+                distance = distance
+                    .checked_add(1)
+                    .filter(|distance| distance.leading_zeros() >= 7)
+                    .ok_or(GitObjectError::InvalidPackFile)?
+                    << 7
+                    | (byte & 0x7f) as u64;
+            }
+            position += 1;
+
+            Some(DeltaBase::Offset(
+                offset
+                    .checked_sub(distance)
+                    .ok_or(GitObjectError::InvalidPackFile)?,
+            ))
+        }
+        OBJ_REF_DELTA => {
+            let hash_size = object_format.hash_byte_len();
+            let hash = header
+                .get(position..position + hash_size)
+                .ok_or(GitObjectError::InvalidPackFile)?;
+            position += hash_size;
+
+            Some(DeltaBase::Hash(to_hex(hash)))
+        }
+        _ => None,
+    };
+
+    Ok(EntryHeader {
+        object_type,
+        size,
+        base,
+        data_offset: offset + position as u64,
+    })
+}
+
+fn read_delta_size(bytes: &mut impl Iterator<Item = u8>) -> Result<u64, GitObjectError> {
+    let mut size = 0;
+    let mut shift = 0;
+    loop {
+        let byte = bytes.next().ok_or(GitObjectError::InvalidPackFile)?;
+        if shift >= VARINT_MAX_SHIFT {
+            return Err(GitObjectError::InvalidPackFile);
+        }
+        size |= ((byte & 0x7f) as u64) << shift;
+        shift += 7;
+
+        if byte & 0x80 == 0 {
+            return Ok(size);
+        }
+    }
+}
+
+pub fn to_hex(bytes: &[u8]) -> String {
+    bytes.iter().fold(String::new(), |mut output, byte| {
+        let _ = write!(output, "{byte:02x}");
+        output
+    })
+}
+
+pub fn from_hex(hash: &str) -> Option<Vec<u8>> {
+    (0..hash.len())
+        .step_by(2)
+        .map(|i| u8::from_str_radix(hash.get(i..i + 2)?, 16).ok())
+        .collect()
+}
+
+fn read_u32(data: &[u8], position: usize) -> Result<u32, GitObjectError> {
+    data.get(position..position + 4)
+        .map(|bytes| u32::from_be_bytes([bytes[0], bytes[1], bytes[2], bytes[3]]))
+        .ok_or(GitObjectError::InvalidPackFile)
+}
+
+// Builds packs from hand written entries, shared with the project tests
+#[cfg(test)]
+pub mod test_utils {
+    use std::io::Write;
+
+    use flate2::write::ZlibEncoder;
+
+    use super::*;
+
+    pub fn hash_bytes(hash: &str) -> Vec<u8> {
+        (0..hash.len())
+            .step_by(2)
+            .map(|i| u8::from_str_radix(&hash[i..i + 2], 16).unwrap())
+            .collect()
+    }
+
+    // `base` is the already encoded delta base (an offset or a hash) and is empty for
+    // objects stored whole
+    pub fn encode_entry(object_type: u8, size: u64, base: &[u8], data: &[u8]) -> Vec<u8> {
+        let mut entry = vec![(object_type << 4) | (size & 0x0f) as u8];
+        let mut size = size >> 4;
+        while size > 0 {
+            *entry.last_mut().unwrap() |= 0x80;
+            entry.push((size & 0x7f) as u8);
+            size >>= 7;
+        }
+        entry.extend_from_slice(base);
+
+        let mut zlib = ZlibEncoder::new(Vec::new(), flate2::Compression::default());
+        zlib.write_all(data).unwrap();
+        entry.extend(zlib.finish().unwrap());
+
+        entry
+    }
+
+    pub fn encode_blob(data: &[u8]) -> Vec<u8> {
+        encode_entry(OBJ_BLOB, data.len() as u64, &[], data)
+    }
+
+    pub fn encode_object(object_type: &Header, data: &[u8]) -> Vec<u8> {
+        let object_type = match object_type {
+            Header::Commit => OBJ_COMMIT,
+            Header::Tree => OBJ_TREE,
+            Header::Tag => OBJ_TAG,
+            _ => OBJ_BLOB,
+        };
+
+        encode_entry(object_type, data.len() as u64, &[], data)
+    }
+
+    // Entries are written in the given order, the index is sorted by hash
+    pub fn write_pack(index_path: &Path, objects: &[(String, Vec<u8>)]) {
+        let mut pack = PACK_SIGNATURE.to_vec();
+        pack.extend_from_slice(&2u32.to_be_bytes());
+        pack.extend_from_slice(&(objects.len() as u32).to_be_bytes());
+
+        let mut offsets = Vec::new();
+        for (hash, entry) in objects {
+            offsets.push((hash_bytes(hash), pack.len() as u32));
+            pack.extend_from_slice(entry);
+        }
+        offsets.sort();
+
+        let mut index = INDEX_SIGNATURE.to_vec();
+        index.extend_from_slice(&INDEX_VERSION.to_be_bytes());
+        for first_byte in 0..=255u8 {
+            let count = offsets
+                .iter()
+                .filter(|(hash, _)| hash[0] <= first_byte)
+                .count() as u32;
+            index.extend_from_slice(&count.to_be_bytes());
+        }
+        for (hash, _) in &offsets {
+            index.extend_from_slice(hash);
+        }
+        for _ in &offsets {
+            index.extend_from_slice(&0u32.to_be_bytes());
+        }
+        for (_, offset) in &offsets {
+            index.extend_from_slice(&offset.to_be_bytes());
+        }
+
+        fs::create_dir_all(index_path.parent().unwrap()).unwrap();
+        fs::write(index_path, index).unwrap();
+        fs::write(index_path.with_extension("pack"), pack).unwrap();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::test_utils::*;
+    use super::*;
+    use tempdir::TempDir;
+
+    const BASE_HASH: &str = "1111111111111111111111111111111111111111";
+    const FIRST_DELTA_HASH: &str = "2222222222222222222222222222222222222222";
+    const SECOND_DELTA_HASH: &str = "3333333333333333333333333333333333333333";
+
+    // A blob, an offset delta on top of it and a reference delta on top of that
+    fn create_pack(folder: &Path) -> PathBuf {
+        let base = encode_entry(OBJ_BLOB, 11, &[], b"hello world");
+        // Only the sizes at the start of the deltas are read, not the instructions
+        let first_delta = encode_entry(OBJ_OFS_DELTA, 4, &[base.len() as u8], &[11, 5, 0, 0]);
+        let second_delta = encode_entry(
+            OBJ_REF_DELTA,
+            4,
+            &hash_bytes(FIRST_DELTA_HASH),
+            &[5, 0x80 | 0x2c, 0x02, 0],
+        );
+
+        let index_path = folder.join("pack-test.idx");
+        write_pack(
+            &index_path,
+            &[
+                (BASE_HASH.to_string(), base),
+                (FIRST_DELTA_HASH.to_string(), first_delta),
+                (SECOND_DELTA_HASH.to_string(), second_delta),
+            ],
+        );
+
+        index_path
+    }
+
+    #[test]
+    fn test_delta_chain_length() {
+        let folder = TempDir::new("pack_delta_chain_length").unwrap();
+        let pack = GitPack::open(&create_pack(folder.path()), ObjectFormat::Sha1).unwrap();
+
+        assert_eq!(pack.len(), 3);
+        assert_eq!(pack.delta_chain_length(BASE_HASH), Ok(0));
+        assert_eq!(pack.delta_chain_length(FIRST_DELTA_HASH), Ok(1));
+        assert_eq!(pack.delta_chain_length(SECOND_DELTA_HASH), Ok(2));
+        assert_eq!(
+            pack.delta_chain_length("4444444444444444444444444444444444444444"),
+            Err(GitObjectError::FileReadError)
+        );
+    }
+
+    #[test]
+    fn test_object_header() {
+        let folder = TempDir::new("pack_object_header").unwrap();
+        let pack = GitPack::open(&create_pack(folder.path()), ObjectFormat::Sha1).unwrap();
+
+        assert_eq!(pack.object_header(BASE_HASH), Ok((Header::Blob, 11)));
+        assert_eq!(pack.object_header(FIRST_DELTA_HASH), Ok((Header::Blob, 5)));
+        assert_eq!(
+            pack.object_header(SECOND_DELTA_HASH),
+            Ok((Header::Blob, 300))
+        );
+    }
+
+    #[test]
+    fn test_parse_entry_header_overflow() {
+        // A size with more 7 bit groups than a u64 holds
+        let size = [vec![0x80 | (OBJ_BLOB << 4)], vec![0xff; 12], vec![0x01]].concat();
+        assert!(parse_entry_header(&size, 100, ObjectFormat::Sha1).is_err());
+
+        // An offset delta whose distance doesn't fit in a u64
+        let distance = [vec![OBJ_OFS_DELTA << 4 | 1], vec![0xff; 12], vec![0x01]].concat();
+        assert!(parse_entry_header(&distance, 100, ObjectFormat::Sha1).is_err());
+    }
+
+    #[test]
+    fn test_read_object() {
+        let folder = TempDir::new("pack_read_object").unwrap();
+        let index_path = folder.path().join("pack-test.idx");
+
+        let base = encode_entry(OBJ_BLOB, 11, &[], b"hello world");
+        // Copies "hello " from the base and inserts "there", then appends "!" to that
+        let first_delta_data = [11, 11, 0x91, 0, 6, 5, b't', b'h', b'e', b'r', b'e'];
+        let first_delta = encode_entry(
+            OBJ_OFS_DELTA,
+            first_delta_data.len() as u64,
+            &[base.len() as u8],
+            &first_delta_data,
+        );
+        let second_delta_data = [11, 12, 0x90, 11, 1, b'!'];
+        let second_delta = encode_entry(
+            OBJ_REF_DELTA,
+            second_delta_data.len() as u64,
+            &hash_bytes(FIRST_DELTA_HASH),
+            &second_delta_data,
+        );
+        write_pack(
+            &index_path,
+            &[
+                (BASE_HASH.to_string(), base),
+                (FIRST_DELTA_HASH.to_string(), first_delta),
+                (SECOND_DELTA_HASH.to_string(), second_delta),
+            ],
+        );
+        let pack = GitPack::open(&index_path, ObjectFormat::Sha1).unwrap();
+
+        assert_eq!(
+            pack.read_object(BASE_HASH),
+            Ok((Header::Blob, b"hello world".to_vec()))
+        );
+        assert_eq!(
+            pack.read_object(FIRST_DELTA_HASH),
+            Ok((Header::Blob, b"hello there".to_vec()))
+        );
+        assert_eq!(
+            pack.read_object(SECOND_DELTA_HASH),
+            Ok((Header::Blob, b"hello there!".to_vec()))
+        );
+        assert_eq!(
+            pack.read_object("4444444444444444444444444444444444444444"),
+            Err(GitObjectError::FileReadError)
+        );
+    }
+
+    #[test]
+    fn test_apply_delta() {
+        let size = |mut size: usize| {
+            let mut bytes = Vec::new();
+            loop {
+                let byte = (size & 0x7f) as u8;
+                size >>= 7;
+                if size == 0 {
+                    bytes.push(byte);
+                    return bytes;
+                }
+                bytes.push(byte | 0x80);
+            }
+        };
+        let base = (0..0x10100).map(|i| (i % 251) as u8).collect::<Vec<_>>();
+
+        // A copy of 3 bytes at the two byte offset 0x0102, an insert and a copy without
+        // size bytes, which copies 0x10000 bytes from the start
+        let delta = [
+            size(base.len()),
+            size(3 + 2 + 0x10000),
+            vec![0x80 | 0x01 | 0x02 | 0x10, 0x02, 0x01, 3],
+            vec![2, b'o', b'k'],
+            vec![0x80],
+        ]
+        .concat();
+        let expected = [&base[0x0102..0x0105], b"ok", &base[..0x10000]].concat();
+        assert_eq!(apply_delta(&base, &delta), Ok(expected));
+
+        let invalid_deltas = [
+            // The base has another size
+            [size(10), size(1), vec![1, b'a']].concat(),
+            // The result has another size
+            [size(base.len()), size(2), vec![1, b'a']].concat(),
+            // The copy reads past the end of the base
+            [
+                size(base.len()),
+                size(2),
+                vec![0x80 | 0x07 | 0x10, 0xff, 0xff, 0xff, 2],
+            ]
+            .concat(),
+            // The insert is cut short
+            [size(base.len()), size(3), vec![3, b'a']].concat(),
+            // Instruction 0 is reserved
+            [size(base.len()), size(0), vec![0]].concat(),
+            // The result size doesn't fit in a u64
+            [size(base.len()), vec![0xff; 10], vec![0x01]].concat(),
+            // A huge result size is not reserved up front
+            [size(base.len()), size(usize::MAX), vec![1, b'a']].concat(),
+            // The instructions write more than the result size
+            [size(base.len()), size(1), vec![2, b'o', b'k']].concat(),
+        ];
+        for delta in invalid_deltas {
+            assert_eq!(
+                apply_delta(&base, &delta),
+                Err(GitObjectError::InvalidPackFile)
+            );
+        }
+    }
+
+    #[test]
+    fn test_find_offset() {
+        let folder = TempDir::new("pack_find_offset").unwrap();
+        let index_path = folder.path().join("pack-test.idx");
+        let hashes = [
+            "0a00000000000000000000000000000000000000",
+            "aa00000000000000000000000000000000000000",
+            "aa11000000000000000000000000000000000000",
+            "aaff000000000000000000000000000000000000",
+            "ff00000000000000000000000000000000000000",
+        ];
+        write_pack(
+            &index_path,
+            &hashes
+                .iter()
+                .map(|hash| (hash.to_string(), encode_blob(hash.as_bytes())))
+                .collect::<Vec<_>>(),
+        );
+        let pack = GitPack::open(&index_path, ObjectFormat::Sha1).unwrap();
+
+        let mut offsets = hashes
+            .iter()
+            .map(|hash| pack.find_offset(hash).unwrap())
+            .collect::<Vec<u64>>();
+        offsets.dedup();
+        assert_eq!(offsets.len(), hashes.len());
+        assert!(offsets.windows(2).all(|offsets| offsets[0] < offsets[1]));
+
+        assert_eq!(
+            pack.find_offset("aa10000000000000000000000000000000000000"),
+            None
+        );
+        assert_eq!(
+            pack.find_offset("5500000000000000000000000000000000000000"),
+            None
+        );
+        assert_eq!(pack.find_offset("aa11"), None);
+        assert_eq!(
+            pack.find_offset("zz00000000000000000000000000000000000000"),
+            None
+        );
+    }
+
+    #[test]
+    fn test_read_pack_objects() {
+        let base = encode_entry(OBJ_BLOB, 11, &[], b"hello world");
+        // Copies "hello " from the base and inserts "there"
+        let delta_data = [11, 11, 0x91, 0, 6, 5, b't', b'h', b'e', b'r', b'e'];
+        let delta = encode_entry(
+            OBJ_OFS_DELTA,
+            delta_data.len() as u64,
+            &[base.len() as u8],
+            &delta_data,
+        );
+
+        let mut pack = PACK_SIGNATURE.to_vec();
+        pack.extend_from_slice(&2u32.to_be_bytes());
+        pack.extend_from_slice(&2u32.to_be_bytes());
+        pack.extend_from_slice(&base);
+        pack.extend_from_slice(&delta);
+        let checksum = ObjectFormat::Sha1.digest(&pack);
+        pack.extend_from_slice(&checksum);
+
+        let objects = read_pack_objects(&pack, ObjectFormat::Sha1).unwrap();
+        assert_eq!(objects.len(), 2);
+        assert_eq!(
+            objects.get(&ObjectFormat::Sha1.hash(b"blob 11\0hello world")),
+            Some(&(Header::Blob, b"hello world".to_vec()))
+        );
+        assert_eq!(
+            objects.get(&ObjectFormat::Sha1.hash(b"blob 11\0hello there")),
+            Some(&(Header::Blob, b"hello there".to_vec()))
+        );
+
+        let last = pack.len() - 1;
+        pack[last] ^= 0xff;
+        assert_eq!(
+            read_pack_objects(&pack, ObjectFormat::Sha1).unwrap_err(),
+            GitObjectError::InvalidPackFile
+        );
+
+        // A count far beyond what the pack holds fails on the missing entries
+        pack[8..12].copy_from_slice(&u32::MAX.to_be_bytes());
+        assert!(read_pack_objects(&pack, ObjectFormat::Sha1).is_err());
+    }
+
+    #[test]
+    fn test_invalid_index() {
+        assert_eq!(
+            GitPack::from_index_data(PathBuf::new(), b"DIRC", ObjectFormat::Sha1).unwrap_err(),
+            GitObjectError::InvalidPackFile
+        );
+    }
+}
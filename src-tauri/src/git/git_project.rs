@@ -1,14 +1,48 @@
+use chrono::NaiveDate;
 use serde::{Deserialize, Serialize};
-use std::{fs, path::PathBuf};
+use std::{
+    fs,
+    path::{Path, PathBuf},
+    sync::Arc,
+    time::Duration,
+};
 use strum::IntoEnumIterator;
 
 use super::{
-    git_branch::GitBranch,
+    git_activity::{commit_activity, Bucket},
+    git_ancestry::{branches_containing, is_ancestor_of},
+    git_bisect::{bisect_next, BisectStatus},
+    git_branch::{create_branch, delete_branch, GitBranch},
+    git_cache::GitCache,
+    git_commit::GitCommit,
+    git_commit_author::GitCommitAuthor,
+    git_commit_detail::CommitDetail,
+    git_config::GitConfig,
+    git_diff::{diff_staged, TreeChange},
+    git_directory::{blob_hash_at, list_dir, DirEntry},
     git_files::{GitFilesOptional, GitFilesRequired},
     git_folders::{GitBranchType, GitFolders, GitRefs, GIT_FOLDER},
+    git_index::GitIndex,
+    git_pack::{GitPack, PACK_FOLDER},
     git_project_state::GitProjectState,
+    git_reflog::{read_reflog, ReflogEntry},
+    git_refs::{
+        all_refs, is_valid_ref_name, list_branches, resolve_head, GitRef, HeadState, RefInfo,
+    },
+    git_revspec::resolve_revspec,
+    git_snapshot::RepoSnapshot,
+    git_status::{status, working_file_matches_blob, GitStatusEntry},
+    git_tag::{create_annotated_tag, create_lightweight_tag, tags_by_date, TagDetails, TagRef},
+    git_worktree::{resolve_git_dirs, resolve_ref_followed, WorktreeInfo},
+    object::{
+        expand_hash, peek_object_header, read_raw_compressed, GitObject, Header, ObjectFormat,
+    },
 };
-use crate::errors::git_error::GitError;
+use crate::errors::{git_error::GitError, git_object_error::GitObjectError};
+
+// Modification times are only as precise as the file system, a pack added within this
+// long of the last change to the pack folder may leave its time as it was
+const PACK_DIR_RACY_INTERVAL: Duration = Duration::from_secs(2);
 
 #[derive(Debug, PartialEq, Serialize, Deserialize, Clone)]
 #[serde(rename_all = "camelCase")]
@@ -19,6 +53,10 @@ pub struct GitProject {
     remotes: Vec<String>,
     remote_branches: Vec<GitBranch>,
     tags: Vec<GitBranch>,
+    #[serde(default)]
+    object_format: ObjectFormat,
+    #[serde(skip)]
+    cache: GitCache,
 }
 
 impl GitProject {
@@ -30,15 +68,54 @@ impl GitProject {
             remotes: Vec::new(),
             remote_branches: Vec::new(),
             tags: Vec::new(),
+            object_format: ObjectFormat::Sha1,
+            cache: GitCache::default(),
         }
     }
 
+    // A repository has a `.git` folder, or a `.git` file pointing at its git dir as in
+    // worktrees and submodules, with a HEAD inside. Branches are only read by `update`
+    pub fn open(directory: &str) -> Result<GitProject, GitObjectError> {
+        // Symlinks are resolved once so that every path of the project is built from the
+        // same root. On Windows dunce keeps the usual `C:\` form instead of the `\\?\` one
+        // from fs::canonicalize
+        let directory =
+            dunce::canonicalize(directory).map_err(|_| GitObjectError::NotAGitRepository)?;
+        let directory = directory
+            .to_str()
+            .ok_or(GitObjectError::NotAGitRepository)?;
+
+        let (git_dir, _) = resolve_git_dirs(Path::new(directory))
+            .map_err(|_| GitObjectError::NotAGitRepository)?;
+        if !git_dir.join(GitFilesRequired::HEAD.to_string()).is_file() {
+            return Err(GitObjectError::NotAGitRepository);
+        }
+
+        let mut project = GitProject::new(directory);
+        project.fetch_object_format();
+        project.set_state(GitProjectState::Valid);
+
+        Ok(project)
+    }
+
+    // Opens the repository together with what the first paint needs, for a fast startup
+    pub fn open_with_snapshot(
+        directory: &str,
+    ) -> Result<(GitProject, RepoSnapshot), GitObjectError> {
+        let project = GitProject::open(directory)?;
+        let snapshot = RepoSnapshot::read(&project)?;
+
+        Ok((project, snapshot))
+    }
+
     pub fn update(&mut self) -> Result<(), GitError> {
+        self.cache.clear();
         self.local_branches.clear();
         self.remotes.clear();
         self.remote_branches.clear();
         self.tags.clear();
 
+        self.fetch_object_format();
         self.fetch_remotes_directories()?;
         self.fetch_branches(GitBranchType::Local)?;
         self.fetch_branches(GitBranchType::Tags)?;
@@ -60,13 +137,10 @@ impl GitProject {
             GitError::InvalidGitFolder
         })?;
 
-        let remotes_dir = format!(
-            "{}/{}/{}/{}",
-            self.directory,
-            GIT_FOLDER,
-            GitFolders::REFS,
-            GitRefs::REMOTES
-        );
+        let remotes_dir = self
+            .common_dir()
+            .join(GitFolders::REFS.to_string())
+            .join(GitRefs::REMOTES.to_string());
 
         fs::read_dir(remotes_dir)
             .map(|entries| {
@@ -98,13 +172,13 @@ impl GitProject {
             GitBranchType::Tags => GitRefs::TAGS.to_string(),
         };
 
-        let mut dirs_to_check: Vec<String> = vec![format!(
-            "{}/{}/{}/{}",
-            self.directory,
-            GIT_FOLDER,
-            GitFolders::REFS.to_string(),
+        let branch_root = format!(
+            "{}/{}/{}",
+            self.common_dir().display(),
+            GitFolders::REFS,
             branch_dir
-        )];
+        );
+        let mut dirs_to_check: Vec<String> = vec![branch_root.clone()];
 
         while let Some(current_dir) = dirs_to_check.pop() {
             if let Ok(entries) = fs::read_dir(&current_dir) {
@@ -116,26 +190,11 @@ impl GitProject {
                         dirs_to_check.push(path.to_str().unwrap().to_string());
                     } else {
                         let branch_name = path.file_name().unwrap().to_str().unwrap().to_string();
-                        let commit_hash = fs::read_to_string(path).unwrap();
-
-                        let full_branch_name = if current_dir
-                            != format!(
-                                "{}/{}/{}/{}",
-                                self.directory,
-                                GIT_FOLDER,
-                                GitFolders::REFS,
-                                branch_dir
-                            ) {
-                            current_dir.replace(
-                                &format!(
-                                    "{}/{}/{}/{}/",
-                                    self.directory,
-                                    GIT_FOLDER,
-                                    GitFolders::REFS,
-                                    branch_dir
-                                ),
-                                "",
-                            ) + "/"
+                        let commit_hash = fs::read_to_string(path).unwrap().trim().to_string();
+
+                        let full_branch_name = if current_dir != branch_root {
+                            current_dir.replace(&format!("{}/", branch_root), "")
+                                + "/"
                                 + &branch_name
                         } else {
                             branch_name
@@ -168,8 +227,8 @@ impl GitProject {
     }
 
     pub fn fetch_packed_refs(&mut self) -> Result<(), GitError> {
-        let packed_refs_path = PathBuf::from(self.get_directory())
-            .join(GIT_FOLDER)
+        let packed_refs_path = self
+            .common_dir()
             .join(GitFilesOptional::PackedRefs.to_string());
 
         if let Ok(refs) = fs::read_to_string(packed_refs_path) {
@@ -206,6 +265,22 @@ impl GitProject {
         Err(GitError::PackedRefsError)
     }
 
+    // Without these every object or ref read fails with a generic error, so they are
+    // reported by name before anything else is read
+    pub fn check_repository_layout(&self) -> Result<(), GitError> {
+        let missing: Vec<String> = [GitFolders::OBJECTS, GitFolders::REFS]
+            .iter()
+            .map(|folder| folder.to_string())
+            .filter(|folder| !self.common_dir().join(folder).is_dir())
+            .collect();
+
+        if !missing.is_empty() {
+            return Err(GitError::CorruptRepository(missing));
+        }
+
+        Ok(())
+    }
+
     pub fn has_required_files(&self) -> Result<(), GitError> {
         let mut required_git_files: Vec<String> = GitFilesRequired::iter()
             .map(|file| file.to_string())
@@ -216,7 +291,7 @@ impl GitProject {
             .collect();
 
         let git_folder_entries =
-            fs::read_dir(format!("{}/{}", self.directory, GIT_FOLDER)).unwrap();
+            fs::read_dir(self.common_dir()).map_err(|_| GitError::InvalidGitFolder)?;
 
         for entry in git_folder_entries {
             entry
@@ -273,4 +348,342 @@ impl GitProject {
     pub fn get_directory(&self) -> &String {
         &self.directory
     }
+
+    pub fn get_object_format(&self) -> ObjectFormat {
+        self.object_format
+    }
+
+    // A linked worktree keeps HEAD and its index in its own git dir, while objects, refs
+    // and packed-refs live in the common dir its `commondir` file points to
+    pub fn git_dir(&self) -> PathBuf {
+        resolve_git_dirs(Path::new(&self.directory))
+            .map(|(git_dir, _)| git_dir)
+            .unwrap_or_else(|_| PathBuf::from(&self.directory).join(GIT_FOLDER))
+    }
+
+    pub fn common_dir(&self) -> PathBuf {
+        resolve_git_dirs(Path::new(&self.directory))
+            .map(|(_, common_dir)| common_dir)
+            .unwrap_or_else(|_| PathBuf::from(&self.directory).join(GIT_FOLDER))
+    }
+
+    // `core.bare` is what git goes by, repositories without it are bare when they are
+    // their own git dir
+    pub fn is_bare(&self) -> bool {
+        GitConfig::read(self)
+            .get_bool("core", None, "bare")
+            .unwrap_or_else(|| self.git_dir() == Path::new(&self.directory))
+    }
+
+    pub fn objects_dir(&self) -> PathBuf {
+        self.common_dir().join(GitFolders::OBJECTS.to_string())
+    }
+
+    // The indexes are parsed once and kept until the pack folder changes, adding or
+    // removing a pack updates the modification time of the folder
+    pub fn packs(&self) -> Result<Vec<Arc<GitPack>>, GitObjectError> {
+        let pack_dir = self.objects_dir().join(PACK_FOLDER);
+        let modified = fs::metadata(&pack_dir)
+            .and_then(|metadata| metadata.modified())
+            .ok();
+        if let Some(packs) = self.cache.get_packs(modified) {
+            return Ok(packs);
+        }
+
+        let packs = match fs::read_dir(&pack_dir) {
+            Ok(entries) => entries
+                .flatten()
+                .map(|entry| entry.path())
+                .filter(|path| path.extension().is_some_and(|extension| extension == "idx"))
+                .map(|path| GitPack::open(&path, self.object_format).map(Arc::new))
+                .collect::<Result<Vec<Arc<GitPack>>, GitObjectError>>()?,
+            Err(_) => Vec::new(),
+        };
+        if let Some(modified) = modified.filter(|modified| {
+            modified
+                .elapsed()
+                .is_ok_and(|elapsed| elapsed >= PACK_DIR_RACY_INTERVAL)
+        }) {
+            self.cache.set_packs(modified, packs.clone());
+        }
+
+        Ok(packs)
+    }
+
+    // Loose object folders are named after the first two hex digits of their objects,
+    // the ones left empty after packing are removed and counted
+    pub fn prune_empty_object_dirs(&self) -> Result<usize, GitObjectError> {
+        let entries =
+            fs::read_dir(self.objects_dir()).map_err(|_| GitObjectError::FileReadError)?;
+
+        let mut removed = 0;
+        for entry in entries {
+            let path = entry.map_err(|_| GitObjectError::FileReadError)?.path();
+            let is_object_dir = path.file_name().is_some_and(|name| {
+                name.len() == 2
+                    && name
+                        .to_string_lossy()
+                        .chars()
+                        .all(|c| c.is_ascii_hexdigit())
+            });
+            if !is_object_dir || !path.is_dir() {
+                continue;
+            }
+
+            let is_empty = fs::read_dir(&path)
+                .map_err(|_| GitObjectError::FileReadError)?
+                .next()
+                .is_none();
+            // Another process may have written an object there in the meantime
+            if is_empty && fs::remove_dir(&path).is_ok() {
+                removed += 1;
+            }
+        }
+
+        Ok(removed)
+    }
+
+    pub fn blob_size(&self, hash: &str) -> Result<u64, GitObjectError> {
+        match peek_object_header(self, hash)? {
+            (Header::Blob, size) => Ok(size),
+            _ => Err(GitObjectError::InvalidBlobFile),
+        }
+    }
+
+    pub fn read_raw_compressed(&self, hash: &str) -> Result<Vec<u8>, GitObjectError> {
+        read_raw_compressed(self, hash)
+    }
+
+    pub fn fetch_object_format(&mut self) {
+        self.object_format = resolve_git_dirs(Path::new(&self.directory))
+            .ok()
+            .and_then(|(_, common_dir)| fs::read_to_string(common_dir.join("config")).ok())
+            .map(|config| ObjectFormat::from_config(&config))
+            .unwrap_or_default();
+    }
+
+    pub fn worktrees(&self) -> Result<Vec<WorktreeInfo>, GitObjectError> {
+        WorktreeInfo::list(self)
+    }
+
+    pub fn bisect_status(&self) -> Result<Option<BisectStatus>, GitObjectError> {
+        BisectStatus::read(self)
+    }
+
+    pub fn bisect_next(
+        &self,
+        good: &[String],
+        bad: &str,
+    ) -> Result<Option<String>, GitObjectError> {
+        bisect_next(self, good, bad)
+    }
+
+    pub fn is_ancestor_of(&self, ancestor: &str, descendant: &str) -> Result<bool, GitObjectError> {
+        is_ancestor_of(self, ancestor, descendant)
+    }
+
+    pub fn branches_containing(&self, commit_hash: &str) -> Result<Vec<String>, GitObjectError> {
+        branches_containing(self, commit_hash)
+    }
+
+    pub fn commit_activity(
+        &self,
+        ref_name: &str,
+        bucket: Bucket,
+    ) -> Result<Vec<(NaiveDate, usize)>, GitObjectError> {
+        commit_activity(self, ref_name, bucket)
+    }
+
+    // HEAD of a freshly initialized repository names a branch that has no commit yet
+    pub fn is_unborn(&self) -> Result<bool, GitObjectError> {
+        Ok(self.head_hash()?.is_none())
+    }
+
+    pub fn head_commit(&self) -> Result<GitCommit, GitObjectError> {
+        if let Some(commit) = self.cache.get_head_commit() {
+            return Ok(commit);
+        }
+
+        let hash = self.head_hash()?.ok_or(GitObjectError::UnbornBranch)?;
+        let commit = GitCommit::from_hash(self, &hash)?;
+        self.cache.set_head_commit(commit.clone());
+
+        Ok(commit)
+    }
+
+    pub fn status(&self) -> Result<Vec<GitStatusEntry>, GitObjectError> {
+        status(self)
+    }
+
+    pub fn diff_staged(&self) -> Result<Vec<TreeChange>, GitObjectError> {
+        diff_staged(self)
+    }
+
+    // Writes the trees of the staged files and returns the hash of the root one
+    pub fn write_tree(&self) -> Result<String, GitObjectError> {
+        GitIndex::read(self)?.write_tree(self)
+    }
+
+    pub fn commit_detail(&self, hash: &str) -> Result<CommitDetail, GitObjectError> {
+        CommitDetail::read(self, hash)
+    }
+
+    pub fn working_file_matches_blob(
+        &self,
+        path: &Path,
+        blob_hash: &str,
+    ) -> Result<bool, GitObjectError> {
+        working_file_matches_blob(self, path, blob_hash)
+    }
+
+    fn head_hash(&self) -> Result<Option<String>, GitObjectError> {
+        self.resolve_ref("HEAD")
+    }
+
+    pub fn resolve_ref(&self, ref_name: &str) -> Result<Option<String>, GitObjectError> {
+        if let Some(hash) = self.cache.get_ref(ref_name) {
+            return Ok(Some(hash));
+        }
+
+        // Missing refs are not cached, a branch created later has to show up without
+        // waiting for the watcher. Neither are symbolic refs other than HEAD, the watcher
+        // only reports the ref they point at when that one moves
+        let (git_dir, common_dir) = resolve_git_dirs(Path::new(&self.directory))?;
+        let (hash, symbolic) =
+            resolve_ref_followed(&git_dir, &common_dir, ref_name, self.object_format)?;
+        if let Some(hash) = hash.as_ref().filter(|_| !symbolic || ref_name == "HEAD") {
+            self.cache.set_ref(ref_name, hash.clone());
+        }
+
+        Ok(hash)
+    }
+
+    // Looks the revision up the same way git does, in $GIT_DIR (only for names like
+    // HEAD or ORIG_HEAD), then under refs/, and finally accepts full object hashes
+    pub fn resolve_revision(&self, revision: &str) -> Result<String, GitObjectError> {
+        let mut ref_names = Vec::new();
+        if revision.starts_with("refs/")
+            || revision.chars().all(|c| c.is_ascii_uppercase() || c == '_')
+        {
+            ref_names.push(revision.to_string());
+        }
+        ref_names.extend([
+            format!("refs/{}", revision),
+            format!("refs/tags/{}", revision),
+            format!("refs/heads/{}", revision),
+            format!("refs/remotes/{}", revision),
+            format!("refs/remotes/{}/HEAD", revision),
+        ]);
+
+        // Names git wouldn't accept for a ref can only be hashes
+        for ref_name in ref_names.iter().filter(|name| is_valid_ref_name(name)) {
+            if let Some(hash) = self.resolve_ref(ref_name)? {
+                return Ok(hash);
+            }
+        }
+
+        if revision.len() == self.object_format.hash_hex_len()
+            && revision.chars().all(|c| c.is_ascii_hexdigit())
+        {
+            return Ok(revision.to_lowercase());
+        }
+
+        // Abbreviated hashes have to name an existing object
+        match expand_hash(self, revision) {
+            Ok(hash) => Ok(hash),
+            Err(GitObjectError::AmbiguousHash) => Err(GitObjectError::AmbiguousHash),
+            Err(_) => Err(GitObjectError::RefNotFound),
+        }
+    }
+
+    pub fn expand_hash(&self, prefix: &str) -> Result<String, GitObjectError> {
+        expand_hash(self, prefix)
+    }
+
+    pub fn reflog(&self, ref_name: &str) -> Result<Vec<ReflogEntry>, GitObjectError> {
+        read_reflog(self, ref_name)
+    }
+
+    pub fn resolve_revspec(&self, revspec: &str) -> Result<String, GitObjectError> {
+        resolve_revspec(self, revspec)
+    }
+
+    pub fn list_dir(&self, ref_name: &str, path: &str) -> Result<Vec<DirEntry>, GitObjectError> {
+        list_dir(self, ref_name, path)
+    }
+
+    pub fn blob_hash_at(
+        &self,
+        commit_hash: &str,
+        path: &str,
+    ) -> Result<Option<String>, GitObjectError> {
+        blob_hash_at(self, commit_hash, path)
+    }
+
+    // Called with the paths reported by the file watcher, relative to the project
+    // directory or absolute
+    pub fn invalidate(&self, paths: &[PathBuf]) {
+        let Ok((git_dir, common_dir)) = resolve_git_dirs(Path::new(&self.directory)) else {
+            self.cache.clear();
+            return;
+        };
+
+        for path in paths {
+            let path = Path::new(&self.directory).join(path);
+            if let Ok(relative_path) = path
+                .strip_prefix(&git_dir)
+                .or_else(|_| path.strip_prefix(&common_dir))
+            {
+                self.cache.invalidate(relative_path);
+            }
+        }
+    }
+
+    // Initialized submodules point at their git data in `.git/modules/<name>` of the
+    // parent repository through a `.git` file
+    pub fn open_submodule(&self, path: &str) -> Result<GitProject, GitObjectError> {
+        GitProject::open(&Path::new(&self.directory).join(path).to_string_lossy())
+    }
+
+    pub fn tag_details(&self, tag_name: &str) -> Result<TagDetails, GitObjectError> {
+        TagDetails::read(self, tag_name)
+    }
+
+    pub fn tags_by_date(&self) -> Result<Vec<TagRef>, GitObjectError> {
+        tags_by_date(self)
+    }
+
+    pub fn all_refs(&self) -> Result<Vec<RefInfo>, GitObjectError> {
+        all_refs(self)
+    }
+
+    pub fn list_branches(&self) -> Result<Vec<GitRef>, GitObjectError> {
+        list_branches(self)
+    }
+
+    pub fn create_branch(&self, name: &str, start_point: &str) -> Result<(), GitObjectError> {
+        create_branch(self, name, start_point)
+    }
+
+    pub fn delete_branch(&self, name: &str, force: bool) -> Result<(), GitObjectError> {
+        delete_branch(self, name, force)
+    }
+
+    pub fn create_lightweight_tag(&self, name: &str, target: &str) -> Result<(), GitObjectError> {
+        create_lightweight_tag(self, name, target)
+    }
+
+    pub fn create_annotated_tag(
+        &self,
+        name: &str,
+        target: &str,
+        tagger: GitCommitAuthor,
+        message: &str,
+    ) -> Result<(), GitObjectError> {
+        create_annotated_tag(self, name, target, tagger, message)
+    }
+
+    pub fn resolve_head(&self) -> Result<HeadState, GitObjectError> {
+        resolve_head(self)
+    }
 }
@@ -0,0 +1,185 @@
+use serde::{Deserialize, Serialize};
+use std::{
+    fs::{self, OpenOptions},
+    io::Write,
+    path::PathBuf,
+};
+
+use super::{
+    git_commit_author::GitCommitAuthor, git_folders::GitFolders, git_project::GitProject,
+    git_refs::remove_empty_parents,
+};
+use crate::errors::git_object_error::GitObjectError;
+
+const LOGS_FOLDER: &str = "logs";
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ReflogEntry {
+    old_hash: String,
+    new_hash: String,
+    committer: GitCommitAuthor,
+    message: String,
+}
+
+impl ReflogEntry {
+    pub fn new(
+        old_hash: String,
+        new_hash: String,
+        committer: GitCommitAuthor,
+        message: String,
+    ) -> Self {
+        Self {
+            old_hash,
+            new_hash,
+            committer,
+            message,
+        }
+    }
+
+    pub fn get_old_hash(&self) -> &String {
+        &self.old_hash
+    }
+
+    pub fn get_new_hash(&self) -> &String {
+        &self.new_hash
+    }
+
+    pub fn get_committer(&self) -> &GitCommitAuthor {
+        &self.committer
+    }
+
+    pub fn get_message(&self) -> &String {
+        &self.message
+    }
+
+    // `<old> <new> <name> <<email>> <timestamp> <timezone>\t<message>`
+    pub fn from_line(line: &str) -> Result<Self, GitObjectError> {
+        let (old_hash, rest) = line.split_once(' ').ok_or(GitObjectError::ParsingError)?;
+        let (new_hash, rest) = rest.split_once(' ').ok_or(GitObjectError::ParsingError)?;
+        let (committer, message) = rest.split_once('\t').unwrap_or((rest, ""));
+
+        Ok(Self {
+            old_hash: old_hash.to_string(),
+            new_hash: new_hash.to_string(),
+            committer: GitCommitAuthor::from_string(committer)
+                .map_err(|_| GitObjectError::ParsingError)?,
+            message: message.to_string(),
+        })
+    }
+
+    pub fn to_line(&self) -> String {
+        let user = self.committer.get_user();
+        format!(
+            "{} {} {} <{}> {} {}\t{}",
+            self.old_hash,
+            self.new_hash,
+            user.name,
+            user.email,
+            self.committer.date_seconds,
+            self.committer.timezone,
+            self.message
+        )
+    }
+}
+
+// HEAD and the other pseudo refs are logged per worktree, everything under refs/ is shared
+pub fn reflog_path(project: &GitProject, ref_name: &str) -> PathBuf {
+    let git_dir = if ref_name.starts_with("refs/") {
+        project.common_dir()
+    } else {
+        project.git_dir()
+    };
+
+    git_dir.join(LOGS_FOLDER).join(ref_name)
+}
+
+// Oldest entry first, a ref that was never logged has an empty reflog
+pub fn read_reflog(
+    project: &GitProject,
+    ref_name: &str,
+) -> Result<Vec<ReflogEntry>, GitObjectError> {
+    let Ok(log) = fs::read_to_string(reflog_path(project, ref_name)) else {
+        return Ok(Vec::new());
+    };
+
+    log.lines()
+        .filter(|line| !line.is_empty())
+        .map(ReflogEntry::from_line)
+        .collect()
+}
+
+pub fn append_reflog(
+    project: &GitProject,
+    ref_name: &str,
+    entry: &ReflogEntry,
+) -> Result<(), GitObjectError> {
+    let path = reflog_path(project, ref_name);
+    if let Some(parent) = path.parent() {
+        fs::create_dir_all(parent).map_err(|_| GitObjectError::FileReadError)?;
+    }
+
+    OpenOptions::new()
+        .create(true)
+        .append(true)
+        .open(path)
+        .and_then(|mut log| writeln!(log, "{}", entry.to_line()))
+        .map_err(|_| GitObjectError::FileReadError)
+}
+
+// A ref that was never logged has nothing to delete
+pub fn delete_reflog(project: &GitProject, ref_name: &str) -> Result<(), GitObjectError> {
+    let path = reflog_path(project, ref_name);
+    match fs::remove_file(&path) {
+        Err(error) if error.kind() != std::io::ErrorKind::NotFound => {
+            Err(GitObjectError::FileReadError)
+        }
+        _ => {
+            remove_empty_parents(
+                &path,
+                &reflog_path(project, GitFolders::REFS.to_string().as_str()),
+            );
+            Ok(())
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_reflog_entry_from_line() {
+        let entry = ReflogEntry::from_line(
+            "0000000000000000000000000000000000000000 df6773ea47ed3fce3b3bb14e3d1101963e77ef08 Test User <test.user@email.com> 1704153600 +0200\tcommit (initial): First",
+        )
+        .unwrap();
+
+        assert_eq!(
+            entry.get_old_hash(),
+            "0000000000000000000000000000000000000000"
+        );
+        assert_eq!(
+            entry.get_new_hash(),
+            "df6773ea47ed3fce3b3bb14e3d1101963e77ef08"
+        );
+        assert_eq!(entry.get_committer().get_user().name, "Test User");
+        assert_eq!(entry.get_committer().date_seconds, 1704153600);
+        assert_eq!(entry.get_message(), "commit (initial): First");
+    }
+
+    #[test]
+    fn test_reflog_entry_to_line() {
+        let line = "0000000000000000000000000000000000000000 df6773ea47ed3fce3b3bb14e3d1101963e77ef08 Test User <test.user@email.com> 1704153600 +0200\tbranch: Created from HEAD";
+
+        assert_eq!(ReflogEntry::from_line(line).unwrap().to_line(), line);
+    }
+
+    #[test]
+    fn test_reflog_entry_from_line_invalid() {
+        assert_eq!(
+            ReflogEntry::from_line("not a reflog line"),
+            Err(GitObjectError::ParsingError)
+        );
+    }
+}
@@ -0,0 +1,571 @@
+use serde::{Deserialize, Serialize};
+use std::{
+    collections::BTreeMap,
+    fs::{self, OpenOptions},
+    io::{ErrorKind, Write},
+    path::Path,
+};
+
+use super::{
+    git_files::{GitFilesOptional, GitFilesRequired},
+    git_folders::{GitFolders, GitRefs},
+    git_project::GitProject,
+    git_tag::peel_tag,
+    git_worktree::{ref_names, symbolic_ref_target},
+};
+use crate::errors::git_object_error::GitObjectError;
+
+const PACKED_PEELED_PREFIX: char = '^';
+const PACKED_COMMENT_PREFIX: char = '#';
+pub const LOCK_SUFFIX: &str = ".lock";
+const FORBIDDEN_REF_CHARS: [char; 8] = [' ', '~', '^', ':', '?', '*', '[', '\\'];
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub enum RefCategory {
+    Branch,
+    Tag,
+    Remote,
+    Other,
+}
+
+impl RefCategory {
+    fn from_name(name: &str) -> Self {
+        if name.starts_with("refs/heads/") {
+            RefCategory::Branch
+        } else if name.starts_with("refs/tags/") {
+            RefCategory::Tag
+        } else if name.starts_with("refs/remotes/") {
+            RefCategory::Remote
+        } else {
+            RefCategory::Other
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct RefInfo {
+    name: String,
+    target: String,
+    peeled: Option<String>,
+    category: RefCategory,
+}
+
+impl RefInfo {
+    pub fn get_name(&self) -> &String {
+        &self.name
+    }
+
+    pub fn get_target(&self) -> &String {
+        &self.target
+    }
+
+    pub fn get_peeled(&self) -> Option<&String> {
+        self.peeled.as_ref()
+    }
+
+    pub fn get_category(&self) -> &RefCategory {
+        &self.category
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct GitRef {
+    name: String,
+    hash: String,
+    symbolic_target: Option<String>,
+}
+
+impl GitRef {
+    pub fn get_name(&self) -> &String {
+        &self.name
+    }
+
+    pub fn get_hash(&self) -> &String {
+        &self.hash
+    }
+
+    // The ref this one points at when it is symbolic, like `refs/heads/main`
+    pub fn get_symbolic_target(&self) -> Option<&String> {
+        self.symbolic_target.as_ref()
+    }
+}
+
+// The hash of a branch is None while it is unborn
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub enum HeadState {
+    Branch { name: String, hash: Option<String> },
+    Detached(String),
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct PackedRef {
+    name: String,
+    hash: String,
+    peeled: Option<String>,
+}
+
+impl PackedRef {
+    pub fn get_name(&self) -> &String {
+        &self.name
+    }
+
+    pub fn get_hash(&self) -> &String {
+        &self.hash
+    }
+
+    // The object an annotated tag points at, when git wrote its `^` line
+    pub fn get_peeled(&self) -> Option<&String> {
+        self.peeled.as_ref()
+    }
+}
+
+// What is checked out, HEAD is read from the git dir so linked worktrees get their own
+pub fn resolve_head(project: &GitProject) -> Result<HeadState, GitObjectError> {
+    let head = fs::read_to_string(project.git_dir().join(GitFilesRequired::HEAD.to_string()))
+        .map_err(|_| GitObjectError::FileReadError)?;
+    let head = head.trim();
+
+    match symbolic_ref_target(head) {
+        Some(ref_name) => {
+            let name = ref_name
+                .strip_prefix(&format!("{}/{}/", GitFolders::REFS, GitRefs::HEADS))
+                .ok_or(GitObjectError::InvalidRefFile)?;
+
+            Ok(HeadState::Branch {
+                name: name.to_string(),
+                hash: project.resolve_ref(ref_name)?,
+            })
+        }
+        None if head.len() == project.get_object_format().hash_hex_len()
+            && head.chars().all(|c| c.is_ascii_hexdigit()) =>
+        {
+            Ok(HeadState::Detached(head.to_string()))
+        }
+        None => Err(GitObjectError::InvalidRefFile),
+    }
+}
+
+// The refs of `packed-refs` in file order, a repository without one has none
+pub fn parse_packed_refs(project: &GitProject) -> Result<Vec<PackedRef>, GitObjectError> {
+    let packed_refs = project
+        .common_dir()
+        .join(GitFilesOptional::PackedRefs.to_string());
+
+    match fs::read_to_string(packed_refs) {
+        Ok(packed_refs) => Ok(packed_refs_from_str(&packed_refs)),
+        Err(error) if error.kind() == std::io::ErrorKind::NotFound => Ok(Vec::new()),
+        Err(_) => Err(GitObjectError::FileReadError),
+    }
+}
+
+// A new ref can't be created over an existing one of the same kind, nor where it would
+// need a folder that is a ref already or the other way around
+pub fn check_new_ref_name(
+    project: &GitProject,
+    refs: &GitRefs,
+    name: &str,
+) -> Result<(), GitObjectError> {
+    if !is_valid_ref_name(name)
+        || name.starts_with('-')
+        || name == GitFilesRequired::HEAD.to_string()
+    {
+        return Err(GitObjectError::InvalidRefName);
+    }
+
+    let conflicts = |existing: &String| {
+        existing == name
+            || existing.starts_with(&format!("{}/", name))
+            || name.starts_with(&format!("{}/", existing))
+    };
+    if ref_names(&project.common_dir(), refs)?
+        .iter()
+        .any(conflicts)
+    {
+        return Err(GitObjectError::RefAlreadyExists);
+    }
+
+    Ok(())
+}
+
+// Creates the loose ref `ref_name` at `hash` like git does: `<ref>.lock` is taken with
+// `create_new` so concurrent writers fail instead of overwriting each other, the ref is
+// checked again while it is held and the lock is renamed into place
+pub fn create_ref(project: &GitProject, ref_name: &str, hash: &str) -> Result<(), GitObjectError> {
+    let path = project.common_dir().join(ref_name);
+    let lock_path = project
+        .common_dir()
+        .join(format!("{}{}", ref_name, LOCK_SUFFIX));
+    path.parent()
+        .map_or(Ok(()), fs::create_dir_all)
+        .map_err(|_| GitObjectError::FileReadError)?;
+
+    let mut lock = OpenOptions::new()
+        .write(true)
+        .create_new(true)
+        .open(&lock_path)
+        .map_err(|error| match error.kind() {
+            ErrorKind::AlreadyExists => GitObjectError::RefLocked,
+            _ => GitObjectError::FileReadError,
+        })?;
+
+    let written = if path.exists()
+        || parse_packed_refs(project)?
+            .iter()
+            .any(|packed| packed.get_name() == ref_name)
+    {
+        Err(GitObjectError::RefAlreadyExists)
+    } else {
+        writeln!(lock, "{}", hash)
+            .and_then(|_| lock.sync_all())
+            .and_then(|_| fs::rename(&lock_path, &path))
+            .map_err(|_| GitObjectError::FileReadError)
+    };
+    if written.is_err() {
+        _ = fs::remove_file(&lock_path);
+    }
+    project.invalidate(&[path]);
+
+    written
+}
+
+// Drops a ref and its peeled line from `packed-refs`, returning whether it was there.
+// Like create_ref, `packed-refs.lock` is held while the file is read and rewritten, and
+// renamed over it so a crash never leaves a truncated `packed-refs` behind
+pub fn remove_packed_ref(project: &GitProject, ref_name: &str) -> Result<bool, GitObjectError> {
+    let path = project
+        .common_dir()
+        .join(GitFilesOptional::PackedRefs.to_string());
+    if !path.exists() {
+        return Ok(false);
+    }
+    let lock_path =
+        project
+            .common_dir()
+            .join(format!("{}{}", GitFilesOptional::PackedRefs, LOCK_SUFFIX));
+
+    let mut lock = OpenOptions::new()
+        .write(true)
+        .create_new(true)
+        .open(&lock_path)
+        .map_err(|error| match error.kind() {
+            ErrorKind::AlreadyExists => GitObjectError::RefLocked,
+            _ => GitObjectError::FileReadError,
+        })?;
+
+    let removed = match fs::read_to_string(&path) {
+        Ok(packed_refs) => without_packed_ref(&packed_refs, ref_name).map_or(Ok(false), |kept| {
+            lock.write_all(kept.as_bytes())
+                .and_then(|_| lock.sync_all())
+                .and_then(|_| fs::rename(&lock_path, &path))
+                .map(|_| true)
+                .map_err(|_| GitObjectError::FileReadError)
+        }),
+        Err(error) if error.kind() == ErrorKind::NotFound => Ok(false),
+        Err(_) => Err(GitObjectError::FileReadError),
+    };
+    if !matches!(removed, Ok(true)) {
+        _ = fs::remove_file(&lock_path);
+    }
+
+    removed
+}
+
+// The packed refs without `ref_name` and its peeled line, None when it isn't there
+fn without_packed_ref(packed_refs: &str, ref_name: &str) -> Option<String> {
+    let mut removed = false;
+    let mut kept = String::new();
+    let mut skip_peeled = false;
+    for line in packed_refs.lines() {
+        if skip_peeled && line.starts_with(PACKED_PEELED_PREFIX) {
+            continue;
+        }
+
+        skip_peeled = line
+            .split_once(' ')
+            .is_some_and(|(_, name)| name.trim_end() == ref_name)
+            && !line.starts_with(PACKED_COMMENT_PREFIX);
+        if skip_peeled {
+            removed = true;
+            continue;
+        }
+
+        kept.push_str(line);
+        kept.push('\n');
+    }
+
+    removed.then_some(kept)
+}
+
+// Once a nested ref like `feature/x` or its log is gone, the folders left behind would
+// keep a ref named `feature` from being created. Nothing above `root` is removed
+pub fn remove_empty_parents(path: &Path, root: &Path) {
+    let mut current = path.parent();
+    while let Some(folder) = current.filter(|folder| folder.starts_with(root) && *folder != root) {
+        if fs::remove_dir(folder).is_err() {
+            break;
+        }
+        current = folder.parent();
+    }
+}
+
+fn packed_refs_from_str(packed_refs: &str) -> Vec<PackedRef> {
+    let mut refs = Vec::<PackedRef>::new();
+    let mut last_is_ref = false;
+    for line in packed_refs.lines() {
+        if line.starts_with(PACKED_COMMENT_PREFIX) {
+            continue;
+        }
+
+        // The peeled line belongs to the ref right above it
+        if let Some(peeled) = line.strip_prefix(PACKED_PEELED_PREFIX) {
+            if let Some(packed_ref) = refs.last_mut().filter(|_| last_is_ref) {
+                packed_ref.peeled = Some(peeled.to_string());
+            }
+            last_is_ref = false;
+            continue;
+        }
+
+        last_is_ref = false;
+        let Some((hash, name)) = line.split_once(' ') else {
+            continue;
+        };
+        refs.push(PackedRef {
+            name: name.to_string(),
+            hash: hash.to_string(),
+            peeled: None,
+        });
+        last_is_ref = true;
+    }
+
+    refs
+}
+
+// The local branches with the commit they point at, symbolic branches are followed and
+// the ones pointing at a branch that doesn't exist are left out, like malformed ones
+pub fn list_branches(project: &GitProject) -> Result<Vec<GitRef>, GitObjectError> {
+    let common_dir = project.common_dir();
+    let hash_len = project.get_object_format().hash_hex_len();
+
+    let mut branches = Vec::new();
+    for name in ref_names(&common_dir, &GitRefs::HEADS)? {
+        let full_name = format!("{}/{}/{}", GitFolders::REFS, GitRefs::HEADS, name);
+
+        // Packed branches have no file and are always direct
+        let symbolic_target = match fs::read_to_string(common_dir.join(&full_name)) {
+            Ok(content) => {
+                let content = content.trim();
+                match symbolic_ref_target(content) {
+                    Some(target) => Some(target.to_string()),
+                    None if content.len() == hash_len
+                        && content.chars().all(|c| c.is_ascii_hexdigit()) =>
+                    {
+                        None
+                    }
+                    None => continue,
+                }
+            }
+            Err(_) => None,
+        };
+
+        if let Ok(Some(hash)) = project.resolve_ref(&full_name) {
+            branches.push(GitRef {
+                name,
+                hash,
+                symbolic_target,
+            });
+        }
+    }
+
+    Ok(branches)
+}
+
+// Every ref under refs/ sorted by full name, loose refs shadow the packed ones and
+// symbolic refs are followed to their hash. Only tags get a peeled object, taken from
+// the `^` lines of packed-refs when git already wrote one
+pub fn all_refs(project: &GitProject) -> Result<Vec<RefInfo>, GitObjectError> {
+    let common_dir = project.common_dir();
+    let mut targets = BTreeMap::<String, (String, Option<String>)>::new();
+
+    let mut dirs_to_check = vec![common_dir.join(GitFolders::REFS.to_string())];
+    while let Some(current_dir) = dirs_to_check.pop() {
+        let Ok(entries) = fs::read_dir(&current_dir) else {
+            continue;
+        };
+
+        for entry in entries {
+            let path = entry.map_err(|_| GitObjectError::FileReadError)?.path();
+            if path.is_dir() {
+                dirs_to_check.push(path);
+                continue;
+            }
+
+            let Ok(name) = path.strip_prefix(&common_dir) else {
+                continue;
+            };
+            let name = name.to_string_lossy().replace('\\', "/");
+            if name.ends_with(LOCK_SUFFIX) {
+                continue;
+            }
+
+            // A symbolic ref pointing at a ref that doesn't exist has no target, and a
+            // malformed ref is left out rather than hiding every other one
+            if let Ok(Some(target)) = project.resolve_ref(&name) {
+                targets.insert(name, (target, None));
+            }
+        }
+    }
+
+    for packed_ref in parse_packed_refs(project)? {
+        targets
+            .entry(packed_ref.name)
+            .or_insert((packed_ref.hash, packed_ref.peeled));
+    }
+
+    Ok(targets
+        .into_iter()
+        .map(|(name, (target, packed_peeled))| {
+            let category = RefCategory::from_name(&name);
+            let peeled = match (&category, packed_peeled) {
+                (RefCategory::Tag, Some(peeled)) => Some(peeled),
+                // A tag of an object that is missing is listed without a peeled object
+                (RefCategory::Tag, None) => peel_tag(project, target.clone())
+                    .ok()
+                    .map(|(peeled, _)| peeled),
+                _ => None,
+            };
+
+            RefInfo {
+                name,
+                target,
+                peeled,
+                category,
+            }
+        })
+        .collect())
+}
+
+// The rules of `git check-ref-format --allow-onelevel`, so a short branch name like
+// `main` is valid as well as a full `refs/heads/main`
+pub fn is_valid_ref_name(name: &str) -> bool {
+    if name.is_empty() || name == "@" || name.ends_with('.') {
+        return false;
+    }
+    if name.contains("..") || name.contains("@{") {
+        return false;
+    }
+    if name
+        .chars()
+        .any(|c| c.is_ascii_control() || FORBIDDEN_REF_CHARS.contains(&c))
+    {
+        return false;
+    }
+
+    // This also rules out a leading or trailing slash and `//`, which leave an empty
+    // component
+    name.split('/').all(|component| {
+        !component.is_empty() && !component.starts_with('.') && !component.ends_with(LOCK_SUFFIX)
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_is_valid_ref_name() {
+        for name in [
+            "main",
+            "feature/x",
+            "refs/heads/feature/x",
+            "v1.0",
+            "fix-#12",
+            "a@b",
+            "unicode/ünï",
+        ] {
+            assert!(is_valid_ref_name(name), "{name} should be valid");
+        }
+
+        for name in [
+            "",
+            "@",
+            "bad..name",
+            "ends.lock",
+            "feature/x.lock/y",
+            "/leading",
+            "trailing/",
+            "double//slash",
+            "ends.",
+            ".hidden",
+            "feature/.hidden",
+            "has space",
+            "tab\there",
+            "del\u{7f}",
+            "tilde~1",
+            "caret^",
+            "colon:",
+            "question?",
+            "star*",
+            "open[",
+            "back\\slash",
+            "at@{1}",
+        ] {
+            assert!(!is_valid_ref_name(name), "{name:?} should be invalid");
+        }
+    }
+
+    #[test]
+    fn test_packed_refs_from_str() {
+        let packed_refs = "# pack-refs with: peeled fully-peeled sorted \n\
+            1111111111111111111111111111111111111111 refs/heads/main\n\
+            2222222222222222222222222222222222222222 refs/remotes/origin/main\n\
+            3333333333333333333333333333333333333333 refs/tags/v1\n\
+            ^1111111111111111111111111111111111111111\n\
+            ^2222222222222222222222222222222222222222\n\
+            4444444444444444444444444444444444444444 refs/tags/v2\n";
+
+        let refs = packed_refs_from_str(packed_refs);
+        assert_eq!(
+            refs.iter()
+                .map(|packed_ref| (
+                    packed_ref.get_name().as_str(),
+                    packed_ref.get_hash().as_str(),
+                    packed_ref.get_peeled().map(|peeled| peeled.as_str())
+                ))
+                .collect::<Vec<_>>(),
+            vec![
+                (
+                    "refs/heads/main",
+                    "1111111111111111111111111111111111111111",
+                    None
+                ),
+                (
+                    "refs/remotes/origin/main",
+                    "2222222222222222222222222222222222222222",
+                    None
+                ),
+                (
+                    "refs/tags/v1",
+                    "3333333333333333333333333333333333333333",
+                    Some("1111111111111111111111111111111111111111")
+                ),
+                (
+                    "refs/tags/v2",
+                    "4444444444444444444444444444444444444444",
+                    None
+                ),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_packed_refs_from_str_empty() {
+        assert_eq!(packed_refs_from_str(""), Vec::new());
+        assert_eq!(
+            packed_refs_from_str("# pack-refs with: peeled\n^1111\ngarbage\n"),
+            Vec::new()
+        );
+    }
+}
@@ -0,0 +1,348 @@
+use chrono::NaiveDate;
+use std::{
+    fs,
+    time::{SystemTime, UNIX_EPOCH},
+};
+
+use super::{
+    git_commit::GitCommit,
+    git_config::GitConfig,
+    git_project::GitProject,
+    git_reflog::{read_reflog, reflog_path},
+    git_refs::is_valid_ref_name,
+    git_tag::peel_tag,
+    git_worktree::symbolic_ref_target,
+    object::GitObject,
+};
+use crate::errors::git_object_error::GitObjectError;
+
+const HEAD: &str = "HEAD";
+const HEADS_PREFIX: &str = "refs/heads/";
+const UPSTREAM_SUFFIXES: [&str; 2] = ["@{upstream}", "@{u}"];
+const SECONDS_PER_DAY: i64 = 24 * 60 * 60;
+
+#[derive(Debug, PartialEq)]
+enum Navigation {
+    // `~n`, the n-th first parent
+    Ancestor(usize),
+    // `^n`, the n-th parent of a merge, `^0` being the commit itself
+    Parent(usize),
+}
+
+// Resolves revisions like `main~2`, `HEAD^2`, `@`, `@{u}` or `HEAD@{1}` to a commit hash
+pub fn resolve_revspec(project: &GitProject, revspec: &str) -> Result<String, GitObjectError> {
+    let (base, navigations) = split_revspec(revspec)?;
+
+    // `v1.0~1` and `v1.0^0` walk from the commit an annotated tag points at
+    let mut hash = resolve_base(project, base)?;
+    if !navigations.is_empty() {
+        hash = peel_tag(project, hash)?.0;
+    }
+    for navigation in navigations {
+        let (generations, parent) = match navigation {
+            Navigation::Ancestor(generations) => (generations, 1),
+            Navigation::Parent(0) => continue,
+            Navigation::Parent(parent) => (1, parent),
+        };
+
+        for _ in 0..generations {
+            let commit = GitCommit::from_hash(project, &hash)?;
+            hash = commit
+                .get_parent_hashes()
+                .get(parent - 1)
+                .ok_or(GitObjectError::RefNotFound)?
+                .clone();
+        }
+    }
+
+    Ok(hash)
+}
+
+fn resolve_base(project: &GitProject, base: &str) -> Result<String, GitObjectError> {
+    if base == "@" {
+        return project.resolve_revision(HEAD);
+    }
+
+    if let Some(branch) = UPSTREAM_SUFFIXES
+        .iter()
+        .find_map(|suffix| strip_suffix_ignore_case(base, suffix))
+    {
+        let branch = match branch {
+            "" | "@" => current_branch(project)?,
+            branch => branch.to_string(),
+        };
+
+        return project.resolve_revision(&upstream_ref(project, &branch)?);
+    }
+
+    if let Some((ref_name, selector)) = base
+        .strip_suffix('}')
+        .and_then(|base| base.rsplit_once("@{"))
+    {
+        return resolve_reflog(project, ref_name, selector);
+    }
+
+    project.resolve_revision(base)
+}
+
+// `<ref>@{n}` is the value the ref had n changes ago, `<ref>@{date}` the value it had
+// at that point in time
+fn resolve_reflog(
+    project: &GitProject,
+    ref_name: &str,
+    selector: &str,
+) -> Result<String, GitObjectError> {
+    let entries = read_reflog(project, &reflog_ref_name(project, ref_name)?)?;
+
+    let entry = match selector.parse::<usize>() {
+        Ok(index) => index
+            .checked_add(1)
+            .and_then(|back| entries.len().checked_sub(back))
+            .and_then(|position| entries.get(position)),
+        Err(_) => {
+            let date = parse_date(selector, now()).ok_or(GitObjectError::RefNotFound)?;
+            // Like git, dates older than the log fall back to its oldest entry
+            entries
+                .iter()
+                .rev()
+                .find(|entry| entry.get_committer().date_seconds <= date)
+                .or(entries.first())
+        }
+    };
+
+    entry
+        .map(|entry| entry.get_new_hash().clone())
+        .ok_or(GitObjectError::RefNotFound)
+}
+
+fn reflog_ref_name(project: &GitProject, name: &str) -> Result<String, GitObjectError> {
+    match name {
+        // `@{n}` alone is about the current branch, or HEAD when it is detached
+        "" => Ok(current_branch(project)
+            .map(|branch| format!("{}{}", HEADS_PREFIX, branch))
+            .unwrap_or_else(|_| HEAD.to_string())),
+        "@" => Ok(HEAD.to_string()),
+        // The name ends up in a path under logs/, so `..` must not lead out of it
+        name if !is_valid_ref_name(name) => Err(GitObjectError::RefNotFound),
+        name if name.starts_with("refs/")
+            || name.chars().all(|c| c.is_ascii_uppercase() || c == '_') =>
+        {
+            Ok(name.to_string())
+        }
+        name => [
+            format!("{}{}", HEADS_PREFIX, name),
+            format!("refs/remotes/{}", name),
+            format!("refs/tags/{}", name),
+            format!("refs/{}", name),
+        ]
+        .into_iter()
+        .find(|ref_name| reflog_path(project, ref_name).is_file())
+        .ok_or(GitObjectError::RefNotFound),
+    }
+}
+
+pub fn now() -> i64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|duration| duration.as_secs() as i64)
+        .unwrap_or_default()
+}
+
+// Accepts the forms people actually type: `yesterday`, `2 days ago` (or `2.days.ago`)
+// and `YYYY-MM-DD` with an optional `HH:MM[:SS]`, read as UTC
+fn parse_date(date: &str, now: i64) -> Option<i64> {
+    let date = date.replace('.', " ").to_lowercase();
+    let words = date.split_whitespace().collect::<Vec<&str>>();
+
+    match words.as_slice() {
+        ["now"] => return Some(now),
+        ["yesterday"] => return Some(now - SECONDS_PER_DAY),
+        [count, unit, "ago"] => {
+            let count = count.parse::<i64>().ok()?;
+            let unit_seconds = match unit.trim_end_matches('s') {
+                "second" => 1,
+                "minute" => 60,
+                "hour" => 60 * 60,
+                "day" => SECONDS_PER_DAY,
+                "week" => 7 * SECONDS_PER_DAY,
+                "month" => 30 * SECONDS_PER_DAY,
+                "year" => 365 * SECONDS_PER_DAY,
+                _ => return None,
+            };
+
+            return count
+                .checked_mul(unit_seconds)
+                .and_then(|seconds| now.checked_sub(seconds));
+        }
+        _ => {}
+    }
+
+    let (day, time) = date.split_once(' ').unwrap_or((&date, "00:00"));
+    let mut day = day.split('-').map(|part| part.parse::<u32>().ok());
+    let (year, month, day) = (day.next()??, day.next()??, day.next()??);
+    let day = NaiveDate::from_ymd_opt(i32::try_from(year).ok()?, month, day)?;
+
+    let mut time = time.trim().split(':').map(|part| part.parse::<i64>().ok());
+    let hours = time.next()??;
+    let minutes = time.next()??;
+    let seconds = time.next().unwrap_or(Some(0))?;
+    if !(0..24).contains(&hours) || !(0..60).contains(&minutes) || !(0..=60).contains(&seconds) {
+        return None;
+    }
+
+    Some(day.and_hms_opt(0, 0, 0)?.and_utc().timestamp() + hours * 3600 + minutes * 60 + seconds)
+}
+
+fn split_revspec(revspec: &str) -> Result<(&str, Vec<Navigation>), GitObjectError> {
+    // Operators inside `@{...}` belong to the base
+    let mut depth = 0;
+    let base_end = revspec
+        .char_indices()
+        .find(|(_, c)| {
+            match c {
+                '{' => depth += 1,
+                '}' => depth -= 1,
+                _ => {}
+            }
+            depth == 0 && (*c == '~' || *c == '^')
+        })
+        .map(|(index, _)| index)
+        .unwrap_or(revspec.len());
+
+    let (base, mut rest) = revspec.split_at(base_end);
+    if base.is_empty() {
+        return Err(GitObjectError::RefNotFound);
+    }
+
+    let mut navigations = Vec::new();
+    while let Some(operator) = rest.chars().next() {
+        rest = &rest[operator.len_utf8()..];
+        let digits = rest
+            .find(|c: char| !c.is_ascii_digit())
+            .unwrap_or(rest.len());
+        let count = match &rest[..digits] {
+            "" => 1,
+            count => count.parse().map_err(|_| GitObjectError::RefNotFound)?,
+        };
+        rest = &rest[digits..];
+
+        navigations.push(match operator {
+            '~' => Navigation::Ancestor(count),
+            '^' => Navigation::Parent(count),
+            _ => return Err(GitObjectError::RefNotFound),
+        });
+    }
+
+    Ok((base, navigations))
+}
+
+fn strip_suffix_ignore_case<'a>(value: &'a str, suffix: &str) -> Option<&'a str> {
+    let start = value.len().checked_sub(suffix.len())?;
+
+    value
+        .get(start..)
+        .filter(|end| end.eq_ignore_ascii_case(suffix))
+        .map(|_| &value[..start])
+}
+
+fn current_branch(project: &GitProject) -> Result<String, GitObjectError> {
+    let head = fs::read_to_string(project.git_dir().join(HEAD))
+        .map_err(|_| GitObjectError::FileReadError)?;
+
+    // A detached HEAD has no upstream
+    symbolic_ref_target(&head)
+        .and_then(|ref_name| ref_name.strip_prefix(HEADS_PREFIX))
+        .map(str::to_string)
+        .ok_or(GitObjectError::RefNotFound)
+}
+
+// Uses the `branch.<name>.remote` and `branch.<name>.merge` settings, where a remote
+// of `.` means the upstream is another local branch
+fn upstream_ref(project: &GitProject, branch: &str) -> Result<String, GitObjectError> {
+    let config = fs::read_to_string(project.common_dir().join("config")).unwrap_or_default();
+    let (remote, merge) = branch_config(&config, branch).ok_or(GitObjectError::RefNotFound)?;
+    let merge_branch = merge.strip_prefix(HEADS_PREFIX).unwrap_or(&merge);
+
+    Ok(match remote.as_str() {
+        "." => format!("{}{}", HEADS_PREFIX, merge_branch),
+        remote => format!("refs/remotes/{}/{}", remote, merge_branch),
+    })
+}
+
+fn branch_config(config: &str, branch: &str) -> Option<(String, String)> {
+    let config = GitConfig::parse(config);
+
+    config
+        .get("branch", Some(branch), "remote")
+        .cloned()
+        .zip(config.get("branch", Some(branch), "merge").cloned())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_split_revspec() {
+        assert_eq!(split_revspec("main"), Ok(("main", vec![])));
+        assert_eq!(
+            split_revspec("@~2^2^"),
+            Ok((
+                "@",
+                vec![
+                    Navigation::Ancestor(2),
+                    Navigation::Parent(2),
+                    Navigation::Parent(1)
+                ]
+            ))
+        );
+        assert_eq!(
+            split_revspec("main@{u}~"),
+            Ok(("main@{u}", vec![Navigation::Ancestor(1)]))
+        );
+        assert_eq!(split_revspec("~1"), Err(GitObjectError::RefNotFound));
+        assert_eq!(split_revspec("main~x"), Err(GitObjectError::RefNotFound));
+        assert_eq!(split_revspec("main~1é"), Err(GitObjectError::RefNotFound));
+        assert_eq!(split_revspec("main^é"), Err(GitObjectError::RefNotFound));
+    }
+
+    #[test]
+    fn test_strip_suffix_ignore_case() {
+        assert_eq!(strip_suffix_ignore_case("main@{U}", "@{u}"), Some("main"));
+        assert_eq!(strip_suffix_ignore_case("@{u}", "@{upstream}"), None);
+    }
+
+    #[test]
+    fn test_parse_date() {
+        let now = 1704153600;
+
+        assert_eq!(parse_date("now", now), Some(now));
+        assert_eq!(parse_date("yesterday", now), Some(now - SECONDS_PER_DAY));
+        assert_eq!(
+            parse_date("2.days.ago", now),
+            Some(now - 2 * SECONDS_PER_DAY)
+        );
+        assert_eq!(parse_date("3 hours ago", now), Some(now - 3 * 3600));
+        assert_eq!(parse_date("1970-01-01", now), Some(0));
+        assert_eq!(parse_date("2024-01-02", now), Some(1704153600));
+        assert_eq!(parse_date("2024-01-02 10:30", now), Some(1704191400));
+        assert_eq!(parse_date("2024-13-02", now), None);
+        assert_eq!(parse_date("2024-02-30", now), None);
+        assert_eq!(parse_date("someday", now), None);
+        assert_eq!(parse_date("9223372036854775807 years ago", now), None);
+        assert_eq!(parse_date("99999999999-01-01", now), None);
+        assert_eq!(parse_date("2024-01-02 99999999999:00", now), None);
+    }
+
+    #[test]
+    fn test_branch_config() {
+        let config = "[core]\n\tbare = false\n[branch \"main\"]\n\tremote = origin\n\tmerge = refs/heads/main\n[branch \"topic\"]\n\tremote = .\n";
+
+        assert_eq!(
+            branch_config(config, "main"),
+            Some(("origin".to_string(), "refs/heads/main".to_string()))
+        );
+        assert_eq!(branch_config(config, "topic"), None);
+        assert_eq!(branch_config(config, "other"), None);
+    }
+}
@@ -0,0 +1,99 @@
+use serde::{Deserialize, Serialize};
+
+use super::{
+    git_commit_author::GitCommitAuthor, git_folders::GitRefs, git_project::GitProject,
+    git_refs::HeadState, git_worktree::ref_names,
+};
+use crate::errors::git_object_error::GitObjectError;
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct HeadSummary {
+    hash: String,
+    subject: String,
+    author: GitCommitAuthor,
+}
+
+impl HeadSummary {
+    pub fn get_hash(&self) -> &String {
+        &self.hash
+    }
+
+    pub fn get_subject(&self) -> &String {
+        &self.subject
+    }
+
+    pub fn get_author(&self) -> &GitCommitAuthor {
+        &self.author
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct RepoSnapshot {
+    branch: Option<String>,
+    head: Option<HeadSummary>,
+    branch_count: usize,
+    tag_count: usize,
+    clean: bool,
+}
+
+impl RepoSnapshot {
+    // Everything the first paint of a repository shows, the HEAD commit goes through the
+    // project cache so later requests for it are not read again
+    pub fn read(project: &GitProject) -> Result<Self, GitObjectError> {
+        let (branch, head_hash) = match project.resolve_head()? {
+            HeadState::Branch { name, hash } => (Some(name), hash),
+            HeadState::Detached(hash) => (None, Some(hash)),
+        };
+
+        let head = match head_hash {
+            Some(hash) => {
+                let commit = project.head_commit()?;
+                Some(HeadSummary {
+                    hash,
+                    subject: commit.subject().to_string(),
+                    author: commit.get_author().clone(),
+                })
+            }
+            None => None,
+        };
+
+        let common_dir = project.common_dir();
+        let branch_count = ref_names(&common_dir, &GitRefs::HEADS)?.len();
+        let tag_count = ref_names(&common_dir, &GitRefs::TAGS)?.len();
+
+        // A bare repository has no working tree to be dirty
+        let clean = project.is_bare() || project.status()?.is_empty();
+
+        Ok(Self {
+            branch,
+            head,
+            branch_count,
+            tag_count,
+            clean,
+        })
+    }
+
+    // None when HEAD is detached
+    pub fn get_branch(&self) -> Option<&String> {
+        self.branch.as_ref()
+    }
+
+    // None on an unborn branch
+    pub fn get_head(&self) -> Option<&HeadSummary> {
+        self.head.as_ref()
+    }
+
+    pub fn get_branch_count(&self) -> usize {
+        self.branch_count
+    }
+
+    pub fn get_tag_count(&self) -> usize {
+        self.tag_count
+    }
+
+    pub fn is_clean(&self) -> bool {
+        self.clean
+    }
+}
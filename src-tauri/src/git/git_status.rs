@@ -0,0 +1,429 @@
+use serde::{Deserialize, Serialize};
+use std::{
+    collections::{BTreeMap, BTreeSet},
+    fs,
+    io::Read,
+    path::{Path, PathBuf},
+    time::{SystemTime, UNIX_EPOCH},
+};
+
+use super::{
+    git_folders::GIT_FOLDER,
+    git_hash::hash_object,
+    git_ignore::{IgnoreRules, GITIGNORE_FILE},
+    git_index::{GitIndex, GitIndexEntry, INDEX_FILE},
+    git_project::GitProject,
+    git_tree::{GitTree, GitTreeMode},
+    object::{open_object_reader, GitObject, Header, ObjectFormat},
+};
+use crate::errors::git_object_error::GitObjectError;
+
+const HASH_CHUNK_SIZE: usize = 64 * 1024;
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub enum GitFileState {
+    Untracked,
+    Added,
+    Modified,
+    Deleted,
+    Conflicted,
+    // The file or a directory above it couldn't be read
+    Unreadable,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct GitStatusEntry {
+    path: String,
+    staged: Option<GitFileState>,
+    unstaged: Option<GitFileState>,
+}
+
+impl GitStatusEntry {
+    pub fn get_path(&self) -> &String {
+        &self.path
+    }
+
+    pub fn get_staged(&self) -> Option<&GitFileState> {
+        self.staged.as_ref()
+    }
+
+    pub fn get_unstaged(&self) -> Option<&GitFileState> {
+        self.unstaged.as_ref()
+    }
+}
+
+pub fn status(project: &GitProject) -> Result<Vec<GitStatusEntry>, GitObjectError> {
+    // An unborn branch has no commit yet, so everything is compared against the empty tree
+    let mut head = BTreeMap::new();
+    match project.head_commit() {
+        Ok(commit) => flatten_tree(project, commit.get_tree_hash(), "", &mut head)?,
+        Err(GitObjectError::UnbornBranch) => {}
+        Err(error) => return Err(error),
+    }
+
+    // Each indexed file keeps its index entry when its working tree copy is looked at.
+    // Sparse directories are trees outside the sparse checkout, so they are expanded
+    // like diff_staged does and their files are never looked for on disk
+    let index = GitIndex::read(project)?;
+    let mut indexed = BTreeMap::<String, (String, Option<&GitIndexEntry>)>::new();
+    let mut conflicted = BTreeSet::new();
+    for entry in index.entries() {
+        if entry.stage != 0 {
+            conflicted.insert(entry.path.clone());
+        } else if entry.is_sparse_directory() {
+            let mut files = BTreeMap::new();
+            let prefix = format!("{}/", entry.path.trim_end_matches('/'));
+            flatten_tree(project, &entry.hash, &prefix, &mut files)?;
+            indexed.extend(files.into_iter().map(|(path, hash)| (path, (hash, None))));
+        } else {
+            let checked_out = !entry.skip_worktree && entry.mode != GitTreeMode::Submodule;
+            indexed.insert(
+                entry.path.clone(),
+                (entry.hash.clone(), Some(entry).filter(|_| checked_out)),
+            );
+        }
+    }
+
+    let tracked = indexed
+        .keys()
+        .chain(conflicted.iter())
+        .cloned()
+        .collect::<BTreeSet<String>>();
+    let mut working_tree = WorkingTree::default();
+    read_working_tree(
+        Path::new(project.get_directory()),
+        "",
+        false,
+        &tracked,
+        &mut IgnoreRules::for_project(project),
+        &mut working_tree,
+    );
+    let index_modified = fs::metadata(project.git_dir().join(INDEX_FILE))
+        .and_then(|metadata| metadata.modified())
+        .ok();
+
+    let mut entries = BTreeMap::<String, GitStatusEntry>::new();
+    let mut set_state = |path: &str, staged: bool, state: GitFileState| {
+        let entry = entries
+            .entry(path.to_string())
+            .or_insert_with(|| GitStatusEntry {
+                path: path.to_string(),
+                staged: None,
+                unstaged: None,
+            });
+
+        if staged {
+            entry.staged = Some(state);
+        } else {
+            entry.unstaged = Some(state);
+        }
+    };
+
+    for (path, (indexed_hash, index_entry)) in &indexed {
+        match head.get(path) {
+            None => set_state(path, true, GitFileState::Added),
+            Some(hash) if hash != indexed_hash => set_state(path, true, GitFileState::Modified),
+            _ => {}
+        }
+
+        let Some(index_entry) = index_entry else {
+            continue;
+        };
+
+        let changed = match working_tree.files.get(path) {
+            None if working_tree.is_unreadable(path) => Err(GitObjectError::FileReadError),
+            None => {
+                set_state(path, false, GitFileState::Deleted);
+                continue;
+            }
+            Some(file) => working_file_changed(
+                index_entry,
+                file,
+                project.get_object_format(),
+                index_modified,
+            ),
+        };
+        match changed {
+            Ok(true) => set_state(path, false, GitFileState::Modified),
+            Ok(false) => {}
+            Err(_) => set_state(path, false, GitFileState::Unreadable),
+        }
+    }
+
+    // Unmerged paths only have their conflict to resolve, the stages are not compared
+    // with HEAD or the working tree like `git status` does
+    for path in &conflicted {
+        set_state(path, false, GitFileState::Conflicted);
+    }
+
+    for path in head.keys().filter(|path| !tracked.contains(*path)) {
+        set_state(path, true, GitFileState::Deleted);
+    }
+
+    // Untracked files are listed without being read
+    for path in working_tree
+        .files
+        .keys()
+        .filter(|path| !tracked.contains(*path))
+    {
+        set_state(path, false, GitFileState::Untracked);
+    }
+
+    Ok(entries.into_values().collect())
+}
+
+fn flatten_tree(
+    project: &GitProject,
+    hash: &str,
+    prefix: &str,
+    files: &mut BTreeMap<String, String>,
+) -> Result<(), GitObjectError> {
+    let tree = GitTree::from_hash(project, hash)?;
+
+    for entry in tree.entries() {
+        let path = format!("{}{}", prefix, entry.name);
+        match entry.mode {
+            GitTreeMode::Tree => flatten_tree(project, &entry.hash, &format!("{}/", path), files)?,
+            _ => {
+                files.insert(path, entry.hash.clone());
+            }
+        }
+    }
+
+    Ok(())
+}
+
+// The paths found in the working tree, they are only read when compared with the index
+#[derive(Debug, Default)]
+struct WorkingTree {
+    files: BTreeMap<String, PathBuf>,
+    // Directories that couldn't be listed, each ending with a slash
+    unreadable_directories: Vec<String>,
+}
+
+impl WorkingTree {
+    fn is_unreadable(&self, path: &str) -> bool {
+        self.unreadable_directories
+            .iter()
+            .any(|directory| path.starts_with(directory.as_str()))
+    }
+}
+
+// Ignored paths are left out unless they are tracked, and an ignored directory is only
+// walked into when something under it is tracked. A directory that can't be read is
+// recorded instead of failing the whole walk
+fn read_working_tree(
+    directory: &Path,
+    prefix: &str,
+    ignored: bool,
+    tracked: &BTreeSet<String>,
+    ignore_rules: &mut IgnoreRules,
+    working_tree: &mut WorkingTree,
+) {
+    let Ok(read_dir) = fs::read_dir(directory) else {
+        working_tree.unreadable_directories.push(prefix.to_string());
+        return;
+    };
+
+    let pattern_count = ignore_rules.pattern_count();
+    ignore_rules.add_file(&directory.join(GITIGNORE_FILE), prefix);
+
+    for entry in read_dir.flatten() {
+        let name = entry.file_name().to_string_lossy().to_string();
+        if name == GIT_FOLDER {
+            continue;
+        }
+
+        let path = entry.path();
+        let relative_path = format!("{}{}", prefix, name);
+        let is_dir = entry.file_type().is_ok_and(|file_type| file_type.is_dir());
+        let ignored = ignored || ignore_rules.is_ignored(&relative_path, is_dir);
+
+        if is_dir {
+            let subdirectory_prefix = format!("{}/", relative_path);
+            let has_tracked = tracked
+                .range(subdirectory_prefix.clone()..)
+                .next()
+                .is_some_and(|tracked_path| tracked_path.starts_with(&subdirectory_prefix));
+
+            // Nested repositories are submodules and are not walked into
+            if (!ignored || has_tracked) && !path.join(GIT_FOLDER).exists() {
+                read_working_tree(
+                    &path,
+                    &subdirectory_prefix,
+                    ignored,
+                    tracked,
+                    ignore_rules,
+                    working_tree,
+                );
+            }
+        } else if !ignored || tracked.contains(&relative_path) {
+            working_tree.files.insert(relative_path, path);
+        }
+    }
+
+    ignore_rules.truncate(pattern_count);
+}
+
+// Files whose stat data matches the index entry are unchanged, like in git, so only
+// the others are hashed
+fn working_file_changed(
+    index_entry: &GitIndexEntry,
+    path: &Path,
+    object_format: ObjectFormat,
+    index_modified: Option<SystemTime>,
+) -> Result<bool, GitObjectError> {
+    let metadata = fs::symlink_metadata(path).map_err(|_| GitObjectError::FileReadError)?;
+    if stat_matches(index_entry, &metadata, index_modified) {
+        return Ok(false);
+    }
+
+    let hash = if metadata.file_type().is_symlink() {
+        let target = fs::read_link(path).map_err(|_| GitObjectError::FileReadError)?;
+        hash_object(
+            object_format,
+            &Header::Blob,
+            target.to_string_lossy().as_bytes(),
+        )
+    } else {
+        hash_file_as_blob(path, object_format)?
+    };
+
+    Ok(hash != index_entry.hash)
+}
+
+// A file written in the same second as the index may have changed after it was staged
+// without its stat data showing it, so it is only trusted when it is older than the
+// index. The index keeps the low 32 bits of the size and of the times
+fn stat_matches(
+    index_entry: &GitIndexEntry,
+    metadata: &fs::Metadata,
+    index_modified: Option<SystemTime>,
+) -> bool {
+    let Ok(modified) = metadata.modified() else {
+        return false;
+    };
+    if index_modified.is_none_or(|index_modified| modified >= index_modified) {
+        return false;
+    }
+    let Ok(since_epoch) = modified.duration_since(UNIX_EPOCH) else {
+        return false;
+    };
+
+    metadata.len() as u32 == index_entry.size
+        && since_epoch.as_secs() as u32 == index_entry.mtime.seconds
+        && since_epoch.subsec_nanos() == index_entry.mtime.nanoseconds
+        && inode_matches(index_entry, metadata)
+}
+
+#[cfg(unix)]
+fn inode_matches(index_entry: &GitIndexEntry, metadata: &fs::Metadata) -> bool {
+    use std::os::unix::fs::MetadataExt;
+
+    metadata.ino() as u32 == index_entry.ino
+}
+
+// Git for Windows doesn't record inodes
+#[cfg(not(unix))]
+fn inode_matches(_index_entry: &GitIndexEntry, _metadata: &fs::Metadata) -> bool {
+    true
+}
+
+// Working tree files can be far larger than what is reasonable to keep in memory,
+// so they are fed to the hasher a chunk at a time
+pub fn hash_file_as_blob(
+    path: &Path,
+    object_format: ObjectFormat,
+) -> Result<String, GitObjectError> {
+    let mut file = fs::File::open(path).map_err(|_| GitObjectError::FileReadError)?;
+    let size = file
+        .metadata()
+        .map_err(|_| GitObjectError::FileReadError)?
+        .len();
+
+    let mut hasher = object_format.hasher();
+    hasher.update(format!("blob {}\0", size).as_bytes());
+
+    let mut buffer = vec![0; HASH_CHUNK_SIZE];
+    loop {
+        let read = file
+            .read(&mut buffer)
+            .map_err(|_| GitObjectError::FileReadError)?;
+        if read == 0 {
+            break;
+        }
+        hasher.update(&buffer[..read]);
+    }
+
+    Ok(hasher.finalize_hex())
+}
+
+// Cheaper than hashing when the blob is already known: files whose size differs are
+// rejected from the metadata alone, otherwise both sides are compared a chunk at a time
+pub fn working_file_matches_blob(
+    project: &GitProject,
+    path: &Path,
+    blob_hash: &str,
+) -> Result<bool, GitObjectError> {
+    // Packed blobs are as common as loose ones after a clone or gc
+    let (object_type, blob_size, mut blob) = open_object_reader(project, blob_hash)?;
+    if object_type != Header::Blob {
+        return Err(GitObjectError::InvalidBlobFile);
+    }
+
+    let mut file = fs::File::open(Path::new(project.get_directory()).join(path))
+        .map_err(|_| GitObjectError::FileReadError)?;
+    let file_size = file
+        .metadata()
+        .map_err(|_| GitObjectError::FileReadError)?
+        .len();
+    if file_size != blob_size {
+        return Ok(false);
+    }
+
+    let mut file_buffer = vec![0; HASH_CHUNK_SIZE];
+    let mut blob_buffer = vec![0; HASH_CHUNK_SIZE];
+    loop {
+        let read = file
+            .read(&mut file_buffer)
+            .map_err(|_| GitObjectError::FileReadError)?;
+        if read == 0 {
+            return Ok(true);
+        }
+
+        blob.read_exact(&mut blob_buffer[..read])
+            .map_err(|_| GitObjectError::DecompressionError)?;
+        if file_buffer[..read] != blob_buffer[..read] {
+            return Ok(false);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_hash_file_as_blob() {
+        let folder = tempdir::TempDir::new("hash_file_as_blob").unwrap();
+        let path = folder.path().join("large.bin");
+
+        // Spans several chunks and ends in the middle of one
+        let content = (0..HASH_CHUNK_SIZE * 3 + 17)
+            .map(|i| (i % 251) as u8)
+            .collect::<Vec<u8>>();
+        fs::write(&path, &content).unwrap();
+
+        for object_format in [ObjectFormat::Sha1, ObjectFormat::Sha256] {
+            assert_eq!(
+                hash_file_as_blob(&path, object_format),
+                Ok(hash_object(object_format, &Header::Blob, &content))
+            );
+        }
+        assert_eq!(
+            hash_file_as_blob(&folder.path().join("missing"), ObjectFormat::Sha1),
+            Err(GitObjectError::FileReadError)
+        );
+    }
+}
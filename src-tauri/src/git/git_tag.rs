@@ -0,0 +1,534 @@
+use serde::{Deserialize, Serialize};
+use std::fmt;
+
+use super::{
+    git_commit::GitCommit,
+    git_commit_author::GitCommitAuthor,
+    git_folders::{GitFolders, GitRefs},
+    git_project::GitProject,
+    git_refs::{check_new_ref_name, create_ref},
+    git_worktree::ref_names,
+    object::{peek_object_header, GitObject, Header, ObjectFormat},
+};
+use crate::errors::git_object_error::GitObjectError;
+
+const MAX_TAG_DEPTH: usize = 10;
+const SIGNATURE_MARKERS: [&str; 3] = [
+    "-----BEGIN PGP SIGNATURE-----",
+    "-----BEGIN SSH SIGNATURE-----",
+    "-----BEGIN SIGNED MESSAGE-----",
+];
+
+#[derive(Debug, Clone)]
+pub struct GitTag {
+    object: String,
+    object_type: Header,
+    tag: String,
+    tagger: Option<GitCommitAuthor>,
+    message: String,
+    // The stored bytes of a tag that was read, so it keeps its hash and its signature
+    // is checked against exactly what was signed
+    raw_data: Option<Vec<u8>>,
+}
+
+impl PartialEq for GitTag {
+    fn eq(&self, other: &Self) -> bool {
+        self.object == other.object
+            && self.object_type == other.object_type
+            && self.tag == other.tag
+            && self.tagger == other.tagger
+            && self.message == other.message
+    }
+}
+
+impl GitTag {
+    pub fn new(
+        object: &str,
+        object_type: Header,
+        tag: &str,
+        tagger: Option<GitCommitAuthor>,
+        message: &str,
+    ) -> GitTag {
+        GitTag {
+            object: object.to_string(),
+            object_type,
+            tag: tag.to_string(),
+            tagger,
+            message: message.to_string(),
+            raw_data: None,
+        }
+    }
+
+    pub fn get_object(&self) -> &String {
+        &self.object
+    }
+
+    pub fn get_object_type(&self) -> &Header {
+        &self.object_type
+    }
+
+    pub fn get_tag(&self) -> &String {
+        &self.tag
+    }
+
+    pub fn get_tagger(&self) -> Option<&GitCommitAuthor> {
+        self.tagger.as_ref()
+    }
+
+    pub fn get_message(&self) -> &String {
+        &self.message
+    }
+
+    // None when the tag points at a tree, a blob or another tag
+    pub fn get_target_commit(
+        &self,
+        project: &GitProject,
+    ) -> Result<Option<GitCommit>, GitObjectError> {
+        match self.object_type {
+            Header::Commit => GitCommit::from_hash(project, &self.object).map(Some),
+            _ => Ok(None),
+        }
+    }
+
+    // The bytes git signs, unlike commits the signature is appended to the message
+    pub fn signing_payload(&self) -> Vec<u8> {
+        let data = self.get_data();
+        let Some(message_start) = data
+            .windows(2)
+            .position(|window| window == b"\n\n")
+            .map(|position| position + 2)
+        else {
+            return data;
+        };
+
+        let message = &data[message_start..];
+        let signature_start = SIGNATURE_MARKERS
+            .iter()
+            .filter_map(|marker| {
+                message
+                    .windows(marker.len())
+                    .enumerate()
+                    .filter(|(position, window)| {
+                        *window == marker.as_bytes()
+                            && (*position == 0 || message[position - 1] == b'\n')
+                    })
+                    .map(|(position, _)| position)
+                    .last()
+            })
+            .max();
+
+        match signature_start {
+            Some(position) => data[..message_start + position].to_vec(),
+            None => data,
+        }
+    }
+}
+
+impl GitObject for GitTag {
+    fn get_type(&self) -> Header {
+        Header::Tag
+    }
+
+    fn get_data_string(&self) -> String {
+        self.to_string()
+    }
+
+    // Reading drops the newline that ends a tag, it is written back here. Tags that
+    // were read are written exactly as they were stored
+    fn get_data(&self) -> Vec<u8> {
+        match &self.raw_data {
+            Some(raw_data) => raw_data.clone(),
+            None => {
+                let mut data = self.get_data_string().into_bytes();
+                data.push(b'\n');
+
+                data
+            }
+        }
+    }
+
+    fn from_object_data(
+        data: &[u8],
+        _size: usize,
+        _object_format: ObjectFormat,
+    ) -> Result<Self, GitObjectError> {
+        let raw_data = data;
+        let data = std::str::from_utf8(data).map_err(|_| GitObjectError::InvalidTagFile)?;
+
+        let data = data.strip_suffix('\n').unwrap_or(data);
+        let (headers, message) = data.split_once("\n\n").unwrap_or((data, ""));
+
+        let mut object = None;
+        let mut object_type = None;
+        let mut tag = None;
+        let mut tagger = None;
+        for line in headers.lines() {
+            let (prefix, value) = line.split_once(' ').ok_or(GitObjectError::InvalidTagFile)?;
+
+            match prefix {
+                "object" => object = Some(value),
+                "type" => object_type = Some(Header::from(value)),
+                "tag" => tag = Some(value),
+                "tagger" => {
+                    tagger = Some(
+                        GitCommitAuthor::from_string(value)
+                            .map_err(|_| GitObjectError::InvalidTagFile)?,
+                    )
+                }
+                _ => return Err(GitObjectError::InvalidTagFile),
+            }
+        }
+
+        let object_type = object_type
+            .filter(|object_type| *object_type != Header::Invalid)
+            .ok_or(GitObjectError::InvalidTagFile)?;
+
+        let mut git_tag = GitTag::new(
+            object.ok_or(GitObjectError::InvalidTagFile)?,
+            object_type,
+            tag.ok_or(GitObjectError::InvalidTagFile)?,
+            tagger,
+            message,
+        );
+        git_tag.raw_data = Some(raw_data.to_vec());
+
+        Ok(git_tag)
+    }
+}
+
+impl fmt::Display for GitTag {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let tagger = self
+            .tagger
+            .as_ref()
+            .map(|tagger| {
+                format!(
+                    "tagger {} <{}> {} {}\n",
+                    tagger.get_user().name,
+                    tagger.get_user().email,
+                    tagger.date_seconds,
+                    tagger.timezone
+                )
+            })
+            .unwrap_or_default();
+
+        write!(
+            f,
+            "object {}\ntype {}\ntag {}\n{}\n{}",
+            self.object, self.object_type, self.tag, tagger, self.message
+        )
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct TagDetails {
+    name: String,
+    tagger: Option<GitCommitAuthor>,
+    message: Option<String>,
+    commit_hash: String,
+    commit_subject: String,
+}
+
+impl TagDetails {
+    pub fn get_name(&self) -> &String {
+        &self.name
+    }
+
+    pub fn get_tagger(&self) -> Option<&GitCommitAuthor> {
+        self.tagger.as_ref()
+    }
+
+    pub fn get_message(&self) -> Option<&String> {
+        self.message.as_ref()
+    }
+
+    pub fn get_commit_hash(&self) -> &String {
+        &self.commit_hash
+    }
+
+    pub fn get_commit_subject(&self) -> &String {
+        &self.commit_subject
+    }
+
+    pub fn read(project: &GitProject, tag_name: &str) -> Result<TagDetails, GitObjectError> {
+        let hash = project
+            .resolve_ref(&format!("refs/tags/{}", tag_name))?
+            .ok_or(GitObjectError::RefNotFound)?;
+
+        let (commit_hash, annotation) = peel_tag(project, hash)?;
+        let commit = GitCommit::from_hash(project, &commit_hash)?;
+        let (tagger, message) = match annotation {
+            Some(GitTag {
+                tagger, message, ..
+            }) => (tagger, Some(message)),
+            None => (None, None),
+        };
+
+        Ok(TagDetails {
+            name: tag_name.to_string(),
+            tagger,
+            message,
+            commit_hash,
+            commit_subject: commit.subject().to_string(),
+        })
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct TagRef {
+    name: String,
+    commit_hash: String,
+    commit_time: i64,
+}
+
+impl TagRef {
+    pub fn get_name(&self) -> &String {
+        &self.name
+    }
+
+    pub fn get_commit_hash(&self) -> &String {
+        &self.commit_hash
+    }
+
+    pub fn get_commit_time(&self) -> i64 {
+        self.commit_time
+    }
+}
+
+// Points `refs/tags/<name>` straight at the object `target` resolves to
+pub fn create_lightweight_tag(
+    project: &GitProject,
+    name: &str,
+    target: &str,
+) -> Result<(), GitObjectError> {
+    check_new_ref_name(project, &GitRefs::TAGS, name)?;
+    let (hash, _) = resolve_tag_target(project, target)?;
+
+    create_ref(project, &tag_ref_name(name), &hash)
+}
+
+// Writes a tag object for `target` and points `refs/tags/<name>` at it. The newline git
+// ends every tag with is added when the tag is written, so the message is kept without it
+pub fn create_annotated_tag(
+    project: &GitProject,
+    name: &str,
+    target: &str,
+    tagger: GitCommitAuthor,
+    message: &str,
+) -> Result<(), GitObjectError> {
+    check_new_ref_name(project, &GitRefs::TAGS, name)?;
+    let (hash, object_type) = resolve_tag_target(project, target)?;
+
+    let message = message.trim_end_matches('\n');
+    let tag = GitTag::new(&hash, object_type, name, Some(tagger), message);
+    tag.write_object(project)?;
+
+    create_ref(
+        project,
+        &tag_ref_name(name),
+        &tag.get_hash_with_format(project.get_object_format()),
+    )
+}
+
+// Any object can be tagged, so the target is only resolved and not peeled
+fn resolve_tag_target(
+    project: &GitProject,
+    target: &str,
+) -> Result<(String, Header), GitObjectError> {
+    let hash = project.resolve_revision(target)?;
+    let (object_type, _) = peek_object_header(project, &hash)?;
+
+    Ok((hash, object_type))
+}
+
+fn tag_ref_name(name: &str) -> String {
+    format!("{}/{}/{}", GitFolders::REFS, GitRefs::TAGS, name)
+}
+
+// Follows annotated tags, which can be nested, down to the object they point at and
+// returns it together with the outermost annotation. Lightweight tags have none
+pub fn peel_tag(
+    project: &GitProject,
+    mut hash: String,
+) -> Result<(String, Option<GitTag>), GitObjectError> {
+    let mut annotation = None;
+    for _ in 0..MAX_TAG_DEPTH {
+        if peek_object_header(project, &hash)?.0 != Header::Tag {
+            return Ok((hash, annotation));
+        }
+
+        let tag = GitTag::from_hash(project, &hash)?;
+        hash = tag.get_object().clone();
+        annotation.get_or_insert(tag);
+    }
+
+    Err(GitObjectError::InvalidTagFile)
+}
+
+// Newest first by the committer date of the tagged commit, the annotation date is
+// ignored so lightweight and annotated tags sort the same way. Tags of trees and blobs
+// have no date to sort by and are left out, like tags that are broken or point at an
+// object that is missing
+pub fn tags_by_date(project: &GitProject) -> Result<Vec<TagRef>, GitObjectError> {
+    let mut tags = Vec::new();
+    for name in ref_names(&project.common_dir(), &GitRefs::TAGS)? {
+        let Ok(Some(hash)) = project.resolve_ref(&format!("refs/tags/{}", name)) else {
+            continue;
+        };
+        let Ok((commit_hash, _)) = peel_tag(project, hash) else {
+            continue;
+        };
+        if !matches!(
+            peek_object_header(project, &commit_hash),
+            Ok((Header::Commit, _))
+        ) {
+            continue;
+        }
+        let Ok(commit_time) = GitCommit::commit_time(project, &commit_hash) else {
+            continue;
+        };
+
+        tags.push(TagRef {
+            name,
+            commit_hash,
+            commit_time,
+        });
+    }
+
+    // The sort is stable, so tags on commits from the same second stay in name order
+    tags.sort_by_key(|tag| std::cmp::Reverse(tag.commit_time));
+    Ok(tags)
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io::Read;
+
+    use super::*;
+    use crate::git::git_user::GitUser;
+
+    fn create_encoded_tag_file(file_content: &str) -> Vec<u8> {
+        let file_content_to_encode =
+            format!("tag {}\x00{}\n", file_content.len() + 1, file_content);
+        let mut zlib = flate2::bufread::ZlibEncoder::new(
+            file_content_to_encode.as_bytes(),
+            flate2::Compression::default(),
+        );
+        let mut encoded_file_content = Vec::new();
+        zlib.read_to_end(&mut encoded_file_content).unwrap();
+
+        encoded_file_content
+    }
+
+    #[test]
+    fn test_git_tag_from_encoded_data() {
+        let tag = GitTag::new(
+            "df6773ea47ed3fce3b3bb14e3d1101963e77ef08",
+            Header::Commit,
+            "v1.0.0",
+            Some(GitCommitAuthor::new(
+                GitUser::new("Test User".to_string(), "test.user@email.com".to_string()),
+                1234,
+                "+0200".to_string(),
+            )),
+            "First release\n",
+        );
+
+        let encoded_data = create_encoded_tag_file(&tag.get_data_string());
+
+        assert_eq!(GitTag::from_encoded_data(&encoded_data), Ok(tag));
+    }
+
+    #[test]
+    fn test_git_tag_from_encoded_data_without_final_newline() {
+        let file_content = "object df6773ea47ed3fce3b3bb14e3d1101963e77ef08\ntype commit\ntag v1.0.0\n\nMise \u{e0} jour du r\u{e9}sum\u{e9}";
+        let file_content_to_encode = format!("tag {}\x00{}", file_content.len(), file_content);
+        let mut zlib = flate2::bufread::ZlibEncoder::new(
+            file_content_to_encode.as_bytes(),
+            flate2::Compression::default(),
+        );
+        let mut encoded_data = Vec::new();
+        zlib.read_to_end(&mut encoded_data).unwrap();
+
+        let tag = GitTag::from_encoded_data(&encoded_data).unwrap();
+        assert_eq!(tag.get_message(), "Mise \u{e0} jour du r\u{e9}sum\u{e9}");
+    }
+
+    #[test]
+    fn test_git_tag_signing_payload() {
+        let tagger = GitCommitAuthor::new(
+            GitUser::new("Test User".to_string(), "test.user@email.com".to_string()),
+            1234,
+            "+0200".to_string(),
+        );
+        let unsigned = GitTag::new(
+            "df6773ea47ed3fce3b3bb14e3d1101963e77ef08",
+            Header::Commit,
+            "v1.0.0",
+            Some(tagger.clone()),
+            "First release\n",
+        );
+        let signed = GitTag::new(
+            "df6773ea47ed3fce3b3bb14e3d1101963e77ef08",
+            Header::Commit,
+            "v1.0.0",
+            Some(tagger),
+            "First release\n-----BEGIN PGP SIGNATURE-----\n\niQEzBAABCAAdFiEE\n-----END PGP SIGNATURE-----\n",
+        );
+
+        assert_eq!(
+            signed.signing_payload(),
+            unsigned.get_data_string().into_bytes()
+        );
+        assert_eq!(unsigned.signing_payload(), unsigned.get_data());
+    }
+
+    #[test]
+    fn test_git_tag_keeps_stored_bytes() {
+        // The headers are out of the usual order and nothing follows the signature, so
+        // rebuilding the tag from its fields would give other bytes
+        let file_content = "type commit\nobject df6773ea47ed3fce3b3bb14e3d1101963e77ef08\ntag v1.0.0\n\nFirst release\n-----BEGIN SSH SIGNATURE-----\nU1NIU0lH\n-----END SSH SIGNATURE-----";
+        let file_content_to_encode = format!("tag {}\x00{}", file_content.len(), file_content);
+        let mut zlib = flate2::bufread::ZlibEncoder::new(
+            file_content_to_encode.as_bytes(),
+            flate2::Compression::default(),
+        );
+        let mut encoded_data = Vec::new();
+        zlib.read_to_end(&mut encoded_data).unwrap();
+
+        let tag = GitTag::from_encoded_data(&encoded_data).unwrap();
+        assert_eq!(tag.get_data(), file_content.as_bytes());
+        assert_eq!(
+            tag.get_hash(),
+            ObjectFormat::Sha1.hash(file_content_to_encode.as_bytes())
+        );
+        assert_eq!(
+            tag.signing_payload(),
+            b"type commit\nobject df6773ea47ed3fce3b3bb14e3d1101963e77ef08\ntag v1.0.0\n\nFirst release\n"
+        );
+    }
+
+    #[test]
+    fn test_git_tag_from_encoded_data_invalid() {
+        let encoded_data = create_encoded_tag_file("object abc\ntype unknown\ntag v1\n\n");
+
+        assert_eq!(
+            GitTag::from_encoded_data(&encoded_data),
+            Err(GitObjectError::InvalidTagFile)
+        );
+    }
+
+    #[test]
+    fn test_git_tag_from_encoded_data_empty() {
+        assert_eq!(
+            GitTag::from_encoded_data(&[]),
+            Err(GitObjectError::EmptyObject)
+        );
+        assert_eq!(
+            GitTag::from_encoded_data(&[0x00, 0x01]),
+            Err(GitObjectError::DecompressionError)
+        );
+    }
+}
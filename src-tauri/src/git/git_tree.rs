@@ -1,6 +1,19 @@
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+use std::{
+    collections::HashSet,
+    io::{Cursor, Write},
+    path::Path,
+};
+
 use crate::errors::git_object_error::GitObjectError;
 
-use super::object::{GitObject, Header};
+use super::{
+    git_blob::GitBlob,
+    git_diff::{diff_tree_entries, diff_trees, TreeChange},
+    git_pack::{from_hex, to_hex},
+    git_project::GitProject,
+    object::{parse_object_header, read_object_data, GitObject, Header, ObjectFormat},
+};
 
 #[derive(Debug, Clone, PartialEq)]
 pub enum GitTreeMode {
@@ -12,14 +25,46 @@ pub enum GitTreeMode {
 }
 
 impl GitTreeMode {
-    pub fn from_mode_str(mode: &str) -> Self {
+    pub fn from_mode_str(mode: &str) -> Result<Self, GitObjectError> {
         match mode {
-            "100644" => GitTreeMode::File,
-            "100755" => GitTreeMode::Executable,
-            "120000" => GitTreeMode::Symlink,
-            "040000" => GitTreeMode::Tree,
-            "160000" => GitTreeMode::Submodule,
-            _ => panic!("Invalid mode: {}", mode),
+            "100644" => Ok(GitTreeMode::File),
+            "100755" => Ok(GitTreeMode::Executable),
+            "120000" => Ok(GitTreeMode::Symlink),
+            // git itself writes directories without the leading zero
+            "040000" | "40000" => Ok(GitTreeMode::Tree),
+            "160000" => Ok(GitTreeMode::Submodule),
+            _ => Err(GitObjectError::InvalidTreeMode),
+        }
+    }
+
+    // Submodules are directories with their own repository and are detected separately
+    pub fn from_path(path: &Path) -> std::io::Result<Self> {
+        let metadata = std::fs::symlink_metadata(path)?;
+        if metadata.file_type().is_symlink() {
+            return Ok(GitTreeMode::Symlink);
+        }
+        if metadata.is_dir() {
+            return Ok(GitTreeMode::Tree);
+        }
+
+        #[cfg(unix)]
+        {
+            use std::os::unix::fs::PermissionsExt;
+
+            if metadata.permissions().mode() & 0o111 != 0 {
+                return Ok(GitTreeMode::Executable);
+            }
+        }
+
+        // The executable bit isn't tracked on Windows
+        Ok(GitTreeMode::File)
+    }
+
+    // How the mode is written in tree objects, git leaves out the leading zero of trees
+    pub fn to_object_mode_str(&self) -> &str {
+        match self {
+            GitTreeMode::Tree => "40000",
+            _ => self.to_mode_str(),
         }
     }
 
@@ -34,6 +79,22 @@ impl GitTreeMode {
     }
 }
 
+// Modes cross the Tauri boundary as the strings git writes, like "100644"
+impl Serialize for GitTreeMode {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_str(self.to_mode_str())
+    }
+}
+
+impl<'de> Deserialize<'de> for GitTreeMode {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let mode = String::deserialize(deserializer)?;
+
+        GitTreeMode::from_mode_str(&mode)
+            .map_err(|_| serde::de::Error::custom(format!("invalid tree mode {}", mode)))
+    }
+}
+
 impl std::fmt::Display for GitTreeMode {
     fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
         match self {
@@ -43,14 +104,57 @@ impl std::fmt::Display for GitTreeMode {
     }
 }
 
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 pub struct GitTreeEntry {
     pub mode: GitTreeMode,
+    #[serde(deserialize_with = "deserialize_hash")]
     pub hash: String,
     pub name: String,
 }
 
+// Entries are stored with their hash as raw bytes, so only a full hex hash of the
+// format of the tree can be written back
+fn is_entry_hash(hash: &str, object_format: ObjectFormat) -> bool {
+    hash.len() == object_format.hash_hex_len() && hash.chars().all(|c| c.is_ascii_hexdigit())
+}
+
+fn deserialize_hash<'de, D: Deserializer<'de>>(deserializer: D) -> Result<String, D::Error> {
+    let hash = String::deserialize(deserializer)?;
+    if ![ObjectFormat::Sha1, ObjectFormat::Sha256]
+        .into_iter()
+        .any(|object_format| is_entry_hash(&hash, object_format))
+    {
+        return Err(serde::de::Error::custom(format!(
+            "invalid tree entry hash {}",
+            hash
+        )));
+    }
+
+    Ok(hash)
+}
+
+impl GitTreeEntry {
+    // A submodule entry, whose hash is a commit of the submodule and not of this repository
+    pub fn is_gitlink(&self) -> bool {
+        self.mode == GitTreeMode::Submodule
+    }
+
+    fn sort_key(&self) -> Vec<u8> {
+        let mut key = self.name.as_bytes().to_vec();
+        if self.mode == GitTreeMode::Tree {
+            key.push(b'/');
+        }
+
+        key
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 pub struct GitTree {
     entries: Vec<GitTreeEntry>,
+    // A tree only holds hashes of the format of its repository
+    #[serde(default)]
+    object_format: ObjectFormat,
 }
 
 impl Default for GitTree {
@@ -61,27 +165,164 @@ impl Default for GitTree {
 
 impl GitTree {
     pub fn new() -> Self {
+        Self::with_object_format(ObjectFormat::Sha1)
+    }
+
+    pub fn with_object_format(object_format: ObjectFormat) -> Self {
         Self {
             entries: Vec::new(),
+            object_format,
         }
     }
 
-    pub fn add_entry(&mut self, mode: GitTreeMode, hash: String, name: String) {
-        self.entries.push(GitTreeEntry { mode, hash, name });
+    // Keeps the entries in the order git writes them, where trees sort as if their
+    // name ended with a slash
+    pub fn add_entry(
+        &mut self,
+        mode: GitTreeMode,
+        hash: String,
+        name: String,
+    ) -> Result<(), GitObjectError> {
+        if !is_entry_hash(&hash, self.object_format) {
+            return Err(GitObjectError::InvalidHash);
+        }
+
+        let entry = GitTreeEntry { mode, hash, name };
+        let position = self
+            .entries
+            .partition_point(|existing| existing.sort_key() <= entry.sort_key());
+
+        self.entries.insert(position, entry);
+
+        Ok(())
     }
 
     pub fn entries(&self) -> &Vec<GitTreeEntry> {
         &self.entries
     }
 
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+
+    pub fn entries_page(&self, offset: usize, limit: usize) -> &[GitTreeEntry] {
+        let start = offset.min(self.entries.len());
+        let end = start.saturating_add(limit).min(self.entries.len());
+
+        &self.entries[start..end]
+    }
+
+    // Only the first entry when a corrupt tree has the name more than once
     pub fn get_entry_by_name(&self, name: &str) -> Option<&GitTreeEntry> {
         self.entries.iter().find(|entry| entry.name == name)
     }
 
+    // Names git would reject the tree for, each reported once in the order they appear.
+    // A file and a folder with the same name count too since they share a path. Only a
+    // lenient read keeps such a tree, the strict one fails on the first of them
+    pub fn duplicate_names(&self) -> Vec<String> {
+        let mut seen = HashSet::new();
+        let mut duplicates = Vec::new();
+        for entry in &self.entries {
+            if !seen.insert(entry.name.as_str()) && !duplicates.contains(&entry.name) {
+                duplicates.push(entry.name.clone());
+            }
+        }
+
+        duplicates
+    }
+
     pub fn get_entry_by_hash(&self, hash: &str) -> Option<&GitTreeEntry> {
         self.entries.iter().find(|entry| entry.hash == hash)
     }
 
+    // Every path in this tree and its subtrees where the blob is stored, the same
+    // content can be checked in under several names
+    pub fn find_paths_for_hash(
+        &self,
+        project: &GitProject,
+        blob_hash: &str,
+    ) -> Result<Vec<String>, GitObjectError> {
+        let mut paths = Vec::new();
+        self.collect_paths_for_hash(project, blob_hash, "", &mut paths)?;
+
+        Ok(paths)
+    }
+
+    fn collect_paths_for_hash(
+        &self,
+        project: &GitProject,
+        blob_hash: &str,
+        prefix: &str,
+        paths: &mut Vec<String>,
+    ) -> Result<(), GitObjectError> {
+        for entry in &self.entries {
+            let path = format!("{}{}", prefix, entry.name);
+            match entry.mode {
+                GitTreeMode::Tree => GitTree::from_hash(project, &entry.hash)?
+                    .collect_paths_for_hash(project, blob_hash, &format!("{}/", path), paths)?,
+                GitTreeMode::Submodule => {}
+                _ if entry.hash == blob_hash => paths.push(path),
+                _ => {}
+            }
+        }
+
+        Ok(())
+    }
+
+    // Bytes the files would take once checked out, blob sizes come from the object
+    // headers so nothing is inflated. Submodules are separate checkouts and not counted
+    pub fn checkout_size(&self, project: &GitProject) -> Result<u64, GitObjectError> {
+        let mut size = 0;
+        for entry in &self.entries {
+            size += match entry.mode {
+                GitTreeMode::Tree => {
+                    GitTree::from_hash(project, &entry.hash)?.checkout_size(project)?
+                }
+                GitTreeMode::Submodule => 0,
+                _ => project.blob_size(&entry.hash)?,
+            };
+        }
+
+        Ok(size)
+    }
+
+    pub fn diff(
+        &self,
+        project: &GitProject,
+        new: &GitTree,
+    ) -> Result<Vec<TreeChange>, GitObjectError> {
+        diff_trees(project, self, new)
+    }
+
+    // Only the entries of the two trees themselves, a changed subtree is a single change
+    // and nothing has to be read
+    pub fn diff_shallow(&self, new: &GitTree) -> Vec<TreeChange> {
+        diff_tree_entries(self, new)
+    }
+
+    // Root commits have no parent tree, so everything they contain is an addition
+    pub fn diff_against_empty(
+        &self,
+        project: &GitProject,
+    ) -> Result<Vec<TreeChange>, GitObjectError> {
+        diff_trees(project, &GitTree::new(), self)
+    }
+
+    // The object exactly as git stores it before compression, the entries are kept in
+    // git's order by add_entry and nothing follows the last hash
+    pub fn to_canonical_bytes(&self) -> Vec<u8> {
+        let data = self.get_data();
+        let mut bytes = format!("{} {}\0", self.get_type(), data.len()).into_bytes();
+        bytes.extend(data);
+
+        bytes
+    }
+
     pub fn get_trees(&self) -> Vec<&GitTreeEntry> {
         self.entries
             .iter()
@@ -95,6 +336,125 @@ impl GitTree {
             .filter(|entry| entry.mode != GitTreeMode::Tree)
             .collect()
     }
+
+    pub fn write_tar(
+        &self,
+        project: &GitProject,
+        prefix: &str,
+        out: &mut dyn Write,
+    ) -> Result<(), GitObjectError> {
+        let mut builder = tar::Builder::new(out);
+        self.append_to_tar(project, prefix, &mut builder)?;
+
+        builder.finish().map_err(|_| GitObjectError::ArchiveError)
+    }
+
+    fn append_to_tar(
+        &self,
+        project: &GitProject,
+        prefix: &str,
+        builder: &mut tar::Builder<&mut dyn Write>,
+    ) -> Result<(), GitObjectError> {
+        for entry in &self.entries {
+            let path = format!("{}{}", prefix, entry.name);
+
+            let mut header = tar::Header::new_gnu();
+            let result = match entry.mode {
+                GitTreeMode::Tree => {
+                    GitTree::from_hash(project, &entry.hash)?.append_to_tar(
+                        project,
+                        &format!("{}/", path),
+                        builder,
+                    )?;
+                    continue;
+                }
+                // Like `git archive`, the contents of submodules are not included
+                GitTreeMode::Submodule => continue,
+                GitTreeMode::Symlink => {
+                    let blob = GitBlob::from_hash(project, &entry.hash)?;
+                    let target = String::from_utf8_lossy(blob.data()).to_string();
+
+                    header.set_entry_type(tar::EntryType::Symlink);
+                    header.set_mode(0o777);
+                    header.set_size(0);
+                    builder.append_link(&mut header, &path, target)
+                }
+                GitTreeMode::File | GitTreeMode::Executable => {
+                    let blob = GitBlob::from_hash(project, &entry.hash)?;
+                    let mode = match entry.mode {
+                        GitTreeMode::Executable => 0o755,
+                        _ => 0o644,
+                    };
+
+                    header.set_entry_type(tar::EntryType::Regular);
+                    header.set_mode(mode);
+                    header.set_size(blob.size() as u64);
+                    builder.append_data(&mut header, &path, blob.data())
+                }
+            };
+
+            result.map_err(|_| GitObjectError::ArchiveError)?;
+        }
+
+        Ok(())
+    }
+
+    pub fn write_zip(
+        &self,
+        project: &GitProject,
+        prefix: &str,
+        out: &mut dyn Write,
+    ) -> Result<(), GitObjectError> {
+        // The zip central directory needs a seekable writer, so the archive is built in
+        // memory and copied out once finished
+        let mut writer = zip::ZipWriter::new(Cursor::new(Vec::new()));
+        self.append_to_zip(project, prefix, &mut writer)?;
+
+        let archive = writer.finish().map_err(|_| GitObjectError::ArchiveError)?;
+        out.write_all(archive.get_ref())
+            .map_err(|_| GitObjectError::ArchiveError)
+    }
+
+    fn append_to_zip(
+        &self,
+        project: &GitProject,
+        prefix: &str,
+        writer: &mut zip::ZipWriter<Cursor<Vec<u8>>>,
+    ) -> Result<(), GitObjectError> {
+        for entry in &self.entries {
+            let path = format!("{}{}", prefix, entry.name);
+
+            // Symlinks can't be created reliably on Windows, so they are stored as
+            // regular files containing the link target
+            let mode = match entry.mode {
+                GitTreeMode::Tree => {
+                    GitTree::from_hash(project, &entry.hash)?.append_to_zip(
+                        project,
+                        &format!("{}/", path),
+                        writer,
+                    )?;
+                    continue;
+                }
+                GitTreeMode::Submodule => continue,
+                GitTreeMode::Executable => 0o755,
+                GitTreeMode::File | GitTreeMode::Symlink => 0o644,
+            };
+
+            let blob = GitBlob::from_hash(project, &entry.hash)?;
+            let options = zip::write::SimpleFileOptions::default()
+                .compression_method(zip::CompressionMethod::Deflated)
+                .unix_permissions(mode);
+
+            writer
+                .start_file(path, options)
+                .map_err(|_| GitObjectError::ArchiveError)?;
+            writer
+                .write_all(blob.data())
+                .map_err(|_| GitObjectError::ArchiveError)?;
+        }
+
+        Ok(())
+    }
 }
 
 impl GitObject for GitTree {
@@ -102,6 +462,7 @@ impl GitObject for GitTree {
         Header::Tree
     }
 
+    // Readable form of the entries with hex hashes, the stored one is `get_data`
     fn get_data_string(&self) -> String {
         let mut data = String::new();
         for entry in &self.entries {
@@ -116,33 +477,99 @@ impl GitObject for GitTree {
         data
     }
 
-    fn from_encoded_data(encoded_data: &[u8]) -> Result<Self, GitObjectError>
-    where
-        Self: Sized,
-    {
-        let decoded_data = Self::decode_data(encoded_data)?;
-        let (data, _) = Self::check_header_valid_and_get_data(&decoded_data)?;
-
-        let mut tree = Self::new();
-        let mut data = &data[..data.len() - 1];
-        while !data.is_empty() {
-            let (mode, rest_object) = data
-                .split_once(' ')
-                .ok_or(GitObjectError::InvalidTreeFile)?;
-            let (name, rest_object) = rest_object
-                .split_once('\0')
-                .ok_or(GitObjectError::InvalidTreeFile)?;
-            let hash = rest_object
-                .get(..40)
+    // Each entry is `<mode> <name>\0` followed by the hash as raw bytes, the hashes
+    // were checked when the entries were added
+    fn get_data(&self) -> Vec<u8> {
+        let mut data = Vec::new();
+        for entry in &self.entries {
+            data.extend_from_slice(
+                format!("{} {}\0", entry.mode.to_object_mode_str(), entry.name).as_bytes(),
+            );
+            data.extend_from_slice(&from_hex(&entry.hash).unwrap_or_default());
+        }
+
+        data
+    }
+
+    fn from_object_data(
+        data: &[u8],
+        size: usize,
+        object_format: ObjectFormat,
+    ) -> Result<Self, GitObjectError> {
+        Self::from_data(data, size, object_format, false)
+    }
+}
+
+impl GitTree {
+    pub fn from_hash_lenient(project: &GitProject, hash: &str) -> Result<Self, GitObjectError> {
+        let (_, size, data) = read_object_data(project, hash)?;
+        Self::from_data(&data, size, project.get_object_format(), true)
+    }
+
+    // Some non-git tools leave a stray newline after the last entry, which would
+    // otherwise be read as the start of another, malformed, entry
+    pub fn from_encoded_data_lenient(
+        encoded_data: &[u8],
+        object_format: ObjectFormat,
+    ) -> Result<Self, GitObjectError> {
+        let decoded_data = Self::decode_data_bytes(encoded_data)?;
+        let (_, size, data) = parse_object_header(&decoded_data)?;
+
+        Self::from_data(data, size as usize, object_format, true)
+    }
+
+    // Only the size from the header is read, which for loose trees is the whole payload
+    // since it ends right after the last hash
+    fn from_data(
+        data: &[u8],
+        size: usize,
+        object_format: ObjectFormat,
+        lenient: bool,
+    ) -> Result<Self, GitObjectError> {
+        let mut tree = Self::with_object_format(object_format);
+        let data = &data[..size];
+        let mut position = 0;
+        while position < data.len() {
+            if lenient && &data[position..] == b"\n" {
+                break;
+            }
+
+            let mode_end = position
+                + data[position..]
+                    .iter()
+                    .position(|byte| *byte == b' ')
+                    .ok_or(GitObjectError::InvalidTreeFile)?;
+            let name_end = mode_end
+                + 1
+                + data[mode_end + 1..]
+                    .iter()
+                    .position(|byte| *byte == 0)
+                    .ok_or(GitObjectError::InvalidTreeFile)?;
+            let hash_end = name_end + 1 + object_format.hash_byte_len();
+
+            let mode = std::str::from_utf8(&data[position..mode_end])
+                .map_err(|_| GitObjectError::InvalidTreeFile)?;
+            let name = std::str::from_utf8(&data[mode_end + 1..name_end])
+                .map_err(|_| GitObjectError::InvalidTreeFile)?;
+            let hash = data
+                .get(name_end + 1..hash_end)
+                .map(to_hex)
                 .ok_or(GitObjectError::InvalidTreeFile)?;
 
-            data = &rest_object[40..];
+            position = hash_end;
 
-            tree.add_entry(
-                GitTreeMode::from_mode_str(mode),
-                hash.to_string(),
-                name.to_string(),
-            );
+            // Stored trees are already sorted, keep them exactly as they were written
+            tree.entries.push(GitTreeEntry {
+                mode: GitTreeMode::from_mode_str(mode)?,
+                hash,
+                name: name.to_string(),
+            });
+        }
+
+        if !lenient {
+            if let Some(name) = tree.duplicate_names().into_iter().next() {
+                return Err(GitObjectError::DuplicateTreeEntry(name));
+            }
         }
 
         Ok(tree)
@@ -156,26 +583,61 @@ mod tests {
     use super::*;
 
     fn create_encoded_tree_file(entries: Vec<GitTreeEntry>) -> Result<Vec<u8>, GitObjectError> {
-        let mut file_content = String::new();
+        let mut file_content = Vec::new();
         for entry in entries {
-            file_content.push_str(&format!(
-                "{} {}\0{}",
-                entry.mode.to_mode_str(),
-                entry.name,
-                entry.hash,
-            ));
+            file_content.extend_from_slice(
+                format!("{} {}\0", entry.mode.to_mode_str(), entry.name).as_bytes(),
+            );
+            file_content.extend_from_slice(&from_hex(&entry.hash).unwrap());
         }
 
-        let file_content_to_encode = format!("tree {}\x00{}\n", file_content.len(), file_content);
-        let mut zlib = flate2::bufread::ZlibEncoder::new(
-            file_content_to_encode.as_bytes(),
-            flate2::Compression::default(),
-        );
-        let mut encoded_file_content = Vec::new();
-        zlib.read_to_end(&mut encoded_file_content)
-            .map_err(|_| GitObjectError::DecompressionError)?;
+        Ok(encode_tree_data(&file_content))
+    }
+
+    fn encode_tree_data(data: &[u8]) -> Vec<u8> {
+        let mut zlib = flate2::write::ZlibEncoder::new(Vec::new(), flate2::Compression::default());
+        zlib.write_all(format!("tree {}\x00", data.len()).as_bytes())
+            .and_then(|_| zlib.write_all(data))
+            .unwrap();
+
+        zlib.finish().unwrap()
+    }
+
+    #[test]
+    fn test_git_tree_trailing_newline() {
+        let mut tree = GitTree::new();
+        tree.add_entry(
+            GitTreeMode::File,
+            "df6773ea47ed3fce3b3bb14e3d1101963e77ef08".to_string(),
+            "file1".to_string(),
+        )
+        .unwrap();
+        tree.add_entry(
+            GitTreeMode::Tree,
+            "df6773ea47ed3fce3b3bb14e3d1101963e77ef09".to_string(),
+            "tree1".to_string(),
+        )
+        .unwrap();
+
+        let clean = encode_tree_data(&tree.get_data());
+        for parsed in [
+            GitTree::from_encoded_data(&clean),
+            GitTree::from_encoded_data_lenient(&clean, ObjectFormat::Sha1),
+        ] {
+            assert_eq!(parsed.unwrap().get_data_string(), tree.get_data_string());
+        }
 
-        Ok(encoded_file_content)
+        let with_newline = encode_tree_data(&[tree.get_data(), b"\n".to_vec()].concat());
+        assert_eq!(
+            GitTree::from_encoded_data(&with_newline).err(),
+            Some(GitObjectError::InvalidTreeFile)
+        );
+        assert_eq!(
+            GitTree::from_encoded_data_lenient(&with_newline, ObjectFormat::Sha1)
+                .unwrap()
+                .get_data_string(),
+            tree.get_data_string()
+        );
     }
 
     #[test]
@@ -215,16 +677,397 @@ mod tests {
         );
     }
 
+    #[test]
+    fn test_git_tree_from_encoded_data_round_trip() {
+        let modes = [
+            GitTreeMode::File,
+            GitTreeMode::Executable,
+            GitTreeMode::Symlink,
+            GitTreeMode::Tree,
+            GitTreeMode::Submodule,
+        ];
+        let trees = vec![
+            vec![],
+            vec![GitTreeEntry {
+                mode: GitTreeMode::File,
+                hash: "df6773ea47ed3fce3b3bb14e3d1101963e77ef08".to_string(),
+                name: "README.md".to_string(),
+            }],
+            vec![
+                GitTreeEntry {
+                    mode: GitTreeMode::Tree,
+                    hash: "df6773ea47ed3fce3b3bb14e3d1101963e77ef09".to_string(),
+                    name: "name with spaces".to_string(),
+                },
+                GitTreeEntry {
+                    mode: GitTreeMode::Symlink,
+                    hash: "df6773ea47ed3fce3b3bb14e3d1101963e77ef0a".to_string(),
+                    name: "fișier-ünicode".to_string(),
+                },
+            ],
+            (0..500)
+                .map(|index| GitTreeEntry {
+                    mode: modes[index % modes.len()].clone(),
+                    hash: format!("{:040x}", index),
+                    name: format!("entry_{}", index),
+                })
+                .collect(),
+        ];
+
+        for entries in trees {
+            let expected_entries = entries
+                .iter()
+                .map(|entry| (entry.mode.clone(), entry.hash.clone(), entry.name.clone()))
+                .collect::<Vec<_>>();
+            let encoded_data = create_encoded_tree_file(entries).unwrap();
+
+            let tree = GitTree::from_encoded_data(encoded_data.as_slice()).unwrap();
+            let parsed_entries = tree
+                .entries()
+                .iter()
+                .map(|entry| (entry.mode.clone(), entry.hash.clone(), entry.name.clone()))
+                .collect::<Vec<_>>();
+
+            assert_eq!(parsed_entries, expected_entries);
+        }
+    }
+
+    #[test]
+    fn test_git_tree_from_encoded_data_written_by_git() {
+        // `git cat-file tree` of a tree with README.md and a bin folder, git writes the
+        // folder mode without the leading zero and nothing after the last hash
+        let data =
+            b"100644 README.md\0\xce\x016%\x03\x0b\xa8\xdb\xa9\x06\xf7V\x96\x7f\x9e\x9c\xa3\x94FJ\
+            40000 bin\0M0\xb2\xdd\xd4\xdb\xd8-j\xd7\xeeM*N\xa3`\xf5\xd6[a";
+        let mut zlib = flate2::write::ZlibEncoder::new(Vec::new(), flate2::Compression::default());
+        zlib.write_all(format!("tree {}\x00", data.len()).as_bytes())
+            .and_then(|_| zlib.write_all(data))
+            .unwrap();
+
+        let tree = GitTree::from_encoded_data(&zlib.finish().unwrap()).unwrap();
+
+        assert_eq!(
+            tree.entries()
+                .iter()
+                .map(|entry| (entry.mode.clone(), entry.hash.as_str(), entry.name.as_str()))
+                .collect::<Vec<_>>(),
+            vec![
+                (
+                    GitTreeMode::File,
+                    "ce013625030ba8dba906f756967f9e9ca394464a",
+                    "README.md"
+                ),
+                (
+                    GitTreeMode::Tree,
+                    "4d30b2ddd4dbd82d6ad7ee4d2a4ea360f5d65b61",
+                    "bin"
+                ),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_git_tree_to_canonical_bytes_written_by_git() {
+        // The same tree with a folder that sorts after `bin.txt` only because of the slash
+        let data =
+            b"100644 README.md\0\xce\x016%\x03\x0b\xa8\xdb\xa9\x06\xf7V\x96\x7f\x9e\x9c\xa3\x94FJ\
+            100644 bin.txt\0\xce\x016%\x03\x0b\xa8\xdb\xa9\x06\xf7V\x96\x7f\x9e\x9c\xa3\x94FJ\
+            40000 bin\0M0\xb2\xdd\xd4\xdb\xd8-j\xd7\xeeM*N\xa3`\xf5\xd6[a";
+        let mut object = format!("tree {}\x00", data.len()).into_bytes();
+        object.extend_from_slice(data);
+
+        let mut zlib = flate2::write::ZlibEncoder::new(Vec::new(), flate2::Compression::default());
+        zlib.write_all(&object).unwrap();
+        let tree = GitTree::from_encoded_data(&zlib.finish().unwrap()).unwrap();
+        assert_eq!(tree.to_canonical_bytes(), object);
+
+        let mut rebuilt = GitTree::new();
+        for entry in tree.entries().iter().rev() {
+            rebuilt
+                .add_entry(entry.mode.clone(), entry.hash.clone(), entry.name.clone())
+                .unwrap();
+        }
+        assert_eq!(rebuilt.to_canonical_bytes(), object);
+    }
+
+    #[test]
+    fn test_git_tree_from_encoded_data_truncated_hash() {
+        let file_content = "100644 file1\0df6773ea47ed3fce";
+        let file_content_to_encode = format!("tree {}\x00{}", file_content.len(), file_content);
+        let mut zlib = flate2::bufread::ZlibEncoder::new(
+            file_content_to_encode.as_bytes(),
+            flate2::Compression::default(),
+        );
+        let mut encoded_file_content = Vec::new();
+        zlib.read_to_end(&mut encoded_file_content).unwrap();
+
+        assert!(matches!(
+            GitTree::from_encoded_data(encoded_file_content.as_slice()),
+            Err(GitObjectError::InvalidTreeFile)
+        ));
+    }
+
+    #[test]
+    fn test_git_tree_from_encoded_data_sha256() {
+        let entries = vec![
+            GitTreeEntry {
+                mode: GitTreeMode::File,
+                hash: format!("{:064x}", 1),
+                name: "file1".to_string(),
+            },
+            GitTreeEntry {
+                mode: GitTreeMode::Tree,
+                hash: format!("{:064x}", 2),
+                name: "tree1".to_string(),
+            },
+        ];
+        let encoded_data = create_encoded_tree_file(entries).unwrap();
+
+        let tree =
+            GitTree::from_encoded_data_with_format(&encoded_data, ObjectFormat::Sha256).unwrap();
+
+        assert_eq!(tree.entries().len(), 2);
+        assert_eq!(
+            tree.get_entry_by_name("tree1").unwrap().hash,
+            format!("{:064x}", 2)
+        );
+    }
+
+    #[test]
+    fn test_git_tree_add_entry_sorted() {
+        let mut tree = GitTree::new();
+        tree.add_entry(GitTreeMode::File, format!("{:040x}", 1), "b".to_string())
+            .unwrap();
+        tree.add_entry(
+            GitTreeMode::File,
+            format!("{:040x}", 2),
+            "a.txt".to_string(),
+        )
+        .unwrap();
+        tree.add_entry(GitTreeMode::Tree, format!("{:040x}", 3), "a".to_string())
+            .unwrap();
+        tree.add_entry(GitTreeMode::File, format!("{:040x}", 4), "a-b".to_string())
+            .unwrap();
+
+        // "a/" sorts after "a-b" and "a.txt" since '/' comes after '-' and '.'
+        assert_eq!(
+            tree.entries()
+                .iter()
+                .map(|entry| entry.name.as_str())
+                .collect::<Vec<_>>(),
+            vec!["a-b", "a.txt", "a", "b"]
+        );
+    }
+
+    #[test]
+    fn test_git_tree_add_entry_invalid_hash() {
+        let mut tree = GitTree::new();
+        for hash in [
+            "blob_hash",
+            "abc",
+            "g".repeat(40).as_str(),
+            "+f".repeat(20).as_str(),
+        ] {
+            assert_eq!(
+                tree.add_entry(GitTreeMode::File, hash.to_string(), "file".to_string()),
+                Err(GitObjectError::InvalidHash)
+            );
+        }
+
+        assert!(tree.entries().is_empty());
+    }
+
+    #[test]
+    fn test_git_tree_add_entry_object_format() {
+        let mut tree = GitTree::new();
+        assert_eq!(
+            tree.add_entry(GitTreeMode::File, format!("{:064x}", 1), "file".to_string()),
+            Err(GitObjectError::InvalidHash)
+        );
+
+        let mut tree = GitTree::with_object_format(ObjectFormat::Sha256);
+        tree.add_entry(GitTreeMode::File, format!("{:064x}", 1), "file".to_string())
+            .unwrap();
+        assert_eq!(
+            tree.add_entry(
+                GitTreeMode::File,
+                format!("{:040x}", 2),
+                "other".to_string()
+            ),
+            Err(GitObjectError::InvalidHash)
+        );
+        assert_eq!(tree.len(), 1);
+    }
+
+    #[test]
+    fn test_git_tree_duplicate_names() {
+        let entries = ["file.txt", "file.txt", "other", "src", "src", "file.txt"]
+            .iter()
+            .enumerate()
+            .map(|(index, name)| GitTreeEntry {
+                mode: if index == 4 {
+                    GitTreeMode::Tree
+                } else {
+                    GitTreeMode::File
+                },
+                hash: format!("df6773ea47ed3fce3b3bb14e3d1101963e77ef0{}", index),
+                name: name.to_string(),
+            })
+            .collect();
+        let encoded = create_encoded_tree_file(entries).unwrap();
+        assert_eq!(
+            GitTree::from_encoded_data(&encoded),
+            Err(GitObjectError::DuplicateTreeEntry("file.txt".to_string()))
+        );
+
+        let tree = GitTree::from_encoded_data_lenient(&encoded, ObjectFormat::Sha1).unwrap();
+        assert_eq!(tree.duplicate_names(), vec!["file.txt", "src"]);
+        assert_eq!(
+            tree.get_entry_by_name("file.txt").unwrap().hash,
+            "df6773ea47ed3fce3b3bb14e3d1101963e77ef00"
+        );
+
+        let mut tree = GitTree::new();
+        tree.add_entry(
+            GitTreeMode::File,
+            "df6773ea47ed3fce3b3bb14e3d1101963e77ef08".to_string(),
+            "file.txt".to_string(),
+        )
+        .unwrap();
+        assert!(tree.duplicate_names().is_empty());
+    }
+
+    #[test]
+    fn test_git_tree_entries_page() {
+        let mut tree = GitTree::new();
+        for index in (0..25).rev() {
+            tree.add_entry(
+                GitTreeMode::File,
+                format!("{:040x}", index),
+                format!("file_{:02}", index),
+            )
+            .unwrap();
+        }
+
+        let names = |entries: &[GitTreeEntry]| {
+            entries
+                .iter()
+                .map(|entry| entry.name.clone())
+                .collect::<Vec<_>>()
+        };
+
+        assert_eq!(
+            names(tree.entries_page(0, 3)),
+            vec!["file_00", "file_01", "file_02"]
+        );
+        assert_eq!(names(tree.entries_page(10, 2)), vec!["file_10", "file_11"]);
+        assert_eq!(names(tree.entries_page(23, 10)), vec!["file_23", "file_24"]);
+        assert!(tree.entries_page(30, 10).is_empty());
+        assert!(tree.entries_page(5, 0).is_empty());
+        assert_eq!(tree.len(), 25);
+    }
+
+    #[test]
+    fn test_git_tree_from_encoded_data_empty() {
+        assert_eq!(
+            GitTree::from_encoded_data(&[]).err(),
+            Some(GitObjectError::EmptyObject)
+        );
+        assert_eq!(
+            GitTree::from_encoded_data(&[0x00, 0x01]).err(),
+            Some(GitObjectError::DecompressionError)
+        );
+    }
+
     #[test]
     fn test_git_tree_mode_from_mode_str() {
-        assert_eq!(GitTreeMode::from_mode_str("100644"), GitTreeMode::File);
+        assert_eq!(GitTreeMode::from_mode_str("100644"), Ok(GitTreeMode::File));
         assert_eq!(
             GitTreeMode::from_mode_str("100755"),
+            Ok(GitTreeMode::Executable)
+        );
+        assert_eq!(
+            GitTreeMode::from_mode_str("120000"),
+            Ok(GitTreeMode::Symlink)
+        );
+        assert_eq!(GitTreeMode::from_mode_str("040000"), Ok(GitTreeMode::Tree));
+        assert_eq!(GitTreeMode::from_mode_str("40000"), Ok(GitTreeMode::Tree));
+        assert_eq!(
+            GitTreeMode::from_mode_str("160000"),
+            Ok(GitTreeMode::Submodule)
+        );
+        assert_eq!(
+            GitTreeMode::from_mode_str("100664"),
+            Err(GitObjectError::InvalidTreeMode)
+        );
+    }
+
+    #[test]
+    fn test_git_tree_from_encoded_data_size_mismatch() {
+        let mut data = b"100644 file1\0".to_vec();
+        data.extend_from_slice(&[1; 20]);
+
+        for size in [10, data.len() + 20] {
+            let mut zlib =
+                flate2::write::ZlibEncoder::new(Vec::new(), flate2::Compression::default());
+            zlib.write_all(format!("tree {}\x00", size).as_bytes())
+                .and_then(|_| zlib.write_all(&data))
+                .unwrap();
+            let encoded_data = zlib.finish().unwrap();
+
+            assert_eq!(
+                GitTree::from_encoded_data(&encoded_data).err(),
+                Some(GitObjectError::SizeMismatch)
+            );
+            assert_eq!(
+                GitTree::from_encoded_data_lenient(&encoded_data, ObjectFormat::Sha1).err(),
+                Some(GitObjectError::SizeMismatch)
+            );
+        }
+    }
+
+    #[test]
+    fn test_git_tree_from_encoded_data_invalid_mode() {
+        let mut data = b"100644 file1\0".to_vec();
+        data.extend_from_slice(&[1; 20]);
+        data.extend_from_slice(b"777777 file2\0");
+        data.extend_from_slice(&[2; 20]);
+
+        assert_eq!(
+            GitTree::from_encoded_data(&encode_tree_data(&data)).err(),
+            Some(GitObjectError::InvalidTreeMode)
+        );
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_git_tree_mode_from_path() {
+        use std::os::unix::fs::PermissionsExt;
+
+        let folder = tempdir::TempDir::new("git_tree_mode_from_path").unwrap();
+        let file = folder.path().join("file");
+        let executable = folder.path().join("executable");
+        let symlink = folder.path().join("symlink");
+
+        std::fs::write(&file, "file").unwrap();
+        std::fs::write(&executable, "#!/bin/sh").unwrap();
+        std::fs::set_permissions(&executable, std::fs::Permissions::from_mode(0o755)).unwrap();
+        std::os::unix::fs::symlink(&file, &symlink).unwrap();
+
+        assert_eq!(GitTreeMode::from_path(&file).unwrap(), GitTreeMode::File);
+        assert_eq!(
+            GitTreeMode::from_path(&executable).unwrap(),
             GitTreeMode::Executable
         );
-        assert_eq!(GitTreeMode::from_mode_str("120000"), GitTreeMode::Symlink);
-        assert_eq!(GitTreeMode::from_mode_str("040000"), GitTreeMode::Tree);
-        assert_eq!(GitTreeMode::from_mode_str("160000"), GitTreeMode::Submodule);
+        assert_eq!(
+            GitTreeMode::from_path(&symlink).unwrap(),
+            GitTreeMode::Symlink
+        );
+        assert_eq!(
+            GitTreeMode::from_path(folder.path()).unwrap(),
+            GitTreeMode::Tree
+        );
+        assert!(GitTreeMode::from_path(&folder.path().join("missing")).is_err());
     }
 
     #[test]
@@ -242,4 +1085,49 @@ mod tests {
         assert_eq!(format!("{}", GitTreeMode::Submodule), "blob");
         assert_eq!(format!("{}", GitTreeMode::Tree), "tree");
     }
+
+    fn mock_git_tree() -> GitTree {
+        let mut tree = GitTree::new();
+        tree.add_entry(GitTreeMode::File, format!("{:040x}", 1), "file".to_string())
+            .unwrap();
+        tree.add_entry(GitTreeMode::Tree, format!("{:040x}", 2), "src".to_string())
+            .unwrap();
+        tree.add_entry(
+            GitTreeMode::Executable,
+            format!("{:040x}", 3),
+            "run.sh".to_string(),
+        )
+        .unwrap();
+
+        tree
+    }
+
+    #[test]
+    fn test_serialize_git_tree() {
+        let git_tree = mock_git_tree();
+        let serialized = serde_json::to_string(&git_tree).unwrap();
+        let expected = r#"{"entries":[{"mode":"100644","hash":"0000000000000000000000000000000000000001","name":"file"},{"mode":"100755","hash":"0000000000000000000000000000000000000003","name":"run.sh"},{"mode":"040000","hash":"0000000000000000000000000000000000000002","name":"src"}]}"#;
+        assert_eq!(serialized, expected);
+    }
+
+    #[test]
+    fn test_deserialize_git_tree() {
+        let json_str = r#"{"entries":[{"mode":"100644","hash":"0000000000000000000000000000000000000001","name":"file"},{"mode":"100755","hash":"0000000000000000000000000000000000000003","name":"run.sh"},{"mode":"40000","hash":"0000000000000000000000000000000000000002","name":"src"}]}"#;
+        let deserialized: GitTree = serde_json::from_str(json_str).unwrap();
+        assert_eq!(deserialized, mock_git_tree());
+    }
+
+    #[test]
+    fn test_deserialize_git_tree_invalid_hash() {
+        let json_str = r#"{"entries":[{"mode":"100644","hash":"blob_hash","name":"file"}]}"#;
+        let result: Result<GitTree, serde_json::Error> = serde_json::from_str(json_str);
+        assert!(result.is_err());
+    }
+
+    #[test]
+    fn test_deserialize_git_tree_invalid_mode() {
+        let json_str = r#"{"entries":[{"mode":"File","hash":"0000000000000000000000000000000000000001","name":"file"}]}"#;
+        let result: Result<GitTree, serde_json::Error> = serde_json::from_str(json_str);
+        assert!(result.is_err());
+    }
 }
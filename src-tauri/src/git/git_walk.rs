@@ -0,0 +1,74 @@
+use std::{
+    cmp::Reverse,
+    collections::{BinaryHeap, HashSet, VecDeque},
+};
+
+use super::{git_commit::GitCommit, git_project::GitProject, object::GitObject};
+use crate::errors::git_object_error::GitObjectError;
+
+// Walks the history from a commit the way `git log` does, the newest commit by committer
+// date comes first so the branches of a merge interleave. Commits with the same date come
+// out in the order they were reached, and only the dates are read until a commit is yielded
+pub struct CommitWalker<'a> {
+    project: &'a GitProject,
+    queue: BinaryHeap<(i64, Reverse<usize>, String)>,
+    visited: HashSet<String>,
+    reached: usize,
+    errors: VecDeque<GitObjectError>,
+}
+
+impl<'a> CommitWalker<'a> {
+    pub fn new(project: &'a GitProject, start: &str) -> Self {
+        let mut walker = Self {
+            project,
+            queue: BinaryHeap::new(),
+            visited: HashSet::new(),
+            reached: 0,
+            errors: VecDeque::new(),
+        };
+        if let Err(error) = walker.enqueue(start) {
+            walker.errors.push_back(error);
+        }
+
+        walker
+    }
+
+    fn enqueue(&mut self, hash: &str) -> Result<(), GitObjectError> {
+        if !self.visited.insert(hash.to_string()) {
+            return Ok(());
+        }
+
+        let date = GitCommit::commit_time(self.project, hash)?;
+        self.queue
+            .push((date, Reverse(self.reached), hash.to_string()));
+        self.reached += 1;
+
+        Ok(())
+    }
+}
+
+impl Iterator for CommitWalker<'_> {
+    type Item = Result<GitCommit, GitObjectError>;
+
+    // A commit that can't be read is returned as an error once, the walk then goes on
+    // with the commits already reached
+    fn next(&mut self) -> Option<Self::Item> {
+        if let Some(error) = self.errors.pop_front() {
+            return Some(Err(error));
+        }
+
+        let (_, _, hash) = self.queue.pop()?;
+        let commit = match GitCommit::from_hash(self.project, &hash) {
+            Ok(commit) => commit,
+            Err(error) => return Some(Err(error)),
+        };
+
+        for parent_hash in commit.get_parent_hashes() {
+            if let Err(error) = self.enqueue(parent_hash) {
+                self.errors.push_back(error);
+            }
+        }
+
+        Some(Ok(commit))
+    }
+}
@@ -0,0 +1,341 @@
+use serde::{Deserialize, Serialize};
+use std::{
+    fs,
+    path::{Component, Path, PathBuf},
+};
+
+use super::{
+    git_files::GitFilesOptional,
+    git_folders::{GitFolders, GitRefs, GIT_FOLDER},
+    git_project::GitProject,
+    git_refs::{is_valid_ref_name, LOCK_SUFFIX},
+    object::ObjectFormat,
+};
+use crate::errors::git_object_error::GitObjectError;
+
+pub const WORKTREES_FOLDER: &str = "worktrees";
+const GITDIR_FILE: &str = "gitdir";
+const COMMONDIR_FILE: &str = "commondir";
+const HEAD_FILE: &str = "HEAD";
+const MAX_SYMBOLIC_REF_DEPTH: usize = 5;
+const SYMBOLIC_REF_PREFIX: &str = "ref:";
+
+#[derive(Debug, PartialEq, Serialize, Deserialize, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct WorktreeInfo {
+    name: Option<String>,
+    path: String,
+    branch: Option<String>,
+    head: Option<String>,
+}
+
+impl WorktreeInfo {
+    pub fn new(
+        name: Option<String>,
+        path: String,
+        branch: Option<String>,
+        head: Option<String>,
+    ) -> WorktreeInfo {
+        WorktreeInfo {
+            name,
+            path,
+            branch,
+            head,
+        }
+    }
+
+    pub fn get_name(&self) -> Option<&String> {
+        self.name.as_ref()
+    }
+
+    pub fn get_path(&self) -> &String {
+        &self.path
+    }
+
+    pub fn get_branch(&self) -> Option<&String> {
+        self.branch.as_ref()
+    }
+
+    pub fn get_head(&self) -> Option<&String> {
+        self.head.as_ref()
+    }
+
+    pub fn is_main(&self) -> bool {
+        self.name.is_none()
+    }
+
+    pub fn list(project: &GitProject) -> Result<Vec<WorktreeInfo>, GitObjectError> {
+        let (_, common_dir) = resolve_git_dirs(Path::new(project.get_directory()))?;
+
+        // A bare repository has no working tree, git lists the repository itself
+        let main_path = match project.is_bare() {
+            true => common_dir.as_path(),
+            false => common_dir.parent().ok_or(GitObjectError::FileReadError)?,
+        }
+        .to_string_lossy()
+        .to_string();
+        let object_format = project.get_object_format();
+        let mut worktrees = vec![Self::from_git_dir(
+            None,
+            main_path,
+            &common_dir,
+            &common_dir,
+            object_format,
+        )?];
+
+        let mut linked_worktrees = match fs::read_dir(common_dir.join(WORKTREES_FOLDER)) {
+            Ok(entries) => entries
+                .flatten()
+                .map(|entry| entry.path())
+                .filter(|path| path.is_dir())
+                .collect::<Vec<PathBuf>>(),
+            Err(_) => Vec::new(),
+        };
+        linked_worktrees.sort();
+
+        for git_dir in linked_worktrees {
+            let name = git_dir
+                .file_name()
+                .ok_or(GitObjectError::FileReadError)?
+                .to_string_lossy()
+                .to_string();
+            let gitdir_file = fs::read_to_string(git_dir.join(GITDIR_FILE))
+                .map_err(|_| GitObjectError::FileReadError)?;
+            let path = Path::new(gitdir_file.trim())
+                .parent()
+                .ok_or(GitObjectError::ParsingError)?
+                .to_string_lossy()
+                .to_string();
+
+            worktrees.push(Self::from_git_dir(
+                Some(name),
+                path,
+                &git_dir,
+                &common_dir,
+                object_format,
+            )?);
+        }
+
+        Ok(worktrees)
+    }
+
+    fn from_git_dir(
+        name: Option<String>,
+        path: String,
+        git_dir: &Path,
+        common_dir: &Path,
+        object_format: ObjectFormat,
+    ) -> Result<WorktreeInfo, GitObjectError> {
+        let head = fs::read_to_string(git_dir.join(HEAD_FILE))
+            .map_err(|_| GitObjectError::FileReadError)?;
+
+        match symbolic_ref_target(&head) {
+            Some(ref_name) => {
+                let head = resolve_ref(git_dir, common_dir, ref_name, object_format)?;
+                let branch = ref_name.strip_prefix("refs/heads/").map(String::from);
+
+                Ok(WorktreeInfo::new(name, path, branch, head))
+            }
+            None => Ok(WorktreeInfo::new(
+                name,
+                path,
+                None,
+                Some(head.trim().to_string()),
+            )),
+        }
+    }
+}
+
+// Returns the git directory of the project together with the common directory that holds
+// the objects and shared refs. They differ only for linked worktrees. A bare repository
+// has no `.git` and is its own git directory
+pub fn resolve_git_dirs(directory: &Path) -> Result<(PathBuf, PathBuf), GitObjectError> {
+    let dot_git = directory.join(GIT_FOLDER);
+    if dot_git.is_dir() {
+        return Ok((dot_git.clone(), dot_git));
+    }
+
+    if !dot_git.exists() && is_bare_git_dir(directory) {
+        return Ok((directory.to_path_buf(), directory.to_path_buf()));
+    }
+
+    let gitdir_file = fs::read_to_string(&dot_git).map_err(|_| GitObjectError::FileReadError)?;
+    let git_dir = directory.join(
+        gitdir_file
+            .trim()
+            .strip_prefix("gitdir: ")
+            .ok_or(GitObjectError::ParsingError)?,
+    );
+
+    let common_dir = match fs::read_to_string(git_dir.join(COMMONDIR_FILE)) {
+        Ok(common_dir) => normalize_path(&git_dir.join(common_dir.trim())),
+        Err(_) => git_dir.clone(),
+    };
+
+    Ok((git_dir, common_dir))
+}
+
+// What git itself checks before treating a folder as a git directory
+fn is_bare_git_dir(directory: &Path) -> bool {
+    directory.join(HEAD_FILE).is_file()
+        && directory.join(GitFolders::OBJECTS.to_string()).is_dir()
+        && directory.join(GitFolders::REFS.to_string()).is_dir()
+}
+
+// Removes the `..` and `.` components without touching the filesystem, so that
+// the paths stay comparable with the ones the user opened
+fn normalize_path(path: &Path) -> PathBuf {
+    let mut normalized = PathBuf::new();
+    for component in path.components() {
+        match component {
+            Component::CurDir => {}
+            Component::ParentDir => {
+                normalized.pop();
+            }
+            component => normalized.push(component),
+        }
+    }
+
+    normalized
+}
+
+// The ref a symbolic ref file points at. Editors may leave trailing spaces or CRLF line
+// endings, and git allows any whitespace after `ref:`
+pub fn symbolic_ref_target(content: &str) -> Option<&str> {
+    content
+        .trim()
+        .strip_prefix(SYMBOLIC_REF_PREFIX)
+        .map(str::trim_start)
+}
+
+// Resolves a ref to its commit hash, looking at the per-worktree refs first, then at the
+// shared loose refs and packed-refs. Returns None for an unborn branch.
+pub fn resolve_ref(
+    git_dir: &Path,
+    common_dir: &Path,
+    ref_name: &str,
+    object_format: ObjectFormat,
+) -> Result<Option<String>, GitObjectError> {
+    resolve_ref_followed(git_dir, common_dir, ref_name, object_format).map(|(hash, _)| hash)
+}
+
+// Like `resolve_ref`, also telling whether the ref was symbolic and so depends on
+// the refs it points at
+pub fn resolve_ref_followed(
+    git_dir: &Path,
+    common_dir: &Path,
+    ref_name: &str,
+    object_format: ObjectFormat,
+) -> Result<(Option<String>, bool), GitObjectError> {
+    let mut ref_name = ref_name.to_string();
+    let mut symbolic = false;
+
+    for _ in 0..MAX_SYMBOLIC_REF_DEPTH {
+        // The name becomes a path below the git dirs, so it can't be allowed to leave them
+        if !is_resolvable_ref_name(&ref_name) {
+            return Err(GitObjectError::InvalidRefFile);
+        }
+
+        let loose_ref = [git_dir, common_dir]
+            .iter()
+            .find_map(|dir| fs::read_to_string(dir.join(&ref_name)).ok());
+
+        let target = match loose_ref {
+            Some(target) => target.trim().to_string(),
+            None => {
+                let packed_refs =
+                    fs::read_to_string(common_dir.join(GitFilesOptional::PackedRefs.to_string()))
+                        .unwrap_or_default();
+
+                return packed_refs
+                    .lines()
+                    .find_map(|line| {
+                        line.split_once(' ')
+                            .filter(|(_, name)| name.trim_end() == ref_name)
+                            .map(|(hash, _)| hash)
+                    })
+                    .map(|hash| checked_ref_hash(hash, object_format))
+                    .transpose()
+                    .map(|hash| (hash, symbolic));
+            }
+        };
+
+        match symbolic_ref_target(&target) {
+            Some(target) => {
+                ref_name = target.to_string();
+                symbolic = true;
+            }
+            // Like git, anything after the hash and a whitespace is ignored, e.g. the
+            // branch names FETCH_HEAD lists
+            None => {
+                let hash = target.split_whitespace().next().unwrap_or_default();
+                return checked_ref_hash(hash, object_format).map(|hash| (Some(hash), symbolic));
+            }
+        }
+    }
+
+    Err(GitObjectError::ParsingError)
+}
+
+// Names under refs/ and the all caps pseudo-refs (HEAD, ORIG_HEAD...) kept in the git dir
+fn is_resolvable_ref_name(ref_name: &str) -> bool {
+    is_valid_ref_name(ref_name)
+        && (ref_name.starts_with(&format!("{}/", GitFolders::REFS))
+            || ref_name.chars().all(|c| c.is_ascii_uppercase() || c == '_'))
+}
+
+fn checked_ref_hash(hash: &str, object_format: ObjectFormat) -> Result<String, GitObjectError> {
+    if hash.len() == object_format.hash_hex_len() && hash.chars().all(|c| c.is_ascii_hexdigit()) {
+        Ok(hash.to_string())
+    } else {
+        Err(GitObjectError::InvalidRefFile)
+    }
+}
+
+// The names of the refs in one of the refs folders, nested ones included, then the
+// packed ones that are not shadowed by a loose ref
+pub fn ref_names(common_dir: &Path, refs: &GitRefs) -> Result<Vec<String>, GitObjectError> {
+    let refs_root = common_dir
+        .join(GitFolders::REFS.to_string())
+        .join(refs.to_string());
+
+    let mut names = Vec::new();
+    let mut dirs_to_check = vec![refs_root.clone()];
+    while let Some(current_dir) = dirs_to_check.pop() {
+        let Ok(entries) = fs::read_dir(&current_dir) else {
+            continue;
+        };
+
+        for entry in entries {
+            let path = entry.map_err(|_| GitObjectError::FileReadError)?.path();
+            if path.is_dir() {
+                dirs_to_check.push(path);
+            } else if let Ok(name) = path.strip_prefix(&refs_root) {
+                // Lock files are refs being written by another git process
+                let name = name.to_string_lossy().replace('\\', "/");
+                if !name.ends_with(LOCK_SUFFIX) {
+                    names.push(name);
+                }
+            }
+        }
+    }
+
+    let packed_prefix = format!("{}/{}/", GitFolders::REFS, refs);
+    let packed_refs = common_dir.join(GitFilesOptional::PackedRefs.to_string());
+    if let Ok(packed_refs) = fs::read_to_string(packed_refs) {
+        for line in packed_refs.lines() {
+            let Some((_, ref_name)) = line.split_once(' ') else {
+                continue;
+            };
+
+            if let Some(name) = ref_name.strip_prefix(&packed_prefix) {
+                if !names.iter().any(|existing| existing == name) {
+                    names.push(name.to_string());
+                }
+            }
+        }
+    }
+
+    names.sort();
+    Ok(names)
+}
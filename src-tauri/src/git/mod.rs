@@ -1,12 +1,33 @@
+pub mod git_activity;
+pub mod git_ancestry;
+pub mod git_bisect;
 pub mod git_blob;
 pub mod git_branch;
+pub mod git_bundle;
+pub mod git_cache;
 pub mod git_commit;
 pub mod git_commit_author;
+pub mod git_commit_detail;
+pub mod git_config;
+pub mod git_diff;
+pub mod git_directory;
 pub mod git_files;
 pub mod git_folders;
+pub mod git_hash;
+pub mod git_ignore;
+pub mod git_index;
+pub mod git_pack;
 pub mod git_project;
 pub mod git_project_state;
+pub mod git_reflog;
+pub mod git_refs;
+pub mod git_revspec;
+pub mod git_snapshot;
+pub mod git_status;
+pub mod git_tag;
 pub mod git_tree;
 pub mod git_user;
+pub mod git_walk;
+pub mod git_worktree;
 pub mod object;
 pub mod project_folder;
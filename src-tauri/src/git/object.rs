@@ -1,26 +1,129 @@
 use std::{
+    collections::BTreeSet,
     fmt::Write,
-    io::{Read, Write as IoWrite},
+    fs::{self, File, OpenOptions},
+    io::{BufRead, BufReader, Cursor, Read, Write as IoWrite},
     path::PathBuf,
+    process,
+    sync::atomic::{AtomicUsize, Ordering},
 };
 
 use flate2::{read::ZlibDecoder, write::ZlibEncoder};
+use serde::{Deserialize, Serialize};
 use sha1::{Digest, Sha1};
+use sha2::Sha256;
 
 use crate::errors::git_object_error::{GitObjectError, ObjectError};
 
 use super::{
-    git_folders::{GitFolders, GIT_FOLDER},
-    git_project::GitProject,
+    git_blob::GitBlob, git_commit::GitCommit, git_config::GitConfig, git_hash::hash_object,
+    git_project::GitProject, git_tag::GitTag, git_tree::GitTree,
 };
 
-pub const HASH_SIZE: usize = 20;
+const LOOSE_OBJECT_FOLDER_SIZE: usize = 2;
+// Named like the temporary files git writes objects to, so git's own gc cleans them up
+const TEMP_OBJECT_PREFIX: &str = "tmp_obj_";
+// git refuses to look up abbreviations shorter than this
+const MIN_ABBREV_HASH_LEN: usize = 4;
+// The longest type name, a space, a 20 digit size and the NUL byte
+const OBJECT_HEADER_MAX_SIZE: u64 = 32;
+const ADLER32_SIZE: usize = 4;
+const ADLER32_MODULUS: u32 = 65521;
+// The largest number of bytes that can be summed before the sums overflow a u32
+const ADLER32_CHUNK_SIZE: usize = 5552;
+
+static TEMP_OBJECT_COUNTER: AtomicUsize = AtomicUsize::new(0);
+
+#[derive(Debug, Clone, Copy, PartialEq, Default, Serialize, Deserialize)]
+pub enum ObjectFormat {
+    #[default]
+    Sha1,
+    Sha256,
+}
 
-#[derive(Debug, PartialEq)]
+impl ObjectFormat {
+    // Repositories created with `git init --object-format=sha256` record it in the config
+    pub fn from_config(config: &str) -> Self {
+        match GitConfig::parse(config).get("extensions", None, "objectformat") {
+            Some(format) if format.eq_ignore_ascii_case("sha256") => ObjectFormat::Sha256,
+            _ => ObjectFormat::Sha1,
+        }
+    }
+
+    pub fn hash_byte_len(&self) -> usize {
+        match self {
+            ObjectFormat::Sha1 => 20,
+            ObjectFormat::Sha256 => 32,
+        }
+    }
+
+    pub fn hash_hex_len(&self) -> usize {
+        self.hash_byte_len() * 2
+    }
+
+    pub fn digest(&self, data: &[u8]) -> Vec<u8> {
+        let mut hasher = self.hasher();
+        hasher.update(data);
+        hasher.finalize()
+    }
+
+    pub fn hash(&self, data: &[u8]) -> String {
+        let mut hasher = self.hasher();
+        hasher.update(data);
+        hasher.finalize_hex()
+    }
+
+    // For data that is too large to hash in a single buffer
+    pub fn hasher(&self) -> ObjectHasher {
+        match self {
+            ObjectFormat::Sha1 => ObjectHasher::Sha1(Sha1::new()),
+            ObjectFormat::Sha256 => ObjectHasher::Sha256(Sha256::new()),
+        }
+    }
+
+    pub fn loose_object_path(&self, project: &GitProject, hash: &str) -> PathBuf {
+        let (folder, file) = hash.split_at(LOOSE_OBJECT_FOLDER_SIZE.min(hash.len()));
+
+        project.objects_dir().join(folder).join(file)
+    }
+}
+
+pub enum ObjectHasher {
+    Sha1(Sha1),
+    Sha256(Sha256),
+}
+
+impl ObjectHasher {
+    pub fn update(&mut self, data: &[u8]) {
+        match self {
+            ObjectHasher::Sha1(hasher) => hasher.update(data),
+            ObjectHasher::Sha256(hasher) => hasher.update(data),
+        }
+    }
+
+    pub fn finalize(self) -> Vec<u8> {
+        match self {
+            ObjectHasher::Sha1(hasher) => hasher.finalize().to_vec(),
+            ObjectHasher::Sha256(hasher) => hasher.finalize().to_vec(),
+        }
+    }
+
+    pub fn finalize_hex(self) -> String {
+        self.finalize()
+            .iter()
+            .fold(String::new(), |mut output, byte| {
+                let _ = write!(output, "{byte:02x}");
+                output
+            })
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
 pub enum Header {
     Tree,
     Commit,
     Blob,
+    Tag,
     Invalid,
 }
 
@@ -30,6 +133,7 @@ impl From<&str> for Header {
             "tree" => Header::Tree,
             "commit" => Header::Commit,
             "blob" => Header::Blob,
+            "tag" => Header::Tag,
             _ => Header::Invalid,
         }
     }
@@ -41,6 +145,7 @@ impl std::fmt::Display for Header {
             Header::Tree => "tree",
             Header::Commit => "commit",
             Header::Blob => "blob",
+            Header::Tag => "tag",
             Header::Invalid => "invalid",
         };
 
@@ -48,119 +153,454 @@ impl std::fmt::Display for Header {
     }
 }
 
+// One of the four object types, a header is only known to be one once it is checked
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum GitObjectType {
+    Commit,
+    Tree,
+    Blob,
+    Tag,
+}
+
+impl TryFrom<Header> for GitObjectType {
+    type Error = GitObjectError;
+
+    fn try_from(header: Header) -> Result<Self, Self::Error> {
+        match header {
+            Header::Commit => Ok(GitObjectType::Commit),
+            Header::Tree => Ok(GitObjectType::Tree),
+            Header::Blob => Ok(GitObjectType::Blob),
+            Header::Tag => Ok(GitObjectType::Tag),
+            Header::Invalid => Err(GitObjectError::UnknownObjectType),
+        }
+    }
+}
+
+fn adler32(data: &[u8]) -> u32 {
+    let (mut a, mut b) = (1u32, 0u32);
+    for chunk in data.chunks(ADLER32_CHUNK_SIZE) {
+        for byte in chunk {
+            a += *byte as u32;
+            b += a;
+        }
+        a %= ADLER32_MODULUS;
+        b %= ADLER32_MODULUS;
+    }
+
+    (b << 16) | a
+}
+
+// Inflates just enough of an object to know its type and size, looking in the packs
+// when there is no loose object
+pub fn peek_object_header(
+    project: &GitProject,
+    hash: &str,
+) -> Result<(Header, u64), GitObjectError> {
+    let object_format = project.get_object_format();
+    if hash.len() != object_format.hash_hex_len() {
+        return Err(GitObjectError::InvalidHash);
+    }
+
+    let Ok(object) = File::open(object_format.loose_object_path(project, hash)) else {
+        return project
+            .packs()?
+            .iter()
+            .find(|pack| pack.contains(hash))
+            .ok_or(GitObjectError::FileReadError)?
+            .object_header(hash);
+    };
+
+    read_header_until(object, 0)?
+        .strip_suffix(&[0])
+        .ok_or(GitObjectError::InvalidObjectFile(
+            ObjectError::InvalidHeader,
+        ))
+        .and_then(parse_header_fields)
+}
+
+// The type of a loose object from its zlib stream, for loading objects whose type isn't
+// known in advance. Only the word before the first space is inflated
+pub fn object_type(encoded_data: &[u8]) -> Result<GitObjectType, GitObjectError> {
+    if encoded_data.is_empty() {
+        return Err(GitObjectError::EmptyObject);
+    }
+
+    let header = read_header_until(encoded_data, b' ')?;
+    let object_type = header
+        .strip_suffix(b" ")
+        .and_then(|object_type| std::str::from_utf8(object_type).ok())
+        .ok_or(GitObjectError::InvalidObjectFile(
+            ObjectError::InvalidHeader,
+        ))?;
+
+    GitObjectType::try_from(Header::from(object_type))
+}
+
+// Any of the four object types, for when the type of a hash isn't known in advance
+#[derive(Debug, PartialEq)]
+pub enum AnyGitObject {
+    Commit(GitCommit),
+    Tree(GitTree),
+    Blob(GitBlob),
+    Tag(GitTag),
+}
+
+impl AnyGitObject {
+    // The type is taken from the header of the object
+    pub fn from_hash(project: &GitProject, hash: &str) -> Result<Self, GitObjectError> {
+        let (object_type, size, data) = match read_object_data(project, hash) {
+            // A type that isn't one of the four reads as a malformed header, the type
+            // alone tells the two apart
+            Err(error @ GitObjectError::InvalidObjectFile(ObjectError::InvalidHeader)) => {
+                object_type(&read_raw_compressed(project, hash)?)?;
+                return Err(error);
+            }
+            result => result?,
+        };
+        let object_format = project.get_object_format();
+
+        Ok(match GitObjectType::try_from(object_type)? {
+            GitObjectType::Commit => {
+                Self::Commit(GitCommit::from_object_data(&data, size, object_format)?)
+            }
+            GitObjectType::Tree => {
+                Self::Tree(GitTree::from_object_data(&data, size, object_format)?)
+            }
+            GitObjectType::Blob => {
+                Self::Blob(GitBlob::from_object_data(&data, size, object_format)?)
+            }
+            GitObjectType::Tag => Self::Tag(GitTag::from_object_data(&data, size, object_format)?),
+        })
+    }
+
+    pub fn get_type(&self) -> GitObjectType {
+        match self {
+            Self::Commit(_) => GitObjectType::Commit,
+            Self::Tree(_) => GitObjectType::Tree,
+            Self::Blob(_) => GitObjectType::Blob,
+            Self::Tag(_) => GitObjectType::Tag,
+        }
+    }
+}
+
+fn read_header_until(object: impl Read, delimiter: u8) -> Result<Vec<u8>, GitObjectError> {
+    let mut header = Vec::new();
+    BufReader::new(ZlibDecoder::new(object))
+        .take(OBJECT_HEADER_MAX_SIZE)
+        .read_until(delimiter, &mut header)
+        .map_err(|_| GitObjectError::DecompressionError)?;
+
+    Ok(header)
+}
+
+// The payload of an object as a reader, after its type and size. Loose objects are
+// inflated as they are read, packed ones are rebuilt from the pack in memory first
+pub fn open_object_reader(
+    project: &GitProject,
+    hash: &str,
+) -> Result<(Header, u64, Box<dyn BufRead>), GitObjectError> {
+    let object_format = project.get_object_format();
+    if hash.len() != object_format.hash_hex_len() {
+        return Err(GitObjectError::InvalidHash);
+    }
+
+    let Ok(object) = File::open(object_format.loose_object_path(project, hash)) else {
+        let (object_type, data) = project
+            .packs()?
+            .iter()
+            .find(|pack| pack.contains(hash))
+            .ok_or(GitObjectError::FileReadError)?
+            .read_object(hash)?;
+        let size = data.len() as u64;
+
+        return Ok((object_type, size, Box::new(Cursor::new(data))));
+    };
+
+    let mut reader = BufReader::new(ZlibDecoder::new(object));
+    let mut header = Vec::new();
+    (&mut reader)
+        .take(OBJECT_HEADER_MAX_SIZE)
+        .read_until(0, &mut header)
+        .map_err(|_| GitObjectError::DecompressionError)?;
+    let (object_type, size) = header
+        .strip_suffix(&[0])
+        .ok_or(GitObjectError::InvalidObjectFile(
+            ObjectError::InvalidHeader,
+        ))
+        .and_then(parse_header_fields)?;
+
+    Ok((object_type, size, Box::new(reader.take(size))))
+}
+
+// The full hash of the one object starting with an abbreviated hash, looking at the
+// loose objects in its folder and in every pack
+pub fn expand_hash(project: &GitProject, prefix: &str) -> Result<String, GitObjectError> {
+    let object_format = project.get_object_format();
+    let prefix = prefix.to_lowercase();
+    if prefix.len() < MIN_ABBREV_HASH_LEN
+        || prefix.len() > object_format.hash_hex_len()
+        || !prefix.chars().all(|c| c.is_ascii_hexdigit())
+    {
+        return Err(GitObjectError::InvalidHash);
+    }
+
+    let (folder, file_prefix) = prefix.split_at(LOOSE_OBJECT_FOLDER_SIZE);
+    let mut matches = BTreeSet::new();
+    if let Ok(entries) = std::fs::read_dir(project.objects_dir().join(folder)) {
+        for entry in entries.flatten() {
+            let file_name = entry.file_name().to_string_lossy().to_string();
+            if file_name.len() == object_format.hash_hex_len() - LOOSE_OBJECT_FOLDER_SIZE
+                && file_name.starts_with(file_prefix)
+            {
+                matches.insert(format!("{}{}", folder, file_name));
+            }
+        }
+    }
+
+    for pack in project.packs()? {
+        matches.extend(pack.hashes_with_prefix(&prefix));
+    }
+
+    let mut matches = matches.into_iter();
+    match (matches.next(), matches.next()) {
+        (Some(hash), None) => Ok(hash),
+        (Some(_), Some(_)) => Err(GitObjectError::AmbiguousHash),
+        (None, _) => Err(GitObjectError::ObjectNotFound),
+    }
+}
+
+// The type, declared size and payload of an object. Loose objects are inflated, packed
+// ones are taken as the pack rebuilt them, so either is inflated exactly once
+pub fn read_object_data(
+    project: &GitProject,
+    hash: &str,
+) -> Result<(Header, usize, Vec<u8>), GitObjectError> {
+    let object_format = project.get_object_format();
+    if hash.len() != object_format.hash_hex_len() {
+        return Err(GitObjectError::InvalidHash);
+    }
+
+    if let Ok(encoded_data) = std::fs::read(object_format.loose_object_path(project, hash)) {
+        let mut data = decode_object_data(&encoded_data)?;
+        let (object_type, size, payload) = parse_object_header(&data)?;
+        let payload_start = data.len() - payload.len();
+        data.drain(..payload_start);
+
+        return Ok((object_type, size as usize, data));
+    }
+
+    let (object_type, data) = project
+        .packs()?
+        .iter()
+        .find(|pack| pack.contains(hash))
+        .ok_or(GitObjectError::FileReadError)?
+        .read_object(hash)?;
+
+    Ok((object_type, data.len(), data))
+}
+
+// Whether the loose file of `hash` holds that object, a truncated or corrupt file
+// doesn't count as stored
+pub fn is_loose_object_valid(project: &GitProject, hash: &str) -> bool {
+    let object_format = project.get_object_format();
+
+    fs::read(object_format.loose_object_path(project, hash))
+        .ok()
+        .and_then(|encoded_data| decode_object_data(&encoded_data).ok())
+        .is_some_and(|data| parse_object_header(&data).is_ok() && object_format.hash(&data) == hash)
+}
+
+// A zero byte object file is usually left behind by an interrupted write
+pub fn decode_object_data(encoded_data: &[u8]) -> Result<Vec<u8>, GitObjectError> {
+    if encoded_data.is_empty() {
+        return Err(GitObjectError::EmptyObject);
+    }
+
+    let mut zlib = ZlibDecoder::new(encoded_data);
+    let mut decoded_data = Vec::new();
+
+    zlib.read_to_end(&mut decoded_data)
+        .map_err(|_| GitObjectError::DecompressionError)?;
+
+    // The decoder returns whatever it inflated when the stream is cut short, so the
+    // adler32 checksum that ends every zlib stream is checked here
+    let stream_end = zlib.total_in() as usize;
+    let checksum = stream_end
+        .checked_sub(ADLER32_SIZE)
+        .map(|start| &encoded_data[start..stream_end])
+        .ok_or(GitObjectError::DecompressionError)?;
+    if checksum != adler32(&decoded_data).to_be_bytes() {
+        return Err(GitObjectError::DecompressionError);
+    }
+
+    Ok(decoded_data)
+}
+
+// The zlib stream of a loose object exactly as it is on disk, so it can be copied to
+// another store without inflating it. Packed objects have no such stream, they are
+// rebuilt from the pack and deflated again with the header git would have written
+pub fn read_raw_compressed(project: &GitProject, hash: &str) -> Result<Vec<u8>, GitObjectError> {
+    let object_format = project.get_object_format();
+    if hash.len() != object_format.hash_hex_len() {
+        return Err(GitObjectError::InvalidHash);
+    }
+
+    if let Ok(data) = std::fs::read(object_format.loose_object_path(project, hash)) {
+        return Ok(data);
+    }
+
+    let (object_type, data) = project
+        .packs()?
+        .iter()
+        .find(|pack| pack.contains(hash))
+        .ok_or(GitObjectError::FileReadError)?
+        .read_object(hash)?;
+
+    let mut zlib = ZlibEncoder::new(Vec::new(), flate2::Compression::default());
+    zlib.write_all(format!("{} {}\x00", object_type, data.len()).as_bytes())
+        .and_then(|_| zlib.write_all(&data))
+        .map_err(|_| GitObjectError::CompressionError)?;
+
+    zlib.finish().map_err(|_| GitObjectError::CompressionError)
+}
+
+// The single place the `<type> <size>\0` header of a loose object is read. The payload
+// has to be exactly the declared size
+pub fn parse_object_header(decoded_data: &[u8]) -> Result<(Header, u64, &[u8]), GitObjectError> {
+    let header_end = decoded_data.iter().position(|byte| *byte == 0).ok_or(
+        GitObjectError::InvalidObjectFile(ObjectError::InvalidHeader),
+    )?;
+    let (object_type, size) = parse_header_fields(&decoded_data[..header_end])?;
+
+    let payload = &decoded_data[header_end + 1..];
+    if payload.len() as u64 != size {
+        return Err(GitObjectError::SizeMismatch);
+    }
+
+    Ok((object_type, size, payload))
+}
+
+fn parse_header_fields(header: &[u8]) -> Result<(Header, u64), GitObjectError> {
+    std::str::from_utf8(header)
+        .ok()
+        .and_then(|header| header.split_once(' '))
+        .and_then(|(object_type, size)| Some((Header::from(object_type), size.parse().ok()?)))
+        .filter(|(object_type, _)| *object_type != Header::Invalid)
+        .ok_or(GitObjectError::InvalidObjectFile(
+            ObjectError::InvalidHeader,
+        ))
+}
+
 pub trait GitObject {
     fn get_type(&self) -> Header;
 
     fn get_data_string(&self) -> String;
 
-    fn get_hash(&self) -> String {
-        let data = self.get_data_string();
-        let file_to_hash = format!("{} {}\x00{}\n", self.get_type(), data.len() + 1, data);
-
-        let mut hasher = Sha1::new();
+    // The payload exactly as git stores and hashes it, after the `<type> <size>\0` header
+    fn get_data(&self) -> Vec<u8> {
+        self.get_data_string().into_bytes()
+    }
 
-        hasher.update(file_to_hash.as_bytes());
+    fn get_hash(&self) -> String {
+        self.get_hash_with_format(ObjectFormat::Sha1)
+    }
 
-        hasher
-            .finalize()
-            .iter()
-            .fold(String::new(), |mut output: String, b| {
-                let _ = write!(output, "{b:02X}");
-                output.to_lowercase()
-            })
+    fn get_hash_with_format(&self, object_format: ObjectFormat) -> String {
+        hash_object(object_format, &self.get_type(), &self.get_data())
     }
 
+    // Objects that were packed are read from the packs when there is no loose one
     fn from_hash(project: &GitProject, hash: &str) -> Result<Self, GitObjectError>
     where
         Self: Sized,
     {
-        if hash.len() != HASH_SIZE * 2 {
-            return Err(GitObjectError::InvalidHash);
-        }
-
-        let file_path = PathBuf::from(project.get_directory())
-            .join(GIT_FOLDER)
-            .join(GitFolders::OBJECTS.to_string())
-            .join(&hash[..2])
-            .join(&hash[2..]);
-
-        let data = std::fs::read(file_path).map_err(|_| GitObjectError::FileReadError)?;
-        Self::from_encoded_data(data.as_slice())
+        let (_, size, data) = read_object_data(project, hash)?;
+        Self::from_object_data(&data, size, project.get_object_format())
     }
 
     fn get_encoded_data(&self) -> Result<Vec<u8>, GitObjectError> {
-        let data = self.get_data_string();
-        let file_to_hash = format!("{} {}\x00{}\n", self.get_type(), data.len(), data);
+        let data = self.get_data();
+        let mut file_to_hash = format!("{} {}\x00", self.get_type(), data.len()).into_bytes();
+        file_to_hash.extend_from_slice(&data);
 
         let mut zlib = ZlibEncoder::new(Vec::new(), flate2::Compression::default());
-        zlib.write_all(file_to_hash.as_bytes())
+        zlib.write_all(&file_to_hash)
             .map_err(|_| GitObjectError::CompressionError)?;
 
         zlib.finish().map_err(|_| GitObjectError::CompressionError)
     }
 
+    // The object is written to a temporary file next to it and renamed into place, so
+    // readers never see it half written and an interrupted write leaves no object behind
     fn write_object(&self, project: &GitProject) -> Result<(), GitObjectError> {
         let encoded_data = self.get_encoded_data()?;
 
-        let hash = self.get_hash();
-        let file_path = PathBuf::from(project.get_directory())
-            .join(GIT_FOLDER)
-            .join(GitFolders::OBJECTS.to_string())
-            .join(&hash[..2])
-            .join(&hash[2..]);
+        let object_format = project.get_object_format();
+        let hash = self.get_hash_with_format(object_format);
+        let file_path = object_format.loose_object_path(project, &hash);
+        let folder = file_path.parent().ok_or(GitObjectError::FileReadError)?;
+        fs::create_dir_all(folder).map_err(|_| GitObjectError::FileReadError)?;
+
+        let temp_path = folder.join(format!(
+            "{}{}_{}",
+            TEMP_OBJECT_PREFIX,
+            process::id(),
+            TEMP_OBJECT_COUNTER.fetch_add(1, Ordering::Relaxed)
+        ));
+        let written = OpenOptions::new()
+            .write(true)
+            .create_new(true)
+            .open(&temp_path)
+            .and_then(|mut file| {
+                file.write_all(&encoded_data)?;
+                file.sync_all()
+            })
+            .and_then(|_| fs::rename(&temp_path, &file_path));
+        if written.is_err() {
+            _ = fs::remove_file(&temp_path);
+        }
 
-        std::fs::create_dir_all(file_path.parent().unwrap())
-            .map_err(|_| GitObjectError::FileReadError)?;
-        std::fs::write(file_path, encoded_data).map_err(|_| GitObjectError::FileReadError)
+        written.map_err(|_| GitObjectError::FileReadError)
     }
 
     fn decode_data(encoded_data: &[u8]) -> Result<String, GitObjectError> {
-        let mut zlib = ZlibDecoder::new(encoded_data);
-        let mut decoded_data = String::new();
-
-        zlib.read_to_string(&mut decoded_data)
-            .map_err(|_| GitObjectError::DecompressionError)?;
-
-        Ok(decoded_data)
-    }
-
-    fn check_header_valid_and_get_data(
-        decoded_data: &str,
-    ) -> Result<(&str, usize), GitObjectError> {
-        let (header_data, other_data) =
-            decoded_data
-                .split_once("\0")
-                .ok_or(GitObjectError::InvalidObjectFile(
-                    ObjectError::InvalidHeader,
-                ))?;
-        let (header, size) =
-            header_data
-                .split_once(" ")
-                .ok_or(GitObjectError::InvalidObjectFile(
-                    ObjectError::InvalidHeader,
-                ))?;
-
-        match Header::from(header) {
-            Header::Tree => {}
-            Header::Commit => {}
-            Header::Blob => {}
-            Header::Invalid => {
-                return Err(GitObjectError::InvalidObjectFile(
-                    ObjectError::InvalidHeader,
-                ))
-            }
-        }
-
-        let size = size
-            .parse()
-            .map_err(|_| GitObjectError::InvalidObjectFile(ObjectError::InvalidHeader))?;
+        String::from_utf8(Self::decode_data_bytes(encoded_data)?)
+            .map_err(|_| GitObjectError::DecompressionError)
+    }
 
-        Ok((other_data, size))
+    fn decode_data_bytes(encoded_data: &[u8]) -> Result<Vec<u8>, GitObjectError> {
+        decode_object_data(encoded_data)
     }
 
-    fn from_encoded_data(encoded_data: &[u8]) -> Result<Self, GitObjectError>
+    // `data` is the payload after the header, `size` the size the header declared. Only
+    // objects that embed raw hashes (trees) depend on the object format
+    fn from_object_data(
+        data: &[u8],
+        size: usize,
+        object_format: ObjectFormat,
+    ) -> Result<Self, GitObjectError>
     where
         Self: Sized;
+
+    fn from_encoded_data(encoded_data: &[u8]) -> Result<Self, GitObjectError>
+    where
+        Self: Sized,
+    {
+        Self::from_encoded_data_with_format(encoded_data, ObjectFormat::Sha1)
+    }
+
+    fn from_encoded_data_with_format(
+        encoded_data: &[u8],
+        object_format: ObjectFormat,
+    ) -> Result<Self, GitObjectError>
+    where
+        Self: Sized,
+    {
+        let decoded_data = Self::decode_data_bytes(encoded_data)?;
+        let (_, size, data) = parse_object_header(&decoded_data)?;
+
+        Self::from_object_data(data, size as usize, object_format)
+    }
 }
 
 #[cfg(test)]
@@ -172,6 +612,7 @@ mod tests {
         assert_eq!(Header::from("tree"), Header::Tree);
         assert_eq!(Header::from("commit"), Header::Commit);
         assert_eq!(Header::from("blob"), Header::Blob);
+        assert_eq!(Header::from("tag"), Header::Tag);
         assert_eq!(Header::from("other"), Header::Invalid);
     }
 
@@ -180,6 +621,170 @@ mod tests {
         assert_eq!(Header::Tree.to_string(), "tree");
         assert_eq!(Header::Commit.to_string(), "commit");
         assert_eq!(Header::Blob.to_string(), "blob");
+        assert_eq!(Header::Tag.to_string(), "tag");
         assert_eq!(Header::Invalid.to_string(), "invalid");
     }
+
+    #[test]
+    fn test_parse_object_header() {
+        assert_eq!(
+            parse_object_header(b"blob 5\0hello"),
+            Ok((Header::Blob, 5, b"hello".as_slice()))
+        );
+        assert_eq!(
+            parse_object_header(b"commit 5\0hello\n"),
+            Ok((Header::Commit, 5, b"hello\n".as_slice()))
+        );
+        assert_eq!(
+            parse_object_header(b"tree 0\0"),
+            Ok((Header::Tree, 0, b"".as_slice()))
+        );
+    }
+
+    #[test]
+    fn test_parse_object_header_wrong_size() {
+        for data in [
+            b"blob 6\0hello".as_slice(),
+            b"blob 4\0hello",
+            b"blob 4\0hello\n",
+            b"blob 5\0hello\n",
+            b"blob 5\0hello!",
+            b"blob 18446744073709551615\0hello\n",
+        ] {
+            assert_eq!(parse_object_header(data), Err(GitObjectError::SizeMismatch));
+        }
+    }
+
+    #[test]
+    fn test_parse_object_header_invalid() {
+        for data in [
+            b"blob 5hello".as_slice(),
+            b"blob\0hello",
+            b"blob five\0hello",
+            b"other 5\0hello",
+        ] {
+            assert_eq!(
+                parse_object_header(data),
+                Err(GitObjectError::InvalidObjectFile(
+                    ObjectError::InvalidHeader
+                ))
+            );
+        }
+    }
+
+    #[test]
+    fn test_object_format_from_config() {
+        assert_eq!(ObjectFormat::from_config(""), ObjectFormat::Sha1);
+        assert_eq!(
+            ObjectFormat::from_config("[core]\n\tobjectformat = sha256\n"),
+            ObjectFormat::Sha1
+        );
+        assert_eq!(
+            ObjectFormat::from_config(
+                "[core]\n\trepositoryformatversion = 1\n[extensions]\n\tobjectFormat = sha256\n"
+            ),
+            ObjectFormat::Sha256
+        );
+    }
+
+    #[test]
+    fn test_object_format_hash_len() {
+        assert_eq!(ObjectFormat::Sha1.hash_byte_len(), 20);
+        assert_eq!(ObjectFormat::Sha1.hash_hex_len(), 40);
+        assert_eq!(ObjectFormat::Sha256.hash_byte_len(), 32);
+        assert_eq!(ObjectFormat::Sha256.hash_hex_len(), 64);
+
+        assert_eq!(
+            ObjectFormat::Sha1.hash(b"").len(),
+            ObjectFormat::Sha1.hash_hex_len()
+        );
+        assert_eq!(
+            ObjectFormat::Sha256.hash(b""),
+            "e3b0c44298fc1c149afbf4c8996fb92427ae41e4649b934ca495991b7852b855"
+        );
+    }
+
+    #[test]
+    fn test_object_format_loose_object_path() {
+        let project = GitProject::new("/repo");
+        let hash = "e3b0c44298fc1c149afbf4c8996fb92427ae41e4649b934ca495991b7852b855";
+
+        assert_eq!(
+            ObjectFormat::Sha256.loose_object_path(&project, hash),
+            PathBuf::from("/repo/.git/objects/e3")
+                .join("b0c44298fc1c149afbf4c8996fb92427ae41e4649b934ca495991b7852b855")
+        );
+    }
+
+    #[test]
+    fn test_object_type() {
+        let encode = |data: &[u8]| {
+            let mut encoder = ZlibEncoder::new(Vec::new(), flate2::Compression::default());
+            encoder.write_all(data).unwrap();
+            encoder.finish().unwrap()
+        };
+
+        let blob = GitBlob::new(5, b"hello".to_vec());
+        assert_eq!(
+            object_type(&blob.get_encoded_data().unwrap()),
+            Ok(GitObjectType::Blob)
+        );
+        assert_eq!(object_type(&encode(b"tree 0\0")), Ok(GitObjectType::Tree));
+        assert_eq!(
+            object_type(&encode(b"commit 3\0abc")),
+            Ok(GitObjectType::Commit)
+        );
+        assert_eq!(object_type(&encode(b"tag 3\0abc")), Ok(GitObjectType::Tag));
+
+        for unknown in [&b"bogus 3\0abc"[..], b"invalid 0\0"] {
+            assert_eq!(
+                object_type(&encode(unknown)),
+                Err(GitObjectError::UnknownObjectType)
+            );
+        }
+        assert_eq!(
+            object_type(&encode(b"blob")),
+            Err(GitObjectError::InvalidObjectFile(
+                ObjectError::InvalidHeader
+            ))
+        );
+        assert_eq!(object_type(&[]), Err(GitObjectError::EmptyObject));
+        assert_eq!(
+            object_type(b"not zlib"),
+            Err(GitObjectError::DecompressionError)
+        );
+    }
+
+    #[test]
+    fn test_adler32() {
+        assert_eq!(adler32(b""), 1);
+        assert_eq!(adler32(b"Wikipedia"), 0x11e60398);
+        assert_eq!(adler32(&[0xff; 10000]), 0xb623eb2b);
+    }
+
+    #[test]
+    fn test_decode_data_corrupt_trailer() {
+        let blob = GitBlob::new(5, b"hello".to_vec());
+        let mut encoded_data = blob.get_encoded_data().unwrap();
+        assert!(GitBlob::decode_data_bytes(&encoded_data).is_ok());
+
+        // The deflate body is intact, only the adler32 checksum after it is wrong
+        let last = encoded_data.len() - 1;
+        encoded_data[last] ^= 0xff;
+        assert_eq!(
+            GitBlob::decode_data_bytes(&encoded_data),
+            Err(GitObjectError::DecompressionError)
+        );
+        assert_eq!(
+            GitBlob::decode_data(&encoded_data),
+            Err(GitObjectError::DecompressionError)
+        );
+
+        // A missing checksum means the object was cut short
+        encoded_data.truncate(last - 3);
+        assert_eq!(
+            GitBlob::decode_data_bytes(&encoded_data),
+            Err(GitObjectError::DecompressionError)
+        );
+    }
 }
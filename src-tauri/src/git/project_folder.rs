@@ -1,22 +1,17 @@
-use super::{
-    git_folders::GitBranchType, git_project::GitProject, git_project_state::GitProjectState,
-};
+use std::path::Path;
+
+use super::{git_folders::GitBranchType, git_project::GitProject};
 use crate::{database::storage::DATABASE, errors::git_error::GitError};
-use std::fs;
 
+// The folder is validated by `GitProject::open` like every other way of opening a
+// repository, so bare repositories, worktrees and submodules are accepted here too
 pub fn check_valid_git_project(directory: &str) -> Result<GitProject, GitError> {
-    fs::read_dir(directory)
-        .map(|read_dir| {
-            for entry in read_dir.flatten() {
-                let path = entry.path();
-                if path.is_dir() && path.file_name().unwrap() == ".git" {
-                    return Ok(GitProject::new(directory));
-                }
-            }
+    // A symlink loop fails like any folder that can't be opened
+    if !Path::new(directory).is_dir() {
+        return Err(GitError::CannotOpenFolder);
+    }
 
-            Err(GitError::NoGitFolder)
-        })
-        .map_err(|_| GitError::CannotOpenFolder)?
+    GitProject::open(directory).map_err(|_| GitError::NoGitFolder)
 }
 
 #[tauri::command]
@@ -27,9 +22,9 @@ pub fn set_current_project(project: Option<GitProject>) {
 #[tauri::command]
 pub fn open_git_project(directory: &str) -> Result<GitProject, GitError> {
     check_valid_git_project(directory).map(|mut git_project| {
+        git_project.check_repository_layout()?;
         git_project.has_required_files()?;
 
-        git_project.set_state(GitProjectState::Valid);
         _ = git_project.fetch_branches(GitBranchType::Local);
         _ = git_project.fetch_branches(GitBranchType::Tags);
         _ = git_project.fetch_remotes_directories();
@@ -68,20 +63,46 @@ pub fn remove_database_project(project: GitProject) -> Result<(), GitError> {
 
 #[cfg(test)]
 mod tests {
-    use std::io::{Read, Write};
+    use chrono::NaiveDate;
+    use std::{
+        fs,
+        io::{Read, Write},
+        path::{Path, PathBuf},
+        sync::Arc,
+        time::{Duration, SystemTime},
+    };
 
     use crate::{
-        errors::git_object_error::GitObjectError,
+        errors::git_object_error::{CommitError, GitObjectError},
         git::{
+            git_activity::Bucket,
             git_blob::GitBlob,
             git_branch::GitBranch,
             git_commit::GitCommit,
-            git_commit_author::GitCommitAuthor,
+            git_commit_author::{local_timezone, GitCommitAuthor},
+            git_config::{get_user, GitConfig},
+            git_diff::{diff_blob_summary, ChangeKind, DiffStat, FileStat, TreeChange},
+            git_directory::DirEntryKind,
             git_files::{GitFilesOptional, GitFilesRequired},
             git_folders::{GitFolders, GitRefs, GIT_FOLDER},
+            git_index::{
+                test_utils::{create_index_file, create_unmerged_index_file},
+                GitIndex, INDEX_FILE,
+            },
+            git_pack::{
+                from_hex,
+                test_utils::{encode_blob, encode_entry, encode_object, hash_bytes, write_pack},
+            },
+            git_project_state::GitProjectState,
+            git_refs::{HeadState, RefCategory},
+            git_status::GitFileState,
+            git_tag::GitTag,
             git_tree::{GitTree, GitTreeEntry, GitTreeMode},
             git_user::GitUser,
-            object::GitObject,
+            git_walk::CommitWalker,
+            object::{
+                peek_object_header, AnyGitObject, GitObject, GitObjectType, Header, ObjectFormat,
+            },
         },
     };
     use strum::IntoEnumIterator;
@@ -192,7 +213,7 @@ mod tests {
 
     fn create_encoded_blob_file(data: Option<String>) -> Result<Vec<u8>, GitObjectError> {
         let file_content = data.unwrap_or_else(|| "test".to_string());
-        let file_content_to_encode = format!("blob {}\x00{}\n", file_content.len(), file_content);
+        let file_content_to_encode = format!("blob {}\x00{}", file_content.len(), file_content);
 
         let mut zlib = flate2::bufread::ZlibEncoder::new(
             file_content_to_encode.as_bytes(),
@@ -206,19 +227,18 @@ mod tests {
     }
 
     fn create_encoded_tree_file(entries: Vec<GitTreeEntry>) -> Result<Vec<u8>, GitObjectError> {
-        let mut file_content = String::new();
+        let mut file_content = Vec::new();
         for entry in entries {
-            file_content.push_str(&format!(
-                "{} {}\0{}",
-                entry.mode.to_mode_str(),
-                entry.name,
-                entry.hash,
-            ));
+            file_content.extend_from_slice(
+                format!("{} {}\0", entry.mode.to_mode_str(), entry.name).as_bytes(),
+            );
+            file_content.extend_from_slice(&from_hex(&entry.hash).unwrap());
         }
 
-        let file_content_to_encode = format!("tree {}\x00{}\n", file_content.len(), file_content);
+        let mut file_content_to_encode = format!("tree {}\x00", file_content.len()).into_bytes();
+        file_content_to_encode.extend_from_slice(&file_content);
         let mut zlib = flate2::bufread::ZlibEncoder::new(
-            file_content_to_encode.as_bytes(),
+            file_content_to_encode.as_slice(),
             flate2::Compression::default(),
         );
         let mut encoded_file_content = Vec::new();
@@ -263,7 +283,8 @@ mod tests {
             "{}{}{}{}\n{}",
             tree_line, parent_lines, author_line, committer_line, message
         );
-        let file_content_to_encode = format!("commit {}\x00{}\n", file_content.len(), file_content);
+        let file_content_to_encode =
+            format!("commit {}\x00{}\n", file_content.len() + 1, file_content);
 
         let mut zlib = flate2::bufread::ZlibEncoder::new(
             file_content_to_encode.as_bytes(),
@@ -314,12 +335,14 @@ mod tests {
             GitTreeMode::File,
             "df6773ea47ed3fce3b3bb14e3d1101963e77ef08".to_string(),
             "test1".to_string(),
-        );
+        )
+        .unwrap();
         tree.add_entry(
             GitTreeMode::File,
             "df6773ea47ed3fce3b3bb14e3d1101963e77ef09".to_string(),
             "test2".to_string(),
-        );
+        )
+        .unwrap();
         tree.write_object(&git_project).unwrap();
 
         let tree = GitTree::from_hash(&git_project, &tree.get_hash()).unwrap();
@@ -886,4 +909,4076 @@ mod tests {
             Err(GitError::PackedRefsError)
         );
     }
+
+    #[test]
+    fn test_git_project_worktrees() {
+        let folder = TempDir::new("test_git_project_worktrees").unwrap();
+        let test_git_folder = folder.path().to_str().unwrap();
+        let worktree_folder = TempDir::new("test_git_project_worktrees_linked").unwrap();
+        let test_worktree_folder = worktree_folder.path().to_str().unwrap();
+
+        create_sample_git_folder(test_git_folder);
+        create_local_branch(
+            test_git_folder,
+            "main",
+            "6e18e0fdeac4932d71ad981dc4dc497c49f3c606\n",
+        );
+        create_local_branch(
+            test_git_folder,
+            "feature/worktree",
+            "88f877967c8c63e23979f07f50f93daf9b2ae872\n",
+        );
+        fs::write(
+            format!("{}/{}/HEAD", test_git_folder, GIT_FOLDER),
+            "ref: refs/heads/main\n",
+        )
+        .unwrap();
+
+        let linked_git_dir = format!("{}/{}/worktrees/linked", test_git_folder, GIT_FOLDER);
+        fs::create_dir_all(&linked_git_dir).unwrap();
+        fs::write(
+            format!("{}/HEAD", linked_git_dir),
+            "ref: refs/heads/feature/worktree\n",
+        )
+        .unwrap();
+        fs::write(format!("{}/commondir", linked_git_dir), "../..\n").unwrap();
+        fs::write(
+            format!("{}/gitdir", linked_git_dir),
+            format!("{}/{}\n", test_worktree_folder, GIT_FOLDER),
+        )
+        .unwrap();
+        fs::write(
+            format!("{}/{}", test_worktree_folder, GIT_FOLDER),
+            format!("gitdir: {}\n", linked_git_dir),
+        )
+        .unwrap();
+
+        let git_project = open_git_project(test_git_folder).unwrap();
+        let worktrees = git_project.worktrees().unwrap();
+
+        assert_eq!(worktrees.len(), 2);
+        assert!(worktrees[0].is_main());
+        assert_eq!(worktrees[0].get_path(), test_git_folder);
+        assert_eq!(worktrees[0].get_branch(), Some(&"main".to_string()));
+        assert_eq!(
+            worktrees[0].get_head(),
+            Some(&"6e18e0fdeac4932d71ad981dc4dc497c49f3c606".to_string())
+        );
+        assert_eq!(worktrees[1].get_name(), Some(&"linked".to_string()));
+        assert_eq!(worktrees[1].get_path(), test_worktree_folder);
+        assert_eq!(
+            worktrees[1].get_branch(),
+            Some(&"feature/worktree".to_string())
+        );
+        assert_eq!(
+            worktrees[1].get_head(),
+            Some(&"88f877967c8c63e23979f07f50f93daf9b2ae872".to_string())
+        );
+
+        let worktree_project = GitProject::new(test_worktree_folder);
+        assert_eq!(worktree_project.worktrees().unwrap(), worktrees);
+    }
+
+    #[test]
+    fn test_git_project_bisect_status() {
+        let folder = TempDir::new("test_git_project_bisect_status").unwrap();
+        let test_git_folder = folder.path().to_str().unwrap();
+
+        create_sample_git_folder(test_git_folder);
+        let git_project = open_git_project(test_git_folder).unwrap();
+        assert_eq!(git_project.bisect_status(), Ok(None));
+
+        let git_directory = format!("{}/{}", test_git_folder, GIT_FOLDER);
+        let bisect_refs = format!("{}/{}/bisect", git_directory, GitFolders::REFS);
+        fs::create_dir_all(&bisect_refs).unwrap();
+        fs::write(format!("{}/BISECT_START", git_directory), "main\n").unwrap();
+        fs::write(format!("{}/BISECT_TERMS", git_directory), "bad\ngood\n").unwrap();
+        fs::write(
+            format!("{}/BISECT_EXPECTED_REV", git_directory),
+            "88f877967c8c63e23979f07f50f93daf9b2ae872\n",
+        )
+        .unwrap();
+        fs::write(
+            format!("{}/bad", bisect_refs),
+            "6e18e0fdeac4932d71ad981dc4dc497c49f3c606\n",
+        )
+        .unwrap();
+        for hash in [
+            "df6773ea47ed3fce3b3bb14e3d1101963e77ef09",
+            "50c8353444afbef3172c999ef6cff8d31309ac3e",
+        ] {
+            fs::write(format!("{}/good-{}", bisect_refs, hash), hash).unwrap();
+        }
+        fs::write(
+            format!(
+                "{}/skip-25723a3e66cd8dcbaf085ed83b86a8007df7ff32",
+                bisect_refs
+            ),
+            "25723a3e66cd8dcbaf085ed83b86a8007df7ff32",
+        )
+        .unwrap();
+
+        let status = git_project.bisect_status().unwrap().unwrap();
+        assert_eq!(status.get_start(), "main");
+        assert_eq!(
+            status.get_bad(),
+            Some(&"6e18e0fdeac4932d71ad981dc4dc497c49f3c606".to_string())
+        );
+        assert_eq!(
+            status.get_good(),
+            &vec![
+                "50c8353444afbef3172c999ef6cff8d31309ac3e".to_string(),
+                "df6773ea47ed3fce3b3bb14e3d1101963e77ef09".to_string(),
+            ]
+        );
+        assert_eq!(
+            status.get_skipped(),
+            &vec!["25723a3e66cd8dcbaf085ed83b86a8007df7ff32".to_string()]
+        );
+        assert_eq!(
+            status.get_current(),
+            Some(&"88f877967c8c63e23979f07f50f93daf9b2ae872".to_string())
+        );
+    }
+
+    #[test]
+    fn test_git_project_bisect_next() {
+        let folder = TempDir::new("test_git_project_bisect_next").unwrap();
+        let test_git_folder = folder.path().to_str().unwrap();
+
+        create_sample_git_folder(test_git_folder);
+        let git_project = open_git_project(test_git_folder).unwrap();
+
+        let mut history = Vec::<String>::new();
+        for index in 0..6 {
+            let author = GitCommitAuthor::new(
+                GitUser::new("Test User".to_string(), "test.user@email.com".to_string()),
+                100 + index,
+                "+0000".to_string(),
+            );
+            let commit = GitCommit::new(
+                "tree",
+                history.last().cloned().as_slice(),
+                author.clone(),
+                author,
+                &format!("commit {}", index),
+            );
+            commit.write_object(&git_project).unwrap();
+            history.push(commit.get_hash());
+        }
+
+        // Suspects are commits 1 to 5, commit 3 has two suspects on each side
+        assert_eq!(
+            git_project.bisect_next(&history[..1], &history[5]),
+            Ok(Some(history[3].clone()))
+        );
+        assert_eq!(
+            git_project.bisect_next(&history[..3], &history[5]),
+            Ok(Some(history[4].clone()))
+        );
+        assert_eq!(
+            git_project.bisect_next(&history[4..5], &history[5]),
+            Ok(None)
+        );
+    }
+
+    #[test]
+    fn test_git_project_tag_details() {
+        let folder = TempDir::new("test_git_project_tag_details").unwrap();
+        let test_git_folder = folder.path().to_str().unwrap();
+
+        create_sample_git_folder(test_git_folder);
+        let git_project = open_git_project(test_git_folder).unwrap();
+
+        let author = GitCommitAuthor::new(
+            GitUser::new("Test User".to_string(), "test.user@email.com".to_string()),
+            100,
+            "+0000".to_string(),
+        );
+        let commit = GitCommit::new(
+            "tree",
+            &[],
+            author.clone(),
+            author.clone(),
+            "Release the thing\n\nWith a longer body",
+        );
+        commit.write_object(&git_project).unwrap();
+
+        let tag = GitTag::new(
+            &commit.get_hash(),
+            Header::Commit,
+            "v2",
+            Some(author.clone()),
+            "Version 2\n",
+        );
+        tag.write_object(&git_project).unwrap();
+
+        create_tag(test_git_folder, "v1", &commit.get_hash());
+        create_tag(test_git_folder, "v2", &tag.get_hash());
+
+        let lightweight = git_project.tag_details("v1").unwrap();
+        assert_eq!(lightweight.get_name(), "v1");
+        assert_eq!(lightweight.get_tagger(), None);
+        assert_eq!(lightweight.get_message(), None);
+        assert_eq!(lightweight.get_commit_hash(), &commit.get_hash());
+        assert_eq!(lightweight.get_commit_subject(), "Release the thing");
+
+        let annotated = git_project.tag_details("v2").unwrap();
+        assert_eq!(annotated.get_name(), "v2");
+        assert_eq!(annotated.get_tagger(), Some(&author));
+        assert_eq!(annotated.get_message(), Some(&"Version 2\n".to_string()));
+        assert_eq!(annotated.get_commit_hash(), &commit.get_hash());
+        assert_eq!(annotated.get_commit_subject(), "Release the thing");
+
+        assert_eq!(
+            git_project.tag_details("missing"),
+            Err(GitObjectError::RefNotFound)
+        );
+    }
+
+    #[test]
+    fn test_git_project_unborn() {
+        let folder = TempDir::new("test_git_project_unborn").unwrap();
+        let test_git_folder = folder.path().to_str().unwrap();
+
+        create_sample_git_folder(test_git_folder);
+        fs::write(
+            format!("{}/{}/HEAD", test_git_folder, GIT_FOLDER),
+            "ref: refs/heads/main\n",
+        )
+        .unwrap();
+        fs::create_dir(format!("{}/src", test_git_folder)).unwrap();
+        fs::write(format!("{}/README.md", test_git_folder), "readme").unwrap();
+        fs::write(format!("{}/src/main.rs", test_git_folder), "fn main() {}").unwrap();
+
+        let git_project = open_git_project(test_git_folder).unwrap();
+
+        assert_eq!(git_project.is_unborn(), Ok(true));
+        assert_eq!(git_project.head_commit(), Err(GitObjectError::UnbornBranch));
+        assert!(git_project.get_local_branches().is_empty());
+
+        let status = git_project.status().unwrap();
+        assert_eq!(
+            status
+                .iter()
+                .map(|entry| entry.get_path().as_str())
+                .collect::<Vec<_>>(),
+            vec!["README.md", "src/main.rs"]
+        );
+        assert!(status.iter().all(|entry| entry.get_staged().is_none()
+            && entry.get_unstaged() == Some(&GitFileState::Untracked)));
+
+        let author = GitCommitAuthor::new(
+            GitUser::new("Test User".to_string(), "test.user@email.com".to_string()),
+            100,
+            "+0000".to_string(),
+        );
+        let commit = GitCommit::new("tree", &[], author.clone(), author, "Initial commit");
+        commit.write_object(&git_project).unwrap();
+        create_local_branch(test_git_folder, "main", &commit.get_hash());
+
+        assert_eq!(git_project.is_unborn(), Ok(false));
+        assert_eq!(git_project.head_commit(), Ok(commit));
+    }
+
+    #[test]
+    fn test_git_project_sha256_objects() {
+        let folder = TempDir::new("test_git_project_sha256_objects").unwrap();
+        let test_git_folder = folder.path().to_str().unwrap();
+
+        create_sample_git_folder(test_git_folder);
+        fs::write(
+            format!("{}/{}/config", test_git_folder, GIT_FOLDER),
+            "[core]\n\trepositoryformatversion = 1\n[extensions]\n\tobjectformat = sha256\n",
+        )
+        .unwrap();
+
+        let git_project = open_git_project(test_git_folder).unwrap();
+        assert_eq!(git_project.get_object_format(), ObjectFormat::Sha256);
+
+        let mut tree = GitTree::with_object_format(ObjectFormat::Sha256);
+        tree.add_entry(
+            GitTreeMode::File,
+            format!("{:064x}", 1),
+            "README.md".to_string(),
+        )
+        .unwrap();
+        tree.write_object(&git_project).unwrap();
+
+        let hash = tree.get_hash_with_format(ObjectFormat::Sha256);
+        assert_eq!(hash.len(), 64);
+        assert!(fs::metadata(format!(
+            "{}/{}/objects/{}/{}",
+            test_git_folder,
+            GIT_FOLDER,
+            &hash[..2],
+            &hash[2..]
+        ))
+        .is_ok());
+
+        let read_tree = GitTree::from_hash(&git_project, &hash).unwrap();
+        assert_eq!(
+            read_tree.get_entry_by_name("README.md").unwrap().hash,
+            format!("{:064x}", 1)
+        );
+        assert!(matches!(
+            GitTree::from_hash(&git_project, &tree.get_hash()),
+            Err(GitObjectError::InvalidHash)
+        ));
+    }
+
+    fn create_archive_tree(git_project: &GitProject) -> GitTree {
+        let readme = GitBlob::new(6, b"readme".to_vec());
+        let script = GitBlob::new(10, b"echo hello".to_vec());
+        let link_target = GitBlob::new(9, b"README.md".to_vec());
+        for blob in [&readme, &script, &link_target] {
+            blob.write_object(git_project).unwrap();
+        }
+
+        let mut bin = GitTree::new();
+        bin.add_entry(
+            GitTreeMode::Executable,
+            script.get_hash(),
+            "run.sh".to_string(),
+        )
+        .unwrap();
+        bin.write_object(git_project).unwrap();
+
+        let mut root = GitTree::new();
+        root.add_entry(
+            GitTreeMode::File,
+            readme.get_hash(),
+            "README.md".to_string(),
+        )
+        .unwrap();
+        root.add_entry(GitTreeMode::Tree, bin.get_hash(), "bin".to_string())
+            .unwrap();
+        root.add_entry(
+            GitTreeMode::Symlink,
+            link_target.get_hash(),
+            "link".to_string(),
+        )
+        .unwrap();
+        root.write_object(git_project).unwrap();
+
+        root
+    }
+
+    #[test]
+    fn test_git_tree_write_tar() {
+        let folder = TempDir::new("test_git_tree_write_tar").unwrap();
+        let test_git_folder = folder.path().to_str().unwrap();
+
+        create_sample_git_folder(test_git_folder);
+        let git_project = open_git_project(test_git_folder).unwrap();
+        let tree = create_archive_tree(&git_project);
+
+        let mut archive = Vec::new();
+        tree.write_tar(&git_project, "project/", &mut archive)
+            .unwrap();
+
+        let mut entries = Vec::new();
+        for entry in tar::Archive::new(archive.as_slice()).entries().unwrap() {
+            let mut entry = entry.unwrap();
+            let path = entry.path().unwrap().to_string_lossy().to_string();
+            let mode = entry.header().mode().unwrap();
+            let link = entry
+                .link_name()
+                .unwrap()
+                .map(|link| link.to_string_lossy().to_string());
+            let mut content = String::new();
+            entry.read_to_string(&mut content).unwrap();
+
+            entries.push((path, mode, content, link));
+        }
+
+        assert_eq!(
+            entries,
+            vec![
+                (
+                    "project/README.md".to_string(),
+                    0o644,
+                    "readme".to_string(),
+                    None
+                ),
+                (
+                    "project/bin/run.sh".to_string(),
+                    0o755,
+                    "echo hello".to_string(),
+                    None
+                ),
+                (
+                    "project/link".to_string(),
+                    0o777,
+                    String::new(),
+                    Some("README.md".to_string())
+                ),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_git_tree_write_zip() {
+        let folder = TempDir::new("test_git_tree_write_zip").unwrap();
+        let test_git_folder = folder.path().to_str().unwrap();
+
+        create_sample_git_folder(test_git_folder);
+        let git_project = open_git_project(test_git_folder).unwrap();
+        let tree = create_archive_tree(&git_project);
+
+        let mut archive = Vec::new();
+        tree.write_zip(&git_project, "project/", &mut archive)
+            .unwrap();
+
+        let mut zip = zip::ZipArchive::new(std::io::Cursor::new(archive)).unwrap();
+        let mut entries = Vec::new();
+        for index in 0..zip.len() {
+            let mut file = zip.by_index(index).unwrap();
+            let mut content = String::new();
+            file.read_to_string(&mut content).unwrap();
+
+            entries.push((file.name().to_string(), content));
+        }
+
+        assert_eq!(
+            entries,
+            vec![
+                ("project/README.md".to_string(), "readme".to_string()),
+                ("project/bin/run.sh".to_string(), "echo hello".to_string()),
+                ("project/link".to_string(), "README.md".to_string()),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_git_project_invalidate() {
+        let folder = TempDir::new("test_git_project_invalidate").unwrap();
+        let test_git_folder = folder.path().to_str().unwrap();
+
+        create_sample_git_folder(test_git_folder);
+        fs::write(
+            format!("{}/{}/HEAD", test_git_folder, GIT_FOLDER),
+            "ref: refs/heads/main\n",
+        )
+        .unwrap();
+        let git_project = open_git_project(test_git_folder).unwrap();
+
+        let author = GitCommitAuthor::new(
+            GitUser::new("Test User".to_string(), "test.user@email.com".to_string()),
+            100,
+            "+0000".to_string(),
+        );
+        let first = GitCommit::new("tree", &[], author.clone(), author.clone(), "First");
+        let second = GitCommit::new(
+            "tree",
+            &[first.get_hash()],
+            author.clone(),
+            author,
+            "Second",
+        );
+        first.write_object(&git_project).unwrap();
+        second.write_object(&git_project).unwrap();
+
+        create_local_branch(test_git_folder, "main", &first.get_hash());
+        assert_eq!(git_project.head_commit(), Ok(first.clone()));
+        assert_eq!(
+            git_project.resolve_ref("refs/heads/main"),
+            Ok(Some(first.get_hash()))
+        );
+
+        create_local_branch(test_git_folder, "main", &second.get_hash());
+        git_project.invalidate(&[PathBuf::from(".git/objects/ab/cdef")]);
+        assert_eq!(git_project.head_commit(), Ok(first.clone()));
+        assert_eq!(
+            git_project.resolve_ref("refs/heads/main"),
+            Ok(Some(first.get_hash()))
+        );
+
+        git_project.invalidate(&[folder.path().join(".git/refs/heads/main")]);
+        assert_eq!(git_project.head_commit(), Ok(second.clone()));
+        assert_eq!(
+            git_project.resolve_ref("refs/heads/main"),
+            Ok(Some(second.get_hash()))
+        );
+
+        fs::write(
+            format!("{}/{}/HEAD", test_git_folder, GIT_FOLDER),
+            format!("{}\n", first.get_hash()),
+        )
+        .unwrap();
+        git_project.invalidate(&[PathBuf::from(".git/HEAD")]);
+        assert_eq!(git_project.head_commit(), Ok(first.clone()));
+
+        // Only the ref a symbolic ref points at is reported when it moves
+        fs::create_dir_all(format!(
+            "{}/{}/refs/remotes/origin",
+            test_git_folder, GIT_FOLDER
+        ))
+        .unwrap();
+        let remote_ref = |hash: &str| {
+            fs::write(
+                format!(
+                    "{}/{}/refs/remotes/origin/main",
+                    test_git_folder, GIT_FOLDER
+                ),
+                format!("{}\n", hash),
+            )
+            .unwrap()
+        };
+        remote_ref(&first.get_hash());
+        fs::write(
+            format!(
+                "{}/{}/refs/remotes/origin/HEAD",
+                test_git_folder, GIT_FOLDER
+            ),
+            "ref: refs/remotes/origin/main\n",
+        )
+        .unwrap();
+        assert_eq!(
+            git_project.resolve_ref("refs/remotes/origin/HEAD"),
+            Ok(Some(first.get_hash()))
+        );
+
+        remote_ref(&second.get_hash());
+        git_project.invalidate(&[PathBuf::from(".git/refs/remotes/origin/main")]);
+        assert_eq!(
+            git_project.resolve_ref("refs/remotes/origin/HEAD"),
+            Ok(Some(second.get_hash()))
+        );
+    }
+
+    #[test]
+    fn test_git_project_open_submodule() {
+        let folder = TempDir::new("test_git_project_open_submodule").unwrap();
+        let test_git_folder = folder.path().to_str().unwrap();
+
+        create_sample_git_folder(test_git_folder);
+        let git_project = open_git_project(test_git_folder).unwrap();
+
+        let module_git_dir = format!("{}/{}/modules/sub", test_git_folder, GIT_FOLDER);
+        fs::create_dir_all(format!("{}/refs/heads", module_git_dir)).unwrap();
+        fs::create_dir_all(format!("{}/objects", module_git_dir)).unwrap();
+        fs::write(format!("{}/HEAD", module_git_dir), "ref: refs/heads/main\n").unwrap();
+        fs::write(
+            format!("{}/refs/heads/main", module_git_dir),
+            "df6773ea47ed3fce3b3bb14e3d1101963e77ef08\n",
+        )
+        .unwrap();
+
+        fs::create_dir_all(format!("{}/libs/sub", test_git_folder)).unwrap();
+        fs::write(
+            format!("{}/libs/sub/{}", test_git_folder, GIT_FOLDER),
+            "gitdir: ../../.git/modules/sub\n",
+        )
+        .unwrap();
+        fs::create_dir_all(format!("{}/libs/uninitialized", test_git_folder)).unwrap();
+
+        let submodule = git_project.open_submodule("libs/sub").unwrap();
+        assert_eq!(
+            submodule.get_directory(),
+            &format!("{}/libs/sub", test_git_folder)
+        );
+        assert_eq!(
+            submodule.resolve_ref("HEAD"),
+            Ok(Some("df6773ea47ed3fce3b3bb14e3d1101963e77ef08".to_string()))
+        );
+
+        assert_eq!(
+            git_project.open_submodule("libs/uninitialized"),
+            Err(GitObjectError::NotAGitRepository)
+        );
+    }
+
+    #[test]
+    fn test_git_project_list_dir() {
+        let folder = TempDir::new("test_git_project_list_dir").unwrap();
+        let test_git_folder = folder.path().to_str().unwrap();
+
+        create_sample_git_folder(test_git_folder);
+        let git_project = open_git_project(test_git_folder).unwrap();
+
+        let file_hash = format!("{:040x}", 1);
+        let submodule_hash = format!("{:040x}", 2);
+
+        let mut src = GitTree::new();
+        src.add_entry(GitTreeMode::File, file_hash.clone(), "main.rs".to_string())
+            .unwrap();
+        src.write_object(&git_project).unwrap();
+
+        let docs = GitTree::new();
+        docs.write_object(&git_project).unwrap();
+
+        let mut root = GitTree::new();
+        root.add_entry(GitTreeMode::File, file_hash.clone(), "b.txt".to_string())
+            .unwrap();
+        root.add_entry(GitTreeMode::Tree, src.get_hash(), "src".to_string())
+            .unwrap();
+        root.add_entry(
+            GitTreeMode::Executable,
+            file_hash.clone(),
+            "a.sh".to_string(),
+        )
+        .unwrap();
+        root.add_entry(GitTreeMode::Symlink, file_hash.clone(), "link".to_string())
+            .unwrap();
+        root.add_entry(
+            GitTreeMode::Submodule,
+            submodule_hash.clone(),
+            "vendor".to_string(),
+        )
+        .unwrap();
+        root.add_entry(GitTreeMode::Tree, docs.get_hash(), "docs".to_string())
+            .unwrap();
+        root.write_object(&git_project).unwrap();
+
+        let author = GitCommitAuthor::new(
+            GitUser::new("Test User".to_string(), "test.user@email.com".to_string()),
+            100,
+            "+0000".to_string(),
+        );
+        let commit = GitCommit::new(&root.get_hash(), &[], author.clone(), author, "Initial");
+        commit.write_object(&git_project).unwrap();
+        create_local_branch(test_git_folder, "main", &commit.get_hash());
+
+        let entries = git_project.list_dir("main", "").unwrap();
+        assert_eq!(
+            entries
+                .iter()
+                .map(|entry| (entry.get_name().as_str(), entry.get_kind().clone()))
+                .collect::<Vec<_>>(),
+            vec![
+                ("docs", DirEntryKind::Directory),
+                ("src", DirEntryKind::Directory),
+                ("a.sh", DirEntryKind::File),
+                ("b.txt", DirEntryKind::File),
+                ("link", DirEntryKind::Symlink),
+                ("vendor", DirEntryKind::Submodule),
+            ]
+        );
+        assert_eq!(entries[5].get_hash(), &submodule_hash);
+
+        let entries = git_project.list_dir(&commit.get_hash(), "src/").unwrap();
+        assert_eq!(entries.len(), 1);
+        assert_eq!(entries[0].get_name(), "main.rs");
+        assert_eq!(entries[0].get_hash(), &file_hash);
+
+        assert_eq!(
+            git_project.list_dir("refs/heads/main", "b.txt"),
+            Err(GitObjectError::PathNotFound)
+        );
+        assert_eq!(
+            git_project.list_dir("missing", ""),
+            Err(GitObjectError::RefNotFound)
+        );
+    }
+
+    #[test]
+    fn test_git_commit_parent_subjects() {
+        let folder = TempDir::new("test_git_commit_parent_subjects").unwrap();
+        let test_git_folder = folder.path().to_str().unwrap();
+
+        create_sample_git_folder(test_git_folder);
+        let git_project = open_git_project(test_git_folder).unwrap();
+
+        let author = GitCommitAuthor::new(
+            GitUser::new("Test User".to_string(), "test.user@email.com".to_string()),
+            100,
+            "+0000".to_string(),
+        );
+        let main = GitCommit::new(
+            "tree",
+            &[],
+            author.clone(),
+            author.clone(),
+            "Fix the build\n\nDetails",
+        );
+        let feature = GitCommit::new("tree", &[], author.clone(), author.clone(), "Add feature");
+        main.write_object(&git_project).unwrap();
+        feature.write_object(&git_project).unwrap();
+
+        let missing_hash = format!("{:040x}", 1);
+        let merge = GitCommit::new(
+            "tree",
+            &[main.get_hash(), feature.get_hash(), missing_hash.clone()],
+            author.clone(),
+            author,
+            "Merge branch 'feature'",
+        );
+
+        assert_eq!(
+            merge.parent_subjects(&git_project),
+            Ok(vec![
+                "Fix the build".to_string(),
+                "Add feature".to_string(),
+                format!("<missing commit {}>", missing_hash),
+            ])
+        );
+    }
+
+    #[test]
+    fn test_git_project_worktree_common_dir() {
+        let folder = TempDir::new("test_git_project_worktree_common_dir").unwrap();
+        let worktree_folder = TempDir::new("test_git_project_worktree_common_dir_wt").unwrap();
+        let test_git_folder = folder.path().to_str().unwrap();
+        let test_worktree_folder = worktree_folder.path().to_str().unwrap();
+
+        create_sample_git_folder(test_git_folder);
+        let main_project = open_git_project(test_git_folder).unwrap();
+
+        let author = GitCommitAuthor::new(
+            GitUser::new("Test User".to_string(), "test.user@email.com".to_string()),
+            100,
+            "+0000".to_string(),
+        );
+        let commit = GitCommit::new("tree", &[], author.clone(), author, "Work in a worktree");
+        commit.write_object(&main_project).unwrap();
+        create_local_branch(
+            test_git_folder,
+            "feature",
+            &format!("{}\n", commit.get_hash()),
+        );
+        fs::write(
+            format!("{}/{}/packed-refs", test_git_folder, GIT_FOLDER),
+            format!("{} refs/heads/packed\n", commit.get_hash()),
+        )
+        .unwrap();
+
+        let linked_git_dir = format!("{}/{}/worktrees/linked", test_git_folder, GIT_FOLDER);
+        fs::create_dir_all(&linked_git_dir).unwrap();
+        fs::write(
+            format!("{}/HEAD", linked_git_dir),
+            "ref: refs/heads/feature\n",
+        )
+        .unwrap();
+        fs::write(format!("{}/commondir", linked_git_dir), "../..\n").unwrap();
+        fs::write(
+            format!("{}/{}", test_worktree_folder, GIT_FOLDER),
+            format!("gitdir: {}\n", linked_git_dir),
+        )
+        .unwrap();
+
+        let mut worktree_project = GitProject::new(test_worktree_folder);
+        worktree_project
+            .fetch_branches(GitBranchType::Local)
+            .unwrap();
+        worktree_project.fetch_packed_refs().unwrap();
+
+        assert_eq!(worktree_project.git_dir(), PathBuf::from(&linked_git_dir));
+        assert_eq!(
+            worktree_project.objects_dir(),
+            PathBuf::from(format!("{}/{}/objects", test_git_folder, GIT_FOLDER))
+        );
+        assert!(worktree_project
+            .get_local_branches()
+            .contains(&GitBranch::new("feature".to_string(), commit.get_hash())));
+        assert!(worktree_project
+            .get_local_branches()
+            .contains(&GitBranch::new("packed".to_string(), commit.get_hash())));
+        assert_eq!(
+            worktree_project.resolve_ref("HEAD"),
+            Ok(Some(commit.get_hash()))
+        );
+        assert_eq!(worktree_project.head_commit(), Ok(commit));
+    }
+
+    #[test]
+    fn test_git_project_working_file_matches_blob() {
+        let folder = TempDir::new("test_git_project_working_file_matches_blob").unwrap();
+        let test_git_folder = folder.path().to_str().unwrap();
+
+        create_sample_git_folder(test_git_folder);
+        let git_project = open_git_project(test_git_folder).unwrap();
+
+        let content = "line of the tracked file\n".repeat(5000);
+        let blob = GitBlob::new(content.len(), content.as_bytes().to_vec());
+        blob.write_object(&git_project).unwrap();
+
+        fs::write(format!("{}/same.txt", test_git_folder), &content).unwrap();
+        assert_eq!(
+            git_project.working_file_matches_blob(Path::new("same.txt"), &blob.get_hash()),
+            Ok(true)
+        );
+
+        // Same size, differs in a single byte near the end
+        let mut changed = content.clone().into_bytes();
+        let last = changed.len() - 2;
+        changed[last] = b'X';
+        fs::write(format!("{}/changed.txt", test_git_folder), &changed).unwrap();
+        assert_eq!(
+            git_project.working_file_matches_blob(Path::new("changed.txt"), &blob.get_hash()),
+            Ok(false)
+        );
+
+        fs::write(format!("{}/shorter.txt", test_git_folder), &content[1..]).unwrap();
+        assert_eq!(
+            git_project.working_file_matches_blob(Path::new("shorter.txt"), &blob.get_hash()),
+            Ok(false)
+        );
+    }
+
+    #[test]
+    fn test_git_commit_changes_root_commit() {
+        let folder = TempDir::new("test_git_commit_changes_root_commit").unwrap();
+        let test_git_folder = folder.path().to_str().unwrap();
+
+        create_sample_git_folder(test_git_folder);
+        let git_project = open_git_project(test_git_folder).unwrap();
+        let tree = create_archive_tree(&git_project);
+
+        let author = GitCommitAuthor::new(
+            GitUser::new("Test User".to_string(), "test.user@email.com".to_string()),
+            100,
+            "+0000".to_string(),
+        );
+        let commit = GitCommit::new(&tree.get_hash(), &[], author.clone(), author, "Initial");
+
+        let changes = commit.changes(&git_project).unwrap();
+        assert_eq!(
+            changes
+                .iter()
+                .map(|change| change.get_path().as_str())
+                .collect::<Vec<&str>>(),
+            vec!["README.md", "bin/run.sh", "link"]
+        );
+        assert!(changes.iter().all(
+            |change| *change.get_kind() == ChangeKind::Added && change.get_old_hash().is_none()
+        ));
+        assert_eq!(
+            changes[1].get_new_hash(),
+            Some(&GitBlob::new(10, b"echo hello".to_vec()).get_hash())
+        );
+        assert_eq!(
+            changes
+                .iter()
+                .map(|change| change.get_new_mode().cloned())
+                .collect::<Vec<_>>(),
+            vec![
+                Some(GitTreeMode::File),
+                Some(GitTreeMode::Executable),
+                Some(GitTreeMode::Symlink)
+            ]
+        );
+        assert_eq!(tree.diff_against_empty(&git_project), Ok(changes));
+    }
+
+    #[test]
+    fn test_git_tree_diff_sorted_by_path() {
+        let folder = TempDir::new("test_git_tree_diff_sorted_by_path").unwrap();
+        let test_git_folder = folder.path().to_str().unwrap();
+
+        create_sample_git_folder(test_git_folder);
+        let git_project = open_git_project(test_git_folder).unwrap();
+
+        let blob = |content: &str| {
+            let blob = GitBlob::new(content.len(), content.as_bytes().to_vec());
+            blob.write_object(&git_project).unwrap();
+            blob.get_hash()
+        };
+        let tree = |entries: Vec<(GitTreeMode, String, &str)>| {
+            let mut tree = GitTree::new();
+            for (mode, hash, name) in entries {
+                tree.add_entry(mode, hash, name.to_string()).unwrap();
+            }
+            tree.write_object(&git_project).unwrap();
+            tree
+        };
+
+        let lib = tree(vec![(GitTreeMode::File, blob("mod v1"), "mod.rs")]);
+        let old_src = tree(vec![
+            (GitTreeMode::File, blob("main v1"), "main.rs"),
+            (GitTreeMode::Tree, lib.get_hash(), "lib"),
+        ]);
+        let old = tree(vec![
+            (GitTreeMode::File, blob("readme"), "README.md"),
+            (GitTreeMode::Tree, old_src.get_hash(), "src"),
+            (GitTreeMode::File, blob("notes v1"), "src.txt"),
+            (GitTreeMode::File, blob("target"), "link"),
+        ]);
+
+        let new_lib = tree(vec![
+            (GitTreeMode::File, blob("mod v1"), "mod.rs"),
+            (GitTreeMode::File, blob("new"), "new.rs"),
+        ]);
+        let new_src = tree(vec![
+            (GitTreeMode::File, blob("main v2"), "main.rs"),
+            (GitTreeMode::Tree, new_lib.get_hash(), "lib"),
+        ]);
+        let docs = tree(vec![(GitTreeMode::File, blob("guide"), "guide.md")]);
+        let new = tree(vec![
+            (GitTreeMode::Tree, docs.get_hash(), "docs"),
+            (GitTreeMode::Tree, new_src.get_hash(), "src"),
+            (GitTreeMode::File, blob("notes v2"), "src.txt"),
+            (GitTreeMode::Symlink, blob("target"), "link"),
+        ]);
+
+        let changes = old.diff(&git_project, &new).unwrap();
+        assert_eq!(
+            changes
+                .iter()
+                .map(|change| (change.get_path().as_str(), change.get_kind().clone()))
+                .collect::<Vec<(&str, ChangeKind)>>(),
+            vec![
+                ("README.md", ChangeKind::Deleted),
+                ("docs/guide.md", ChangeKind::Added),
+                ("link", ChangeKind::Deleted),
+                ("link", ChangeKind::Added),
+                ("src.txt", ChangeKind::Modified),
+                ("src/lib/new.rs", ChangeKind::Added),
+                ("src/main.rs", ChangeKind::Modified),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_git_project_commit_detail() {
+        let folder = TempDir::new("test_git_project_commit_detail").unwrap();
+        let test_git_folder = folder.path().to_str().unwrap();
+
+        create_sample_git_folder(test_git_folder);
+        let git_project = open_git_project(test_git_folder).unwrap();
+
+        let blob = |content: &[u8]| {
+            let blob = GitBlob::new(content.len(), content.to_vec());
+            blob.write_object(&git_project).unwrap();
+            blob.get_hash()
+        };
+        let tree = |entries: Vec<(String, &str)>| {
+            let mut tree = GitTree::new();
+            for (hash, name) in entries {
+                tree.add_entry(GitTreeMode::File, hash, name.to_string())
+                    .unwrap();
+            }
+            tree.write_object(&git_project).unwrap();
+            tree.get_hash()
+        };
+        let author = GitCommitAuthor::new(
+            GitUser::new("Test User".to_string(), "test.user@email.com".to_string()),
+            100,
+            "+0000".to_string(),
+        );
+
+        let parent_tree = tree(vec![
+            (blob(b"one\ntwo\nthree\n"), "notes.txt"),
+            (blob(b"obsolete\n"), "old.txt"),
+        ]);
+        let parent = GitCommit::new(
+            &parent_tree,
+            &[],
+            author.clone(),
+            author.clone(),
+            "Add notes",
+        );
+        parent.write_object(&git_project).unwrap();
+
+        let child_tree = tree(vec![
+            (blob(b"one\n2\nthree\nfour\n"), "notes.txt"),
+            (blob(b"\x89PNG\x00\x01"), "logo.png"),
+        ]);
+        let child = GitCommit::new(
+            &child_tree,
+            &[parent.get_hash()],
+            author.clone(),
+            author,
+            "Update notes\n\nAnd replace the old file with a logo",
+        );
+        child.write_object(&git_project).unwrap();
+
+        let detail = git_project.commit_detail(&child.get_hash()).unwrap();
+        assert_eq!(detail.get_commit(), &child);
+        assert_eq!(
+            detail.get_parent_subjects(),
+            &child.parent_subjects(&git_project).unwrap()
+        );
+
+        let files = child
+            .changes(&git_project)
+            .unwrap()
+            .iter()
+            .map(|change| FileStat::from_change(&git_project, change).unwrap())
+            .collect::<Vec<FileStat>>();
+        assert_eq!(detail.get_files(), &files);
+        assert_eq!(detail.get_stat(), &DiffStat::from_files(&files));
+
+        assert_eq!(
+            detail
+                .get_files()
+                .iter()
+                .map(|file| (
+                    file.get_path().as_str(),
+                    file.get_insertions(),
+                    file.get_deletions(),
+                    file.is_binary()
+                ))
+                .collect::<Vec<(&str, usize, usize, bool)>>(),
+            vec![
+                ("logo.png", 0, 0, true),
+                ("notes.txt", 2, 1, false),
+                ("old.txt", 0, 1, false),
+            ]
+        );
+        assert_eq!(detail.get_stat().get_files_changed(), 3);
+        assert_eq!(detail.get_stat().get_insertions(), 2);
+        assert_eq!(detail.get_stat().get_deletions(), 2);
+    }
+
+    #[test]
+    fn test_git_project_resolve_revspec_at() {
+        let folder = TempDir::new("test_git_project_resolve_revspec_at").unwrap();
+        let test_git_folder = folder.path().to_str().unwrap();
+
+        create_sample_git_folder(test_git_folder);
+        let git_project = open_git_project(test_git_folder).unwrap();
+
+        let author = GitCommitAuthor::new(
+            GitUser::new("Test User".to_string(), "test.user@email.com".to_string()),
+            100,
+            "+0000".to_string(),
+        );
+        let first = GitCommit::new("tree", &[], author.clone(), author.clone(), "First");
+        let second = GitCommit::new(
+            "tree",
+            &[first.get_hash()],
+            author.clone(),
+            author,
+            "Second",
+        );
+        first.write_object(&git_project).unwrap();
+        second.write_object(&git_project).unwrap();
+
+        create_local_branch(test_git_folder, "main", &format!("{}\n", second.get_hash()));
+        fs::write(
+            format!("{}/{}/HEAD", test_git_folder, GIT_FOLDER),
+            "ref: refs/heads/main\n",
+        )
+        .unwrap();
+
+        assert_eq!(git_project.resolve_revspec("@"), Ok(second.get_hash()));
+        assert_eq!(git_project.resolve_revspec("@~1"), Ok(first.get_hash()));
+        assert_eq!(git_project.resolve_revspec("@^"), Ok(first.get_hash()));
+        assert_eq!(git_project.resolve_revspec("main^0"), Ok(second.get_hash()));
+
+        // Navigating from an annotated tag starts at the commit it points at
+        let tag = GitTag::new(&second.get_hash(), Header::Commit, "v1.0", None, "v1.0\n");
+        tag.write_object(&git_project).unwrap();
+        create_tag(test_git_folder, "v1.0", &tag.get_hash());
+        assert_eq!(git_project.resolve_revspec("v1.0"), Ok(tag.get_hash()));
+        assert_eq!(git_project.resolve_revspec("v1.0^0"), Ok(second.get_hash()));
+        assert_eq!(git_project.resolve_revspec("v1.0~1"), Ok(first.get_hash()));
+        assert_eq!(git_project.resolve_revspec("v1.0^"), Ok(first.get_hash()));
+        assert_eq!(
+            git_project.resolve_revspec("@~2"),
+            Err(GitObjectError::RefNotFound)
+        );
+        assert_eq!(
+            git_project.resolve_revspec("@{u}"),
+            Err(GitObjectError::RefNotFound)
+        );
+
+        fs::create_dir_all(format!(
+            "{}/{}/refs/remotes/origin",
+            test_git_folder, GIT_FOLDER
+        ))
+        .unwrap();
+        fs::write(
+            format!(
+                "{}/{}/refs/remotes/origin/main",
+                test_git_folder, GIT_FOLDER
+            ),
+            format!("{}\n", first.get_hash()),
+        )
+        .unwrap();
+        fs::write(
+            format!("{}/{}/config", test_git_folder, GIT_FOLDER),
+            "[branch \"main\"]\n\tremote = origin\n\tmerge = refs/heads/main\n",
+        )
+        .unwrap();
+
+        assert_eq!(git_project.resolve_revspec("@{u}"), Ok(first.get_hash()));
+        assert_eq!(
+            git_project.resolve_revspec("main@{upstream}"),
+            Ok(first.get_hash())
+        );
+    }
+
+    #[test]
+    fn test_git_project_resolve_revspec_reflog() {
+        let folder = TempDir::new("test_git_project_resolve_revspec_reflog").unwrap();
+        let test_git_folder = folder.path().to_str().unwrap();
+
+        create_sample_git_folder(test_git_folder);
+        let git_project = open_git_project(test_git_folder).unwrap();
+
+        let hashes = ["1", "2", "3"].map(|n| n.repeat(40));
+        let zero = "0".repeat(40);
+        fs::create_dir_all(format!("{}/{}/logs", test_git_folder, GIT_FOLDER)).unwrap();
+        fs::write(
+            format!("{}/{}/logs/HEAD", test_git_folder, GIT_FOLDER),
+            format!(
+                "{zero} {} Test User <test.user@email.com> 1704067200 +0000\tcommit (initial): First\n\
+                 {} {} Test User <test.user@email.com> 1704153600 +0000\tcommit: Second\n\
+                 {} {} Test User <test.user@email.com> 1704240000 +0000\tcheckout: moving\n",
+                hashes[0], hashes[0], hashes[1], hashes[1], hashes[2]
+            ),
+        )
+        .unwrap();
+
+        assert_eq!(git_project.reflog("HEAD").unwrap().len(), 3);
+        assert_eq!(
+            git_project.resolve_revspec("HEAD@{0}"),
+            Ok(hashes[2].clone())
+        );
+        assert_eq!(
+            git_project.resolve_revspec("HEAD@{1}"),
+            Ok(hashes[1].clone())
+        );
+        assert_eq!(git_project.resolve_revspec("@@{2}"), Ok(hashes[0].clone()));
+        assert_eq!(
+            git_project.resolve_revspec("HEAD@{3}"),
+            Err(GitObjectError::RefNotFound)
+        );
+        assert_eq!(
+            git_project.resolve_revspec(&format!("HEAD@{{{}}}", usize::MAX)),
+            Err(GitObjectError::RefNotFound)
+        );
+        assert_eq!(
+            git_project.resolve_revspec("HEAD@{9223372036854775807.years.ago}"),
+            Err(GitObjectError::RefNotFound)
+        );
+        assert_eq!(
+            git_project.resolve_revspec("HEAD@{2024-01-02 12:00}"),
+            Ok(hashes[1].clone())
+        );
+        assert_eq!(
+            git_project.resolve_revspec("HEAD@{2000-01-01}"),
+            Ok(hashes[0].clone())
+        );
+        assert_eq!(
+            git_project.resolve_revspec("main@{1}"),
+            Err(GitObjectError::RefNotFound)
+        );
+
+        // A file outside logs/ that reads like a reflog is still not one
+        fs::copy(
+            format!("{}/{}/logs/HEAD", test_git_folder, GIT_FOLDER),
+            format!("{}/{}/escape", test_git_folder, GIT_FOLDER),
+        )
+        .unwrap();
+        for revspec in ["refs/../../escape@{0}", "../../escape@{0}"] {
+            assert_eq!(
+                git_project.resolve_revspec(revspec),
+                Err(GitObjectError::RefNotFound)
+            );
+        }
+    }
+
+    #[test]
+    fn test_git_project_blob_size() {
+        let folder = TempDir::new("test_git_project_blob_size").unwrap();
+        let test_git_folder = folder.path().to_str().unwrap();
+
+        create_sample_git_folder(test_git_folder);
+        let git_project = open_git_project(test_git_folder).unwrap();
+
+        let loose_content = b"stored as a loose object".to_vec();
+        let loose = GitBlob::new(loose_content.len(), loose_content.clone());
+        loose.write_object(&git_project).unwrap();
+
+        let packed_content = "stored in a pack\n".repeat(100).into_bytes();
+        let packed_hash = GitBlob::new(packed_content.len(), packed_content.clone()).get_hash();
+        write_pack(
+            &git_project
+                .objects_dir()
+                .join("pack")
+                .join("pack-sizes.idx"),
+            &[(packed_hash.clone(), encode_blob(&packed_content))],
+        );
+
+        assert_eq!(
+            git_project.blob_size(&loose.get_hash()),
+            Ok(loose_content.len() as u64)
+        );
+        assert_eq!(
+            git_project.blob_size(&packed_hash),
+            Ok(packed_content.len() as u64)
+        );
+        assert_eq!(
+            git_project.blob_size(&format!("{:040x}", 1)),
+            Err(GitObjectError::FileReadError)
+        );
+
+        let tree = create_archive_tree(&git_project);
+        assert_eq!(
+            git_project.blob_size(&tree.get_hash()),
+            Err(GitObjectError::InvalidBlobFile)
+        );
+    }
+
+    #[test]
+    fn test_git_project_tags_by_date() {
+        let folder = TempDir::new("test_git_project_tags_by_date").unwrap();
+        let test_git_folder = folder.path().to_str().unwrap();
+
+        create_sample_git_folder(test_git_folder);
+        let git_project = open_git_project(test_git_folder).unwrap();
+
+        let commit_at = |date_seconds: i64| {
+            let author = GitCommitAuthor::new(
+                GitUser::new("Test User".to_string(), "test.user@email.com".to_string()),
+                date_seconds,
+                "+0000".to_string(),
+            );
+            let commit = GitCommit::new("tree", &[], author.clone(), author, "Release\n");
+            commit.write_object(&git_project).unwrap();
+
+            commit.get_hash()
+        };
+        let oldest = commit_at(100);
+        let middle = commit_at(200);
+        let newest = commit_at(300);
+
+        let tag = GitTag::new(&newest, Header::Commit, "v3", None, "Version 3\n");
+        tag.write_object(&git_project).unwrap();
+
+        create_tag(test_git_folder, "v1", &oldest);
+        create_tag(test_git_folder, "v2", &middle);
+        create_tag(test_git_folder, "v3", &tag.get_hash());
+
+        let blob = GitBlob::new(4, b"key\n".to_vec());
+        blob.write_object(&git_project).unwrap();
+        let blob_tag = GitTag::new(&blob.get_hash(), Header::Blob, "key", None, "Key\n");
+        blob_tag.write_object(&git_project).unwrap();
+        create_tag(test_git_folder, "key", &blob_tag.get_hash());
+        create_tag(test_git_folder, "key-lightweight", &blob.get_hash());
+
+        // Broken tags are left out instead of failing the whole list
+        let missing_tag = GitTag::new(&format!("{:040x}", 9), Header::Commit, "gone", None, "");
+        missing_tag.write_object(&git_project).unwrap();
+        create_tag(test_git_folder, "gone", &missing_tag.get_hash());
+        create_tag(test_git_folder, "dangling", &format!("{:040x}", 9));
+        create_tag(test_git_folder, "v4.lock", &newest);
+        fs::write(
+            format!("{}/{}/refs/tags/broken", test_git_folder, GIT_FOLDER),
+            "garbage\n",
+        )
+        .unwrap();
+
+        let tags = git_project.tags_by_date().unwrap();
+        assert_eq!(
+            tags.iter()
+                .map(|tag| (tag.get_name().as_str(), tag.get_commit_time()))
+                .collect::<Vec<_>>(),
+            vec![("v3", 300), ("v2", 200), ("v1", 100)]
+        );
+        assert_eq!(tags[0].get_commit_hash(), &newest);
+    }
+
+    // Writes the object exactly as given, named after the hash of its raw bytes, so
+    // nothing in it comes from the serializers under test
+    fn write_raw_object(git_project: &GitProject, header: Header, payload: &[u8]) -> String {
+        let raw = [
+            format!("{} {}\0", header, payload.len()).as_bytes(),
+            payload,
+        ]
+        .concat();
+        let hash = ObjectFormat::Sha1.hash(&raw);
+
+        let mut zlib = flate2::write::ZlibEncoder::new(Vec::new(), flate2::Compression::default());
+        zlib.write_all(&raw).unwrap();
+
+        let path = ObjectFormat::Sha1.loose_object_path(git_project, &hash);
+        fs::create_dir_all(path.parent().unwrap()).unwrap();
+        fs::write(path, zlib.finish().unwrap()).unwrap();
+
+        hash
+    }
+
+    fn create_fixture_objects(git_project: &GitProject) {
+        let readme = write_raw_object(git_project, Header::Blob, b"# Fixture\n\nSome text\n");
+        let script = write_raw_object(git_project, Header::Blob, b"#!/bin/sh\necho run\n");
+        let bin = write_raw_object(
+            git_project,
+            Header::Tree,
+            &[b"100755 run.sh\0", from_hex(&script).unwrap().as_slice()].concat(),
+        );
+        let root = write_raw_object(
+            git_project,
+            Header::Tree,
+            &[
+                b"100644 README.md\0",
+                from_hex(&readme).unwrap().as_slice(),
+                b"40000 bin\0",
+                from_hex(&bin).unwrap().as_slice(),
+            ]
+            .concat(),
+        );
+
+        let first = write_raw_object(
+            git_project,
+            Header::Commit,
+            format!(
+                "tree {}\nauthor Test User <test.user@email.com> 1704153600 +0200\ncommitter Test User <test.user@email.com> 1704153600 +0200\n\nFirst commit\n",
+                bin
+            )
+            .as_bytes(),
+        );
+        let second = write_raw_object(
+            git_project,
+            Header::Commit,
+            format!(
+                "tree {}\nparent {}\nauthor Test User <test.user@email.com> 1704240000 +0200\ncommitter Other User <other.user@email.com> 1704243600 -0500\n\nAdd the readme\n\nWith a longer body\nover two lines\n",
+                root, first
+            )
+            .as_bytes(),
+        );
+
+        let tag = write_raw_object(
+            git_project,
+            Header::Tag,
+            format!(
+                "object {}\ntype commit\ntag v1.0.0\ntagger Test User <test.user@email.com> 1704250000 +0200\n\nFirst release\n",
+                second
+            )
+            .as_bytes(),
+        );
+
+        // The hashes `git hash-object -w` and `git mktree` give the same objects
+        assert_eq!(
+            [readme, script, bin, root, first, second, tag],
+            [
+                "0d58821a6d7c5ae26ba2d41232a0ac8dc3a31c40",
+                "85ba14df52f8c72688537de6e7555fb402217b1e",
+                "ab9886a4a27110546a3771b2bfc93760bb25f679",
+                "75ec8c731a8bd7f3d8cb4fe6fc24cf957542d022",
+                "f91ea79dc1dcd5783117a288aee0c1021e56596e",
+                "44782f12af05a4575a060d6bf25d9250459b9d0f",
+                "65b9d881a320448cba6d5d9c34504c2263f1da82",
+            ]
+        );
+    }
+
+    #[test]
+    fn test_git_objects_round_trip() {
+        let folder = TempDir::new("test_git_objects_round_trip").unwrap();
+        let test_git_folder = folder.path().to_str().unwrap();
+
+        create_sample_git_folder(test_git_folder);
+        let git_project = open_git_project(test_git_folder).unwrap();
+        create_fixture_objects(&git_project);
+
+        let mut checked = 0;
+        for dir in fs::read_dir(git_project.objects_dir()).unwrap() {
+            let dir = dir.unwrap().path();
+            let prefix = dir.file_name().unwrap().to_str().unwrap().to_string();
+            if prefix.len() != 2 {
+                continue;
+            }
+
+            for file in fs::read_dir(&dir).unwrap() {
+                let hash = format!("{}{}", prefix, file.unwrap().file_name().to_str().unwrap());
+                let (header, _) = peek_object_header(&git_project, &hash).unwrap();
+                let rehashed = match GitObjectType::try_from(header).unwrap() {
+                    GitObjectType::Blob => {
+                        GitBlob::from_hash(&git_project, &hash).map(|o| o.get_hash())
+                    }
+                    GitObjectType::Tree => {
+                        GitTree::from_hash(&git_project, &hash).map(|o| o.get_hash())
+                    }
+                    GitObjectType::Commit => {
+                        GitCommit::from_hash(&git_project, &hash).map(|o| o.get_hash())
+                    }
+                    GitObjectType::Tag => {
+                        GitTag::from_hash(&git_project, &hash).map(|o| o.get_hash())
+                    }
+                };
+
+                assert_eq!(rehashed, Ok(hash));
+                checked += 1;
+            }
+        }
+
+        assert_eq!(checked, 7);
+    }
+
+    #[test]
+    fn test_git_project_blob_hash_at() {
+        let folder = TempDir::new("test_git_project_blob_hash_at").unwrap();
+        let test_git_folder = folder.path().to_str().unwrap();
+
+        create_sample_git_folder(test_git_folder);
+        let git_project = open_git_project(test_git_folder).unwrap();
+
+        let tree = create_archive_tree(&git_project);
+        let author = GitCommitAuthor::new(
+            GitUser::new("Test User".to_string(), "test.user@email.com".to_string()),
+            100,
+            "+0000".to_string(),
+        );
+        let commit = GitCommit::new(&tree.get_hash(), &[], author.clone(), author, "Files\n");
+        commit.write_object(&git_project).unwrap();
+
+        let script = GitBlob::new(10, b"echo hello".to_vec());
+        assert_eq!(
+            git_project.blob_hash_at(&commit.get_hash(), "bin/run.sh"),
+            Ok(Some(script.get_hash()))
+        );
+        assert_eq!(
+            git_project.blob_hash_at(&commit.get_hash(), "bin/missing.sh"),
+            Ok(None)
+        );
+        assert_eq!(
+            git_project.blob_hash_at(&commit.get_hash(), "README.md/nested"),
+            Ok(None)
+        );
+        assert_eq!(
+            git_project.blob_hash_at(&commit.get_hash(), "bin"),
+            Err(GitObjectError::InvalidBlobFile)
+        );
+    }
+
+    #[test]
+    fn test_git_project_diff_staged() {
+        let folder = TempDir::new("test_git_project_diff_staged").unwrap();
+        let test_git_folder = folder.path().to_str().unwrap();
+
+        create_sample_git_folder(test_git_folder);
+        fs::write(
+            format!("{}/{}/HEAD", test_git_folder, GIT_FOLDER),
+            "ref: refs/heads/main\n",
+        )
+        .unwrap();
+        let git_project = open_git_project(test_git_folder).unwrap();
+
+        let tree = create_archive_tree(&git_project);
+        let author = GitCommitAuthor::new(
+            GitUser::new("Test User".to_string(), "test.user@email.com".to_string()),
+            100,
+            "+0000".to_string(),
+        );
+        let commit = GitCommit::new(&tree.get_hash(), &[], author.clone(), author, "Files\n");
+        commit.write_object(&git_project).unwrap();
+        create_local_branch(test_git_folder, "main", &commit.get_hash());
+
+        // README.md is modified, bin/run.sh is removed, link is unchanged and new.txt is new
+        let readme = GitBlob::new(7, b"changed".to_vec());
+        let added = GitBlob::new(3, b"new".to_vec());
+        let link = tree.get_entry_by_name("link").unwrap().hash.clone();
+        fs::write(
+            format!("{}/{}/{}", test_git_folder, GIT_FOLDER, INDEX_FILE),
+            create_index_file(
+                vec![
+                    (0o100644, &readme.get_hash(), "README.md", false),
+                    (0o120000, &link, "link", false),
+                    (0o100644, &added.get_hash(), "new.txt", false),
+                ],
+                vec![],
+                ObjectFormat::Sha1,
+            ),
+        )
+        .unwrap();
+
+        let changes = git_project.diff_staged().unwrap();
+        assert_eq!(
+            changes
+                .iter()
+                .map(|change| (change.get_path().as_str(), change.get_kind().clone()))
+                .collect::<Vec<_>>(),
+            vec![
+                ("README.md", ChangeKind::Modified),
+                ("bin/run.sh", ChangeKind::Deleted),
+                ("new.txt", ChangeKind::Added),
+            ]
+        );
+        assert_eq!(changes[0].get_new_hash(), Some(&readme.get_hash()));
+        assert_eq!(changes[2].get_new_hash(), Some(&added.get_hash()));
+    }
+
+    #[test]
+    fn test_git_project_diff_staged_unmerged() {
+        let folder = TempDir::new("test_git_project_diff_staged_unmerged").unwrap();
+        let test_git_folder = folder.path().to_str().unwrap();
+
+        create_sample_git_folder(test_git_folder);
+        fs::write(
+            format!("{}/{}/HEAD", test_git_folder, GIT_FOLDER),
+            "ref: refs/heads/main\n",
+        )
+        .unwrap();
+        let git_project = open_git_project(test_git_folder).unwrap();
+
+        let tree = create_archive_tree(&git_project);
+        let author = GitCommitAuthor::new(
+            GitUser::new("Test User".to_string(), "test.user@email.com".to_string()),
+            100,
+            "+0000".to_string(),
+        );
+        let commit = GitCommit::new(&tree.get_hash(), &[], author.clone(), author, "Files\n");
+        commit.write_object(&git_project).unwrap();
+        create_local_branch(test_git_folder, "main", &commit.get_hash());
+
+        // The merge base of README.md is the HEAD version, and other.txt was added on
+        // both sides, so neither may be diffed from one of its stages
+        let base = tree.get_entry_by_name("README.md").unwrap().hash.clone();
+        let script = GitBlob::new(10, b"echo hello".to_vec()).get_hash();
+        let link = tree.get_entry_by_name("link").unwrap().hash.clone();
+        let ours = GitBlob::new(4, b"ours".to_vec()).get_hash();
+        let theirs = GitBlob::new(6, b"theirs".to_vec()).get_hash();
+        fs::write(
+            format!("{}/{}/{}", test_git_folder, GIT_FOLDER, INDEX_FILE),
+            create_unmerged_index_file(
+                vec![
+                    (0o100644, &base, "README.md", 1),
+                    (0o100644, &ours, "README.md", 2),
+                    (0o100644, &theirs, "README.md", 3),
+                    (0o100755, &script, "bin/run.sh", 0),
+                    (0o120000, &link, "link", 0),
+                    (0o100644, &ours, "other.txt", 2),
+                    (0o100644, &theirs, "other.txt", 3),
+                ],
+                ObjectFormat::Sha1,
+            ),
+        )
+        .unwrap();
+
+        let changes = git_project.diff_staged().unwrap();
+        assert_eq!(
+            changes
+                .iter()
+                .map(|change| (change.get_path().as_str(), change.get_kind().clone()))
+                .collect::<Vec<_>>(),
+            vec![
+                ("README.md", ChangeKind::Unmerged),
+                ("other.txt", ChangeKind::Unmerged),
+            ]
+        );
+        assert_eq!(changes[0].get_new_hash(), None);
+    }
+
+    #[test]
+    fn test_git_project_read_index() {
+        let folder = TempDir::new("test_git_project_read_index").unwrap();
+        let test_git_folder = folder.path().to_str().unwrap();
+
+        create_sample_git_folder(test_git_folder);
+        let git_project = open_git_project(test_git_folder).unwrap();
+
+        // Only a missing index is an empty one, one that can't be read is an error
+        assert_eq!(GitIndex::read(&git_project), Ok(GitIndex::new()));
+
+        fs::create_dir(format!("{}/{}/{}", test_git_folder, GIT_FOLDER, INDEX_FILE)).unwrap();
+        assert_eq!(
+            GitIndex::read(&git_project),
+            Err(GitObjectError::FileReadError)
+        );
+        assert_eq!(git_project.status(), Err(GitObjectError::FileReadError));
+    }
+
+    #[test]
+    fn test_git_project_status_sparse_and_conflicted() {
+        let folder = TempDir::new("test_git_project_status_sparse_and_conflicted").unwrap();
+        let test_git_folder = folder.path().to_str().unwrap();
+
+        create_sample_git_folder(test_git_folder);
+        fs::write(
+            format!("{}/{}/HEAD", test_git_folder, GIT_FOLDER),
+            "ref: refs/heads/main\n",
+        )
+        .unwrap();
+        let git_project = open_git_project(test_git_folder).unwrap();
+
+        let tree = create_archive_tree(&git_project);
+        let author = GitCommitAuthor::new(
+            GitUser::new("Test User".to_string(), "test.user@email.com".to_string()),
+            100,
+            "+0000".to_string(),
+        );
+        let commit = GitCommit::new(&tree.get_hash(), &[], author.clone(), author, "Files\n");
+        commit.write_object(&git_project).unwrap();
+        create_local_branch(test_git_folder, "main", &commit.get_hash());
+
+        // bin/ is outside the sparse checkout, and link is a plain file holding the symlink
+        // target so that it hashes the same
+        fs::write(format!("{}/README.md", test_git_folder), "readme").unwrap();
+        fs::write(format!("{}/link", test_git_folder), "README.md").unwrap();
+        let readme = tree.get_entry_by_name("README.md").unwrap().hash.clone();
+        let link = tree.get_entry_by_name("link").unwrap().hash.clone();
+        let bin = tree.get_entry_by_name("bin").unwrap().hash.clone();
+        let index_path = format!("{}/{}/{}", test_git_folder, GIT_FOLDER, INDEX_FILE);
+        let write_index = |bin: &str| {
+            fs::write(
+                &index_path,
+                create_index_file(
+                    vec![
+                        (0o100644, &readme, "README.md", false),
+                        (0o040000, bin, "bin/", true),
+                        (0o120000, &link, "link", false),
+                    ],
+                    vec![],
+                    ObjectFormat::Sha1,
+                ),
+            )
+            .unwrap();
+        };
+
+        write_index(&bin);
+        assert_eq!(git_project.status(), Ok(vec![]));
+
+        let script = GitBlob::new(9, b"echo bye\n".to_vec());
+        script.write_object(&git_project).unwrap();
+        let mut changed_bin = GitTree::new();
+        changed_bin
+            .add_entry(
+                GitTreeMode::Executable,
+                script.get_hash(),
+                "run.sh".to_string(),
+            )
+            .unwrap();
+        changed_bin.write_object(&git_project).unwrap();
+
+        write_index(&changed_bin.get_hash());
+        let status = git_project.status().unwrap();
+        assert_eq!(
+            status
+                .iter()
+                .map(|entry| (
+                    entry.get_path().as_str(),
+                    entry.get_staged(),
+                    entry.get_unstaged()
+                ))
+                .collect::<Vec<_>>(),
+            vec![("bin/run.sh", Some(&GitFileState::Modified), None)]
+        );
+
+        // Every stage of a conflicted path is reported once, and its file on disk is
+        // not untracked
+        fs::write(format!("{}/link", test_git_folder), "conflict").unwrap();
+        fs::write(
+            &index_path,
+            create_unmerged_index_file(
+                vec![
+                    (0o100644, &readme, "README.md", 0),
+                    (0o120000, &link, "link", 1),
+                    (0o100644, &readme, "link", 2),
+                    (0o100644, &script.get_hash(), "link", 3),
+                ],
+                ObjectFormat::Sha1,
+            ),
+        )
+        .unwrap();
+
+        let status = git_project.status().unwrap();
+        assert_eq!(
+            status
+                .iter()
+                .map(|entry| (
+                    entry.get_path().as_str(),
+                    entry.get_staged(),
+                    entry.get_unstaged()
+                ))
+                .collect::<Vec<_>>(),
+            vec![
+                ("bin/run.sh", Some(&GitFileState::Deleted), None),
+                ("link", None, Some(&GitFileState::Conflicted)),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_git_project_status_ignored() {
+        let folder = TempDir::new("test_git_project_status_ignored").unwrap();
+        let test_git_folder = folder.path().to_str().unwrap();
+
+        let git_path = create_sample_git_folder(test_git_folder);
+        fs::write(format!("{}/HEAD", git_path), "ref: refs/heads/main\n").unwrap();
+        fs::create_dir_all(format!("{}/info", git_path)).unwrap();
+        fs::write(format!("{}/info/exclude", git_path), "secret.txt\n").unwrap();
+
+        let files = [
+            (".gitignore", "target/\n*.log\n!keep.log\n"),
+            ("debug.log", "debug"),
+            ("keep.log", "keep"),
+            ("secret.txt", "secret"),
+            ("src/.gitignore", "generated.rs\n"),
+            ("src/generated.rs", "generated"),
+            ("src/main.rs", "fn main() {}"),
+            ("target/out.bin", "out"),
+            ("target/tracked.txt", "tracked"),
+        ];
+        for (path, content) in files {
+            let path = Path::new(test_git_folder).join(path);
+            fs::create_dir_all(path.parent().unwrap()).unwrap();
+            fs::write(path, content).unwrap();
+        }
+
+        // A tracked file is reported even inside an ignored directory
+        let tracked = GitBlob::new(7, b"tracked".to_vec());
+        fs::write(
+            format!("{}/{}", git_path, INDEX_FILE),
+            create_index_file(
+                vec![(0o100644, &tracked.get_hash(), "target/tracked.txt", false)],
+                vec![],
+                ObjectFormat::Sha1,
+            ),
+        )
+        .unwrap();
+        let git_project = open_git_project(test_git_folder).unwrap();
+
+        let status = git_project.status().unwrap();
+        assert_eq!(
+            status
+                .iter()
+                .map(|entry| (
+                    entry.get_path().as_str(),
+                    entry.get_staged(),
+                    entry.get_unstaged()
+                ))
+                .collect::<Vec<_>>(),
+            vec![
+                (".gitignore", None, Some(&GitFileState::Untracked)),
+                ("keep.log", None, Some(&GitFileState::Untracked)),
+                ("src/.gitignore", None, Some(&GitFileState::Untracked)),
+                ("src/main.rs", None, Some(&GitFileState::Untracked)),
+                ("target/tracked.txt", Some(&GitFileState::Added), None),
+            ]
+        );
+
+        fs::write(format!("{}/target/tracked.txt", test_git_folder), "changed").unwrap();
+        let status = git_project.status().unwrap();
+        assert_eq!(
+            status.last().unwrap().get_unstaged(),
+            Some(&GitFileState::Modified)
+        );
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_git_project_status_stat_data() {
+        let folder = TempDir::new("test_git_project_status_stat_data").unwrap();
+        let test_git_folder = folder.path().to_str().unwrap();
+
+        let git_path = create_sample_git_folder(test_git_folder);
+        fs::write(format!("{}/HEAD", git_path), "ref: refs/heads/main\n").unwrap();
+        let file_path = format!("{}/a.txt", test_git_folder);
+        fs::write(&file_path, "a").unwrap();
+        fs::File::options()
+            .write(true)
+            .open(&file_path)
+            .unwrap()
+            .set_modified(SystemTime::now() - Duration::from_secs(60))
+            .unwrap();
+
+        // The index names another blob, so a file that was hashed would show as modified
+        let staged = GitBlob::new(1, b"b".to_vec());
+        let mut index = create_index_file(
+            vec![(0o100644, &staged.get_hash(), "a.txt", false)],
+            vec![],
+            ObjectFormat::Sha1,
+        );
+        crate::git::git_index::test_utils::stat_first_entry(
+            &mut index,
+            &fs::metadata(&file_path).unwrap(),
+            ObjectFormat::Sha1,
+        );
+        fs::write(format!("{}/{}", git_path, INDEX_FILE), index).unwrap();
+        let git_project = open_git_project(test_git_folder).unwrap();
+
+        let unstaged = || {
+            git_project
+                .status()
+                .unwrap()
+                .iter()
+                .map(|entry| entry.get_unstaged().cloned())
+                .collect::<Vec<_>>()
+        };
+        assert_eq!(unstaged(), vec![None]);
+
+        // Rewriting the file with the same size still changes its modification time, so
+        // it is hashed again
+        fs::write(&file_path, "a").unwrap();
+        assert_eq!(unstaged(), vec![Some(GitFileState::Modified)]);
+    }
+
+    #[test]
+    fn test_git_project_packs_cached() {
+        let folder = TempDir::new("test_git_project_packs_cached").unwrap();
+        let test_git_folder = folder.path().to_str().unwrap();
+
+        create_sample_git_folder(test_git_folder);
+        let git_project = open_git_project(test_git_folder).unwrap();
+
+        let pack_dir = git_project.objects_dir().join("pack");
+        let content = b"stored in a pack".to_vec();
+        let hash = GitBlob::new(content.len(), content.clone()).get_hash();
+        let index_path = pack_dir.join("pack-cached.idx");
+        write_pack(&index_path, &[(hash.clone(), encode_blob(&content))]);
+
+        // A folder changed just now may change again without a new modification time
+        let packs = git_project.packs().unwrap();
+        assert!(!Arc::ptr_eq(&packs[0], &git_project.packs().unwrap()[0]));
+
+        fs::File::open(&pack_dir)
+            .unwrap()
+            .set_modified(SystemTime::now() - Duration::from_secs(60))
+            .unwrap();
+        let packs = git_project.packs().unwrap();
+        for _ in 0..100 {
+            assert!(Arc::ptr_eq(&packs[0], &git_project.packs().unwrap()[0]));
+        }
+        assert_eq!(git_project.blob_size(&hash), Ok(content.len() as u64));
+
+        // New and removed packs show up without invalidating anything
+        let added_content = b"stored in another pack".to_vec();
+        let added_hash = GitBlob::new(added_content.len(), added_content.clone()).get_hash();
+        write_pack(
+            &pack_dir.join("pack-added.idx"),
+            &[(added_hash.clone(), encode_blob(&added_content))],
+        );
+        assert_eq!(
+            git_project.blob_size(&added_hash),
+            Ok(added_content.len() as u64)
+        );
+
+        fs::remove_file(&index_path).unwrap();
+        assert_eq!(
+            git_project.blob_size(&hash),
+            Err(GitObjectError::FileReadError)
+        );
+    }
+
+    #[test]
+    fn test_git_project_branches_containing() {
+        let folder = TempDir::new("test_git_project_branches_containing").unwrap();
+        let test_git_folder = folder.path().to_str().unwrap();
+
+        create_sample_git_folder(test_git_folder);
+        let git_project = open_git_project(test_git_folder).unwrap();
+
+        let author = GitCommitAuthor::new(
+            GitUser::new("Test User".to_string(), "test.user@email.com".to_string()),
+            100,
+            "+0000".to_string(),
+        );
+        let commit = |parents: &[String], message: &str| {
+            let commit = GitCommit::new("tree", parents, author.clone(), author.clone(), message);
+            commit.write_object(&git_project).unwrap();
+
+            commit.get_hash()
+        };
+        let first = commit(&[], "First");
+        let second = commit(std::slice::from_ref(&first), "Second");
+        let third = commit(std::slice::from_ref(&second), "Third");
+        let unrelated = commit(&[], "Unrelated");
+
+        create_local_branch(test_git_folder, "main", &third);
+        create_local_branch(test_git_folder, "feature/second", &second);
+        create_local_branch(test_git_folder, "old", &first);
+        create_local_branch(test_git_folder, "orphan", &unrelated);
+
+        assert_eq!(
+            git_project.branches_containing(&second),
+            Ok(vec!["feature/second".to_string(), "main".to_string()])
+        );
+        assert_eq!(
+            git_project.branches_containing(&first),
+            Ok(vec![
+                "feature/second".to_string(),
+                "main".to_string(),
+                "old".to_string()
+            ])
+        );
+        assert_eq!(git_project.is_ancestor_of(&first, &third), Ok(true));
+        assert_eq!(git_project.is_ancestor_of(&third, &first), Ok(false));
+    }
+
+    #[test]
+    fn test_open_git_project_missing_objects() {
+        let folder = TempDir::new("test_open_git_project_missing_objects").unwrap();
+        let test_git_folder = folder.path().to_str().unwrap();
+
+        let git_path = format!("{}/{}", test_git_folder, GIT_FOLDER);
+        fs::create_dir(&git_path).unwrap();
+        fs::write(format!("{}/HEAD", git_path), "ref: refs/heads/main\n").unwrap();
+
+        assert_eq!(
+            open_git_project(test_git_folder),
+            Err(GitError::CorruptRepository(vec![
+                GitFolders::OBJECTS.to_string(),
+                GitFolders::REFS.to_string()
+            ]))
+        );
+
+        fs::create_dir_all(format!("{}/{}", git_path, GitFolders::REFS)).unwrap();
+        assert_eq!(
+            open_git_project(test_git_folder),
+            Err(GitError::CorruptRepository(vec![
+                GitFolders::OBJECTS.to_string()
+            ]))
+        );
+    }
+
+    #[test]
+    fn test_git_project_all_refs() {
+        let folder = TempDir::new("test_git_project_all_refs").unwrap();
+        let test_git_folder = folder.path().to_str().unwrap();
+
+        let git_path = create_sample_git_folder(test_git_folder);
+        let git_project = open_git_project(test_git_folder).unwrap();
+
+        let author = GitCommitAuthor::new(
+            GitUser::new("Test User".to_string(), "test.user@email.com".to_string()),
+            100,
+            "+0000".to_string(),
+        );
+        let first = GitCommit::new("tree", &[], author.clone(), author.clone(), "First");
+        first.write_object(&git_project).unwrap();
+        let first = first.get_hash();
+        let second = GitCommit::new(
+            "tree",
+            std::slice::from_ref(&first),
+            author.clone(),
+            author,
+            "Second",
+        );
+        second.write_object(&git_project).unwrap();
+        let second = second.get_hash();
+        let packed_tag = GitTag::new(&second, Header::Commit, "v2", None, "Version 2\n");
+        packed_tag.write_object(&git_project).unwrap();
+        let loose_tag = GitTag::new(&first, Header::Commit, "v3", None, "Version 3\n");
+        loose_tag.write_object(&git_project).unwrap();
+
+        create_local_branch(test_git_folder, "main", &second);
+        create_tag(test_git_folder, "v1", &first);
+        create_tag(test_git_folder, "v3", &loose_tag.get_hash());
+        fs::create_dir_all(format!("{}/refs/remotes/origin", git_path)).unwrap();
+        fs::write(format!("{}/refs/remotes/origin/main", git_path), &first).unwrap();
+        fs::write(
+            format!("{}/refs/remotes/origin/HEAD", git_path),
+            "ref: refs/remotes/origin/main\n",
+        )
+        .unwrap();
+        fs::create_dir_all(format!("{}/refs/notes", git_path)).unwrap();
+        fs::write(format!("{}/refs/notes/commits", git_path), &second).unwrap();
+
+        // A lock file, a malformed ref and a tag of a missing object don't hide the rest
+        let missing = format!("{:040x}", 9);
+        fs::write(format!("{}/refs/heads/x.lock", git_path), &first).unwrap();
+        fs::write(format!("{}/refs/heads/broken", git_path), "garbage\n").unwrap();
+        create_tag(test_git_folder, "v4", &missing);
+        fs::write(
+            format!("{}/packed-refs", git_path),
+            format!(
+                "# pack-refs with: peeled fully-peeled sorted\n{first} refs/heads/main\n{first} refs/heads/old\n{second} refs/remotes/origin/dev\n{second} refs/stash\n{tag} refs/tags/v2\n^{second}\n",
+                first = first,
+                second = second,
+                tag = packed_tag.get_hash()
+            ),
+        )
+        .unwrap();
+
+        let refs = git_project.all_refs().unwrap();
+        assert_eq!(
+            refs.iter()
+                .map(|info| (
+                    info.get_name().as_str(),
+                    info.get_target().as_str(),
+                    info.get_peeled().map(|peeled| peeled.as_str()),
+                    info.get_category().clone()
+                ))
+                .collect::<Vec<_>>(),
+            vec![
+                (
+                    "refs/heads/main",
+                    second.as_str(),
+                    None,
+                    RefCategory::Branch
+                ),
+                ("refs/heads/old", first.as_str(), None, RefCategory::Branch),
+                (
+                    "refs/notes/commits",
+                    second.as_str(),
+                    None,
+                    RefCategory::Other
+                ),
+                (
+                    "refs/remotes/origin/HEAD",
+                    first.as_str(),
+                    None,
+                    RefCategory::Remote
+                ),
+                (
+                    "refs/remotes/origin/dev",
+                    second.as_str(),
+                    None,
+                    RefCategory::Remote
+                ),
+                (
+                    "refs/remotes/origin/main",
+                    first.as_str(),
+                    None,
+                    RefCategory::Remote
+                ),
+                ("refs/stash", second.as_str(), None, RefCategory::Other),
+                (
+                    "refs/tags/v1",
+                    first.as_str(),
+                    Some(first.as_str()),
+                    RefCategory::Tag
+                ),
+                (
+                    "refs/tags/v2",
+                    packed_tag.get_hash().as_str(),
+                    Some(second.as_str()),
+                    RefCategory::Tag
+                ),
+                (
+                    "refs/tags/v3",
+                    loose_tag.get_hash().as_str(),
+                    Some(first.as_str()),
+                    RefCategory::Tag
+                ),
+                ("refs/tags/v4", missing.as_str(), None, RefCategory::Tag),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_git_tree_find_paths_for_hash() {
+        let folder = TempDir::new("test_git_tree_find_paths_for_hash").unwrap();
+        let test_git_folder = folder.path().to_str().unwrap();
+
+        create_sample_git_folder(test_git_folder);
+        let git_project = open_git_project(test_git_folder).unwrap();
+
+        let shared = GitBlob::new(7, b"shared\n".to_vec());
+        shared.write_object(&git_project).unwrap();
+        let other = GitBlob::new(6, b"other\n".to_vec());
+        other.write_object(&git_project).unwrap();
+
+        let mut docs = GitTree::new();
+        docs.add_entry(GitTreeMode::File, shared.get_hash(), "LICENSE".to_string())
+            .unwrap();
+        docs.add_entry(GitTreeMode::File, other.get_hash(), "notes.txt".to_string())
+            .unwrap();
+        docs.write_object(&git_project).unwrap();
+
+        let mut root = GitTree::new();
+        root.add_entry(GitTreeMode::File, shared.get_hash(), "LICENSE".to_string())
+            .unwrap();
+        root.add_entry(GitTreeMode::Tree, docs.get_hash(), "docs".to_string())
+            .unwrap();
+        root.write_object(&git_project).unwrap();
+
+        assert_eq!(
+            root.find_paths_for_hash(&git_project, &shared.get_hash()),
+            Ok(vec!["LICENSE".to_string(), "docs/LICENSE".to_string()])
+        );
+        assert_eq!(
+            root.find_paths_for_hash(&git_project, &other.get_hash()),
+            Ok(vec!["docs/notes.txt".to_string()])
+        );
+        assert_eq!(
+            root.find_paths_for_hash(&git_project, &format!("{:040x}", 0)),
+            Ok(vec![])
+        );
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_open_git_project_through_symlink() {
+        let folder = TempDir::new("test_open_git_project_through_symlink").unwrap();
+        let real_folder = folder.path().join("real");
+        fs::create_dir(&real_folder).unwrap();
+        let real_folder = real_folder.to_str().unwrap();
+
+        create_sample_git_folder(real_folder);
+        let git_project = open_git_project(real_folder).unwrap();
+        let blob = GitBlob::new(4, b"test".to_vec());
+        blob.write_object(&git_project).unwrap();
+
+        // Both paths resolve to the same project, which can only be in the database once
+        assert_eq!(
+            open_git_project(real_folder),
+            Err(GitError::DatabaseSaveError)
+        );
+        remove_database_project(git_project.clone()).unwrap();
+
+        let linked_folder = folder.path().join("linked");
+        std::os::unix::fs::symlink(real_folder, &linked_folder).unwrap();
+        let linked_project = open_git_project(linked_folder.to_str().unwrap()).unwrap();
+
+        assert_eq!(linked_project.get_directory(), git_project.get_directory());
+        assert_eq!(linked_project.objects_dir(), git_project.objects_dir());
+        assert_eq!(
+            GitBlob::from_hash(&linked_project, &blob.get_hash()),
+            GitBlob::from_hash(&git_project, &blob.get_hash())
+        );
+        assert!(GitBlob::from_hash(&linked_project, &blob.get_hash()).is_ok());
+        assert_eq!(
+            GitProject::open(linked_folder.to_str().unwrap())
+                .unwrap()
+                .get_directory(),
+            git_project.get_directory()
+        );
+
+        let loop_start = folder.path().join("loop_start");
+        let loop_end = folder.path().join("loop_end");
+        std::os::unix::fs::symlink(&loop_end, &loop_start).unwrap();
+        std::os::unix::fs::symlink(&loop_start, &loop_end).unwrap();
+        assert_eq!(
+            open_git_project(loop_start.to_str().unwrap()),
+            Err(GitError::CannotOpenFolder)
+        );
+    }
+
+    #[test]
+    fn test_git_tag_get_target_commit() {
+        let folder = TempDir::new("test_git_tag_get_target_commit").unwrap();
+        let test_git_folder = folder.path().to_str().unwrap();
+
+        create_sample_git_folder(test_git_folder);
+        let git_project = open_git_project(test_git_folder).unwrap();
+
+        let author = GitCommitAuthor::new(
+            GitUser::new("Test User".to_string(), "test.user@email.com".to_string()),
+            100,
+            "+0000".to_string(),
+        );
+        let commit = GitCommit::new("tree", &[], author.clone(), author.clone(), "Release");
+        commit.write_object(&git_project).unwrap();
+        let blob = GitBlob::new(4, b"test".to_vec());
+        blob.write_object(&git_project).unwrap();
+
+        let commit_tag = GitTag::new(
+            &commit.get_hash(),
+            Header::Commit,
+            "v1.0.0",
+            Some(author),
+            "First release\n",
+        );
+        commit_tag.write_object(&git_project).unwrap();
+        let read_tag = GitTag::from_hash(&git_project, &commit_tag.get_hash()).unwrap();
+        assert_eq!(read_tag, commit_tag);
+        assert_eq!(read_tag.get_target_commit(&git_project), Ok(Some(commit)));
+
+        let blob_tag = GitTag::new(&blob.get_hash(), Header::Blob, "key", None, "Key\n");
+        assert_eq!(blob_tag.get_target_commit(&git_project), Ok(None));
+
+        let missing_tag = GitTag::new(&format!("{:040x}", 0), Header::Commit, "gone", None, "");
+        assert_eq!(
+            missing_tag.get_target_commit(&git_project),
+            Err(GitObjectError::FileReadError)
+        );
+    }
+
+    #[test]
+    fn test_git_project_commit_activity() {
+        let folder = TempDir::new("test_git_project_commit_activity").unwrap();
+        let test_git_folder = folder.path().to_str().unwrap();
+
+        create_sample_git_folder(test_git_folder);
+        let git_project = open_git_project(test_git_folder).unwrap();
+
+        // 2024-01-01 is a Monday, the first commit is already on 2024-01-01 in UTC but
+        // still on 2023-12-31 for its author
+        let dates = [
+            (1704069000, "-0100"),
+            (1704103200, "+0000"),
+            (1704110400, "+0000"),
+            (1704189600, "+0000"),
+            (1704369600, "+0000"),
+            (1704373200, "+0000"),
+            (1704376800, "+0000"),
+        ];
+        let mut parents = Vec::new();
+        for (index, (date_seconds, timezone)) in dates.iter().enumerate() {
+            let author = GitCommitAuthor::new(
+                GitUser::new("Test User".to_string(), "test.user@email.com".to_string()),
+                *date_seconds,
+                timezone.to_string(),
+            );
+            let commit = GitCommit::new(
+                "tree",
+                &parents,
+                author.clone(),
+                author,
+                &format!("Commit {}", index),
+            );
+            commit.write_object(&git_project).unwrap();
+            parents = vec![commit.get_hash()];
+        }
+        create_local_branch(test_git_folder, "main", &parents[0]);
+
+        assert_eq!(
+            git_project.commit_activity("main", Bucket::Day),
+            Ok(vec![
+                (NaiveDate::from_ymd_opt(2023, 12, 31).unwrap(), 1),
+                (NaiveDate::from_ymd_opt(2024, 1, 1).unwrap(), 2),
+                (NaiveDate::from_ymd_opt(2024, 1, 2).unwrap(), 1),
+                (NaiveDate::from_ymd_opt(2024, 1, 4).unwrap(), 3),
+            ])
+        );
+        assert_eq!(
+            git_project.commit_activity("main", Bucket::Week),
+            Ok(vec![
+                (NaiveDate::from_ymd_opt(2023, 12, 25).unwrap(), 1),
+                (NaiveDate::from_ymd_opt(2024, 1, 1).unwrap(), 6),
+            ])
+        );
+    }
+
+    #[test]
+    fn test_git_project_list_branches() {
+        let folder = TempDir::new("test_git_project_list_branches").unwrap();
+        let test_git_folder = folder.path().to_str().unwrap();
+
+        let git_path = create_sample_git_folder(test_git_folder);
+        let main = format!("{:040x}", 1);
+        let feature = format!("{:040x}", 2);
+        let packed = format!("{:040x}", 3);
+        create_local_branch(test_git_folder, "main", &format!("{}\n", main));
+        create_local_branch(test_git_folder, "feature/login", &feature);
+        create_local_branch(test_git_folder, "current", "ref: refs/heads/main\n");
+        create_local_branch(test_git_folder, "dangling", "ref: refs/heads/missing\n");
+        fs::write(
+            format!("{}/packed-refs", git_path),
+            format!("{} refs/heads/packed\n{} refs/heads/main\n", packed, packed),
+        )
+        .unwrap();
+        let git_project = open_git_project(test_git_folder).unwrap();
+
+        let branches = git_project.list_branches().unwrap();
+        assert_eq!(
+            branches
+                .iter()
+                .map(|branch| (
+                    branch.get_name().as_str(),
+                    branch.get_hash().as_str(),
+                    branch.get_symbolic_target().map(|target| target.as_str())
+                ))
+                .collect::<Vec<_>>(),
+            vec![
+                ("current", main.as_str(), Some("refs/heads/main")),
+                ("feature/login", feature.as_str(), None),
+                ("main", main.as_str(), None),
+                ("packed", packed.as_str(), None),
+            ]
+        );
+
+        // Malformed branches and lock files are left out instead of failing the list
+        create_local_branch(test_git_folder, "broken", "not a hash");
+        create_local_branch(test_git_folder, "main.lock", &feature);
+        assert_eq!(git_project.list_branches(), Ok(branches));
+    }
+
+    #[test]
+    fn test_git_project_read_raw_compressed() {
+        let folder = TempDir::new("test_git_project_read_raw_compressed").unwrap();
+        let test_git_folder = folder.path().to_str().unwrap();
+
+        create_sample_git_folder(test_git_folder);
+        let git_project = open_git_project(test_git_folder).unwrap();
+
+        let loose_content = b"stored as a loose object".to_vec();
+        let loose = GitBlob::new(loose_content.len(), loose_content.clone());
+        loose.write_object(&git_project).unwrap();
+        let loose_path = ObjectFormat::Sha1.loose_object_path(&git_project, &loose.get_hash());
+
+        let raw = git_project.read_raw_compressed(&loose.get_hash()).unwrap();
+        assert_eq!(raw, fs::read(loose_path).unwrap());
+        assert_eq!(
+            GitBlob::decode_data_bytes(&raw).unwrap(),
+            b"blob 24\0stored as a loose object"
+        );
+
+        let packed_content = b"stored in a pack".to_vec();
+        let packed_hash = GitBlob::new(packed_content.len(), packed_content.clone()).get_hash();
+        write_pack(
+            &git_project.objects_dir().join("pack").join("pack-raw.idx"),
+            &[(packed_hash.clone(), encode_blob(&packed_content))],
+        );
+
+        let raw = git_project.read_raw_compressed(&packed_hash).unwrap();
+        assert_eq!(
+            GitBlob::decode_data_bytes(&raw).unwrap(),
+            b"blob 16\0stored in a pack"
+        );
+
+        assert_eq!(
+            git_project.read_raw_compressed(&format!("{:040x}", 1)),
+            Err(GitObjectError::FileReadError)
+        );
+        assert_eq!(
+            git_project.read_raw_compressed("1234"),
+            Err(GitObjectError::InvalidHash)
+        );
+    }
+
+    #[test]
+    fn test_git_project_resolve_head() {
+        let folder = TempDir::new("test_git_project_resolve_head").unwrap();
+        let test_git_folder = folder.path().to_str().unwrap();
+
+        let git_path = create_sample_git_folder(test_git_folder);
+        let main = format!("{:040x}", 1);
+        create_local_branch(test_git_folder, "main", &main);
+        let git_project = open_git_project(test_git_folder).unwrap();
+
+        let set_head = |head: &str| {
+            fs::write(format!("{}/HEAD", git_path), head).unwrap();
+            git_project.invalidate(&[PathBuf::from(".git/HEAD")]);
+        };
+
+        set_head("ref: refs/heads/main\n");
+        assert_eq!(
+            git_project.resolve_head(),
+            Ok(HeadState::Branch {
+                name: "main".to_string(),
+                hash: Some(main.clone())
+            })
+        );
+
+        set_head("ref: refs/heads/feature/unborn\n");
+        assert_eq!(
+            git_project.resolve_head(),
+            Ok(HeadState::Branch {
+                name: "feature/unborn".to_string(),
+                hash: None
+            })
+        );
+
+        set_head(&format!("{}\n", main));
+        assert_eq!(git_project.resolve_head(), Ok(HeadState::Detached(main)));
+
+        for head in ["ref: refs/tags/v1\n", "not a hash\n"] {
+            set_head(head);
+            assert_eq!(
+                git_project.resolve_head(),
+                Err(GitObjectError::InvalidRefFile)
+            );
+        }
+    }
+
+    #[test]
+    fn test_git_project_prune_empty_object_dirs() {
+        let folder = TempDir::new("test_git_project_prune_empty_object_dirs").unwrap();
+        let test_git_folder = folder.path().to_str().unwrap();
+
+        create_sample_git_folder(test_git_folder);
+        let git_project = open_git_project(test_git_folder).unwrap();
+
+        let content = b"kept as a loose object".to_vec();
+        let blob = GitBlob::new(content.len(), content);
+        blob.write_object(&git_project).unwrap();
+
+        let objects_dir = git_project.objects_dir();
+        fs::create_dir_all(objects_dir.join("ab")).unwrap();
+        fs::create_dir_all(objects_dir.join("cd")).unwrap();
+        fs::create_dir_all(objects_dir.join("info")).unwrap();
+
+        assert_eq!(git_project.prune_empty_object_dirs(), Ok(2));
+        assert!(!objects_dir.join("ab").exists());
+        assert!(!objects_dir.join("cd").exists());
+        assert!(objects_dir.join("info").is_dir());
+        assert!(objects_dir.join(&blob.get_hash()[..2]).is_dir());
+        assert_eq!(GitBlob::from_hash(&git_project, &blob.get_hash()), Ok(blob));
+
+        assert_eq!(git_project.prune_empty_object_dirs(), Ok(0));
+    }
+
+    #[test]
+    fn test_get_user() {
+        let folder = TempDir::new("test_get_user").unwrap();
+        let test_git_folder = folder.path().to_str().unwrap();
+
+        let git_path = create_sample_git_folder(test_git_folder);
+        let git_project = open_git_project(test_git_folder).unwrap();
+        // Without an identity in the repository the global one is used, if there is one
+        let global_user = GitConfig::read_global().user();
+        assert_eq!(get_user(&git_project), global_user);
+
+        fs::write(
+            format!("{}/config", git_path),
+            "[core]\n\tbare = false\n[user]\n\tname = Test User\n",
+        )
+        .unwrap();
+        assert_eq!(
+            get_user(&git_project),
+            global_user.map(|user| GitUser::new("Test User".to_string(), user.email))
+        );
+
+        fs::write(
+            format!("{}/config", git_path),
+            "[core]\n\tbare = false\n[user]\n\tname = Test User\n\temail = test.user@email.com\n",
+        )
+        .unwrap();
+        assert_eq!(
+            get_user(&git_project),
+            Some(GitUser::new(
+                "Test User".to_string(),
+                "test.user@email.com".to_string()
+            ))
+        );
+    }
+
+    #[test]
+    fn test_git_commit_patch_id() {
+        let folder = TempDir::new("test_git_commit_patch_id").unwrap();
+        let test_git_folder = folder.path().to_str().unwrap();
+
+        create_sample_git_folder(test_git_folder);
+        let git_project = open_git_project(test_git_folder).unwrap();
+
+        let blob = |content: &[u8]| {
+            let blob = GitBlob::new(content.len(), content.to_vec());
+            blob.write_object(&git_project).unwrap();
+            blob.get_hash()
+        };
+        let commit = |entries: Vec<(String, &str)>, parents: &[String], message: &str| {
+            let mut tree = GitTree::new();
+            for (hash, name) in entries {
+                tree.add_entry(GitTreeMode::File, hash, name.to_string())
+                    .unwrap();
+            }
+            tree.write_object(&git_project).unwrap();
+
+            let author = GitCommitAuthor::new(
+                GitUser::new("Test User".to_string(), "test.user@email.com".to_string()),
+                message.len() as i64,
+                "+0000".to_string(),
+            );
+            let commit = GitCommit::new(&tree.get_hash(), parents, author.clone(), author, message);
+            commit.write_object(&git_project).unwrap();
+            commit
+        };
+
+        let base = commit(vec![(blob(b"one\ntwo\n"), "notes.txt")], &[], "Base");
+        let picked = commit(
+            vec![(blob(b"one\ntwo\nthree\n"), "notes.txt")],
+            &[base.get_hash()],
+            "Add three",
+        );
+
+        // The other branch has more lines around the change and another file
+        let other = commit(
+            vec![
+                (blob(b"zero\none\ntwo\n"), "notes.txt"),
+                (blob(b"other\n"), "other.txt"),
+            ],
+            &[base.get_hash()],
+            "Other branch",
+        );
+        let cherry_pick = commit(
+            vec![
+                (blob(b"zero\none\ntwo\n  three\n"), "notes.txt"),
+                (blob(b"other\n"), "other.txt"),
+            ],
+            &[other.get_hash()],
+            "Add three\n\n(cherry picked from commit)",
+        );
+        let different = commit(
+            vec![
+                (blob(b"zero\none\ntwo\nfour\n"), "notes.txt"),
+                (blob(b"other\n"), "other.txt"),
+            ],
+            &[other.get_hash()],
+            "Add four",
+        );
+
+        let patch_id = picked.patch_id(&git_project).unwrap();
+        assert_eq!(patch_id.len(), ObjectFormat::Sha1.hash_hex_len());
+        assert_eq!(cherry_pick.patch_id(&git_project), Ok(patch_id.clone()));
+        assert_ne!(different.patch_id(&git_project).unwrap(), patch_id);
+        assert_ne!(base.patch_id(&git_project).unwrap(), patch_id);
+    }
+
+    #[test]
+    fn test_commit_walker() {
+        let folder = TempDir::new("test_commit_walker").unwrap();
+        let test_git_folder = folder.path().to_str().unwrap();
+
+        create_sample_git_folder(test_git_folder);
+        let git_project = open_git_project(test_git_folder).unwrap();
+
+        let commit = |message: &str, parents: &[String], date: i64| {
+            let author = GitCommitAuthor::new(
+                GitUser::new("Test User".to_string(), "test.user@email.com".to_string()),
+                date,
+                "+0000".to_string(),
+            );
+            let commit = GitCommit::new("tree", parents, author.clone(), author, message);
+            commit.write_object(&git_project).unwrap();
+            commit.get_hash()
+        };
+
+        //   root - a ----- b - merge
+        //      \     \         /
+        //       c --- d -----'
+        let root = commit("root", &[], 100);
+        let a = commit("a", std::slice::from_ref(&root), 200);
+        let c = commit("c", std::slice::from_ref(&root), 300);
+        let b = commit("b", std::slice::from_ref(&a), 500);
+        let d = commit("d", &[c.clone(), a.clone()], 400);
+        let merge = commit("merge", &[b.clone(), d.clone()], 600);
+
+        let walked = CommitWalker::new(&git_project, &merge)
+            .map(|commit| commit.unwrap().get_hash())
+            .collect::<Vec<String>>();
+        assert_eq!(walked, vec![merge, b, d.clone(), c, a, root]);
+
+        // Commits with the same date come out in the order they were reached
+        let first = commit("first", &[], 700);
+        let second = commit("second", &[], 700);
+        let tie = commit("tie", &[first.clone(), second.clone()], 800);
+        let walked = CommitWalker::new(&git_project, &tie)
+            .map(|commit| commit.unwrap().get_hash())
+            .collect::<Vec<String>>();
+        assert_eq!(walked, vec![tie, first, second]);
+
+        let missing = format!("{:040x}", 1);
+        let broken = commit("broken", &[missing, d.clone()], 900);
+        let mut walker = CommitWalker::new(&git_project, &broken);
+        assert_eq!(walker.next().unwrap().unwrap().get_hash(), broken);
+        assert_eq!(walker.next(), Some(Err(GitObjectError::FileReadError)));
+        assert_eq!(walker.next().unwrap().unwrap().get_hash(), d);
+        assert_eq!(walker.count(), 3);
+
+        let mut walker = CommitWalker::new(&git_project, &format!("{:040x}", 2));
+        assert_eq!(walker.next(), Some(Err(GitObjectError::FileReadError)));
+        assert_eq!(walker.next(), None);
+    }
+
+    #[test]
+    fn test_git_project_refs_trailing_whitespace() {
+        let folder = TempDir::new("test_git_project_refs_trailing_whitespace").unwrap();
+        let test_git_folder = folder.path().to_str().unwrap();
+
+        let git_path = create_sample_git_folder(test_git_folder);
+        let mut git_project = open_git_project(test_git_folder).unwrap();
+
+        let author = GitCommitAuthor::new(
+            GitUser::new("Test User".to_string(), "test.user@email.com".to_string()),
+            100,
+            "+0000".to_string(),
+        );
+        let commit = GitCommit::new("tree", &[], author.clone(), author, "Edited refs");
+        commit.write_object(&git_project).unwrap();
+        let hash = commit.get_hash();
+
+        create_local_branch(test_git_folder, "main", &format!("{} ", hash));
+        fs::write(format!("{}/HEAD", git_path), "ref: refs/heads/main\r\n").unwrap();
+
+        assert_eq!(
+            git_project.resolve_ref("refs/heads/main"),
+            Ok(Some(hash.clone()))
+        );
+        assert_eq!(git_project.resolve_ref("HEAD"), Ok(Some(hash.clone())));
+        assert_eq!(git_project.head_commit(), Ok(commit));
+        assert_eq!(
+            git_project.resolve_head(),
+            Ok(HeadState::Branch {
+                name: "main".to_string(),
+                hash: Some(hash.clone())
+            })
+        );
+        assert_eq!(git_project.list_branches().unwrap()[0].get_hash(), &hash);
+
+        git_project.fetch_branches(GitBranchType::Local).unwrap();
+        assert!(git_project
+            .get_local_branches()
+            .contains(&GitBranch::new("main".to_string(), hash.clone())));
+
+        fs::write(format!("{}/HEAD", git_path), format!("{}\r\n", hash)).unwrap();
+        git_project.invalidate(&[PathBuf::from(".git/HEAD")]);
+        assert_eq!(git_project.resolve_head(), Ok(HeadState::Detached(hash)));
+    }
+
+    #[test]
+    fn test_git_project_resolve_ref_outside_refs() {
+        let folder = TempDir::new("test_git_project_resolve_ref_outside_refs").unwrap();
+        let test_git_folder = folder.path().to_str().unwrap();
+
+        let git_path = create_sample_git_folder(test_git_folder);
+        let git_project = open_git_project(test_git_folder).unwrap();
+        fs::write(format!("{}/description", git_path), "a repository\n").unwrap();
+
+        for ref_name in ["refs/../description", "description", "refs/heads//main"] {
+            assert_eq!(
+                git_project.resolve_ref(ref_name),
+                Err(GitObjectError::InvalidRefFile)
+            );
+        }
+        assert_eq!(
+            git_project.resolve_revision("refs/../description"),
+            Err(GitObjectError::RefNotFound)
+        );
+
+        // Whatever a ref holds has to be a hash
+        create_local_branch(test_git_folder, "main", "a repository\n");
+        fs::write(format!("{}/ORIG_HEAD", git_path), "ref: description\n").unwrap();
+        fs::write(
+            format!("{}/packed-refs", git_path),
+            "not-a-hash refs/heads/packed\n",
+        )
+        .unwrap();
+        for ref_name in ["refs/heads/main", "ORIG_HEAD", "refs/heads/packed"] {
+            assert_eq!(
+                git_project.resolve_ref(ref_name),
+                Err(GitObjectError::InvalidRefFile)
+            );
+        }
+    }
+
+    #[test]
+    fn test_git_commit_new_objects() {
+        let folder = TempDir::new("test_git_commit_new_objects").unwrap();
+        let test_git_folder = folder.path().to_str().unwrap();
+
+        create_sample_git_folder(test_git_folder);
+        let git_project = open_git_project(test_git_folder).unwrap();
+
+        let blob = |content: &[u8]| {
+            let blob = GitBlob::new(content.len(), content.to_vec());
+            blob.write_object(&git_project).unwrap();
+            blob.get_hash()
+        };
+        let tree = |entries: Vec<(GitTreeMode, String, &str)>| {
+            let mut tree = GitTree::new();
+            for (mode, hash, name) in entries {
+                tree.add_entry(mode, hash, name.to_string()).unwrap();
+            }
+            tree.write_object(&git_project).unwrap();
+            tree.get_hash()
+        };
+        let author = GitCommitAuthor::new(
+            GitUser::new("Test User".to_string(), "test.user@email.com".to_string()),
+            100,
+            "+0000".to_string(),
+        );
+
+        let readme = blob(b"readme\n");
+        let lib = blob(b"lib\n");
+        let docs = tree(vec![(GitTreeMode::File, readme.clone(), "README.md")]);
+        let src = tree(vec![(GitTreeMode::File, lib.clone(), "lib.rs")]);
+        let parent_tree = tree(vec![
+            (GitTreeMode::Tree, docs.clone(), "docs"),
+            (GitTreeMode::Tree, src.clone(), "src"),
+        ]);
+        let parent = GitCommit::new(&parent_tree, &[], author.clone(), author.clone(), "Base");
+        parent.write_object(&git_project).unwrap();
+
+        // The added file goes into src, and a copy of the readme adds no new blob
+        let main = blob(b"main\n");
+        let new_src = tree(vec![
+            (GitTreeMode::File, lib, "lib.rs"),
+            (GitTreeMode::File, main.clone(), "main.rs"),
+            (GitTreeMode::File, readme, "README.md"),
+        ]);
+        let child_tree = tree(vec![
+            (GitTreeMode::Tree, docs, "docs"),
+            (GitTreeMode::Tree, new_src.clone(), "src"),
+        ]);
+        let child = GitCommit::new(
+            &child_tree,
+            &[parent.get_hash()],
+            author.clone(),
+            author,
+            "Add main",
+        );
+
+        assert_eq!(
+            child.new_objects(&git_project),
+            Ok(vec![child_tree, new_src, main])
+        );
+
+        let root_objects = parent.new_objects(&git_project).unwrap();
+        assert_eq!(root_objects.len(), 5);
+        assert!(root_objects.contains(&parent_tree));
+    }
+
+    #[test]
+    fn test_get_user_worktree_config() {
+        let folder = TempDir::new("test_get_user_worktree_config").unwrap();
+        let test_git_folder = folder.path().to_str().unwrap();
+
+        let git_path = create_sample_git_folder(test_git_folder);
+        let git_project = open_git_project(test_git_folder).unwrap();
+
+        fs::write(
+            format!("{}/config.worktree", git_path),
+            "[user]\n\temail = worktree.user@email.com\n",
+        )
+        .unwrap();
+        let config =
+            "[core]\n\tbare = false\n[user]\n\tname = Test User\n\temail = test.user@email.com\n";
+        fs::write(format!("{}/config", git_path), config).unwrap();
+
+        // config.worktree is only read once the extension is enabled
+        assert_eq!(
+            get_user(&git_project),
+            Some(GitUser::new(
+                "Test User".to_string(),
+                "test.user@email.com".to_string()
+            ))
+        );
+
+        fs::write(
+            format!("{}/config", git_path),
+            format!("{}[extensions]\n\tworktreeConfig = true\n", config),
+        )
+        .unwrap();
+        assert_eq!(
+            get_user(&git_project),
+            Some(GitUser::new(
+                "Test User".to_string(),
+                "worktree.user@email.com".to_string()
+            ))
+        );
+    }
+
+    #[test]
+    fn test_diff_blob_summary() {
+        let folder = TempDir::new("test_diff_blob_summary").unwrap();
+        let test_git_folder = folder.path().to_str().unwrap();
+
+        create_sample_git_folder(test_git_folder);
+        let git_project = open_git_project(test_git_folder).unwrap();
+
+        let blob = |content: &[u8]| {
+            let blob = GitBlob::new(content.len(), content.to_vec());
+            blob.write_object(&git_project).unwrap();
+            blob.get_hash()
+        };
+        let old = blob(b"one\ntwo\nthree\nfour\n");
+        let new = blob(b"zero\none\n2\nthree\nfour\nfive\n");
+        let binary = blob(b"\x89PNG\x00\x01");
+
+        assert_eq!(diff_blob_summary(&git_project, &old, &new), Ok((3, 1)));
+        assert_eq!(diff_blob_summary(&git_project, &new, &old), Ok((1, 3)));
+        assert_eq!(diff_blob_summary(&git_project, &old, &old), Ok((0, 0)));
+        assert_eq!(diff_blob_summary(&git_project, &old, &binary), Ok((0, 0)));
+        assert_eq!(
+            diff_blob_summary(&git_project, &old, &format!("{:040x}", 1)),
+            Err(GitObjectError::FileReadError)
+        );
+    }
+
+    #[test]
+    fn test_git_project_write_tree() {
+        let folder = TempDir::new("test_git_project_write_tree").unwrap();
+        let test_git_folder = folder.path().to_str().unwrap();
+
+        create_sample_git_folder(test_git_folder);
+        let git_project = open_git_project(test_git_folder).unwrap();
+
+        let blob = |content: &[u8]| {
+            let blob = GitBlob::new(content.len(), content.to_vec());
+            blob.write_object(&git_project).unwrap();
+            blob.get_hash()
+        };
+        let (f, g, top) = (blob(b"f"), blob(b"g"), blob(b"top"));
+        let index_path = format!("{}/{}/{}", test_git_folder, GIT_FOLDER, INDEX_FILE);
+        let write_index = |extensions: Vec<(&[u8], &[u8])>| {
+            let entries = vec![
+                (0o100644, f.as_str(), "a/b/f", false),
+                (0o100755, g.as_str(), "a/g", false),
+                (0o100644, top.as_str(), "top", false),
+            ];
+            fs::write(
+                &index_path,
+                create_index_file(entries, extensions, ObjectFormat::Sha1),
+            )
+            .unwrap();
+        };
+
+        write_index(vec![]);
+        let root_hash = git_project.write_tree().unwrap();
+        let root = GitTree::from_hash(&git_project, &root_hash).unwrap();
+        assert_eq!(
+            root.entries()
+                .iter()
+                .map(|entry| (entry.name.as_str(), entry.mode.clone()))
+                .collect::<Vec<_>>(),
+            vec![("a", GitTreeMode::Tree), ("top", GitTreeMode::File)]
+        );
+        let a_hash = root.get_entry_by_name("a").unwrap().hash.clone();
+        let a = GitTree::from_hash(&git_project, &a_hash).unwrap();
+        assert_eq!(
+            a.get_entry_by_name("g").unwrap().mode,
+            GitTreeMode::Executable
+        );
+        let b_hash = a.get_entry_by_name("b").unwrap().hash.clone();
+
+        // `a` is still valid in the cache while the root was invalidated, so only the root
+        // is written again and the deleted trees of `a` stay deleted
+        let object_path = |hash: &str| ObjectFormat::Sha1.loose_object_path(&git_project, hash);
+        for hash in [&root_hash, &a_hash, &b_hash] {
+            fs::remove_file(object_path(hash)).unwrap();
+        }
+        let mut extension = b"\0-1 1\na\x002 1\n".to_vec();
+        extension.extend_from_slice(&from_hex(&a_hash).unwrap());
+        extension.extend_from_slice(b"b\x001 0\n");
+        extension.extend_from_slice(&from_hex(&b_hash).unwrap());
+        write_index(vec![(b"TREE", &extension)]);
+
+        assert_eq!(git_project.write_tree(), Ok(root_hash.clone()));
+        assert!(object_path(&root_hash).exists());
+        assert!(!object_path(&a_hash).exists());
+        assert!(!object_path(&b_hash).exists());
+    }
+
+    #[test]
+    fn test_git_commit_ancestors_within() {
+        let folder = TempDir::new("test_git_commit_ancestors_within").unwrap();
+        let test_git_folder = folder.path().to_str().unwrap();
+
+        create_sample_git_folder(test_git_folder);
+        let git_project = open_git_project(test_git_folder).unwrap();
+
+        let author = GitCommitAuthor::new(
+            GitUser::new("Test User".to_string(), "test.user@email.com".to_string()),
+            100,
+            "+0000".to_string(),
+        );
+        let commit = |parents: &[&GitCommit], message: &str| {
+            let parents = parents
+                .iter()
+                .map(|parent| parent.get_hash())
+                .collect::<Vec<_>>();
+            let commit = GitCommit::new(
+                &GitTree::new().get_hash(),
+                &parents,
+                author.clone(),
+                author.clone(),
+                message,
+            );
+            commit.write_object(&git_project).unwrap();
+            commit
+        };
+
+        // a - b - c - merge - tip
+        //  \            /
+        //   ------ side
+        let a = commit(&[], "a");
+        let b = commit(&[&a], "b");
+        let c = commit(&[&b], "c");
+        let side = commit(&[&a], "side");
+        let merge = commit(&[&c, &side], "merge");
+        let tip = commit(&[&merge], "tip");
+
+        let messages = |depth: usize| {
+            tip.ancestors_within(&git_project, depth)
+                .unwrap()
+                .iter()
+                .map(|commit| commit.get_message().clone())
+                .collect::<Vec<_>>()
+        };
+
+        assert!(messages(0).is_empty());
+        assert_eq!(messages(1), vec!["merge"]);
+        assert_eq!(messages(2), vec!["merge", "c", "side"]);
+        assert_eq!(messages(3), vec!["merge", "c", "side", "b", "a"]);
+        assert_eq!(messages(10), messages(3));
+    }
+
+    #[test]
+    fn test_any_git_object_from_hash() {
+        let folder = TempDir::new("test_any_git_object_from_hash").unwrap();
+        let test_git_folder = folder.path().to_str().unwrap();
+
+        create_sample_git_folder(test_git_folder);
+        let git_project = open_git_project(test_git_folder).unwrap();
+
+        let blob = GitBlob::new(5, b"hello".to_vec());
+        blob.write_object(&git_project).unwrap();
+        let mut tree = GitTree::new();
+        tree.add_entry(GitTreeMode::File, blob.get_hash(), "hello.txt".to_string())
+            .unwrap();
+        tree.write_object(&git_project).unwrap();
+        let author = GitCommitAuthor::new(
+            GitUser::new("Test User".to_string(), "test.user@email.com".to_string()),
+            100,
+            "+0000".to_string(),
+        );
+        let commit = GitCommit::new(&tree.get_hash(), &[], author.clone(), author, "Hello");
+        commit.write_object(&git_project).unwrap();
+        let tag = GitTag::new(
+            &commit.get_hash(),
+            Header::Commit,
+            "v1",
+            None,
+            "Version 1\n",
+        );
+        tag.write_object(&git_project).unwrap();
+
+        let object = AnyGitObject::from_hash(&git_project, &blob.get_hash()).unwrap();
+        assert_eq!(object.get_type(), GitObjectType::Blob);
+        assert_eq!(object, AnyGitObject::Blob(blob));
+        assert_eq!(
+            AnyGitObject::from_hash(&git_project, &tree.get_hash()),
+            Ok(AnyGitObject::Tree(tree))
+        );
+        assert_eq!(
+            AnyGitObject::from_hash(&git_project, &commit.get_hash()),
+            Ok(AnyGitObject::Commit(commit))
+        );
+        assert_eq!(
+            AnyGitObject::from_hash(&git_project, &tag.get_hash()),
+            Ok(AnyGitObject::Tag(tag))
+        );
+
+        let unknown = write_raw_object(&git_project, Header::Invalid, b"data\n");
+        assert_eq!(
+            AnyGitObject::from_hash(&git_project, &unknown),
+            Err(GitObjectError::UnknownObjectType)
+        );
+        assert_eq!(
+            AnyGitObject::from_hash(&git_project, &format!("{:040x}", 1)),
+            Err(GitObjectError::FileReadError)
+        );
+    }
+
+    #[test]
+    fn test_git_tree_diff_submodule_update() {
+        let folder = TempDir::new("test_git_tree_diff_submodule_update").unwrap();
+        let test_git_folder = folder.path().to_str().unwrap();
+
+        create_sample_git_folder(test_git_folder);
+        let git_project = open_git_project(test_git_folder).unwrap();
+
+        // The pinned commits only exist in the submodule, so they are never read
+        let (old_pin, new_pin) = (format!("{:040x}", 1), format!("{:040x}", 2));
+        let readme = GitBlob::new(7, b"readme\n".to_vec());
+        readme.write_object(&git_project).unwrap();
+
+        let tree = |pin: &str| {
+            let mut tree = GitTree::new();
+            tree.add_entry(
+                GitTreeMode::File,
+                readme.get_hash(),
+                "README.md".to_string(),
+            )
+            .unwrap();
+            tree.add_entry(GitTreeMode::Submodule, pin.to_string(), "lib".to_string())
+                .unwrap();
+            tree
+        };
+        let (old, new) = (tree(&old_pin), tree(&new_pin));
+        assert!(new.get_entry_by_name("lib").unwrap().is_gitlink());
+        assert!(!new.get_entry_by_name("README.md").unwrap().is_gitlink());
+
+        let changes = old.diff(&git_project, &new).unwrap();
+        assert_eq!(
+            changes,
+            vec![TreeChange::SubmoduleChange {
+                path: "lib".to_string(),
+                old: old_pin.clone(),
+                new: new_pin.clone(),
+            }]
+        );
+        assert_eq!(changes[0].get_kind(), &ChangeKind::SubmoduleUpdated);
+        assert_eq!(changes[0].get_old_hash(), Some(&old_pin));
+        assert_eq!(changes[0].get_new_hash(), Some(&new_pin));
+        assert_eq!(old.diff_shallow(&new), changes);
+
+        // Counted like the `Subproject commit` lines `git diff --stat` compares
+        let stat = FileStat::from_change(&git_project, &changes[0]).unwrap();
+        assert_eq!((stat.get_insertions(), stat.get_deletions()), (1, 1));
+        assert!(!stat.is_binary());
+    }
+
+    #[test]
+    fn test_read_packed_objects() {
+        let folder = TempDir::new("test_read_packed_objects").unwrap();
+        let test_git_folder = folder.path().to_str().unwrap();
+
+        create_sample_git_folder(test_git_folder);
+        let git_project = open_git_project(test_git_folder).unwrap();
+
+        // Objects exactly as git stores them, hashed the way git hashes them
+        let object = |object_type: Header, data: Vec<u8>| {
+            let raw = [
+                format!("{} {}\0", object_type, data.len()).as_bytes(),
+                &data,
+            ]
+            .concat();
+            (
+                ObjectFormat::Sha1.hash(&raw),
+                encode_object(&object_type, &data),
+            )
+        };
+        let (blob_hash, blob) = object(Header::Blob, b"hello\n".to_vec());
+        let (tree_hash, tree) = object(
+            Header::Tree,
+            [
+                b"100644 hello.txt\0".to_vec(),
+                from_hex(&blob_hash).unwrap(),
+            ]
+            .concat(),
+        );
+        let (commit_hash, commit) = object(
+            Header::Commit,
+            format!(
+                "tree {}\n\
+                author Test User <test.user@email.com> 100 +0000\n\
+                committer Test User <test.user@email.com> 100 +0000\n\
+                \n\
+                Packed commit\n",
+                tree_hash
+            )
+            .into_bytes(),
+        );
+        write_pack(
+            &git_project
+                .objects_dir()
+                .join("pack")
+                .join("pack-objects.idx"),
+            &[
+                (commit_hash.clone(), commit),
+                (tree_hash.clone(), tree),
+                (blob_hash.clone(), blob),
+            ],
+        );
+
+        let commit = GitCommit::from_hash(&git_project, &commit_hash).unwrap();
+        assert_eq!(commit.get_tree_hash(), &tree_hash);
+        assert_eq!(commit.subject(), "Packed commit");
+
+        let tree = GitTree::from_hash(&git_project, commit.get_tree_hash()).unwrap();
+        let entry = tree.get_entry_by_name("hello.txt").unwrap();
+        assert_eq!(entry.hash, blob_hash);
+        assert_eq!(entry.mode, GitTreeMode::File);
+
+        let blob = GitBlob::from_hash(&git_project, &entry.hash).unwrap();
+        assert_eq!(blob.data(), b"hello\n");
+
+        assert_eq!(
+            GitBlob::from_hash(&git_project, &format!("{:040x}", 1)),
+            Err(GitObjectError::FileReadError)
+        );
+    }
+
+    #[test]
+    fn test_git_project_create_and_delete_branch() {
+        let folder = TempDir::new("test_git_project_create_and_delete_branch").unwrap();
+        let test_git_folder = folder.path().to_str().unwrap();
+
+        let git_path = create_sample_git_folder(test_git_folder);
+        let git_project = open_git_project(test_git_folder).unwrap();
+        fs::write(
+            format!("{}/config", git_path),
+            "[user]\n\tname = Test User\n\temail = test.user@email.com\n",
+        )
+        .unwrap();
+
+        let author = GitCommitAuthor::new(
+            GitUser::new("Test User".to_string(), "test.user@email.com".to_string()),
+            100,
+            "+0000".to_string(),
+        );
+        let commit = GitCommit::new(&GitTree::new().get_hash(), &[], author.clone(), author, "a");
+        commit.write_object(&git_project).unwrap();
+        let blob = GitBlob::new(4, b"blob".to_vec());
+        blob.write_object(&git_project).unwrap();
+        create_local_branch(test_git_folder, "main", &commit.get_hash());
+        fs::write(format!("{}/HEAD", git_path), "ref: refs/heads/main\n").unwrap();
+        fs::write(
+            format!("{}/packed-refs", git_path),
+            format!(
+                "# pack-refs with: peeled\n{hash} refs/heads/packed\n{hash} refs/tags/v1\n",
+                hash = commit.get_hash()
+            ),
+        )
+        .unwrap();
+
+        git_project.create_branch("feature/x", "main").unwrap();
+        assert_eq!(
+            git_project.resolve_ref("refs/heads/feature/x"),
+            Ok(Some(commit.get_hash()))
+        );
+        let reflog = git_project.reflog("refs/heads/feature/x").unwrap();
+        assert_eq!(reflog.len(), 1);
+        assert_eq!(reflog[0].get_old_hash(), &format!("{:040x}", 0));
+        assert_eq!(reflog[0].get_new_hash(), &commit.get_hash());
+        assert_eq!(reflog[0].get_message(), "branch: Created from main");
+        let logged = reflog[0].get_committer();
+        assert_eq!(logged.timezone, local_timezone(logged.date_seconds));
+
+        for (name, start_point, error) in [
+            ("feature/x", "main", GitObjectError::RefAlreadyExists),
+            ("packed", "main", GitObjectError::RefAlreadyExists),
+            ("feature", "main", GitObjectError::RefAlreadyExists),
+            ("main/sub", "main", GitObjectError::RefAlreadyExists),
+            ("bad..name", "main", GitObjectError::InvalidRefName),
+            ("HEAD", "main", GitObjectError::InvalidRefName),
+            (
+                "from-blob",
+                &blob.get_hash(),
+                GitObjectError::InvalidCommitFile(CommitError::InvalidHeader),
+            ),
+            ("from-nothing", "missing", GitObjectError::RefNotFound),
+        ] {
+            assert_eq!(git_project.create_branch(name, start_point), Err(error));
+        }
+
+        assert_eq!(
+            git_project.delete_branch("main", false),
+            Err(GitObjectError::BranchCheckedOut)
+        );
+
+        // A branch can be checked out in a linked worktree only
+        let worktree_folder = TempDir::new("test_git_project_delete_branch_linked").unwrap();
+        let linked_git_dir = format!("{}/worktrees/linked", git_path);
+        fs::create_dir_all(&linked_git_dir).unwrap();
+        fs::write(
+            format!("{}/HEAD", linked_git_dir),
+            "ref: refs/heads/linked\n",
+        )
+        .unwrap();
+        fs::write(format!("{}/commondir", linked_git_dir), "../..\n").unwrap();
+        fs::write(
+            format!("{}/gitdir", linked_git_dir),
+            format!("{}/{}\n", worktree_folder.path().display(), GIT_FOLDER),
+        )
+        .unwrap();
+        git_project.create_branch("linked", "main").unwrap();
+        assert_eq!(
+            git_project.delete_branch("linked", false),
+            Err(GitObjectError::BranchCheckedOut)
+        );
+        git_project.delete_branch("linked", true).unwrap();
+
+        // A lock left by another writer stops the branch from being created
+        fs::write(format!("{}/refs/heads/locked.lock", git_path), "").unwrap();
+        assert_eq!(
+            git_project.create_branch("locked", "main"),
+            Err(GitObjectError::RefLocked)
+        );
+        assert_eq!(git_project.resolve_ref("refs/heads/locked"), Ok(None));
+        assert_eq!(
+            git_project.delete_branch("missing", true),
+            Err(GitObjectError::RefNotFound)
+        );
+
+        // The emptied `feature` folder goes away with the branch
+        git_project.delete_branch("feature/x", false).unwrap();
+        assert_eq!(git_project.resolve_ref("refs/heads/feature/x"), Ok(None));
+        assert!(git_project
+            .reflog("refs/heads/feature/x")
+            .unwrap()
+            .is_empty());
+        git_project.create_branch("feature", "v1").unwrap();
+        assert_eq!(
+            git_project.resolve_ref("refs/heads/feature"),
+            Ok(Some(commit.get_hash()))
+        );
+
+        // Another writer holding `packed-refs` keeps the packed branch in place
+        fs::write(format!("{}/packed-refs.lock", git_path), "").unwrap();
+        assert_eq!(
+            git_project.delete_branch("packed", false),
+            Err(GitObjectError::RefLocked)
+        );
+        assert_eq!(
+            git_project.resolve_ref("refs/heads/packed"),
+            Ok(Some(commit.get_hash()))
+        );
+        fs::remove_file(format!("{}/packed-refs.lock", git_path)).unwrap();
+
+        git_project.delete_branch("packed", false).unwrap();
+        assert_eq!(git_project.resolve_ref("refs/heads/packed"), Ok(None));
+        assert!(!Path::new(&format!("{}/packed-refs.lock", git_path)).exists());
+        assert_eq!(
+            fs::read_to_string(format!("{}/packed-refs", git_path)).unwrap(),
+            format!(
+                "# pack-refs with: peeled\n{} refs/tags/v1\n",
+                commit.get_hash()
+            )
+        );
+
+        git_project.delete_branch("main", true).unwrap();
+        assert_eq!(git_project.resolve_ref("refs/heads/main"), Ok(None));
+    }
+
+    #[test]
+    fn test_read_packed_delta_objects() {
+        let folder = TempDir::new("test_read_packed_delta_objects").unwrap();
+        let test_git_folder = folder.path().to_str().unwrap();
+
+        create_sample_git_folder(test_git_folder);
+        let git_project = open_git_project(test_git_folder).unwrap();
+
+        let hash = |content: &[u8]| GitBlob::new(content.len(), content.to_vec()).get_hash();
+        let (base, first, second) = (
+            b"fn main() {}\n".to_vec(),
+            b"fn main() { run() }\n".to_vec(),
+            b"// entry\nfn main() { run() }\n".to_vec(),
+        );
+
+        // An offset delta on the base, then a reference delta on that one. The entry type
+        // numbers are 6 and 7
+        let base_entry = encode_blob(&base);
+        let first_delta = [&[13, 20, 0x90, 11, 7][..], b" run() ", &[0x91, 11, 2]].concat();
+        let second_delta = [&[20, 29, 9][..], b"// entry\n", &[0x90, 20]].concat();
+        write_pack(
+            &git_project
+                .objects_dir()
+                .join("pack")
+                .join("pack-deltas.idx"),
+            &[
+                (hash(&base), base_entry.clone()),
+                (
+                    hash(&first),
+                    encode_entry(
+                        6,
+                        first_delta.len() as u64,
+                        &[base_entry.len() as u8],
+                        &first_delta,
+                    ),
+                ),
+                (
+                    hash(&second),
+                    encode_entry(
+                        7,
+                        second_delta.len() as u64,
+                        &hash_bytes(&hash(&first)),
+                        &second_delta,
+                    ),
+                ),
+            ],
+        );
+
+        for content in [base, first, second] {
+            let blob = GitBlob::from_hash(&git_project, &hash(&content)).unwrap();
+            assert_eq!(blob.data(), content.as_slice());
+        }
+    }
+
+    #[test]
+    fn test_git_project_create_tags() {
+        let folder = TempDir::new("test_git_project_create_tags").unwrap();
+        let test_git_folder = folder.path().to_str().unwrap();
+
+        create_sample_git_folder(test_git_folder);
+        let git_project = open_git_project(test_git_folder).unwrap();
+
+        let tagger = GitCommitAuthor::new(
+            GitUser::new("Test User".to_string(), "test.user@email.com".to_string()),
+            100,
+            "+0000".to_string(),
+        );
+        let commit = GitCommit::new(
+            &GitTree::new().get_hash(),
+            &[],
+            tagger.clone(),
+            tagger.clone(),
+            "Release the thing",
+        );
+        commit.write_object(&git_project).unwrap();
+        create_local_branch(test_git_folder, "main", &commit.get_hash());
+
+        git_project.create_lightweight_tag("v1", "main").unwrap();
+        let lightweight = git_project.tag_details("v1").unwrap();
+        assert_eq!(lightweight.get_commit_hash(), &commit.get_hash());
+        assert_eq!(lightweight.get_tagger(), None);
+        assert_eq!(lightweight.get_message(), None);
+        assert_eq!(
+            git_project.resolve_ref("refs/tags/v1"),
+            Ok(Some(commit.get_hash()))
+        );
+
+        git_project
+            .create_annotated_tag(
+                "release/v2",
+                &commit.get_hash(),
+                tagger.clone(),
+                "Version 2",
+            )
+            .unwrap();
+        let annotated = git_project.tag_details("release/v2").unwrap();
+        assert_eq!(annotated.get_commit_hash(), &commit.get_hash());
+        assert_eq!(annotated.get_commit_subject(), "Release the thing");
+        assert_eq!(annotated.get_tagger(), Some(&tagger));
+        assert_eq!(annotated.get_message(), Some(&"Version 2".to_string()));
+
+        let tag_hash = git_project
+            .resolve_ref("refs/tags/release/v2")
+            .unwrap()
+            .unwrap();
+        let tag = GitTag::from_hash(&git_project, &tag_hash).unwrap();
+        assert_eq!(tag.get_tag(), "release/v2");
+        assert_eq!(tag.get_object_type(), &Header::Commit);
+
+        for (name, error) in [
+            ("v1", GitObjectError::RefAlreadyExists),
+            ("release", GitObjectError::RefAlreadyExists),
+            ("v1/patch", GitObjectError::RefAlreadyExists),
+            ("ends.lock", GitObjectError::InvalidRefName),
+        ] {
+            assert_eq!(git_project.create_lightweight_tag(name, "main"), Err(error));
+        }
+        assert_eq!(
+            git_project.create_annotated_tag("v1", "main", tagger.clone(), "Again"),
+            Err(GitObjectError::RefAlreadyExists)
+        );
+        assert_eq!(
+            git_project.create_lightweight_tag("v3", "missing"),
+            Err(GitObjectError::RefNotFound)
+        );
+    }
+
+    #[test]
+    fn test_git_project_open() {
+        let folder = TempDir::new("test_git_project_open").unwrap();
+        let test_git_folder = folder.path().to_str().unwrap();
+
+        assert_eq!(
+            GitProject::open(test_git_folder),
+            Err(GitObjectError::NotAGitRepository)
+        );
+        assert_eq!(
+            GitProject::open(&format!("{}/missing", test_git_folder)),
+            Err(GitObjectError::NotAGitRepository)
+        );
+
+        // A .git folder without HEAD isn't a repository yet
+        let git_path = format!("{}/{}", test_git_folder, GIT_FOLDER);
+        fs::create_dir(&git_path).unwrap();
+        assert_eq!(
+            GitProject::open(test_git_folder),
+            Err(GitObjectError::NotAGitRepository)
+        );
+
+        fs::write(format!("{}/HEAD", git_path), "ref: refs/heads/main\n").unwrap();
+        let git_project = GitProject::open(test_git_folder).unwrap();
+        assert_eq!(git_project.get_directory(), test_git_folder);
+        assert_eq!(git_project.get_state(), GitProjectState::Valid);
+
+        // A linked worktree only has a .git file pointing at its git dir
+        let worktree_git_dir = format!("{}/worktrees/linked", git_path);
+        fs::create_dir_all(&worktree_git_dir).unwrap();
+        let worktree = format!("{}/linked", test_git_folder);
+        fs::create_dir(&worktree).unwrap();
+        fs::write(
+            format!("{}/{}", worktree, GIT_FOLDER),
+            format!("gitdir: {}\n", worktree_git_dir),
+        )
+        .unwrap();
+        assert_eq!(
+            GitProject::open(&worktree),
+            Err(GitObjectError::NotAGitRepository)
+        );
+
+        fs::write(
+            format!("{}/HEAD", worktree_git_dir),
+            "ref: refs/heads/linked\n",
+        )
+        .unwrap();
+        assert!(GitProject::open(&worktree).is_ok());
+    }
+
+    #[test]
+    fn test_git_tree_checkout_size() {
+        let folder = TempDir::new("test_git_tree_checkout_size").unwrap();
+        let test_git_folder = folder.path().to_str().unwrap();
+        create_sample_git_folder(test_git_folder);
+        let project = open_git_project(test_git_folder).unwrap();
+
+        let readme = GitBlob::new(11, b"hello world".to_vec());
+        readme.write_object(&project).unwrap();
+        let main = GitBlob::new(12, b"fn main() {}".to_vec());
+        main.write_object(&project).unwrap();
+        let empty = GitBlob::new(0, Vec::new());
+        empty.write_object(&project).unwrap();
+
+        let mut nested = GitTree::new();
+        nested
+            .add_entry(GitTreeMode::File, empty.get_hash(), "empty".to_string())
+            .unwrap();
+        nested.write_object(&project).unwrap();
+
+        let mut src = GitTree::new();
+        src.add_entry(GitTreeMode::File, main.get_hash(), "main.rs".to_string())
+            .unwrap();
+        src.add_entry(GitTreeMode::Tree, nested.get_hash(), "nested".to_string())
+            .unwrap();
+        src.write_object(&project).unwrap();
+
+        let mut root = GitTree::new();
+        root.add_entry(GitTreeMode::File, readme.get_hash(), "README".to_string())
+            .unwrap();
+        root.add_entry(GitTreeMode::Executable, main.get_hash(), "run".to_string())
+            .unwrap();
+        root.add_entry(GitTreeMode::Tree, src.get_hash(), "src".to_string())
+            .unwrap();
+        root.add_entry(GitTreeMode::Submodule, "a".repeat(40), "libs".to_string())
+            .unwrap();
+        root.write_object(&project).unwrap();
+
+        assert_eq!(nested.checkout_size(&project), Ok(0));
+        assert_eq!(src.checkout_size(&project), Ok(12));
+        assert_eq!(root.checkout_size(&project), Ok(11 + 12 + 12));
+        assert_eq!(GitTree::new().checkout_size(&project), Ok(0));
+
+        let mut missing = GitTree::new();
+        missing
+            .add_entry(GitTreeMode::File, "b".repeat(40), "gone".to_string())
+            .unwrap();
+        assert!(missing.checkout_size(&project).is_err());
+    }
+
+    #[test]
+    fn test_git_project_git_file_object_lookup() {
+        let folder = TempDir::new("test_git_project_git_file_object_lookup").unwrap();
+        let test_folder = folder.path().to_str().unwrap();
+        let repo_folder = format!("{}/repo", test_folder);
+        fs::create_dir(&repo_folder).unwrap();
+        let git_path = create_sample_git_folder(&repo_folder);
+        fs::write(format!("{}/HEAD", git_path), "ref: refs/heads/main\n").unwrap();
+        let git_project = open_git_project(&repo_folder).unwrap();
+
+        let blob = GitBlob::new(5, b"hello".to_vec());
+        blob.write_object(&git_project).unwrap();
+        let mut tree = GitTree::new();
+        tree.add_entry(GitTreeMode::File, blob.get_hash(), "hello.txt".to_string())
+            .unwrap();
+        tree.write_object(&git_project).unwrap();
+
+        let author = GitCommitAuthor::new(
+            GitUser::new("Test User".to_string(), "test.user@email.com".to_string()),
+            100,
+            "+0000".to_string(),
+        );
+        let commit = GitCommit::new(
+            &tree.get_hash(),
+            &[],
+            author.clone(),
+            author,
+            "initial commit",
+        );
+        commit.write_object(&git_project).unwrap();
+
+        // The checkout only has a .git file with a path relative to itself, as in submodules
+        let checkout_folder = format!("{}/checkout", test_folder);
+        fs::create_dir(&checkout_folder).unwrap();
+        fs::write(
+            format!("{}/{}", checkout_folder, GIT_FOLDER),
+            "gitdir: ../repo/.git\n",
+        )
+        .unwrap();
+
+        let checkout = GitProject::open(&checkout_folder).unwrap();
+        assert_eq!(
+            fs::canonicalize(checkout.git_dir()).unwrap(),
+            fs::canonicalize(&git_path).unwrap()
+        );
+        assert_eq!(
+            fs::canonicalize(checkout.objects_dir()).unwrap(),
+            fs::canonicalize(git_project.objects_dir()).unwrap()
+        );
+
+        let read_commit = GitCommit::from_hash(&checkout, &commit.get_hash()).unwrap();
+        assert_eq!(read_commit.get_tree_hash(), &tree.get_hash());
+        let read_tree = GitTree::from_hash(&checkout, read_commit.get_tree_hash()).unwrap();
+        assert_eq!(read_tree, tree);
+        let read_blob = GitBlob::from_hash(&checkout, &blob.get_hash()).unwrap();
+        assert_eq!(read_blob.data(), b"hello");
+
+        // Objects written through the checkout land in the real git dir
+        let new_blob = GitBlob::new(5, b"world".to_vec());
+        new_blob.write_object(&checkout).unwrap();
+        assert!(GitBlob::from_hash(&git_project, &new_blob.get_hash()).is_ok());
+        assert!(!Path::new(&checkout_folder)
+            .join(GIT_FOLDER)
+            .join("objects")
+            .exists());
+    }
+
+    #[test]
+    fn test_git_blob_read_lines() {
+        let folder = TempDir::new("test_git_blob_read_lines").unwrap();
+        let test_git_folder = folder.path().to_str().unwrap();
+        create_sample_git_folder(test_git_folder);
+        let git_project = open_git_project(test_git_folder).unwrap();
+
+        let content = (0..1000)
+            .map(|line| format!("line {}\n", line))
+            .collect::<String>()
+            .into_bytes();
+        let blob = GitBlob::new(content.len(), content.clone());
+        blob.write_object(&git_project).unwrap();
+
+        let lines = GitBlob::read_lines(&git_project, &blob.get_hash(), 100, 11).unwrap();
+        assert_eq!(
+            lines,
+            (100..=110)
+                .map(|line| format!("line {}", line))
+                .collect::<Vec<String>>()
+        );
+        assert_eq!(
+            GitBlob::read_lines(&git_project, &blob.get_hash(), 995, 10).unwrap(),
+            vec!["line 995", "line 996", "line 997", "line 998", "line 999"]
+        );
+        assert_eq!(
+            GitBlob::read_lines(&git_project, &blob.get_hash(), 1000, 10),
+            Ok(Vec::new())
+        );
+        // Asking for everything that is left doesn't overflow
+        assert_eq!(
+            GitBlob::read_lines(&git_project, &blob.get_hash(), 998, usize::MAX).unwrap(),
+            vec!["line 998", "line 999"]
+        );
+        assert_eq!(
+            GitBlob::read_lines(&git_project, &blob.get_hash(), 0, 0),
+            Ok(Vec::new())
+        );
+
+        // The last line has no newline and line endings are dropped either way
+        let crlf = GitBlob::new(14, b"first\r\nsecond".to_vec());
+        crlf.write_object(&git_project).unwrap();
+        assert_eq!(
+            GitBlob::read_lines(&git_project, &crlf.get_hash(), 0, 5).unwrap(),
+            vec!["first", "second"]
+        );
+
+        let packed = b"one\ntwo\nthree\n".to_vec();
+        let packed_hash = GitBlob::new(packed.len(), packed.clone()).get_hash();
+        write_pack(
+            &git_project
+                .objects_dir()
+                .join("pack")
+                .join("pack-lines.idx"),
+            &[(packed_hash.clone(), encode_blob(&packed))],
+        );
+        assert_eq!(
+            GitBlob::read_lines(&git_project, &packed_hash, 1, 5).unwrap(),
+            vec!["two", "three"]
+        );
+
+        let mut tree = GitTree::new();
+        tree.add_entry(GitTreeMode::File, blob.get_hash(), "lines.txt".to_string())
+            .unwrap();
+        tree.write_object(&git_project).unwrap();
+        assert_eq!(
+            GitBlob::read_lines(&git_project, &tree.get_hash(), 0, 1),
+            Err(GitObjectError::InvalidBlobFile)
+        );
+        assert_eq!(
+            GitBlob::read_lines(&git_project, &"c".repeat(40), 0, 1),
+            Err(GitObjectError::FileReadError)
+        );
+    }
+
+    #[test]
+    fn test_git_project_bare_repository() {
+        let folder = TempDir::new("test_git_project_bare_repository").unwrap();
+        let test_git_folder = folder.path().to_str().unwrap();
+        // The .git folder of a sample project has the layout of a bare repository
+        let bare_folder = create_sample_git_folder(test_git_folder);
+        fs::write(format!("{}/HEAD", bare_folder), "ref: refs/heads/main\n").unwrap();
+
+        let mut bare = GitProject::open(&bare_folder).unwrap();
+        assert!(bare.is_bare());
+        assert_eq!(bare.git_dir(), PathBuf::from(&bare_folder));
+        assert_eq!(bare.common_dir(), PathBuf::from(&bare_folder));
+        assert_eq!(
+            bare.objects_dir(),
+            PathBuf::from(&bare_folder).join("objects")
+        );
+        assert!(!open_git_project(test_git_folder).unwrap().is_bare());
+
+        let blob = GitBlob::new(4, b"bare".to_vec());
+        blob.write_object(&bare).unwrap();
+        let mut tree = GitTree::new();
+        tree.add_entry(GitTreeMode::File, blob.get_hash(), "bare.txt".to_string())
+            .unwrap();
+        tree.write_object(&bare).unwrap();
+        assert!(Path::new(&bare_folder)
+            .join("objects")
+            .join(&tree.get_hash()[..2])
+            .is_dir());
+        assert_eq!(
+            GitTree::from_hash(&bare, &tree.get_hash()),
+            Ok(tree.clone())
+        );
+
+        let commit_hash = "6e18e0fdeac4932d71ad981dc4dc497c49f3c606";
+        create_local_branch(test_git_folder, "main", commit_hash);
+        bare.update().unwrap();
+        assert_eq!(bare.get_local_branches().len(), 1);
+        assert_eq!(bare.resolve_ref("HEAD"), Ok(Some(commit_hash.to_string())));
+
+        let worktrees = bare.worktrees().unwrap();
+        assert_eq!(worktrees.len(), 1);
+        assert!(worktrees[0].is_main());
+        assert_eq!(worktrees[0].get_path(), &bare_folder);
+        assert_eq!(worktrees[0].get_branch(), Some(&"main".to_string()));
+
+        // core.bare wins over the layout
+        fs::write(
+            format!("{}/config", bare_folder),
+            "[core]\n\tbare = false\n",
+        )
+        .unwrap();
+        assert!(!bare.is_bare());
+    }
+
+    #[test]
+    fn test_git_project_open_with_snapshot() {
+        let folder = TempDir::new("test_git_project_open_with_snapshot").unwrap();
+        let test_git_folder = folder.path().to_str().unwrap();
+
+        assert_eq!(
+            GitProject::open_with_snapshot(test_git_folder),
+            Err(GitObjectError::NotAGitRepository)
+        );
+
+        create_sample_git_folder(test_git_folder);
+        let head_path = format!("{}/{}/HEAD", test_git_folder, GIT_FOLDER);
+        fs::write(&head_path, "ref: refs/heads/main\n").unwrap();
+
+        let (_, snapshot) = GitProject::open_with_snapshot(test_git_folder).unwrap();
+        assert_eq!(snapshot.get_branch(), Some(&"main".to_string()));
+        assert_eq!(snapshot.get_head(), None);
+        assert_eq!(snapshot.get_branch_count(), 0);
+        assert_eq!(snapshot.get_tag_count(), 0);
+        assert!(snapshot.is_clean());
+
+        let (git_project, _) = GitProject::open_with_snapshot(test_git_folder).unwrap();
+        let tree = GitTree::new();
+        tree.write_object(&git_project).unwrap();
+        let author = GitCommitAuthor::new(
+            GitUser::new("Test User".to_string(), "test.user@email.com".to_string()),
+            100,
+            "+0000".to_string(),
+        );
+        let commit = GitCommit::new(
+            &tree.get_hash(),
+            &[],
+            author.clone(),
+            author.clone(),
+            "Initial commit\n\nWith a body",
+        );
+        commit.write_object(&git_project).unwrap();
+        create_local_branch(test_git_folder, "main", &commit.get_hash());
+        create_local_branch(test_git_folder, "feature/snapshot", &commit.get_hash());
+        create_tag(test_git_folder, "v1", &commit.get_hash());
+
+        let (git_project, snapshot) = GitProject::open_with_snapshot(test_git_folder).unwrap();
+        let head_commit = git_project.head_commit().unwrap();
+        let head = snapshot.get_head().unwrap();
+        assert_eq!(head.get_hash(), &commit.get_hash());
+        assert_eq!(head.get_subject(), head_commit.subject());
+        assert_eq!(head.get_author(), head_commit.get_author());
+        assert_eq!(
+            snapshot.get_branch_count(),
+            git_project.list_branches().unwrap().len()
+        );
+        assert_eq!(snapshot.get_branch_count(), 2);
+        assert_eq!(
+            snapshot.get_tag_count(),
+            git_project.tags_by_date().unwrap().len()
+        );
+        assert_eq!(
+            snapshot.is_clean(),
+            git_project.status().unwrap().is_empty()
+        );
+        assert!(snapshot.is_clean());
+
+        fs::write(format!("{}/README.md", test_git_folder), "readme").unwrap();
+        fs::write(&head_path, format!("{}\n", commit.get_hash())).unwrap();
+        let (_, snapshot) = GitProject::open_with_snapshot(test_git_folder).unwrap();
+        assert!(!snapshot.is_clean());
+        assert_eq!(snapshot.get_branch(), None);
+        assert_eq!(snapshot.get_head().unwrap().get_hash(), &commit.get_hash());
+    }
+
+    #[test]
+    fn test_git_project_expand_hash() {
+        let folder = TempDir::new("test_git_project_expand_hash").unwrap();
+        let test_git_folder = folder.path().to_str().unwrap();
+        create_sample_git_folder(test_git_folder);
+        let git_project = open_git_project(test_git_folder).unwrap();
+
+        let blob = GitBlob::new(5, b"short".to_vec());
+        blob.write_object(&git_project).unwrap();
+        let hash = blob.get_hash();
+        assert_eq!(git_project.expand_hash(&hash[..7]), Ok(hash.clone()));
+        assert_eq!(
+            git_project.expand_hash(&hash[..7].to_uppercase()),
+            Ok(hash.clone())
+        );
+        assert_eq!(git_project.expand_hash(&hash), Ok(hash.clone()));
+        assert_eq!(git_project.resolve_revision(&hash[..7]), Ok(hash.clone()));
+
+        // Two packed objects share a prefix, one of them is also a loose object
+        let first = format!("abcd1{}", "0".repeat(35));
+        let second = format!("abcd2{}", "0".repeat(35));
+        write_pack(
+            &git_project
+                .objects_dir()
+                .join("pack")
+                .join("pack-abbrev.idx"),
+            &[
+                (first.clone(), encode_blob(b"first")),
+                (second.clone(), encode_blob(b"second")),
+                (hash.clone(), encode_blob(b"short")),
+            ],
+        );
+
+        assert_eq!(git_project.expand_hash(&hash[..7]), Ok(hash.clone()));
+        assert_eq!(git_project.expand_hash("abcd1"), Ok(first));
+        assert_eq!(git_project.expand_hash("abcd2"), Ok(second));
+        assert_eq!(
+            git_project.expand_hash("abcd"),
+            Err(GitObjectError::AmbiguousHash)
+        );
+        assert_eq!(
+            git_project.resolve_revision("abcd"),
+            Err(GitObjectError::AmbiguousHash)
+        );
+        assert_eq!(
+            git_project.expand_hash("abcd3"),
+            Err(GitObjectError::ObjectNotFound)
+        );
+        assert_eq!(
+            git_project.resolve_revision("abcd3"),
+            Err(GitObjectError::RefNotFound)
+        );
+        assert_eq!(
+            git_project.expand_hash("abc"),
+            Err(GitObjectError::InvalidHash)
+        );
+        assert_eq!(
+            git_project.expand_hash("abcz"),
+            Err(GitObjectError::InvalidHash)
+        );
+    }
+
+    #[test]
+    fn test_git_commit_write() {
+        let folder = TempDir::new("test_git_commit_write").unwrap();
+        let test_git_folder = folder.path().to_str().unwrap();
+        create_sample_git_folder(test_git_folder);
+        let git_project = open_git_project(test_git_folder).unwrap();
+
+        let author = GitCommitAuthor::new(
+            GitUser::new("Test User".to_string(), "test.user@email.com".to_string()),
+            100,
+            "+0200".to_string(),
+        );
+        let commit = GitCommit::new(
+            "50c8353444afbef3172c999ef6cff8d31309ac3e",
+            &["df6773ea47ed3fce3b3bb14e3d1101963e77ef08".to_string()],
+            author.clone(),
+            author,
+            "Write the commit\n\nAs a loose object",
+        );
+
+        let hash = commit.write(&git_project).unwrap();
+        assert_eq!(hash, commit.get_hash());
+        let object_path = git_project.objects_dir().join(&hash[..2]).join(&hash[2..]);
+        assert!(object_path.is_file());
+        assert_eq!(
+            GitCommit::from_hash(&git_project, &hash),
+            Ok(commit.clone())
+        );
+        assert_eq!(
+            GitCommit::from_hash_verified(&git_project, &hash),
+            Ok(commit.clone())
+        );
+
+        // An existing object is not written again
+        let written = fs::read(&object_path).unwrap();
+        let modified = SystemTime::UNIX_EPOCH + Duration::from_secs(100);
+        fs::File::options()
+            .write(true)
+            .open(&object_path)
+            .unwrap()
+            .set_modified(modified)
+            .unwrap();
+        assert_eq!(commit.write(&git_project), Ok(hash.clone()));
+        assert_eq!(
+            fs::metadata(&object_path).unwrap().modified().unwrap(),
+            modified
+        );
+
+        // Unless the file was cut short or doesn't hold the commit
+        for broken in [&written[..written.len() / 2], b"", b"kept"] {
+            fs::write(&object_path, broken).unwrap();
+            assert_eq!(commit.write(&git_project), Ok(hash.clone()));
+            assert_eq!(fs::read(&object_path).unwrap(), written);
+        }
+
+        // Neither is one that is only packed
+        let packed = GitCommit::new(
+            "50c8353444afbef3172c999ef6cff8d31309ac3e",
+            &[],
+            commit.get_author().clone(),
+            commit.get_committer().clone(),
+            "Only in a pack",
+        );
+        let packed_hash = packed.get_hash();
+        write_pack(
+            &git_project
+                .objects_dir()
+                .join("pack")
+                .join("pack-commit.idx"),
+            &[(
+                packed_hash.clone(),
+                encode_object(&Header::Commit, &packed.get_data()),
+            )],
+        );
+        assert_eq!(packed.write(&git_project), Ok(packed_hash.clone()));
+        assert!(!git_project
+            .objects_dir()
+            .join(&packed_hash[..2])
+            .join(&packed_hash[2..])
+            .exists());
+    }
+
+    #[test]
+    fn test_git_project_update_clears_cache() {
+        let folder = TempDir::new("test_git_project_update_clears_cache").unwrap();
+        let test_git_folder = folder.path().to_str().unwrap();
+
+        create_sample_git_folder(test_git_folder);
+        fs::write(
+            format!("{}/{}/HEAD", test_git_folder, GIT_FOLDER),
+            "ref: refs/heads/main\n",
+        )
+        .unwrap();
+        let mut git_project = open_git_project(test_git_folder).unwrap();
+
+        let author = GitCommitAuthor::new(
+            GitUser::new("Test User".to_string(), "test.user@email.com".to_string()),
+            100,
+            "+0000".to_string(),
+        );
+        let first = GitCommit::new("tree", &[], author.clone(), author.clone(), "First");
+        let second = GitCommit::new(
+            "tree",
+            &[first.get_hash()],
+            author.clone(),
+            author,
+            "Second",
+        );
+        first.write_object(&git_project).unwrap();
+        second.write_object(&git_project).unwrap();
+
+        create_local_branch(test_git_folder, "main", &first.get_hash());
+        assert_eq!(git_project.head_commit(), Ok(first));
+
+        create_local_branch(test_git_folder, "main", &second.get_hash());
+        git_project.update().unwrap();
+        assert_eq!(git_project.head_commit(), Ok(second.clone()));
+        assert_eq!(
+            git_project.resolve_ref("refs/heads/main"),
+            Ok(Some(second.get_hash()))
+        );
+    }
+
+    #[test]
+    fn test_git_project_working_file_matches_packed_blob() {
+        let folder = TempDir::new("test_git_project_working_file_matches_packed_blob").unwrap();
+        let test_git_folder = folder.path().to_str().unwrap();
+
+        create_sample_git_folder(test_git_folder);
+        let git_project = open_git_project(test_git_folder).unwrap();
+
+        let content = "line of the packed file\n".repeat(100).into_bytes();
+        let hash = GitBlob::new(content.len(), content.clone()).get_hash();
+        write_pack(
+            &git_project
+                .objects_dir()
+                .join("pack")
+                .join("pack-status.idx"),
+            &[(hash.clone(), encode_blob(&content))],
+        );
+
+        fs::write(format!("{}/same.txt", test_git_folder), &content).unwrap();
+        assert_eq!(
+            git_project.working_file_matches_blob(Path::new("same.txt"), &hash),
+            Ok(true)
+        );
+
+        let mut changed = content.clone();
+        changed[0] = b'X';
+        fs::write(format!("{}/changed.txt", test_git_folder), &changed).unwrap();
+        assert_eq!(
+            git_project.working_file_matches_blob(Path::new("changed.txt"), &hash),
+            Ok(false)
+        );
+    }
+
+    // `git repack -ad` of a repository with two commits and an annotated tag `v1`. The
+    // second commit changes one line of notes.txt, so git stores the first notes.txt as
+    // a delta of the second
+    const SAMPLE_PACK: &[u8] = include_bytes!("../../tests/fixtures/sample.pack");
+    const SAMPLE_PACK_INDEX: &[u8] = include_bytes!("../../tests/fixtures/sample.idx");
+
+    #[test]
+    fn test_git_project_read_git_pack() {
+        let folder = TempDir::new("test_git_project_read_git_pack").unwrap();
+        let test_git_folder = folder.path().to_str().unwrap();
+
+        create_sample_git_folder(test_git_folder);
+        let git_project = open_git_project(test_git_folder).unwrap();
+        let pack_dir = git_project.objects_dir().join("pack");
+        fs::create_dir_all(&pack_dir).unwrap();
+        fs::write(pack_dir.join("pack-sample.pack"), SAMPLE_PACK).unwrap();
+        fs::write(pack_dir.join("pack-sample.idx"), SAMPLE_PACK_INDEX).unwrap();
+
+        let head = "3bf6370657b7c07449036375cbbfa5ccb07f90a3";
+        let first = "c657d663c802390b043580ae5b1a4d294c4da9f1";
+        let commit = GitCommit::from_hash_verified(&git_project, head).unwrap();
+        assert_eq!(commit.get_message(), "Second commit");
+        assert_eq!(commit.get_parent_hashes(), &vec![first.to_string()]);
+        assert_eq!(commit.get_author().date_seconds, 1700000100);
+        assert_eq!(commit.get_hash(), head);
+
+        let tag =
+            GitTag::from_hash(&git_project, "2038053111b1af4322b9033fe74f710d4baca485").unwrap();
+        assert_eq!(tag.get_object(), head);
+        assert_eq!(tag.get_object_type(), &Header::Commit);
+
+        let tree = GitTree::from_hash(&git_project, commit.get_tree_hash()).unwrap();
+        assert_eq!(
+            tree.entries()
+                .iter()
+                .map(|entry| (entry.name.as_str(), entry.hash.as_str()))
+                .collect::<Vec<_>>(),
+            vec![
+                ("README.md", "ce013625030ba8dba906f756967f9e9ca394464a"),
+                ("notes.txt", "499c9b98fdb84a763932c6eed45030769d501d89"),
+            ]
+        );
+
+        // Stored as a delta of the notes.txt of the second commit
+        let notes = (1..=500)
+            .map(|line| format!("line {}\n", line))
+            .collect::<String>();
+        let first_notes =
+            GitBlob::from_hash(&git_project, "204a6d99abeff353ca1aa7017c0149d509586d2f").unwrap();
+        assert_eq!(first_notes.data(), notes.as_bytes());
+        assert_eq!(
+            AnyGitObject::from_hash(&git_project, "ce013625030ba8dba906f756967f9e9ca394464a"),
+            Ok(AnyGitObject::Blob(GitBlob::new(6, b"hello\n".to_vec())))
+        );
+    }
+
+    #[test]
+    fn test_open_git_project_without_git_folder() {
+        let folder = TempDir::new("test_open_git_project_without_git_folder").unwrap();
+        let test_git_folder = folder.path().to_str().unwrap();
+        let git_path = create_sample_git_folder(test_git_folder);
+        fs::write(format!("{}/HEAD", git_path), "ref: refs/heads/main\n").unwrap();
+
+        // The .git folder of a sample project has the layout of a bare repository
+        let bare = open_git_project(&git_path).unwrap();
+        assert!(bare.is_bare());
+        assert_eq!(bare.get_state(), GitProjectState::Valid);
+
+        // A linked worktree or a submodule only has a .git file pointing at its git dir
+        let worktree_git_dir = format!("{}/worktrees/linked", git_path);
+        fs::create_dir_all(&worktree_git_dir).unwrap();
+        fs::write(
+            format!("{}/HEAD", worktree_git_dir),
+            "ref: refs/heads/linked\n",
+        )
+        .unwrap();
+        fs::write(format!("{}/commondir", worktree_git_dir), "../..\n").unwrap();
+        let worktree = format!("{}/linked", test_git_folder);
+        fs::create_dir(&worktree).unwrap();
+        fs::write(
+            format!("{}/{}", worktree, GIT_FOLDER),
+            format!("gitdir: {}\n", worktree_git_dir),
+        )
+        .unwrap();
+        let linked = open_git_project(&worktree).unwrap();
+        assert_eq!(linked.common_dir(), PathBuf::from(&git_path));
+
+        let file = format!("{}/file", test_git_folder);
+        fs::write(&file, "not a folder").unwrap();
+        assert_eq!(open_git_project(&file), Err(GitError::CannotOpenFolder));
+    }
+
+    #[test]
+    fn test_written_objects_match_git() {
+        let folder = TempDir::new("test_written_objects_match_git").unwrap();
+        let test_git_folder = folder.path().to_str().unwrap();
+        create_sample_git_folder(test_git_folder);
+        let git_project = open_git_project(test_git_folder).unwrap();
+
+        let read_loose = |hash: &str| {
+            let path = ObjectFormat::Sha1.loose_object_path(&git_project, hash);
+            GitBlob::decode_data_bytes(&fs::read(path).unwrap()).unwrap()
+        };
+
+        // The hashes and contents are the ones of `git hash-object -w` and `git mktree`,
+        // and `git cat-file -s` gives the size each header has to declare
+        let blob = GitBlob::new(6, b"hello\n".to_vec());
+        blob.write_object(&git_project).unwrap();
+        assert_eq!(blob.get_hash(), "ce013625030ba8dba906f756967f9e9ca394464a");
+        assert_eq!(read_loose(&blob.get_hash()), b"blob 6\0hello\n");
+
+        let mut tree = GitTree::new();
+        tree.add_entry(GitTreeMode::File, blob.get_hash(), "README.md".to_string())
+            .unwrap();
+        tree.write_object(&git_project).unwrap();
+        assert_eq!(tree.get_hash(), "853694aae8816094a0d875fee7ea26278dbf5d0f");
+        assert_eq!(
+            read_loose(&tree.get_hash()),
+            [
+                b"tree 37\x00100644 README.md\0".as_slice(),
+                &from_hex(&blob.get_hash()).unwrap()
+            ]
+            .concat()
+        );
+
+        let author = GitCommitAuthor::new(
+            GitUser::new("Test User".to_string(), "test.user@email.com".to_string()),
+            1704153600,
+            "+0200".to_string(),
+        );
+        let commit = GitCommit::new(
+            &tree.get_hash(),
+            &[],
+            author.clone(),
+            author,
+            "First commit",
+        );
+        assert_eq!(
+            commit.write(&git_project),
+            Ok("d09f54f56610cedae529825a00a27ed751e97253".to_string())
+        );
+        let commit_data = format!(
+            "tree {}\n\
+            author Test User <test.user@email.com> 1704153600 +0200\n\
+            committer Test User <test.user@email.com> 1704153600 +0200\n\
+            \n\
+            First commit\n",
+            tree.get_hash()
+        );
+        assert_eq!(commit_data.len(), 175);
+        assert_eq!(
+            read_loose(&commit.get_hash()),
+            format!("commit 175\0{}", commit_data).into_bytes()
+        );
+        assert_eq!(
+            GitCommit::from_hash(&git_project, &commit.get_hash()),
+            Ok(commit.clone())
+        );
+
+        let tagger = GitCommitAuthor::new(
+            GitUser::new("Test User".to_string(), "test.user@email.com".to_string()),
+            1704250000,
+            "+0200".to_string(),
+        );
+        git_project
+            .create_annotated_tag("v1.0.0", &commit.get_hash(), tagger, "First release\n")
+            .unwrap();
+        assert_eq!(
+            git_project.resolve_ref("refs/tags/v1.0.0"),
+            Ok(Some("c76b23064aec9ece579eb41c4c9dfb69892adbba".to_string()))
+        );
+    }
 }